@@ -0,0 +1,248 @@
+//! User-defined usage alerts from settings.json. After each successful poll
+//! every rule whose window has reached its threshold fires once; it re-arms
+//! when usage falls back below the threshold, normally at the window's reset.
+
+use std::collections::HashSet;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::diagnose;
+use crate::history::Provider;
+use crate::models::{AppUsageData, UsageData, UsageSection};
+
+/// Fire `actions` once `window` of `provider` reaches `percent`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub percent: f64,
+    #[serde(default = "default_window")]
+    pub window: AlertWindow,
+    #[serde(default = "default_provider")]
+    pub provider: Provider,
+    #[serde(default = "default_actions")]
+    pub actions: Vec<AlertAction>,
+    /// Receives a JSON POST for the `webhook` action.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+}
+
+/// The usage a rule watches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertWindow {
+    /// The 5h window.
+    Session,
+    /// The 7d window.
+    Weekly,
+    /// The separate 7d Claude Opus limit; rules on it wait while the plan
+    /// reports none.
+    Opus,
+}
+
+impl AlertWindow {
+    pub fn section(self, usage: &UsageData) -> Option<&UsageSection> {
+        match self {
+            AlertWindow::Session => Some(&usage.session),
+            AlertWindow::Weekly => Some(&usage.weekly),
+            AlertWindow::Opus => usage.opus.as_ref(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertAction {
+    /// A tray notification.
+    Toast,
+    /// The Windows warning sound.
+    Sound,
+    Webhook,
+    /// Blink the widget.
+    Flash,
+}
+
+fn default_window() -> AlertWindow {
+    AlertWindow::Session
+}
+
+fn default_provider() -> Provider {
+    Provider::ClaudeCode
+}
+
+fn default_actions() -> Vec<AlertAction> {
+    vec![AlertAction::Toast]
+}
+
+/// A rule that has just fired, with the usage that set it off.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alert {
+    pub rule: AlertRule,
+    pub percent: f64,
+}
+
+/// Which rules have fired and not yet re-armed, by index into the rules.
+#[derive(Default)]
+struct AlertEngine {
+    fired: HashSet<usize>,
+}
+
+impl AlertEngine {
+    fn evaluate(&mut self, rules: &[AlertRule], data: &AppUsageData) -> Vec<Alert> {
+        let mut alerts = Vec::new();
+        for (index, rule) in rules.iter().enumerate() {
+            let usage = match rule.provider {
+                Provider::ClaudeCode => data.claude_code.as_ref(),
+                Provider::Codex => data.codex.as_ref(),
+                Provider::Antigravity => data.antigravity.as_ref(),
+            };
+            // A provider or window missing from this poll leaves the rule as
+            // it was.
+            let Some(section) = usage.and_then(|usage| rule.window.section(usage)) else {
+                continue;
+            };
+            let percent = section.percentage;
+            if percent < rule.percent {
+                self.fired.remove(&index);
+            } else if self.fired.insert(index) {
+                alerts.push(Alert {
+                    rule: rule.clone(),
+                    percent,
+                });
+            }
+        }
+        alerts
+    }
+}
+
+/// Read the `alerts` setting one rule at a time, so a rule this version
+/// cannot read is dropped on its own rather than taking every rule with it.
+pub fn deserialize_rules<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<AlertRule>, D::Error> {
+    let rules = Vec::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(rules
+        .into_iter()
+        .filter_map(|rule| {
+            serde_json::from_value(rule)
+                .map_err(|error| diagnose::log(format!("alert: ignoring a rule: {error}")))
+                .ok()
+        })
+        .collect())
+}
+
+static ENGINE: Mutex<Option<AlertEngine>> = Mutex::new(None);
+
+fn lock_engine() -> MutexGuard<'static, Option<AlertEngine>> {
+    ENGINE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The rules that `data` has just set off. While `snoozed` the rules are
+/// still tracked, so a threshold crossed during the snooze does not fire once
+/// it ends.
+pub fn evaluate(rules: &[AlertRule], data: &AppUsageData, snoozed: bool) -> Vec<Alert> {
+    let alerts = lock_engine()
+        .get_or_insert_with(AlertEngine::default)
+        .evaluate(rules, data);
+    if snoozed {
+        if !alerts.is_empty() {
+            diagnose::log(format!("{} alert(s) silenced by snooze", alerts.len()));
+        }
+        return Vec::new();
+    }
+    alerts
+}
+
+/// POST `alert` to its rule's webhook in the background, logging failures.
+pub fn send_webhook(alert: &Alert) {
+    let Some(url) = alert.rule.webhook_url.clone() else {
+        diagnose::log("alert: webhook action without a webhook_url");
+        return;
+    };
+    let body = serde_json::json!({
+        "provider": alert.rule.provider,
+        "window": alert.rule.window,
+        "threshold": alert.rule.percent,
+        "percent": alert.percent,
+    });
+    std::thread::spawn(move || {
+        let result = build_agent().and_then(|agent| {
+            agent
+                .post(&url)
+                .send_json(body)
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        });
+        if let Err(error) = result {
+            diagnose::log(format!("alert: webhook failed: {error}"));
+        }
+    });
+}
+
+fn build_agent() -> Result<ureq::Agent, String> {
+    let tls = native_tls::TlsConnector::new().map_err(|e| e.to_string())?;
+    Ok(ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(15))
+        .tls_connector(std::sync::Arc::new(tls))
+        .build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::UsageData;
+
+    #[derive(Deserialize)]
+    struct Settings {
+        #[serde(deserialize_with = "deserialize_rules")]
+        alerts: Vec<AlertRule>,
+    }
+
+    fn claude(session: f64) -> AppUsageData {
+        let mut usage = UsageData::default();
+        usage.session.percentage = session;
+        AppUsageData {
+            claude_code: Some(usage),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rules_fire_once_per_crossing() {
+        let rules: Vec<AlertRule> =
+            serde_json::from_str(r#"[{ "percent": 80 }, { "percent": 90, "actions": ["sound"] }]"#)
+                .unwrap();
+        let mut engine = AlertEngine::default();
+
+        assert!(engine.evaluate(&rules, &claude(50.0)).is_empty());
+        let fired = engine.evaluate(&rules, &claude(85.0));
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].rule.actions, vec![AlertAction::Toast]);
+        assert!(engine.evaluate(&rules, &claude(88.0)).is_empty());
+        assert_eq!(engine.evaluate(&rules, &claude(95.0)).len(), 1);
+
+        // The window reset re-arms both.
+        assert!(engine.evaluate(&rules, &claude(5.0)).is_empty());
+        assert_eq!(engine.evaluate(&rules, &claude(100.0)).len(), 2);
+    }
+
+    #[test]
+    fn opus_rules_watch_the_opus_limit() {
+        let settings: Settings = serde_json::from_str(
+            r#"{ "alerts": [{ "percent": 80, "window": "opus" }, { "percent": 80, "window": "monthly" }] }"#,
+        )
+        .unwrap();
+        // The unknown window drops only its own rule.
+        assert_eq!(settings.alerts.len(), 1);
+        let mut engine = AlertEngine::default();
+
+        // No Opus limit reported: nothing to compare against.
+        assert!(engine.evaluate(&settings.alerts, &claude(95.0)).is_empty());
+        let mut data = claude(10.0);
+        data.claude_code.as_mut().unwrap().opus = Some(UsageSection {
+            percentage: 85.0,
+            resets_at: None,
+        });
+        assert_eq!(engine.evaluate(&settings.alerts, &data).len(), 1);
+    }
+}
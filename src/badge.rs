@@ -0,0 +1,161 @@
+//! Badge mode: instead of the widget, a minimised window whose taskbar button
+//! carries the Claude 5h percentage as an overlay icon. The process has an
+//! explicit AppUserModelID, so the button can be pinned and the badge then
+//! appears on the pinned shortcut. Clicking the button shows the details
+//! instead of restoring the window; closing it turns badge mode off.
+
+use std::sync::atomic::{AtomicIsize, Ordering};
+use std::sync::{Mutex, Once};
+
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::*;
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use crate::native_interop::{self, WM_APP_BADGE_CLICKED, WM_APP_BADGE_CLOSED};
+use crate::palette::Palette;
+use crate::tray_icon::{self, TrayIconKind};
+
+const CLASS_NAME: &str = "ClaudeCodeUsageMonitorBadge";
+
+/// Window the button's clicks are forwarded to.
+static TARGET: AtomicIsize = AtomicIsize::new(0);
+static BADGE: AtomicIsize = AtomicIsize::new(0);
+
+/// Last overlay shown, re-applied when the shell recreates the button.
+static OVERLAY: Mutex<Option<(Option<f64>, Palette, String)>> = Mutex::new(None);
+
+static REGISTER_CLASS: Once = Once::new();
+
+/// Registers the badge class with the icons of `target`'s class.
+fn register_class(target: HWND) {
+    REGISTER_CLASS.call_once(|| unsafe {
+        let hinstance = GetModuleHandleW(PCWSTR::null()).unwrap_or_default();
+        let class_name = native_interop::wide_str(CLASS_NAME);
+        let wc = WNDCLASSEXW {
+            cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+            lpfnWndProc: Some(badge_proc),
+            hInstance: HINSTANCE(hinstance.0),
+            lpszClassName: PCWSTR::from_raw(class_name.as_ptr()),
+            hIcon: HICON(GetClassLongPtrW(target, GCLP_HICON) as *mut _),
+            hIconSm: HICON(GetClassLongPtrW(target, GCLP_HICONSM) as *mut _),
+            ..Default::default()
+        };
+        RegisterClassExW(&wc);
+    });
+}
+
+fn badge_hwnd() -> Option<HWND> {
+    let hwnd = HWND(BADGE.load(Ordering::Relaxed) as *mut _);
+    (!hwnd.is_invalid()).then_some(hwnd)
+}
+
+/// Add the minimised badge window to the taskbar, forwarding clicks to
+/// `target`. Does nothing when it is already shown.
+pub fn show(target: HWND, title: &str) -> windows::core::Result<()> {
+    if badge_hwnd().is_some() {
+        return Ok(());
+    }
+    register_class(target);
+    TARGET.store(target.0 as isize, Ordering::Relaxed);
+
+    unsafe {
+        let hinstance = GetModuleHandleW(PCWSTR::null()).unwrap_or_default();
+        let class_name = native_interop::wide_str(CLASS_NAME);
+        let title = native_interop::wide_str(title);
+        let hwnd = CreateWindowExW(
+            WS_EX_APPWINDOW,
+            PCWSTR::from_raw(class_name.as_ptr()),
+            PCWSTR::from_raw(title.as_ptr()),
+            WS_OVERLAPPED | WS_CAPTION | WS_SYSMENU | WS_MINIMIZEBOX,
+            CW_USEDEFAULT,
+            CW_USEDEFAULT,
+            0,
+            0,
+            HWND::default(),
+            HMENU::default(),
+            hinstance,
+            None,
+        )?;
+        BADGE.store(hwnd.0 as isize, Ordering::Relaxed);
+        let _ = ShowWindow(hwnd, SW_SHOWMINNOACTIVE);
+    }
+    Ok(())
+}
+
+/// Remove the badge window and its taskbar button.
+pub fn hide() {
+    let hwnd = HWND(BADGE.swap(0, Ordering::Relaxed) as *mut _);
+    if !hwnd.is_invalid() {
+        unsafe {
+            let _ = DestroyWindow(hwnd);
+        }
+    }
+}
+
+/// Show `percent` on the button, or the app icon alone while it is unknown.
+pub fn update(percent: Option<f64>, palette: Palette, description: &str) {
+    *OVERLAY.lock().unwrap_or_else(|e| e.into_inner()) =
+        Some((percent, palette, description.to_string()));
+    apply_overlay();
+}
+
+fn apply_overlay() {
+    let Some(hwnd) = badge_hwnd() else {
+        return;
+    };
+    let overlay = OVERLAY.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let Some((percent, palette, description)) = overlay else {
+        return;
+    };
+    unsafe {
+        let _ = SetWindowTextW(
+            hwnd,
+            PCWSTR::from_raw(native_interop::wide_str(&description).as_ptr()),
+        );
+    }
+    match percent {
+        Some(percent) => {
+            let icon = tray_icon::create_icon(TrayIconKind::Claude, Some(percent), palette);
+            native_interop::set_taskbar_overlay(hwnd, Some(icon), &description);
+            unsafe {
+                let _ = DestroyIcon(icon);
+            }
+        }
+        None => native_interop::set_taskbar_overlay(hwnd, None, &description),
+    }
+}
+
+fn forward(msg: u32) {
+    let target = HWND(TARGET.load(Ordering::Relaxed) as *mut _);
+    if target.is_invalid() {
+        return;
+    }
+    unsafe {
+        let _ = PostMessageW(target, msg, WPARAM(0), LPARAM(0));
+    }
+}
+
+unsafe extern "system" fn badge_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        // Clicking the button restores the window; show the details instead.
+        WM_SYSCOMMAND if (wparam.0 as u32 & 0xFFF0) == SC_RESTORE => {
+            forward(WM_APP_BADGE_CLICKED);
+            LRESULT(0)
+        }
+        WM_CLOSE => {
+            forward(WM_APP_BADGE_CLOSED);
+            LRESULT(0)
+        }
+        _ if msg == native_interop::taskbar_button_created_message() => {
+            apply_overlay();
+            LRESULT(0)
+        }
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
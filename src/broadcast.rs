@@ -0,0 +1,110 @@
+//! A hidden top-level window for system broadcasts. Windows only broadcasts
+//! messages such as WM_TIMECHANGE and WM_POWERBROADCAST to top-level windows,
+//! so they never reach the widget while it is a child of the taskbar. This
+//! window receives them and forwards them to the widget as app messages. A
+//! second instance finds it by class name to hand over to the running one.
+
+use std::sync::atomic::{AtomicIsize, Ordering};
+use std::sync::Once;
+
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::*;
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use crate::native_interop::{self, WM_APP_ACTIVATE, WM_APP_POWER_BROADCAST, WM_APP_TIME_CHANGED};
+
+const CLASS_NAME: &str = "ClaudeCodeUsageMonitorBroadcast";
+
+/// Window the broadcasts are forwarded to.
+static TARGET: AtomicIsize = AtomicIsize::new(0);
+
+static REGISTER_CLASS: Once = Once::new();
+
+fn register_class() {
+    REGISTER_CLASS.call_once(|| unsafe {
+        let hinstance = GetModuleHandleW(PCWSTR::null()).unwrap_or_default();
+        let class_name = native_interop::wide_str(CLASS_NAME);
+        let wc = WNDCLASSEXW {
+            cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+            lpfnWndProc: Some(broadcast_proc),
+            hInstance: HINSTANCE(hinstance.0),
+            lpszClassName: PCWSTR::from_raw(class_name.as_ptr()),
+            ..Default::default()
+        };
+        RegisterClassExW(&wc);
+    });
+}
+
+/// Create the listener, forwarding to `target`. It is never shown.
+pub fn listen(target: HWND) -> windows::core::Result<HWND> {
+    register_class();
+    TARGET.store(target.0 as isize, Ordering::Relaxed);
+
+    unsafe {
+        let hinstance = GetModuleHandleW(PCWSTR::null()).unwrap_or_default();
+        let class_name = native_interop::wide_str(CLASS_NAME);
+        CreateWindowExW(
+            WS_EX_TOOLWINDOW,
+            PCWSTR::from_raw(class_name.as_ptr()),
+            PCWSTR::null(),
+            WS_POPUP,
+            0,
+            0,
+            0,
+            0,
+            HWND::default(),
+            HMENU::default(),
+            hinstance,
+            None,
+        )
+    }
+}
+
+/// Ask the already running instance to show its details and refresh.
+/// Returns false when no running instance could be found.
+pub fn activate_running_instance() -> bool {
+    unsafe {
+        let class_name = native_interop::wide_str(CLASS_NAME);
+        let Ok(listener) = FindWindowW(PCWSTR::from_raw(class_name.as_ptr()), PCWSTR::null())
+        else {
+            return false;
+        };
+        // Let the running instance bring its flyout to the foreground.
+        let _ = AllowSetForegroundWindow(ASFW_ANY);
+        PostMessageW(listener, WM_APP_ACTIVATE, WPARAM(0), LPARAM(0)).is_ok()
+    }
+}
+
+fn forward(msg: u32, wparam: WPARAM, lparam: LPARAM) {
+    let target = HWND(TARGET.load(Ordering::Relaxed) as *mut _);
+    if target.is_invalid() {
+        return;
+    }
+    unsafe {
+        let _ = PostMessageW(target, msg, wparam, lparam);
+    }
+}
+
+unsafe extern "system" fn broadcast_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_TIMECHANGE => {
+            forward(WM_APP_TIME_CHANGED, WPARAM(0), LPARAM(0));
+            LRESULT(0)
+        }
+        WM_APP_ACTIVATE => {
+            forward(WM_APP_ACTIVATE, WPARAM(0), LPARAM(0));
+            LRESULT(0)
+        }
+        WM_POWERBROADCAST => {
+            forward(WM_APP_POWER_BROADCAST, wparam, LPARAM(0));
+            LRESULT(1)
+        }
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
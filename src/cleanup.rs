@@ -0,0 +1,109 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{LPARAM, WPARAM};
+use windows::Win32::UI::WindowsAndMessaging::{
+    FindWindowW, GetWindowThreadProcessId, PostMessageW, WM_CLOSE,
+};
+
+use crate::diagnose;
+use crate::native_interop;
+use crate::paths;
+use crate::startup_task;
+use crate::tray_icon;
+use crate::updater;
+use crate::window;
+
+const WINDOW_CLASS_NAME: &str = "ClaudeCodeUsageMonitor";
+const DIAGNOSE_LOG_NAME: &str = "claude-code-usage-monitor.log";
+
+/// `--uninstall-cleanup`: remove everything the app leaves behind outside its
+/// own executable. Runs silently so it can be scripted; the exit code is 0 when
+/// every step succeeded (or had nothing to remove) and 1 otherwise.
+pub fn handle_cli_mode(args: &[String]) -> Option<i32> {
+    if !args.iter().skip(1).any(|arg| arg == "--uninstall-cleanup") {
+        return None;
+    }
+
+    let mut failures = 0;
+    let mut step = |name: &str, result: Result<(), String>| match result {
+        Ok(()) => diagnose::log(format!("uninstall cleanup: {name} done")),
+        Err(error) => {
+            diagnose::log_error(&format!("uninstall cleanup: {name} failed"), error);
+            failures += 1;
+        }
+    };
+
+    // A running instance would rewrite its settings on the way out.
+    step("close running instance", close_running_instance());
+    step("startup entry", {
+        window::set_startup_enabled(false);
+        Ok(())
+    });
+    step("startup task", startup_task::unregister());
+    step("notification registration", tray_icon::unregister_app_id());
+    step("settings and history", remove_dir(roaming_data_dir()));
+    step("update downloads", remove_dir(local_data_dir()));
+    step(
+        "diagnostic log",
+        remove_file(&std::env::temp_dir().join(DIAGNOSE_LOG_NAME)),
+    );
+
+    Some(if failures == 0 { 0 } else { 1 })
+}
+
+/// Settings, usage history and imported transcript activity.
+fn roaming_data_dir() -> Option<PathBuf> {
+    if paths::is_portable() {
+        return Some(paths::data_dir());
+    }
+    std::env::var("APPDATA")
+        .ok()
+        .map(|appdata| PathBuf::from(appdata).join("ClaudeCodeUsageMonitor"))
+}
+
+/// Self-update staging area, inside the data folder in portable mode.
+fn local_data_dir() -> Option<PathBuf> {
+    if paths::is_portable() {
+        return None;
+    }
+    dirs::data_local_dir().map(|dir| dir.join("ClaudeCodeUsageMonitor"))
+}
+
+fn close_running_instance() -> Result<(), String> {
+    let class_name = native_interop::wide_str(WINDOW_CLASS_NAME);
+    unsafe {
+        let Ok(hwnd) = FindWindowW(PCWSTR::from_raw(class_name.as_ptr()), PCWSTR::null()) else {
+            return Ok(());
+        };
+        if hwnd.0.is_null() {
+            return Ok(());
+        }
+
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid));
+        PostMessageW(hwnd, WM_CLOSE, WPARAM(0), LPARAM(0))
+            .map_err(|e| format!("Unable to ask the running app to exit: {e}"))?;
+        updater::wait_for_process_exit(pid, Duration::from_secs(10))
+    }
+}
+
+fn remove_dir(path: Option<PathBuf>) -> Result<(), String> {
+    let Some(path) = path else {
+        return Ok(());
+    };
+    match std::fs::remove_dir_all(&path) {
+        Ok(()) => Ok(()),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(error) => Err(format!("Unable to remove {}: {error}", path.display())),
+    }
+}
+
+fn remove_file(path: &Path) -> Result<(), String> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(error) => Err(format!("Unable to remove {}: {error}", path.display())),
+    }
+}
@@ -0,0 +1,185 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::models::UsageData;
+use crate::otel;
+use crate::transcripts::{self, TranscriptUsage};
+
+const SESSION_WINDOW: Duration = Duration::from_secs(5 * 3600);
+const WEEKLY_WINDOW: Duration = Duration::from_secs(7 * 86_400);
+
+/// API list prices in USD per million tokens.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ModelPrice {
+    input: f64,
+    output: f64,
+    cache_write: f64,
+    cache_read: f64,
+}
+
+const fn price(input: f64, output: f64) -> ModelPrice {
+    // Cache writes (5 minute TTL) cost 1.25x input, cache reads 0.1x.
+    ModelPrice {
+        input,
+        output,
+        cache_write: input * 1.25,
+        cache_read: input * 0.1,
+    }
+}
+
+/// Matched in order against the model id, so more specific names come first.
+const PRICES: &[(&str, ModelPrice)] = &[
+    ("opus-4-5", price(5.0, 25.0)),
+    ("opus-4-6", price(5.0, 25.0)),
+    ("opus", price(15.0, 75.0)),
+    ("sonnet", price(3.0, 15.0)),
+    ("haiku-4", price(1.0, 5.0)),
+    ("3-5-haiku", price(0.8, 4.0)),
+    ("haiku", price(0.25, 1.25)),
+];
+
+/// Unknown Claude models are priced like Sonnet, the Claude Code default.
+const FALLBACK_PRICE: ModelPrice = price(3.0, 15.0);
+
+/// Estimated API-equivalent spend for the current 5h and 7d windows.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CostEstimate {
+    pub session: f64,
+    pub weekly: f64,
+}
+
+fn price_for(model: &str) -> Option<ModelPrice> {
+    if !model.starts_with("claude") {
+        // e.g. "<synthetic>" entries written for local errors.
+        return None;
+    }
+    PRICES
+        .iter()
+        .find(|(pattern, _)| model.contains(pattern))
+        .map(|(_, price)| *price)
+        .or(Some(FALLBACK_PRICE))
+}
+
+fn cost_of(usage: &TranscriptUsage) -> f64 {
+    let Some(price) = usage.model.as_deref().and_then(price_for) else {
+        return 0.0;
+    };
+    (usage.input_tokens as f64 * price.input
+        + usage.output_tokens as f64 * price.output
+        + usage.cache_creation_input_tokens as f64 * price.cache_write
+        + usage.cache_read_input_tokens as f64 * price.cache_read)
+        / 1_000_000.0
+}
+
+fn window_start(resets_at: Option<SystemTime>, length: Duration) -> u64 {
+    let now = SystemTime::now();
+    let start = resets_at
+        .filter(|reset| *reset > now)
+        .and_then(|reset| reset.checked_sub(length))
+        .unwrap_or_else(|| now - length);
+    start
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// Price the Claude Code transcripts written since the start of the current
+/// 5h and 7d windows in `usage`, as of the last transcript refresh. Falls
+/// back to rolling windows when a reset time is unknown.
+pub fn estimate(usage: &UsageData) -> CostEstimate {
+    let session_start = window_start(usage.session.resets_at, SESSION_WINDOW);
+    let weekly_start = window_start(usage.weekly.resets_at, WEEKLY_WINDOW);
+    let entries = transcripts::usage_since(session_start.min(weekly_start));
+    sum_windows(&entries, session_start, weekly_start)
+}
+
+/// Claude Code's own reported spend since the start of the current windows in
+/// `usage`, from the telemetry received while the app was running. A window
+/// that started before the receiver did takes the transcript estimate when
+/// that is larger, since the telemetry misses the time in between.
+pub fn from_telemetry(usage: &UsageData) -> CostEstimate {
+    let session_start = window_start(usage.session.resets_at, SESSION_WINDOW);
+    let weekly_start = window_start(usage.weekly.resets_at, WEEKLY_WINDOW);
+    let mut reported = CostEstimate {
+        session: otel::cost_since(session_start),
+        weekly: otel::cost_since(weekly_start),
+    };
+    if !otel::covers(session_start.min(weekly_start)) {
+        let estimated = estimate(usage);
+        if !otel::covers(session_start) {
+            reported.session = reported.session.max(estimated.session);
+        }
+        if !otel::covers(weekly_start) {
+            reported.weekly = reported.weekly.max(estimated.weekly);
+        }
+    }
+    reported
+}
+
+fn sum_windows(entries: &[TranscriptUsage], session_start: u64, weekly_start: u64) -> CostEstimate {
+    let mut estimate = CostEstimate::default();
+    for entry in entries {
+        let cost = cost_of(entry);
+        if entry.timestamp >= session_start {
+            estimate.session += cost;
+        }
+        if entry.timestamp >= weekly_start {
+            estimate.weekly += cost;
+        }
+    }
+    estimate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(timestamp: u64, model: &str, input: u64, output: u64) -> TranscriptUsage {
+        TranscriptUsage {
+            timestamp,
+            model: Some(model.to_string()),
+            input_tokens: input,
+            output_tokens: output,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
+        }
+    }
+
+    #[test]
+    fn models_are_priced_by_family_and_generation() {
+        assert_eq!(
+            price_for("claude-opus-4-1-20250805"),
+            Some(price(15.0, 75.0))
+        );
+        assert_eq!(
+            price_for("claude-opus-4-5-20251101"),
+            Some(price(5.0, 25.0))
+        );
+        assert_eq!(
+            price_for("claude-sonnet-4-5-20250929"),
+            Some(price(3.0, 15.0))
+        );
+        assert_eq!(
+            price_for("claude-haiku-4-5-20251001"),
+            Some(price(1.0, 5.0))
+        );
+        assert_eq!(
+            price_for("claude-3-5-haiku-20241022"),
+            Some(price(0.8, 4.0))
+        );
+        assert_eq!(price_for("claude-future-model"), Some(FALLBACK_PRICE));
+        assert_eq!(price_for("<synthetic>"), None);
+    }
+
+    #[test]
+    fn costs_are_summed_into_the_windows_they_fall_in() {
+        let entries = [
+            entry(100, "claude-sonnet-4-5-20250929", 1_000_000, 0),
+            entry(200, "claude-opus-4-1-20250805", 0, 100_000),
+        ];
+
+        let estimate = sum_windows(&entries, 150, 0);
+
+        assert!((estimate.session - 7.5).abs() < 0.000001);
+        assert!((estimate.weekly - 10.5).abs() < 0.000001);
+    }
+}
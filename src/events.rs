@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::diagnose;
+use crate::paths;
+
+/// Only the most recent events are kept; older lines are dropped on write.
+const MAX_EVENTS: usize = 500;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// Utilization rose faster than the configured threshold between polls.
+    UsageJump,
+    /// A usage window rolled over into a new one.
+    Reset,
+    /// The user asked for an immediate poll.
+    ManualRefresh,
+}
+
+/// Something worth looking back on, stored as a line of events.jsonl.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Event {
+    pub ts: u64,
+    pub kind: EventKind,
+    pub message: String,
+}
+
+/// Serialises writers so concurrent records never interleave a rewrite.
+static EVENTS: Mutex<()> = Mutex::new(());
+
+fn events_path() -> PathBuf {
+    paths::data_dir().join("events.jsonl")
+}
+
+fn load() -> Vec<Event> {
+    std::fs::read_to_string(events_path())
+        .map(|content| {
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Number of logged events of `kind` at or after `since` (unix seconds).
+pub fn count_since(kind: EventKind, since: u64) -> usize {
+    let _guard = EVENTS.lock().unwrap_or_else(|e| e.into_inner());
+    load()
+        .iter()
+        .filter(|event| event.kind == kind && event.ts >= since)
+        .count()
+}
+
+/// Append an event to the log (and the diagnostic log, when enabled).
+pub fn record(kind: EventKind, message: impl Into<String>) {
+    let event = Event {
+        ts: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0),
+        kind,
+        message: message.into(),
+    };
+    diagnose::log(format!("event: {}", event.message));
+
+    let _guard = EVENTS.lock().unwrap_or_else(|e| e.into_inner());
+    let mut events = load();
+    events.push(event);
+    let skip = events.len().saturating_sub(MAX_EVENTS);
+
+    let mut content = String::new();
+    for event in &events[skip..] {
+        if let Ok(line) = serde_json::to_string(event) {
+            content.push_str(&line);
+            content.push('\n');
+        }
+    }
+
+    let path = events_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Err(error) = std::fs::write(&path, content) {
+        diagnose::log_error("unable to write event log", error);
+    }
+}
@@ -0,0 +1,96 @@
+//! The JSON form of a poll pushed to the MQTT and WebSocket feeds.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::models::{AppUsageData, UsageData, UsageSection};
+
+#[derive(Serialize)]
+struct Payload {
+    updated_at: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    claude_code: Option<PayloadUsage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    codex: Option<PayloadUsage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    antigravity: Option<PayloadUsage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secondary: Option<PayloadUsage>,
+}
+
+#[derive(Serialize)]
+struct PayloadUsage {
+    session: PayloadSection,
+    weekly: PayloadSection,
+}
+
+#[derive(Serialize)]
+struct PayloadSection {
+    percent: f64,
+    /// Unix seconds.
+    resets_at: Option<u64>,
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn payload(data: &AppUsageData, now: SystemTime) -> Payload {
+    let section = |section: &UsageSection| PayloadSection {
+        percent: section.percentage,
+        resets_at: section.resets_at.map(unix_secs),
+    };
+    let usage = |usage: &Option<UsageData>| {
+        usage.as_ref().map(|usage| PayloadUsage {
+            session: section(&usage.session),
+            weekly: section(&usage.weekly),
+        })
+    };
+    Payload {
+        updated_at: unix_secs(now),
+        claude_code: usage(&data.claude_code),
+        codex: usage(&data.codex),
+        antigravity: usage(&data.antigravity),
+        secondary: usage(&data.secondary),
+    }
+}
+
+/// `data` as JSON, with reset times in unix seconds.
+pub fn usage_json(data: &AppUsageData, now: SystemTime) -> String {
+    serde_json::to_string(&payload(data, now)).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn payload_lists_only_polled_providers() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let data = AppUsageData {
+            claude_code: Some(UsageData {
+                session: UsageSection {
+                    percentage: 42.0,
+                    resets_at: Some(now + Duration::from_secs(600)),
+                },
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let json = serde_json::to_value(payload(&data, now)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "updated_at": 1_700_000_000u64,
+                "claude_code": {
+                    "session": { "percent": 42.0, "resets_at": 1_700_000_600u64 },
+                    "weekly": { "percent": 0.0, "resets_at": null }
+                }
+            })
+        );
+    }
+}
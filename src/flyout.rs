@@ -0,0 +1,720 @@
+use std::sync::{Mutex, MutexGuard, Once};
+use std::time::{Duration, Instant};
+
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::*;
+use windows::Win32::Graphics::Gdi::*;
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::HiDpi::GetDpiForWindow;
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_ESCAPE;
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use crate::native_interop::{self, Color};
+
+const CLASS_NAME: &str = "ClaudeCodeUsageMonitorFlyout";
+
+const PADDING: i32 = 12;
+const LINE_HEIGHT: i32 = 18;
+const SECTION_GAP: i32 = 8;
+const ANCHOR_GAP: i32 = 8;
+const MIN_WIDTH: i32 = 180;
+const STRIP_HEIGHT: i32 = 20;
+const STRIP_GAP: i32 = 3;
+const STRIP_MIN_CELL_WIDTH: i32 = 26;
+const HEATMAP_CELL: i32 = 9;
+const HEATMAP_GAP: i32 = 2;
+const HEATMAP_LABEL_WIDTH: i32 = 16;
+/// Hours labelled below the heatmap.
+const HEATMAP_HOUR_LABELS: [usize; 4] = [0, 6, 12, 18];
+const BUTTON_HEIGHT: i32 = 24;
+const BUTTON_PADDING: i32 = 12;
+
+/// A click on the widget that closes the flyout (by deactivating it) must not
+/// immediately reopen it.
+const REOPEN_GUARD: Duration = Duration::from_millis(250);
+
+const OVERLAY_TIMER_ID: usize = 1;
+/// Gap between the overlay and the top edge of the monitor.
+const OVERLAY_TOP_GAP: i32 = 48;
+
+/// A titled block of text lines in the details flyout.
+pub struct FlyoutSection {
+    pub title: String,
+    pub lines: Vec<String>,
+    pub strip: Option<FlyoutStrip>,
+    pub heatmap: Option<FlyoutHeatmap>,
+    pub button: Option<FlyoutButton>,
+}
+
+/// A button at the end of a section. Clicking it closes the flyout and calls
+/// `on_click`.
+pub struct FlyoutButton {
+    pub label: String,
+    pub on_click: fn(),
+}
+
+/// A row of equal cells below a section's lines, each labelled with its value
+/// and shaded towards `accent` by its share of the largest value.
+pub struct FlyoutStrip {
+    pub values: Vec<f64>,
+    pub accent: Color,
+}
+
+/// A captioned grid below the strip with one labelled row per entry of
+/// `values`, its cells shaded towards `accent` by their share of the largest
+/// value.
+pub struct FlyoutHeatmap {
+    pub caption: String,
+    pub row_labels: Vec<String>,
+    pub values: Vec<Vec<f64>>,
+    pub accent: Color,
+}
+
+impl FlyoutHeatmap {
+    fn columns(&self) -> i32 {
+        self.values.iter().map(Vec::len).max().unwrap_or(0) as i32
+    }
+
+    fn width(&self, dpi: u32) -> i32 {
+        scale(HEATMAP_LABEL_WIDTH, dpi) + self.columns() * scale(HEATMAP_CELL + HEATMAP_GAP, dpi)
+    }
+
+    /// Caption, grid and hour labels.
+    fn height(&self, dpi: u32) -> i32 {
+        scale(LINE_HEIGHT, dpi) * 2
+            + self.values.len() as i32 * scale(HEATMAP_CELL + HEATMAP_GAP, dpi)
+    }
+}
+
+struct FlyoutState {
+    hwnd: Option<HWND>,
+    sections: Vec<FlyoutSection>,
+    is_dark: bool,
+    closed_at: Option<Instant>,
+    /// Where each button was last painted, for hit testing clicks.
+    buttons: Vec<(RECT, fn())>,
+}
+
+unsafe impl Send for FlyoutState {}
+
+static FLYOUT: Mutex<FlyoutState> = Mutex::new(FlyoutState {
+    hwnd: None,
+    sections: Vec::new(),
+    is_dark: true,
+    closed_at: None,
+    buttons: Vec::new(),
+});
+
+static REGISTER_CLASS: Once = Once::new();
+
+fn lock_flyout() -> MutexGuard<'static, FlyoutState> {
+    FLYOUT.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn scale(px: i32, dpi: u32) -> i32 {
+    (px as f64 * dpi as f64 / 96.0).round() as i32
+}
+
+fn register_class() {
+    REGISTER_CLASS.call_once(|| unsafe {
+        let hinstance = GetModuleHandleW(PCWSTR::null()).unwrap_or_default();
+        let class_name = native_interop::wide_str(CLASS_NAME);
+        let wc = WNDCLASSEXW {
+            cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+            style: CS_HREDRAW | CS_VREDRAW | CS_DROPSHADOW,
+            lpfnWndProc: Some(flyout_proc),
+            hInstance: HINSTANCE(hinstance.0),
+            hCursor: LoadCursorW(HINSTANCE::default(), IDC_ARROW).unwrap_or_default(),
+            lpszClassName: PCWSTR::from_raw(class_name.as_ptr()),
+            ..Default::default()
+        };
+        RegisterClassExW(&wc);
+    });
+}
+
+pub fn is_open() -> bool {
+    lock_flyout().hwnd.is_some()
+}
+
+/// Open the flyout next to `anchor` (screen coordinates), or close it if it is
+/// already open.
+pub fn toggle(anchor: RECT, sections: Vec<FlyoutSection>, is_dark: bool) {
+    let (open_hwnd, recently_closed) = {
+        let flyout = lock_flyout();
+        (
+            flyout.hwnd,
+            flyout
+                .closed_at
+                .is_some_and(|closed_at| closed_at.elapsed() < REOPEN_GUARD),
+        )
+    };
+
+    if let Some(hwnd) = open_hwnd {
+        unsafe {
+            let _ = DestroyWindow(hwnd);
+        }
+        return;
+    }
+    if recently_closed {
+        return;
+    }
+
+    show(anchor, sections, is_dark);
+}
+
+/// Replace the content of an open flyout, e.g. after a poll completes.
+pub fn update(sections: Vec<FlyoutSection>, is_dark: bool) {
+    let hwnd = {
+        let mut flyout = lock_flyout();
+        let Some(hwnd) = flyout.hwnd else {
+            return;
+        };
+        flyout.sections = sections;
+        flyout.is_dark = is_dark;
+        hwnd
+    };
+    unsafe {
+        let _ = InvalidateRect(hwnd, None, true);
+    }
+}
+
+/// Show the same content as a transient overlay at the top of the monitor
+/// holding the foreground window, without taking focus from it. The overlay
+/// closes itself after `duration`; an open flyout is replaced.
+pub fn show_overlay(sections: Vec<FlyoutSection>, is_dark: bool, duration: Duration) {
+    let open_hwnd = lock_flyout().hwnd;
+    if let Some(hwnd) = open_hwnd {
+        unsafe {
+            let _ = DestroyWindow(hwnd);
+        }
+    }
+
+    let Some(hwnd) = create(sections, is_dark, WS_EX_NOACTIVATE) else {
+        return;
+    };
+    let (width, height) = measure(hwnd);
+    let (x, y) = position_overlay(hwnd, width);
+    unsafe {
+        let _ = SetWindowPos(
+            hwnd,
+            HWND_TOPMOST,
+            x,
+            y,
+            width,
+            height,
+            SWP_SHOWWINDOW | SWP_NOACTIVATE,
+        );
+        SetTimer(hwnd, OVERLAY_TIMER_ID, duration.as_millis() as u32, None);
+    }
+}
+
+fn show(anchor: RECT, sections: Vec<FlyoutSection>, is_dark: bool) {
+    let Some(hwnd) = create(sections, is_dark, WINDOW_EX_STYLE(0)) else {
+        return;
+    };
+    let (width, height) = measure(hwnd);
+    let (x, y) = position_near(anchor, width, height);
+    unsafe {
+        let _ = SetWindowPos(hwnd, HWND_TOPMOST, x, y, width, height, SWP_SHOWWINDOW);
+        let _ = SetForegroundWindow(hwnd);
+    }
+}
+
+/// Create the (still hidden) flyout window with `sections` as its content.
+fn create(sections: Vec<FlyoutSection>, is_dark: bool, ex_style: WINDOW_EX_STYLE) -> Option<HWND> {
+    register_class();
+
+    {
+        let mut flyout = lock_flyout();
+        flyout.sections = sections;
+        flyout.is_dark = is_dark;
+    }
+
+    unsafe {
+        let hinstance = GetModuleHandleW(PCWSTR::null()).unwrap_or_default();
+        let class_name = native_interop::wide_str(CLASS_NAME);
+        let hwnd = CreateWindowExW(
+            WS_EX_TOOLWINDOW | WS_EX_TOPMOST | ex_style,
+            PCWSTR::from_raw(class_name.as_ptr()),
+            PCWSTR::null(),
+            WS_POPUP | WS_BORDER,
+            0,
+            0,
+            0,
+            0,
+            HWND::default(),
+            HMENU::default(),
+            hinstance,
+            None,
+        )
+        .ok()?;
+        lock_flyout().hwnd = Some(hwnd);
+        Some(hwnd)
+    }
+}
+
+fn create_font(dpi: u32, weight: i32) -> HFONT {
+    let font_name = native_interop::wide_str("Segoe UI");
+    unsafe {
+        CreateFontW(
+            scale(-12, dpi),
+            0,
+            0,
+            0,
+            weight,
+            0,
+            0,
+            0,
+            DEFAULT_CHARSET.0 as u32,
+            OUT_TT_PRECIS.0 as u32,
+            CLIP_DEFAULT_PRECIS.0 as u32,
+            CLEARTYPE_QUALITY.0 as u32,
+            (DEFAULT_PITCH.0 | FF_DONTCARE.0) as u32,
+            PCWSTR::from_raw(font_name.as_ptr()),
+        )
+    }
+}
+
+/// Size the flyout to fit the widest line and every section.
+fn measure(hwnd: HWND) -> (i32, i32) {
+    let dpi = unsafe { GetDpiForWindow(hwnd) }.max(96);
+    let flyout = lock_flyout();
+
+    let mut text_width = 0;
+    unsafe {
+        let hdc = GetDC(hwnd);
+        let title_font = create_font(dpi, FW_SEMIBOLD.0 as i32);
+        let body_font = create_font(dpi, FW_NORMAL.0 as i32);
+        let old_font = SelectObject(hdc, title_font);
+        for section in &flyout.sections {
+            SelectObject(hdc, title_font);
+            text_width = text_width.max(text_extent(hdc, &section.title));
+            SelectObject(hdc, body_font);
+            for line in &section.lines {
+                text_width = text_width.max(text_extent(hdc, line));
+            }
+            if let Some(heatmap) = &section.heatmap {
+                text_width = text_width.max(text_extent(hdc, &heatmap.caption));
+            }
+            if let Some(button) = &section.button {
+                text_width = text_width
+                    .max(text_extent(hdc, &button.label) + scale(BUTTON_PADDING * 2, dpi));
+            }
+        }
+        SelectObject(hdc, old_font);
+        let _ = DeleteObject(title_font);
+        let _ = DeleteObject(body_font);
+        ReleaseDC(hwnd, hdc);
+    }
+
+    let line_count: usize = flyout
+        .sections
+        .iter()
+        .map(|section| 1 + section.lines.len())
+        .sum();
+    let gaps = flyout.sections.len().saturating_sub(1) as i32;
+    let strips = flyout.sections.iter().filter_map(|s| s.strip.as_ref());
+    let strip_count = strips.clone().count() as i32;
+    let strip_width = strips
+        .map(|strip| {
+            let cells = strip.values.len() as i32;
+            cells * scale(STRIP_MIN_CELL_WIDTH, dpi)
+                + cells.saturating_sub(1) * scale(STRIP_GAP, dpi)
+        })
+        .max()
+        .unwrap_or(0);
+    let heatmaps = flyout.sections.iter().filter_map(|s| s.heatmap.as_ref());
+    let heatmap_width = heatmaps
+        .clone()
+        .map(|heatmap| heatmap.width(dpi))
+        .max()
+        .unwrap_or(0);
+    let heatmap_height: i32 = heatmaps
+        .map(|heatmap| heatmap.height(dpi) + scale(STRIP_GAP, dpi))
+        .sum();
+
+    let button_count = flyout
+        .sections
+        .iter()
+        .filter(|section| section.button.is_some())
+        .count() as i32;
+
+    let width = (text_width.max(strip_width).max(heatmap_width) + scale(PADDING * 2, dpi))
+        .max(scale(MIN_WIDTH, dpi));
+    let height = scale(LINE_HEIGHT, dpi) * line_count as i32
+        + (scale(STRIP_HEIGHT, dpi) + scale(STRIP_GAP, dpi)) * strip_count
+        + heatmap_height
+        + (scale(BUTTON_HEIGHT, dpi) + scale(STRIP_GAP, dpi)) * button_count
+        + scale(SECTION_GAP, dpi) * gaps
+        + scale(PADDING * 2, dpi);
+    (width, height)
+}
+
+fn text_extent(hdc: HDC, text: &str) -> i32 {
+    let wide: Vec<u16> = text.encode_utf16().collect();
+    let mut size = SIZE::default();
+    unsafe {
+        let _ = GetTextExtentPoint32W(hdc, &wide, &mut size);
+    }
+    size.cx
+}
+
+/// Place the flyout above the anchor (or below it when the anchor sits at the
+/// top of the screen), right-aligned and kept inside the monitor work area.
+fn position_near(anchor: RECT, width: i32, height: i32) -> (i32, i32) {
+    let gap = ANCHOR_GAP;
+    let mut x = anchor.right - width;
+    let mut y = anchor.top - height - gap;
+
+    unsafe {
+        let monitor = MonitorFromRect(&anchor, MONITOR_DEFAULTTONEAREST);
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        if GetMonitorInfoW(monitor, &mut info).as_bool() {
+            let work = info.rcWork;
+            if y < work.top {
+                y = anchor.bottom + gap;
+            }
+            x = x.clamp(work.left, (work.right - width).max(work.left));
+            y = y.clamp(work.top, (work.bottom - height).max(work.top));
+        }
+    }
+    (x, y)
+}
+
+/// Centre the overlay near the top of the monitor the user is looking at.
+/// Uses the full monitor rather than the work area so it also lands in place
+/// over full-screen windows.
+fn position_overlay(hwnd: HWND, width: i32) -> (i32, i32) {
+    unsafe {
+        let monitor = MonitorFromWindow(GetForegroundWindow(), MONITOR_DEFAULTTOPRIMARY);
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        if !GetMonitorInfoW(monitor, &mut info).as_bool() {
+            return (0, 0);
+        }
+        let area = info.rcMonitor;
+        let dpi = GetDpiForWindow(hwnd).max(96);
+        let x = area.left + (area.right - area.left - width) / 2;
+        (x, area.top + scale(OVERLAY_TOP_GAP, dpi))
+    }
+}
+
+fn paint(hwnd: HWND) {
+    let dpi = unsafe { GetDpiForWindow(hwnd) }.max(96);
+    let mut flyout = lock_flyout();
+    let mut buttons = Vec::new();
+    let (bg, title_color, text_color) = if flyout.is_dark {
+        (
+            Color::from_hex("#2B2B2B"),
+            Color::from_hex("#FFFFFF"),
+            Color::from_hex("#C8C8C8"),
+        )
+    } else {
+        (
+            Color::from_hex("#F9F9F9"),
+            Color::from_hex("#1A1A1A"),
+            Color::from_hex("#404040"),
+        )
+    };
+
+    unsafe {
+        let mut ps = PAINTSTRUCT::default();
+        let hdc = BeginPaint(hwnd, &mut ps);
+
+        let mut client_rect = RECT::default();
+        let _ = GetClientRect(hwnd, &mut client_rect);
+        let bg_brush = CreateSolidBrush(COLORREF(bg.to_colorref()));
+        FillRect(hdc, &client_rect, bg_brush);
+        let _ = DeleteObject(bg_brush);
+
+        let _ = SetBkMode(hdc, TRANSPARENT);
+        let title_font = create_font(dpi, FW_SEMIBOLD.0 as i32);
+        let body_font = create_font(dpi, FW_NORMAL.0 as i32);
+        let old_font = SelectObject(hdc, title_font);
+
+        let padding = scale(PADDING, dpi);
+        let line_height = scale(LINE_HEIGHT, dpi);
+        let mut y = padding;
+        for (index, section) in flyout.sections.iter().enumerate() {
+            if index > 0 {
+                y += scale(SECTION_GAP, dpi);
+            }
+            SelectObject(hdc, title_font);
+            let _ = SetTextColor(hdc, COLORREF(title_color.to_colorref()));
+            draw_line(
+                hdc,
+                &section.title,
+                padding,
+                y,
+                client_rect.right,
+                line_height,
+            );
+            y += line_height;
+
+            SelectObject(hdc, body_font);
+            let _ = SetTextColor(hdc, COLORREF(text_color.to_colorref()));
+            for line in &section.lines {
+                draw_line(hdc, line, padding, y, client_rect.right, line_height);
+                y += line_height;
+            }
+
+            if let Some(strip) = &section.strip {
+                y += scale(STRIP_GAP, dpi);
+                let row = RECT {
+                    left: padding,
+                    top: y,
+                    right: client_rect.right - padding,
+                    bottom: y + scale(STRIP_HEIGHT, dpi),
+                };
+                draw_strip(hdc, strip, row, scale(STRIP_GAP, dpi), bg, text_color);
+                let _ = SetTextColor(hdc, COLORREF(text_color.to_colorref()));
+                y = row.bottom;
+            }
+
+            if let Some(heatmap) = &section.heatmap {
+                y += scale(STRIP_GAP, dpi);
+                draw_line(
+                    hdc,
+                    &heatmap.caption,
+                    padding,
+                    y,
+                    client_rect.right,
+                    line_height,
+                );
+                y += line_height;
+                draw_heatmap(hdc, heatmap, padding, y, dpi, bg);
+                y += heatmap.height(dpi) - line_height;
+            }
+
+            if let Some(button) = &section.button {
+                y += scale(STRIP_GAP, dpi);
+                let mut rect = RECT {
+                    left: padding,
+                    top: y,
+                    right: padding
+                        + text_extent(hdc, &button.label)
+                        + scale(BUTTON_PADDING * 2, dpi),
+                    bottom: y + scale(BUTTON_HEIGHT, dpi),
+                };
+                let brush = CreateSolidBrush(COLORREF(
+                    empty_cell(bg).blend(text_color, 0.12).to_colorref(),
+                ));
+                FillRect(hdc, &rect, brush);
+                let _ = DeleteObject(brush);
+                let _ = SetTextColor(hdc, COLORREF(title_color.to_colorref()));
+                let mut label: Vec<u16> = button.label.encode_utf16().collect();
+                let _ = DrawTextW(
+                    hdc,
+                    &mut label,
+                    &mut rect,
+                    DT_CENTER | DT_VCENTER | DT_SINGLELINE,
+                );
+                let _ = SetTextColor(hdc, COLORREF(text_color.to_colorref()));
+                buttons.push((rect, button.on_click));
+                y = rect.bottom;
+            }
+        }
+        flyout.buttons = buttons;
+
+        SelectObject(hdc, old_font);
+        let _ = DeleteObject(title_font);
+        let _ = DeleteObject(body_font);
+        let _ = EndPaint(hwnd, &ps);
+    }
+}
+
+/// Fill for a cell with nothing in it.
+fn empty_cell(bg: Color) -> Color {
+    if bg.is_light() {
+        bg.blend(Color::from_hex("#000000"), 0.08)
+    } else {
+        bg.blend(Color::from_hex("#FFFFFF"), 0.08)
+    }
+}
+
+/// Draw the grid and hour labels of `heatmap` with its top left at `x`, `y`,
+/// in the text color already selected.
+fn draw_heatmap(hdc: HDC, heatmap: &FlyoutHeatmap, x: i32, y: i32, dpi: u32, bg: Color) {
+    let largest = heatmap.values.iter().flatten().copied().fold(0.0, f64::max);
+    let empty = empty_cell(bg);
+    let label_width = scale(HEATMAP_LABEL_WIDTH, dpi);
+    let cell = scale(HEATMAP_CELL, dpi);
+    let pitch = scale(HEATMAP_CELL + HEATMAP_GAP, dpi);
+    let grid_left = x + label_width;
+
+    for (row, values) in heatmap.values.iter().enumerate() {
+        let top = y + row as i32 * pitch;
+        if let Some(label) = heatmap.row_labels.get(row) {
+            let mut label: Vec<u16> = label.encode_utf16().collect();
+            let mut rect = RECT {
+                left: x,
+                top,
+                right: grid_left,
+                bottom: top + cell,
+            };
+            unsafe {
+                let _ = DrawTextW(
+                    hdc,
+                    &mut label,
+                    &mut rect,
+                    DT_LEFT | DT_VCENTER | DT_SINGLELINE | DT_NOCLIP,
+                );
+            }
+        }
+        for (column, value) in values.iter().enumerate() {
+            let left = grid_left + column as i32 * pitch;
+            let rect = RECT {
+                left,
+                top,
+                right: left + cell,
+                bottom: top + cell,
+            };
+            let share = if largest > 0.0 { value / largest } else { 0.0 };
+            unsafe {
+                let brush =
+                    CreateSolidBrush(COLORREF(empty.blend(heatmap.accent, share).to_colorref()));
+                FillRect(hdc, &rect, brush);
+                let _ = DeleteObject(brush);
+            }
+        }
+    }
+
+    let labels_top = y + heatmap.values.len() as i32 * pitch;
+    for hour in HEATMAP_HOUR_LABELS
+        .into_iter()
+        .filter(|hour| (*hour as i32) < heatmap.columns())
+    {
+        draw_line(
+            hdc,
+            &hour.to_string(),
+            grid_left + hour as i32 * pitch,
+            labels_top,
+            grid_left + heatmap.columns() * pitch,
+            scale(LINE_HEIGHT, dpi),
+        );
+    }
+}
+
+fn draw_strip(hdc: HDC, strip: &FlyoutStrip, row: RECT, gap: i32, bg: Color, text: Color) {
+    let cells = strip.values.len() as i32;
+    if cells == 0 {
+        return;
+    }
+    let largest = strip.values.iter().copied().fold(0.0, f64::max);
+    let cell_width = (row.right - row.left - gap * (cells - 1)) / cells;
+    let empty = empty_cell(bg);
+
+    for (index, value) in strip.values.iter().enumerate() {
+        let left = row.left + index as i32 * (cell_width + gap);
+        let mut cell = RECT {
+            left,
+            top: row.top,
+            right: left + cell_width,
+            bottom: row.bottom,
+        };
+        let share = if largest > 0.0 { value / largest } else { 0.0 };
+        let fill = empty.blend(strip.accent, share);
+        let label_color = if fill.is_light() == text.is_light() {
+            if fill.is_light() {
+                Color::from_hex("#1A1A1A")
+            } else {
+                Color::from_hex("#FFFFFF")
+            }
+        } else {
+            text
+        };
+
+        unsafe {
+            let brush = CreateSolidBrush(COLORREF(fill.to_colorref()));
+            FillRect(hdc, &cell, brush);
+            let _ = DeleteObject(brush);
+
+            let _ = SetTextColor(hdc, COLORREF(label_color.to_colorref()));
+            let mut label: Vec<u16> = format!("{value:.0}").encode_utf16().collect();
+            let _ = DrawTextW(
+                hdc,
+                &mut label,
+                &mut cell,
+                DT_CENTER | DT_VCENTER | DT_SINGLELINE,
+            );
+        }
+    }
+}
+
+fn draw_line(hdc: HDC, text: &str, x: i32, y: i32, right: i32, line_height: i32) {
+    let mut wide: Vec<u16> = text.encode_utf16().collect();
+    let mut rect = RECT {
+        left: x,
+        top: y,
+        right,
+        bottom: y + line_height,
+    };
+    unsafe {
+        let _ = DrawTextW(
+            hdc,
+            &mut wide,
+            &mut rect,
+            DT_LEFT | DT_VCENTER | DT_SINGLELINE,
+        );
+    }
+}
+
+unsafe extern "system" fn flyout_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_PAINT => {
+            paint(hwnd);
+            LRESULT(0)
+        }
+        WM_ERASEBKGND => LRESULT(1),
+        WM_ACTIVATE => {
+            if (wparam.0 & 0xFFFF) as u32 == WA_INACTIVE {
+                let _ = DestroyWindow(hwnd);
+            }
+            LRESULT(0)
+        }
+        WM_TIMER if wparam.0 == OVERLAY_TIMER_ID => {
+            let _ = KillTimer(hwnd, OVERLAY_TIMER_ID);
+            let _ = DestroyWindow(hwnd);
+            LRESULT(0)
+        }
+        WM_LBUTTONUP => {
+            let point = POINT {
+                x: (lparam.0 & 0xFFFF) as i16 as i32,
+                y: ((lparam.0 >> 16) & 0xFFFF) as i16 as i32,
+            };
+            let clicked = lock_flyout()
+                .buttons
+                .iter()
+                .find(|(rect, _)| PtInRect(rect, point).as_bool())
+                .map(|(_, on_click)| *on_click);
+            if let Some(on_click) = clicked {
+                let _ = DestroyWindow(hwnd);
+                on_click();
+            }
+            LRESULT(0)
+        }
+        WM_KEYDOWN if wparam.0 == VK_ESCAPE.0 as usize => {
+            let _ = DestroyWindow(hwnd);
+            LRESULT(0)
+        }
+        WM_DESTROY => {
+            let mut flyout = lock_flyout();
+            if flyout.hwnd == Some(hwnd) {
+                flyout.hwnd = None;
+                flyout.closed_at = Some(Instant::now());
+            }
+            LRESULT(0)
+        }
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
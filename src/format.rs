@@ -0,0 +1,334 @@
+use std::time::{Duration, SystemTime};
+
+use crate::localization::Strings;
+use crate::models::UsageSection;
+
+/// Countdown units, largest first: days, hours, minutes, seconds.
+const COUNTDOWN_UNITS: [u64; 4] = [86_400, 3_600, 60, 1];
+
+/// Precision and units for one output surface. Every surface formats through
+/// the same functions and only picks a different `Style`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Style {
+    /// Decimal places on percentages and burn rates.
+    pub percent_decimals: usize,
+    /// How many countdown units to show, largest first ("3h" vs "3h 12m").
+    pub countdown_units: usize,
+    /// Abbreviate token counts ("1.2M") instead of grouping digits ("1,234,567").
+    pub compact_tokens: bool,
+    /// Dollar amounts at or above this are shown without cents.
+    pub whole_dollars_from: f64,
+}
+
+impl Style {
+    /// The taskbar widget and tray tooltips, where space is tight.
+    pub const WIDGET: Style = Style {
+        percent_decimals: 0,
+        countdown_units: 1,
+        compact_tokens: true,
+        whole_dollars_from: 100.0,
+    };
+
+    /// The details flyout, which has room for a second countdown unit.
+    pub const DETAIL: Style = Style {
+        countdown_units: 2,
+        ..Style::WIDGET
+    };
+}
+
+/// Format a usage section as "X% · Yh" style text, or "0 left · Yh" once
+/// its limit is reached.
+pub fn usage_line(section: &UsageSection, style: Style, strings: Strings) -> String {
+    usage_line_at(section, SystemTime::now(), style, strings)
+}
+
+fn usage_line_at(
+    section: &UsageSection,
+    now: SystemTime,
+    style: Style,
+    strings: Strings,
+) -> String {
+    let pct = if section.limit_reached() {
+        strings.none_left.to_string()
+    } else {
+        percent(section.percentage, style)
+    };
+    let cd = countdown(section.resets_at, now, style, strings);
+    if cd.is_empty() {
+        pct
+    } else {
+        format!("{pct} \u{00b7} {cd}")
+    }
+}
+
+/// Like `usage_line`, but shows the recent burn rate instead of the countdown.
+pub fn usage_line_with_rate(section: &UsageSection, rate_per_hour: f64, style: Style) -> String {
+    format!(
+        "{} \u{00b7} {}",
+        percent(section.percentage, style),
+        burn_rate(rate_per_hour, style)
+    )
+}
+
+/// Time left until `resets_at`, e.g. "11h 40m", or "now" once it has passed.
+pub fn time_until(resets_at: SystemTime, style: Style, strings: Strings) -> String {
+    countdown(Some(resets_at), SystemTime::now(), style, strings)
+}
+
+/// Length of `duration`, e.g. "2m 30s".
+pub fn duration(duration: Duration, style: Style, strings: Strings) -> String {
+    countdown_from_secs(duration.as_secs(), style, strings)
+}
+
+/// Read a typed interval such as "45", "90s", "5m" or "1h"; a bare number is
+/// seconds.
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let text = text.trim().to_ascii_lowercase();
+    let (number, unit_secs) = match text.char_indices().last()? {
+        (i, 's') => (&text[..i], 1),
+        (i, 'm') => (&text[..i], 60),
+        (i, 'h') => (&text[..i], 3_600),
+        _ => (text.as_str(), 1),
+    };
+    let value = number.trim().parse::<u64>().ok()?;
+    value.checked_mul(unit_secs).map(Duration::from_secs)
+}
+
+pub fn percent(value: f64, style: Style) -> String {
+    format!("{value:.*}%", style.percent_decimals)
+}
+
+pub fn burn_rate(rate_per_hour: f64, style: Style) -> String {
+    format!("{rate_per_hour:.*}%/h", style.percent_decimals)
+}
+
+/// Token count such as "950", "350K" or "1.2M", or "1,234,567" when the style
+/// does not abbreviate.
+pub fn token_count(tokens: u64, style: Style) -> String {
+    if !style.compact_tokens {
+        return group_digits(tokens);
+    }
+    if tokens >= 1_000_000_000 {
+        format!("{:.1}B", tokens as f64 / 1_000_000_000.0)
+    } else if tokens >= 1_000_000 {
+        format!("{:.1}M", tokens as f64 / 1_000_000.0)
+    } else if tokens >= 1_000 {
+        format!("{:.0}K", tokens as f64 / 1_000.0)
+    } else {
+        tokens.to_string()
+    }
+}
+
+fn group_digits(value: u64) -> String {
+    let digits = value.to_string();
+    let first_group = match digits.len() % 3 {
+        0 => 3,
+        n => n,
+    };
+    let mut grouped = digits[..first_group].to_string();
+    for group in digits.as_bytes()[first_group..].chunks(3) {
+        grouped.push(',');
+        grouped.push_str(std::str::from_utf8(group).unwrap_or_default());
+    }
+    grouped
+}
+
+pub fn usd(amount: f64, style: Style) -> String {
+    // Decide on cents after rounding to them, so $99.996 is "$100", not "$100.00".
+    let amount = (amount * 100.0).round() / 100.0;
+    if amount >= style.whole_dollars_from {
+        format!("${amount:.0}")
+    } else {
+        format!("${amount:.2}")
+    }
+}
+
+fn countdown(
+    resets_at: Option<SystemTime>,
+    now: SystemTime,
+    style: Style,
+    strings: Strings,
+) -> String {
+    let reset = match resets_at {
+        Some(t) => t,
+        None => return String::new(),
+    };
+
+    let remaining = match reset.duration_since(now) {
+        Ok(d) => d,
+        Err(_) => return strings.now.to_string(),
+    };
+
+    countdown_from_secs(remaining.as_secs(), style, strings)
+}
+
+/// Index range into `COUNTDOWN_UNITS` of the units shown for `total_secs`.
+fn shown_units(total_secs: u64, style: Style) -> (usize, usize) {
+    let smallest = COUNTDOWN_UNITS.len() - 1;
+    let first = COUNTDOWN_UNITS
+        .iter()
+        .position(|unit| total_secs >= *unit)
+        .unwrap_or(smallest);
+    let last = (first + style.countdown_units.max(1) - 1).min(smallest);
+    (first, last)
+}
+
+fn countdown_from_secs(total_secs: u64, style: Style, strings: Strings) -> String {
+    let suffixes = [
+        strings.day_suffix,
+        strings.hour_suffix,
+        strings.minute_suffix,
+        strings.second_suffix,
+    ];
+    let (first, last) = shown_units(total_secs, style);
+
+    let mut parts = vec![format!(
+        "{}{}",
+        total_secs / COUNTDOWN_UNITS[first],
+        suffixes[first]
+    )];
+    for i in first + 1..=last {
+        let value = total_secs % COUNTDOWN_UNITS[i - 1] / COUNTDOWN_UNITS[i];
+        if value > 0 {
+            parts.push(format!("{value}{}", suffixes[i]));
+        }
+    }
+    parts.join(" ")
+}
+
+/// Calculate how long until the display text would change
+pub fn time_until_display_change(resets_at: Option<SystemTime>, style: Style) -> Option<Duration> {
+    let reset = resets_at?;
+    let remaining = reset.duration_since(SystemTime::now()).ok()?;
+    Some(time_until_display_change_from_secs(
+        remaining.as_secs(),
+        style,
+    ))
+}
+
+fn time_until_display_change_from_secs(total_secs: u64, style: Style) -> Duration {
+    let (_, last) = shown_units(total_secs, style);
+    Duration::from_secs(total_secs % COUNTDOWN_UNITS[last] + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::localization::LanguageId;
+    use std::fmt::Write;
+    use std::path::PathBuf;
+    use std::time::UNIX_EPOCH;
+
+    const PERCENTAGES: &[f64] = &[0.0, 4.4, 4.5, 49.95, 100.0, 112.3];
+    const COUNTDOWN_SECS: &[u64] = &[
+        0, 59, 60, 3_599, 3_600, 5_400, 86_399, 86_400, 90_061, 604_800,
+    ];
+    const BURN_RATES: &[f64] = &[0.0, 2.44, 12.5];
+    const TOKENS: &[u64] = &[0, 999, 1_000, 1_499, 350_000, 1_234_567, 2_500_000_000];
+    const DOLLARS: &[f64] = &[0.0, 3.456, 99.994, 99.996, 123.4];
+
+    /// Every formatter applied to a fixed set of inputs, one per line.
+    fn render(style: Style) -> String {
+        let strings = LanguageId::English.strings();
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut out = String::new();
+
+        for &percentage in PERCENTAGES {
+            let section = UsageSection {
+                percentage,
+                resets_at: None,
+            };
+            let line = usage_line_at(&section, now, style, strings);
+            writeln!(out, "percent {percentage} => {line}").unwrap();
+        }
+        for &secs in COUNTDOWN_SECS {
+            let section = UsageSection {
+                percentage: 42.0,
+                resets_at: Some(now + Duration::from_secs(secs)),
+            };
+            let line = usage_line_at(&section, now, style, strings);
+            let change = time_until_display_change_from_secs(secs, style).as_secs();
+            writeln!(out, "countdown {secs}s => {line} (changes in {change}s)").unwrap();
+        }
+        let past = UsageSection {
+            percentage: 100.0,
+            resets_at: Some(now - Duration::from_secs(5)),
+        };
+        let line = usage_line_at(&past, now, style, strings);
+        writeln!(out, "countdown past => {line}").unwrap();
+        for &rate in BURN_RATES {
+            let section = UsageSection {
+                percentage: 42.0,
+                resets_at: None,
+            };
+            let line = usage_line_with_rate(&section, rate, style);
+            writeln!(out, "burn rate {rate} => {line}").unwrap();
+        }
+        for &tokens in TOKENS {
+            writeln!(out, "tokens {tokens} => {}", token_count(tokens, style)).unwrap();
+        }
+        for &amount in DOLLARS {
+            writeln!(out, "usd {amount} => {}", usd(amount, style)).unwrap();
+        }
+        out
+    }
+
+    /// Compare against `testdata/format/<name>.golden`. Run with
+    /// `UPDATE_GOLDEN=1` to rewrite the file after an intended change.
+    fn assert_golden(name: &str, actual: &str) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata")
+            .join("format")
+            .join(format!("{name}.golden"));
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(&path, actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("unable to read {}: {e}", path.display()));
+        assert_eq!(
+            expected.replace("\r\n", "\n"),
+            actual,
+            "{} is out of date",
+            path.display()
+        );
+    }
+
+    #[test]
+    fn widget_style_matches_golden() {
+        assert_golden("widget", &render(Style::WIDGET));
+    }
+
+    #[test]
+    fn detail_style_matches_golden() {
+        assert_golden("detail", &render(Style::DETAIL));
+    }
+
+    #[test]
+    fn full_style_matches_golden() {
+        let style = Style {
+            percent_decimals: 1,
+            countdown_units: 4,
+            compact_tokens: false,
+            whole_dollars_from: f64::INFINITY,
+        };
+        assert_golden("full", &render(style));
+    }
+
+    #[test]
+    fn typed_durations_default_to_seconds() {
+        assert_eq!(parse_duration("45"), Some(Duration::from_secs(45)));
+        assert_eq!(parse_duration(" 90s "), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("5 M"), Some(Duration::from_secs(300)));
+        assert_eq!(parse_duration("1h"), Some(Duration::from_secs(3_600)));
+        for text in ["", "m", "-5", "1.5m", "10 minutes", "5d"] {
+            assert_eq!(parse_duration(text), None, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn usd_amounts_drop_cents_once_large() {
+        assert_eq!(usd(3.456, Style::WIDGET), "$3.46");
+        assert_eq!(usd(123.4, Style::WIDGET), "$123");
+    }
+}
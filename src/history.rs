@@ -0,0 +1,1135 @@
+use std::fmt::Write as _;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard, Once};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::diagnose;
+use crate::models::{AppUsageData, UsageData};
+use crate::paths;
+use crate::transcripts::{self, TranscriptUsage};
+
+/// Samples older than this are dropped when the history file is loaded.
+const RETENTION_SECS: u64 = 35 * 86_400;
+
+/// Burn rate looks at samples from this far back...
+const BURN_RATE_WINDOW_SECS: u64 = 60 * 60;
+/// ...and needs at least this much time between the first and last sample.
+const BURN_RATE_MIN_SPAN_SECS: u64 = 10 * 60;
+
+/// The weekly pace is averaged over a day, so a night off or a busy hour does
+/// not swing the projection...
+const WEEKLY_RATE_WINDOW_SECS: u64 = 24 * 3600;
+/// ...once the samples span at least this long.
+const WEEKLY_RATE_MIN_SPAN_SECS: u64 = 3 * 3600;
+
+/// Polls further apart than this are not compared for usage jumps; a rise
+/// across a long sleep is not a spike.
+const JUMP_MAX_GAP_SECS: u64 = 60 * 60;
+
+pub const WEEK_DAYS: usize = 7;
+const WEEK_SECS: u64 = WEEK_DAYS as u64 * 86_400;
+pub const DAY_HOURS: usize = 24;
+
+/// Weeks of history the hour-of-week heatmap is built from.
+const HEATMAP_WEEKS: u64 = 4;
+
+/// A reset time moving by less than this between polls is drift in the
+/// reported value, not a new window.
+const RESET_TOLERANCE_SECS: u64 = 30 * 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Provider {
+    ClaudeCode,
+    Codex,
+    Antigravity,
+}
+
+/// One successful poll for one provider, stored as a line of history.jsonl.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Sample {
+    pub ts: u64,
+    pub provider: Provider,
+    pub session: f64,
+    pub weekly: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_resets_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weekly_resets_at: Option<u64>,
+    /// Worked out from transcript tokens by the history import rather than
+    /// polled.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub estimated: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageWindow {
+    Session,
+    Weekly,
+}
+
+impl UsageWindow {
+    pub const ALL: [UsageWindow; 2] = [UsageWindow::Session, UsageWindow::Weekly];
+
+    /// How long the window runs before it resets.
+    pub fn length(self) -> Duration {
+        match self {
+            UsageWindow::Session => Duration::from_secs(5 * 3_600),
+            UsageWindow::Weekly => Duration::from_secs(7 * 86_400),
+        }
+    }
+}
+
+/// A rise in utilization between two consecutive polls of one provider.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UsageJump {
+    pub provider: Provider,
+    pub window: UsageWindow,
+    pub from: f64,
+    pub to: f64,
+    pub secs: u64,
+}
+
+/// A window rolling over into a new one, seen as its reset time moving on or
+/// its utilization dropping between two polls.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UsageReset {
+    pub provider: Provider,
+    pub window: UsageWindow,
+    /// When the old window ended, unix seconds.
+    pub at: u64,
+    /// Utilization the old window ended on.
+    pub from: f64,
+}
+
+/// Where the 7d window is headed at the recent weekly pace.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WeeklyProjection {
+    /// The limit is reached at this time (unix seconds), before the reset.
+    LimitAt(u64),
+    /// The window resets with about this percentage used.
+    AtReset(f64),
+}
+
+/// Tokens used in one hour, imported from Claude Code session transcripts.
+/// Stored as a line of activity.jsonl.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ActivityBucket {
+    /// Start of the hour, unix seconds.
+    pub hour: u64,
+    pub provider: Provider,
+    pub tokens: u64,
+}
+
+/// In-memory copy of the history file, loaded on first use.
+static HISTORY: Mutex<Option<Vec<Sample>>> = Mutex::new(None);
+
+/// In-memory copy of the activity file, loaded on first use. None until
+/// transcript activity has been imported.
+static ACTIVITY: Mutex<Option<Vec<ActivityBucket>>> = Mutex::new(None);
+static ACTIVITY_LOADED: Once = Once::new();
+
+/// Serialises transcript imports so a backfill and a poll never interleave.
+static ACTIVITY_IMPORT: Mutex<()> = Mutex::new(());
+
+/// Set when the history import ran before any poll was recorded. Usage
+/// samples are then estimated once the first poll gives a scale. Saved with
+/// the settings, so an exit before that poll does not lose the estimate.
+static ESTIMATE_PENDING: AtomicBool = AtomicBool::new(false);
+
+fn history_path() -> PathBuf {
+    paths::data_dir().join("history.jsonl")
+}
+
+fn activity_path() -> PathBuf {
+    paths::data_dir().join("activity.jsonl")
+}
+
+fn now_unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn to_unix_secs(time: Option<SystemTime>) -> Option<u64> {
+    time?
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|duration| duration.as_secs())
+}
+
+fn lock_history() -> MutexGuard<'static, Option<Vec<Sample>>> {
+    let mut history = HISTORY.lock().unwrap_or_else(|e| e.into_inner());
+    if history.is_none() {
+        *history = Some(load());
+    }
+    history
+}
+
+/// Read the history file, dropping unparsable lines and expired samples.
+/// The file is rewritten when anything was dropped so it does not grow forever.
+fn load() -> Vec<Sample> {
+    let path = history_path();
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+
+    let cutoff = now_unix_secs().saturating_sub(RETENTION_SECS);
+    let total_lines = content.lines().filter(|line| !line.is_empty()).count();
+    let samples: Vec<Sample> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Sample>(line).ok())
+        .filter(|sample| sample.ts >= cutoff)
+        .collect();
+
+    if samples.len() != total_lines {
+        rewrite(&samples);
+    }
+    samples
+}
+
+fn rewrite(samples: &[Sample]) {
+    let mut content = String::new();
+    for sample in samples {
+        if let Ok(line) = serde_json::to_string(sample) {
+            content.push_str(&line);
+            content.push('\n');
+        }
+    }
+    if let Err(error) = std::fs::write(history_path(), content) {
+        diagnose::log_error("unable to rewrite usage history", error);
+    }
+}
+
+fn append(samples: &[Sample]) {
+    let path = history_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+
+    let file = OpenOptions::new().create(true).append(true).open(&path);
+    match file {
+        Ok(mut file) => {
+            for sample in samples {
+                if let Ok(line) = serde_json::to_string(sample) {
+                    let _ = writeln!(file, "{line}");
+                }
+            }
+        }
+        Err(error) => diagnose::log_error("unable to append usage history", error),
+    }
+}
+
+/// One sample per provider present in a successful poll. Per-minute API-key
+/// buckets are left out: they are not 5h and 7d usage, and would read as a
+/// reset on nearly every poll.
+fn samples_from(data: &AppUsageData, ts: u64) -> Vec<Sample> {
+    let sample = |provider: Provider, usage: &UsageData| Sample {
+        ts,
+        provider,
+        session: usage.session.percentage,
+        weekly: usage.weekly.percentage,
+        session_resets_at: to_unix_secs(usage.session.resets_at),
+        weekly_resets_at: to_unix_secs(usage.weekly.resets_at),
+        estimated: false,
+    };
+
+    [
+        (Provider::ClaudeCode, data.claude_code.as_ref()),
+        (Provider::Codex, data.codex.as_ref()),
+        (Provider::Antigravity, data.antigravity.as_ref()),
+    ]
+    .into_iter()
+    .filter_map(|(provider, usage)| {
+        usage
+            .filter(|usage| !usage.per_minute)
+            .map(|usage| sample(provider, usage))
+    })
+    .collect()
+}
+
+/// Append one sample per provider present in a successful poll.
+pub fn record(data: &AppUsageData) {
+    let samples = samples_from(data, now_unix_secs());
+    if samples.is_empty() {
+        return;
+    }
+
+    // Load before appending so the new samples are not read back twice.
+    let mut history = lock_history();
+    append(&samples);
+    let has_claude_code = samples
+        .iter()
+        .any(|sample| sample.provider == Provider::ClaudeCode);
+    if let Some(history) = history.as_mut() {
+        history.extend(samples);
+    }
+    drop(history);
+
+    if has_claude_code && ESTIMATE_PENDING.swap(false, Ordering::Relaxed) {
+        let since = now_unix_secs().saturating_sub(RETENTION_SECS);
+        estimate_samples(&transcripts::read_usage_since(since));
+    }
+}
+
+/// Rises of at least `threshold` percentage points since each provider's
+/// previous poll. Call before `record` so the previous poll is still the
+/// latest sample.
+pub fn detect_jumps(data: &AppUsageData, threshold: f64) -> Vec<UsageJump> {
+    let history = lock_history();
+    let Some(history) = history.as_ref() else {
+        return Vec::new();
+    };
+    samples_from(data, now_unix_secs())
+        .iter()
+        .flat_map(|next| {
+            history
+                .iter()
+                .rev()
+                .find(|sample| sample.provider == next.provider)
+                .map(|previous| jumps_between(previous, next, threshold))
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn jumps_between(previous: &Sample, next: &Sample, threshold: f64) -> Vec<UsageJump> {
+    let secs = next.ts.saturating_sub(previous.ts);
+    if secs > JUMP_MAX_GAP_SECS {
+        return Vec::new();
+    }
+    [
+        (UsageWindow::Session, previous.session, next.session),
+        (UsageWindow::Weekly, previous.weekly, next.weekly),
+    ]
+    .into_iter()
+    .filter(|(_, from, to)| to - from >= threshold)
+    .map(|(window, from, to)| UsageJump {
+        provider: next.provider,
+        window,
+        from,
+        to,
+        secs,
+    })
+    .collect()
+}
+
+/// Windows that reset since each provider's previous poll. Call before
+/// `record` so the previous poll is still the latest sample.
+pub fn detect_resets(data: &AppUsageData) -> Vec<UsageReset> {
+    let history = lock_history();
+    let Some(history) = history.as_ref() else {
+        return Vec::new();
+    };
+    samples_from(data, now_unix_secs())
+        .iter()
+        .flat_map(|next| {
+            history
+                .iter()
+                .rev()
+                .find(|sample| sample.provider == next.provider)
+                .map(|previous| resets_between(previous, next))
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// The last `limit` resets of either window of one provider in the kept
+/// history, newest first.
+pub fn recent_resets(provider: Provider, limit: usize) -> Vec<UsageReset> {
+    let samples = samples(provider, 0);
+    let mut resets: Vec<UsageReset> = samples
+        .windows(2)
+        .flat_map(|pair| resets_between(&pair[0], &pair[1]))
+        .collect();
+    resets.sort_by_key(|reset| std::cmp::Reverse(reset.at));
+    resets.truncate(limit);
+    resets
+}
+
+fn resets_between(previous: &Sample, next: &Sample) -> Vec<UsageReset> {
+    [
+        (
+            UsageWindow::Session,
+            previous.session,
+            next.session,
+            previous.session_resets_at,
+            next.session_resets_at,
+        ),
+        (
+            UsageWindow::Weekly,
+            previous.weekly,
+            next.weekly,
+            previous.weekly_resets_at,
+            next.weekly_resets_at,
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(window, from, to, old_reset, new_reset)| {
+        let moved_on = match (old_reset, new_reset) {
+            (Some(old), Some(new)) => new > old + RESET_TOLERANCE_SECS,
+            _ => false,
+        };
+        // An idle window can expire without a new reset time until it is
+        // used again, so a drop counts too.
+        let dropped = to + 0.5 < from;
+        (moved_on || dropped).then(|| UsageReset {
+            provider: next.provider,
+            window,
+            // A window can also be reset early, before the time it reported.
+            at: old_reset.map_or(next.ts, |old| old.min(next.ts)),
+            from,
+        })
+    })
+    .collect()
+}
+
+/// Samples for one provider at or after `since` (unix seconds), oldest first.
+pub fn samples(provider: Provider, since: u64) -> Vec<Sample> {
+    let history = lock_history();
+    history
+        .as_ref()
+        .map(|history| {
+            history
+                .iter()
+                .filter(|sample| sample.provider == provider && sample.ts >= since)
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Every retained sample for all providers, oldest first.
+pub fn all_samples() -> Vec<Sample> {
+    lock_history().clone().unwrap_or_default()
+}
+
+/// Samples as CSV, one row per provider per poll. Times are UTC ISO 8601.
+pub fn samples_to_csv(samples: &[Sample]) -> String {
+    let mut csv = String::from(
+        "timestamp,provider,session_percent,weekly_percent,session_resets_at,weekly_resets_at\n",
+    );
+    for sample in samples {
+        let provider = match sample.provider {
+            Provider::ClaudeCode => "claude_code",
+            Provider::Codex => "codex",
+            Provider::Antigravity => "antigravity",
+        };
+        let _ = writeln!(
+            csv,
+            "{},{provider},{},{},{},{}",
+            format_iso8601(sample.ts),
+            sample.session,
+            sample.weekly,
+            sample
+                .session_resets_at
+                .map(format_iso8601)
+                .unwrap_or_default(),
+            sample
+                .weekly_resets_at
+                .map(format_iso8601)
+                .unwrap_or_default(),
+        );
+    }
+    csv
+}
+
+/// Samples as a pretty-printed JSON array in the history file's own shape.
+pub fn samples_to_json(samples: &[Sample]) -> String {
+    serde_json::to_string_pretty(samples).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// "YYYY-MM-DDTHH:MM:SSZ" for unix seconds.
+fn format_iso8601(secs: u64) -> String {
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let time = secs % 86_400;
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+/// "MM-DD HH:MM" in local time for unix seconds, given the local time zone's
+/// offset from UTC.
+pub fn format_local_short(secs: u64, utc_offset_secs: i64) -> String {
+    let local = secs as i64 + utc_offset_secs;
+    let (_, month, day) = civil_from_days(local.div_euclid(86_400));
+    format!(
+        "{month:02}-{day:02} {}",
+        format_local_time(secs, utc_offset_secs)
+    )
+}
+
+/// "HH:MM" in local time for unix seconds.
+pub fn format_local_time(secs: u64, utc_offset_secs: i64) -> String {
+    let time = (secs as i64 + utc_offset_secs).rem_euclid(86_400);
+    format!("{:02}:{:02}", time / 3600, time % 3600 / 60)
+}
+
+/// Local weekday for unix seconds, Monday being 0.
+pub fn local_weekday(secs: u64, utc_offset_secs: i64) -> usize {
+    let local = secs as i64 + utc_offset_secs;
+    // 1970-01-01 was a Thursday.
+    (local.div_euclid(86_400) + 3).rem_euclid(WEEK_DAYS as i64) as usize
+}
+
+/// (year, month, day) for days since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    // Civil-from-days (Howard Hinnant), valid for any date after 1970.
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// The activity file's buckets, or None when there is no file yet.
+fn load_activity() -> Option<Vec<ActivityBucket>> {
+    let content = std::fs::read_to_string(activity_path()).ok()?;
+    Some(
+        content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect(),
+    )
+}
+
+fn lock_activity() -> MutexGuard<'static, Option<Vec<ActivityBucket>>> {
+    let mut activity = ACTIVITY.lock().unwrap_or_else(|e| e.into_inner());
+    ACTIVITY_LOADED.call_once(|| *activity = load_activity());
+    activity
+}
+
+fn save_activity(buckets: &[ActivityBucket]) {
+    let mut content = String::new();
+    for bucket in buckets {
+        if let Ok(line) = serde_json::to_string(bucket) {
+            content.push_str(&line);
+            content.push('\n');
+        }
+    }
+    let path = activity_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Err(error) = std::fs::write(path, content) {
+        diagnose::log_error("unable to write transcript activity", error);
+    }
+}
+
+/// Replace the hourly buckets from the hour of `since` onwards with those of
+/// `usage`, the responses read from that hour on.
+fn import_activity(since: u64, usage: &[TranscriptUsage]) {
+    let since = since - since % 3600;
+    let cutoff = now_unix_secs().saturating_sub(RETENTION_SECS);
+    let mut activity = lock_activity();
+    let mut buckets: Vec<ActivityBucket> = activity
+        .take()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|bucket| bucket.hour >= cutoff && bucket.hour < since)
+        .collect();
+    for entry in usage.iter().filter(|entry| entry.timestamp >= since) {
+        let hour = entry.timestamp - entry.timestamp % 3600;
+        match buckets.last_mut() {
+            Some(bucket) if bucket.hour == hour => bucket.tokens += entry.total_tokens(),
+            _ => buckets.push(ActivityBucket {
+                hour,
+                provider: Provider::ClaudeCode,
+                tokens: entry.total_tokens(),
+            }),
+        }
+    }
+
+    save_activity(&buckets);
+    *activity = Some(buckets);
+}
+
+/// True while usage samples wait to be estimated from imported transcripts.
+pub fn estimate_pending() -> bool {
+    ESTIMATE_PENDING.load(Ordering::Relaxed)
+}
+
+/// Carry a pending estimate over from the last run.
+pub fn resume_pending_estimate() {
+    ESTIMATE_PENDING.store(true, Ordering::Relaxed);
+}
+
+/// True once transcript activity has been imported at least once.
+pub fn has_activity() -> bool {
+    lock_activity().is_some()
+}
+
+/// One-time import of past Claude Code transcripts, so history views start
+/// with the last few weeks of activity instead of empty. Besides the hourly
+/// token totals, usage samples are estimated for the time before the first
+/// poll, scaled by the latest poll.
+pub fn backfill_from_transcripts() {
+    let _guard = ACTIVITY_IMPORT.lock().unwrap_or_else(|e| e.into_inner());
+    let since = now_unix_secs().saturating_sub(RETENTION_SECS);
+    let usage = transcripts::read_usage_since(since);
+    import_activity(since, &usage);
+    diagnose::log(format!(
+        "imported {} responses from Claude Code transcripts",
+        usage.len()
+    ));
+
+    let polled = lock_history()
+        .as_ref()
+        .is_some_and(|history| history.iter().any(|s| s.provider == Provider::ClaudeCode));
+    if polled {
+        estimate_samples(&usage);
+    } else {
+        ESTIMATE_PENDING.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Pick up transcript activity written since the last import. Only the most
+/// recent hour onwards is rebuilt, from what the last transcript refresh
+/// read.
+pub fn sync_activity() {
+    let _guard = ACTIVITY_IMPORT.lock().unwrap_or_else(|e| e.into_inner());
+    let since = match lock_activity().as_ref() {
+        Some(buckets) => buckets
+            .last()
+            .map(|bucket| bucket.hour)
+            .unwrap_or_else(|| now_unix_secs().saturating_sub(RETENTION_SECS)),
+        None => return,
+    };
+    import_activity(since, &transcripts::usage_since(since));
+}
+
+/// Total imported tokens for one provider at or after `since` (unix seconds).
+pub fn activity_tokens(provider: Provider, since: u64) -> Option<u64> {
+    let since_hour = since - since % 3600;
+    let activity = lock_activity();
+    Some(
+        activity
+            .as_ref()?
+            .iter()
+            .filter(|bucket| bucket.provider == provider && bucket.hour >= since_hour)
+            .map(|bucket| bucket.tokens)
+            .sum(),
+    )
+}
+
+/// Add hourly Claude Code samples estimated from `usage` for the time before
+/// the first recorded poll.
+fn estimate_samples(usage: &[TranscriptUsage]) {
+    let mut history = lock_history();
+    let Some(history) = history.as_mut() else {
+        return;
+    };
+    let polled: Vec<&Sample> = history
+        .iter()
+        .filter(|sample| sample.provider == Provider::ClaudeCode && !sample.estimated)
+        .collect();
+    let (Some(first), Some(latest)) = (polled.first(), polled.last()) else {
+        return;
+    };
+    let estimated = estimated_samples(usage, latest, first.ts);
+    if estimated.is_empty() {
+        diagnose::log("too little recent transcript activity to estimate past usage");
+        return;
+    }
+    diagnose::log(format!(
+        "estimated {} usage samples from transcripts",
+        estimated.len()
+    ));
+    history.retain(|sample| !sample.estimated);
+    history.extend(estimated);
+    history.sort_by_key(|sample| sample.ts);
+    rewrite(history);
+}
+
+/// Hourly samples from the first response in `usage` (oldest first) up to
+/// `until`. Each window is put at the tokens used in it so far, times the
+/// percentage per token `latest` shows for its own window. The 7d windows
+/// follow `latest`'s reset time back a week at a time; a 5h window starts at
+/// the hour of the first response after the previous one ended.
+fn estimated_samples(usage: &[TranscriptUsage], latest: &Sample, until: u64) -> Vec<Sample> {
+    let mut cumulative = Vec::with_capacity(usage.len() + 1);
+    cumulative.push(0);
+    for entry in usage {
+        cumulative.push(cumulative[cumulative.len() - 1] + entry.total_tokens());
+    }
+    let tokens_between = |start: u64, end: u64| {
+        let from = usage.partition_point(|entry| entry.timestamp < start);
+        let to = usage.partition_point(|entry| entry.timestamp < end);
+        cumulative[to.max(from)] - cumulative[from]
+    };
+    let per_token = |percent: f64, resets_at: Option<u64>, window: UsageWindow| {
+        let start = resets_at
+            .filter(|&reset| reset > latest.ts)?
+            .checked_sub(window.length().as_secs())?;
+        let tokens = tokens_between(start, latest.ts + 1);
+        (tokens > 0 && percent > 0.0).then(|| percent / tokens as f64)
+    };
+    let percent =
+        |tokens: u64, rate: f64| ((tokens as f64 * rate).min(100.0) * 10.0).round() / 10.0;
+
+    let Some(weekly_reset) = latest.weekly_resets_at else {
+        return Vec::new();
+    };
+    let Some(weekly_rate) = per_token(latest.weekly, Some(weekly_reset), UsageWindow::Weekly)
+    else {
+        return Vec::new();
+    };
+    let session_rate = per_token(
+        latest.session,
+        latest.session_resets_at,
+        UsageWindow::Session,
+    );
+
+    let session_secs = UsageWindow::Session.length().as_secs();
+    let mut sessions: Vec<(u64, u64)> = Vec::new();
+    for entry in usage {
+        if sessions
+            .last()
+            .is_none_or(|&(_, end)| entry.timestamp >= end)
+        {
+            let start = entry.timestamp - entry.timestamp % 3600;
+            sessions.push((start, start + session_secs));
+        }
+    }
+
+    let Some(first) = usage.first() else {
+        return Vec::new();
+    };
+    let mut samples = Vec::new();
+    let mut ts = first.timestamp - first.timestamp % 3600;
+    while ts < until && ts < weekly_reset {
+        let weekly_end = weekly_reset - (weekly_reset - ts - 1) / WEEK_SECS * WEEK_SECS;
+        let session = session_rate.and_then(|rate| {
+            sessions
+                .iter()
+                .rev()
+                .find(|&&(start, _)| start <= ts)
+                .filter(|&&(_, end)| ts < end)
+                .map(|&(start, end)| (percent(tokens_between(start, ts), rate), end))
+        });
+        samples.push(Sample {
+            ts,
+            provider: Provider::ClaudeCode,
+            session: session.map_or(0.0, |(percent, _)| percent),
+            weekly: percent(tokens_between(weekly_end - WEEK_SECS, ts), weekly_rate),
+            session_resets_at: session.map(|(_, end)| end),
+            weekly_resets_at: Some(weekly_end),
+            estimated: true,
+        });
+        ts += 3600;
+    }
+    samples
+}
+
+/// Weekly percentage points added on each day of the current 7d window,
+/// oldest day first. Days are counted from the start of the window (the next
+/// weekly reset minus seven days), or cover the last seven days when the reset
+/// time is unknown.
+pub fn weekly_day_breakdown(provider: Provider) -> Option<[f64; WEEK_DAYS]> {
+    let now = now_unix_secs();
+    let recent = samples(provider, now.saturating_sub(2 * WEEK_SECS));
+    let window_start = recent
+        .last()?
+        .weekly_resets_at
+        .filter(|&reset| reset > now)
+        .map(|reset| reset.saturating_sub(WEEK_SECS))
+        .unwrap_or_else(|| now.saturating_sub(WEEK_SECS));
+    day_breakdown(&recent, window_start)
+}
+
+/// Spread weekly increases across the days they were observed on. A drop in
+/// the weekly percentage is a reset, so the new value counts in full.
+fn day_breakdown(samples: &[Sample], window_start: u64) -> Option<[f64; WEEK_DAYS]> {
+    let mut days = [0.0; WEEK_DAYS];
+    let mut observed = false;
+    for pair in samples.windows(2) {
+        let (previous, current) = (&pair[0], &pair[1]);
+        if current.ts < window_start {
+            continue;
+        }
+        let delta = if current.weekly + 0.5 < previous.weekly {
+            current.weekly
+        } else {
+            (current.weekly - previous.weekly).max(0.0)
+        };
+        let day = ((current.ts - window_start) / 86_400).min(WEEK_DAYS as u64 - 1) as usize;
+        days[day] += delta;
+        observed = true;
+    }
+    observed.then_some(days)
+}
+
+/// 5h percentage points added in each hour of the week over the last four
+/// weeks, by local weekday (Monday first) and hour. `utc_offset_secs` is the
+/// local time zone's offset from UTC.
+pub fn hourly_heatmap(
+    provider: Provider,
+    utc_offset_secs: i64,
+) -> Option<[[f64; DAY_HOURS]; WEEK_DAYS]> {
+    let now = now_unix_secs();
+    let recent = samples(provider, now.saturating_sub(HEATMAP_WEEKS * WEEK_SECS));
+    hour_breakdown(&recent, utc_offset_secs)
+}
+
+/// Spread session increases across the local hours they were observed in. A
+/// drop is a reset, so the new value counts in full; a rise across a gap
+/// longer than an hour cannot be placed and is skipped.
+fn hour_breakdown(
+    samples: &[Sample],
+    utc_offset_secs: i64,
+) -> Option<[[f64; DAY_HOURS]; WEEK_DAYS]> {
+    let mut cells = [[0.0; DAY_HOURS]; WEEK_DAYS];
+    let mut observed = false;
+    for pair in samples.windows(2) {
+        let (previous, current) = (&pair[0], &pair[1]);
+        if current.ts.saturating_sub(previous.ts) > JUMP_MAX_GAP_SECS {
+            continue;
+        }
+        let delta = if current.session + 0.5 < previous.session {
+            current.session
+        } else {
+            (current.session - previous.session).max(0.0)
+        };
+        let weekday = local_weekday(current.ts, utc_offset_secs);
+        let hour = ((current.ts as i64 + utc_offset_secs).rem_euclid(86_400) / 3600) as usize;
+        cells[weekday][hour] += delta;
+        observed = true;
+    }
+    observed.then_some(cells)
+}
+
+/// Percentage points of the 5h window consumed per hour at the recent pace.
+pub fn session_burn_rate(provider: Provider) -> Option<f64> {
+    let now = now_unix_secs();
+    let recent = samples(provider, now.saturating_sub(BURN_RATE_WINDOW_SECS));
+    burn_rate_per_hour(&recent)
+}
+
+/// Rate of change of the session percentage across `samples` (oldest first).
+fn burn_rate_per_hour(samples: &[Sample]) -> Option<f64> {
+    rate_per_hour(samples, BURN_RATE_MIN_SPAN_SECS, |sample| sample.session)
+}
+
+/// Rate of change of `value` across `samples` (oldest first), if they span
+/// at least `min_span` seconds. Only samples after the most recent reset (a
+/// drop in the value) count.
+fn rate_per_hour(samples: &[Sample], min_span: u64, value: fn(&Sample) -> f64) -> Option<f64> {
+    let since_reset = samples
+        .windows(2)
+        .rposition(|pair| value(&pair[1]) + 0.5 < value(&pair[0]))
+        .map(|index| &samples[index + 1..])
+        .unwrap_or(samples);
+
+    let first = since_reset.first()?;
+    let last = since_reset.last()?;
+    let span = last.ts.saturating_sub(first.ts);
+    if span < min_span {
+        return None;
+    }
+
+    let delta = (value(last) - value(first)).max(0.0);
+    Some(delta * 3600.0 / span as f64)
+}
+
+/// Whether the 7d window reaches its limit before it resets, at the weekly
+/// pace of the last day.
+pub fn weekly_projection(provider: Provider) -> Option<WeeklyProjection> {
+    let now = now_unix_secs();
+    let recent = samples(provider, now.saturating_sub(WEEKLY_RATE_WINDOW_SECS));
+    let latest = recent.last()?;
+    let rate = rate_per_hour(&recent, WEEKLY_RATE_MIN_SPAN_SECS, |sample| sample.weekly)?;
+    project(latest.weekly, rate, now, latest.weekly_resets_at?)
+}
+
+fn project(percent: f64, rate_per_hour: f64, now: u64, resets_at: u64) -> Option<WeeklyProjection> {
+    if percent >= 100.0 || resets_at <= now {
+        return None;
+    }
+    let hours_left = (resets_at - now) as f64 / 3600.0;
+    let at_reset = percent + rate_per_hour * hours_left;
+    if at_reset < 100.0 {
+        return Some(WeeklyProjection::AtReset(at_reset));
+    }
+    let hours_to_limit = (100.0 - percent) / rate_per_hour;
+    Some(WeeklyProjection::LimitAt(
+        now + (hours_to_limit * 3600.0) as u64,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(ts: u64, session: f64) -> Sample {
+        Sample {
+            ts,
+            provider: Provider::ClaudeCode,
+            session,
+            weekly: 0.0,
+            session_resets_at: None,
+            weekly_resets_at: None,
+            estimated: false,
+        }
+    }
+
+    fn weekly_sample(ts: u64, weekly: f64) -> Sample {
+        Sample {
+            weekly,
+            ..sample(ts, 0.0)
+        }
+    }
+
+    #[test]
+    fn api_key_polls_are_not_recorded_as_plan_usage() {
+        let per_minute = UsageData {
+            session: crate::models::UsageSection {
+                percentage: 3.0,
+                resets_at: None,
+            },
+            per_minute: true,
+            ..Default::default()
+        };
+        let data = AppUsageData {
+            claude_code: Some(per_minute),
+            ..Default::default()
+        };
+        // record, detect_jumps and detect_resets all work from these samples,
+        // so an API-key poll neither records a sample nor reports a reset.
+        assert!(samples_from(&data, 1_000).is_empty());
+
+        // The same figures from the 5h window would read as a reset.
+        let mut plan = data.clone();
+        plan.claude_code.as_mut().unwrap().per_minute = false;
+        let samples = samples_from(&plan, 1_000);
+        assert_eq!(samples.len(), 1);
+        assert!(!resets_between(&sample(940, 80.0), &samples[0]).is_empty());
+    }
+
+    #[test]
+    fn burn_rate_is_percent_per_hour_over_the_sampled_span() {
+        let samples = [sample(0, 10.0), sample(900, 13.0), sample(1800, 21.0)];
+
+        let rate = burn_rate_per_hour(&samples).expect("span is long enough");
+
+        assert!((rate - 22.0).abs() < 0.000001);
+    }
+
+    #[test]
+    fn burn_rate_ignores_samples_from_before_a_reset() {
+        let samples = [sample(0, 90.0), sample(600, 2.0), sample(900, 4.0)];
+
+        assert!(burn_rate_per_hour(&samples).is_none());
+        assert!(burn_rate_per_hour(&samples[..1]).is_none());
+    }
+
+    #[test]
+    fn day_breakdown_attributes_weekly_increases_to_the_day_they_happened() {
+        let start = 1_000_000;
+        let samples = [
+            weekly_sample(start - 3600, 40.0),
+            weekly_sample(start + 3600, 42.0),
+            weekly_sample(start + 2 * 86_400, 50.0),
+            weekly_sample(start + 2 * 86_400 + 7200, 53.0),
+            weekly_sample(start + 6 * 86_400, 55.0),
+        ];
+
+        let days = day_breakdown(&samples, start).expect("samples fall in the window");
+
+        assert_eq!(days, [2.0, 0.0, 11.0, 0.0, 0.0, 0.0, 2.0]);
+    }
+
+    #[test]
+    fn day_breakdown_counts_the_new_value_after_a_weekly_reset() {
+        let samples = [weekly_sample(0, 90.0), weekly_sample(3600, 4.0)];
+
+        let days = day_breakdown(&samples, 0).expect("one pair in the window");
+
+        assert_eq!(days[0], 4.0);
+        assert!(day_breakdown(&samples[..1], 0).is_none());
+    }
+
+    #[test]
+    fn weekly_projection_finds_when_the_pace_reaches_the_limit() {
+        // 60% used, 2%/h, reset in 30h: the limit comes after 20h.
+        assert_eq!(
+            project(60.0, 2.0, 0, 30 * 3600),
+            Some(WeeklyProjection::LimitAt(20 * 3600))
+        );
+        // At 1%/h the window resets with 90% used.
+        assert_eq!(
+            project(60.0, 1.0, 0, 30 * 3600),
+            Some(WeeklyProjection::AtReset(90.0))
+        );
+        assert_eq!(project(100.0, 1.0, 0, 30 * 3600), None);
+    }
+
+    #[test]
+    fn a_reset_time_moving_on_is_a_reset_at_the_old_time() {
+        let previous = Sample {
+            session_resets_at: Some(18_000),
+            weekly_resets_at: Some(600_000),
+            ..sample(17_000, 80.0)
+        };
+        let next = Sample {
+            // Drift in the weekly reset time is not a reset.
+            session_resets_at: Some(36_000),
+            weekly_resets_at: Some(600_060),
+            ..sample(18_300, 2.0)
+        };
+
+        assert_eq!(
+            resets_between(&previous, &next),
+            vec![UsageReset {
+                provider: Provider::ClaudeCode,
+                window: UsageWindow::Session,
+                at: 18_000,
+                from: 80.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn a_drop_without_reset_times_is_a_reset_when_seen() {
+        let resets = resets_between(&sample(0, 40.0), &sample(600, 0.0));
+
+        assert_eq!(resets.len(), 1);
+        assert_eq!(resets[0].at, 600);
+        assert!(resets_between(&sample(0, 40.0), &sample(600, 39.8)).is_empty());
+    }
+
+    #[test]
+    fn local_short_format_applies_the_offset() {
+        // 2024-03-01T23:30:00Z
+        assert_eq!(format_local_short(1_709_335_800, 0), "03-01 23:30");
+        assert_eq!(format_local_short(1_709_335_800, 3600), "03-02 00:30");
+    }
+
+    #[test]
+    fn hour_breakdown_places_increases_in_local_weekday_hours() {
+        // 1970-01-05 09:00 UTC was a Monday.
+        let monday_nine = 4 * 86_400 + 9 * 3600;
+        let samples = [
+            sample(monday_nine - 600, 10.0),
+            sample(monday_nine, 16.0),
+            // Hours apart: not placed.
+            sample(monday_nine + 4 * 3600, 40.0),
+        ];
+
+        let cells = hour_breakdown(&samples, 0).expect("one pair close enough");
+        assert_eq!(cells[0][9], 6.0);
+        assert_eq!(cells.iter().flatten().sum::<f64>(), 6.0);
+
+        // Ten hours behind UTC, it was still Sunday evening.
+        let cells = hour_breakdown(&samples, -10 * 3600).unwrap();
+        assert_eq!(cells[6][23], 6.0);
+    }
+
+    #[test]
+    fn jumps_are_reported_per_window_once_over_the_threshold() {
+        let previous = Sample {
+            weekly: 20.0,
+            ..sample(0, 10.0)
+        };
+        let next = Sample {
+            weekly: 35.0,
+            ..sample(60, 45.0)
+        };
+
+        let jumps = jumps_between(&previous, &next, 30.0);
+
+        assert_eq!(
+            jumps,
+            vec![UsageJump {
+                provider: Provider::ClaudeCode,
+                window: UsageWindow::Session,
+                from: 10.0,
+                to: 45.0,
+                secs: 60,
+            }]
+        );
+        assert_eq!(jumps_between(&previous, &next, 10.0).len(), 2);
+    }
+
+    #[test]
+    fn csv_export_has_one_row_per_sample_with_utc_times() {
+        let samples = [Sample {
+            session_resets_at: Some(1_772_704_800),
+            ..sample(1_772_690_400, 12.5)
+        }];
+
+        assert_eq!(
+            samples_to_csv(&samples),
+            "timestamp,provider,session_percent,weekly_percent,session_resets_at,weekly_resets_at\n\
+             2026-03-05T06:00:00Z,claude_code,12.5,0,2026-03-05T10:00:00Z,\n"
+        );
+    }
+
+    #[test]
+    fn iso8601_handles_leap_days_and_year_ends() {
+        assert_eq!(format_iso8601(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_iso8601(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_iso8601(1_767_225_599), "2025-12-31T23:59:59Z");
+    }
+
+    #[test]
+    fn jumps_ignore_drops_and_polls_far_apart() {
+        assert!(jumps_between(&sample(0, 90.0), &sample(60, 2.0), 30.0).is_empty());
+        assert!(jumps_between(&sample(0, 10.0), &sample(4 * 3600, 80.0), 30.0).is_empty());
+    }
+
+    fn response(timestamp: u64, tokens: u64) -> TranscriptUsage {
+        TranscriptUsage {
+            timestamp,
+            model: None,
+            input_tokens: tokens,
+            output_tokens: 0,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
+        }
+    }
+
+    #[test]
+    fn estimated_samples_scale_transcript_tokens_by_the_latest_poll() {
+        let week = 7 * 86_400;
+        // 1000 tokens two days ago, 1000 in the current 5h window.
+        let usage = [response(10 * 3600, 1000), response(58 * 3600, 1000)];
+        let latest = Sample {
+            session_resets_at: Some(62 * 3600),
+            weekly_resets_at: Some(week),
+            weekly: 40.0,
+            ..sample(59 * 3600, 10.0)
+        };
+
+        let samples = estimated_samples(&usage, &latest, 59 * 3600);
+
+        assert_eq!(samples.len(), 49);
+        assert!(samples.iter().all(|sample| sample.estimated));
+        assert_eq!(samples[0].ts, 10 * 3600);
+        assert_eq!(samples[0].weekly, 0.0);
+        // 40% for the 2000 tokens in the week: 20% per 1000.
+        assert_eq!(samples[1].weekly, 20.0);
+        // 10% for the 1000 tokens in the 5h window.
+        assert_eq!(samples[1].session, 10.0);
+        assert_eq!(samples[1].session_resets_at, Some(15 * 3600));
+        // That window ended five hours after it started.
+        assert_eq!(samples[5].session, 0.0);
+        assert_eq!(samples[5].session_resets_at, None);
+        assert_eq!(samples[48].weekly_resets_at, Some(week));
+    }
+}
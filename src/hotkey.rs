@@ -0,0 +1,90 @@
+use windows::Win32::Foundation::HWND;
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    RegisterHotKey, UnregisterHotKey, HOT_KEY_MODIFIERS, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT,
+    MOD_SHIFT, MOD_WIN,
+};
+
+/// A global key combination such as "Ctrl+Alt+U".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hotkey {
+    pub modifiers: HOT_KEY_MODIFIERS,
+    pub vk: u32,
+}
+
+impl Hotkey {
+    /// Parse "+"-separated modifiers followed by a letter, digit or F1-F24.
+    /// At least one modifier is required so a bare key is never swallowed
+    /// system-wide.
+    pub fn parse(text: &str) -> Option<Hotkey> {
+        let mut modifiers = HOT_KEY_MODIFIERS(0);
+        let mut vk = None;
+
+        for part in text.split('+').map(str::trim) {
+            if vk.is_some() {
+                return None;
+            }
+            match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => modifiers |= MOD_CONTROL,
+                "alt" => modifiers |= MOD_ALT,
+                "shift" => modifiers |= MOD_SHIFT,
+                "win" => modifiers |= MOD_WIN,
+                key => vk = Some(key_code(key)?),
+            }
+        }
+
+        if modifiers.0 == 0 {
+            return None;
+        }
+        Some(Hotkey { modifiers, vk: vk? })
+    }
+}
+
+fn key_code(key: &str) -> Option<u32> {
+    let bytes = key.as_bytes();
+    match bytes {
+        [c] if c.is_ascii_alphabetic() => Some(c.to_ascii_uppercase() as u32),
+        [c] if c.is_ascii_digit() => Some(*c as u32),
+        [b'f', ..] => {
+            let n: u32 = key[1..].parse().ok()?;
+            // VK_F1 is 0x70 and the function keys are contiguous up to VK_F24.
+            (1..=24).contains(&n).then(|| 0x70 + n - 1)
+        }
+        _ => None,
+    }
+}
+
+/// Register `hotkey` for `hwnd`, which then receives WM_HOTKEY with `id`.
+pub fn register(hwnd: HWND, id: i32, hotkey: Hotkey) -> windows::core::Result<()> {
+    unsafe { RegisterHotKey(hwnd, id, hotkey.modifiers | MOD_NOREPEAT, hotkey.vk) }
+}
+
+pub fn unregister(hwnd: HWND, id: i32) {
+    unsafe {
+        let _ = UnregisterHotKey(hwnd, id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_modifiers_and_key_in_any_case() {
+        let hotkey = Hotkey::parse("ctrl + Alt+u").unwrap();
+
+        assert_eq!(hotkey.modifiers, MOD_CONTROL | MOD_ALT);
+        assert_eq!(hotkey.vk, b'U' as u32);
+        assert_eq!(Hotkey::parse("Win+Shift+F12").unwrap().vk, 0x7B);
+        assert_eq!(Hotkey::parse("Control+7").unwrap().vk, b'7' as u32);
+    }
+
+    #[test]
+    fn rejects_bare_keys_and_unknown_names() {
+        assert_eq!(Hotkey::parse("U"), None);
+        assert_eq!(Hotkey::parse("Ctrl+Alt"), None);
+        assert_eq!(Hotkey::parse("Ctrl+F25"), None);
+        assert_eq!(Hotkey::parse("Ctrl+U+Alt"), None);
+        assert_eq!(Hotkey::parse("Hyper+U"), None);
+        assert_eq!(Hotkey::parse(""), None);
+    }
+}
@@ -5,18 +5,66 @@ pub(super) const UPDATE_VIA_WINGET_LABEL: &str = "Bijwerken via WinGet";
 pub(super) const STRINGS: Strings = Strings {
     window_title: "Claude Code Gebruiksmonitor",
     refresh: "Vernieuwen",
+    pause_polling: "Bijwerken pauzeren",
     update_frequency: "Updatefrequentie",
+    thirty_seconds: "30 seconden",
     one_minute: "1 minuut",
     five_minutes: "5 minuten",
     fifteen_minutes: "15 minuten",
+    thirty_minutes: "30 minuten",
     one_hour: "1 uur",
+    custom_interval: "Aangepast…",
+    custom_interval_prompt: "Bijwerken elke (minimaal 15 seconden, bijv. 45, 90s, 5m of 1h):",
+    custom_interval_invalid: "Voer een interval van minimaal 15 seconden in, zoals 45, 90s, 5m of 1h.",
+    ok: "OK",
+    cancel: "Annuleren",
+    smart_polling: "Slim (adaptief)",
     models: "Modellen",
     claude_code_model: "Claude Code",
     codex_model: "Codex",
     antigravity_model: "Antigravity",
     settings: "Instellingen",
     start_with_windows: "Opstarten met Windows",
+    start_with_windows_task: "Opstarten met Windows (Taakplanner)",
+    startup_task_failed: "Kan de opstarttaak van Taakplanner niet bijwerken",
     reset_position: "Positie herstellen",
+    reembed_in_taskbar: "Opnieuw in taakbalk plaatsen",
+    float_over_taskbar: "Boven taakbalk zweven",
+    restart_widget: "Widget opnieuw starten",
+    show_burn_rate: "Verbruikstempo tonen i.p.v. aftellen",
+    card_background: "Kaartachtergrond",
+    opacity: "Dekking",
+    show_divider: "Scheidingslijn tonen",
+    emphasize_high_usage: "Hoog gebruik benadrukken",
+    mirror_layout: "Indeling van rechts naar links",
+    one_decimal: "Eén decimaal",
+    bars_show_time_elapsed: "Balken tonen verstreken tijd",
+    show_row: "Rij {row} tonen",
+    blend_with_taskbar: "Laten opgaan in taakbalk",
+    weekly_first: "Weeklimiet bovenaan",
+    urgent_row_first: "Dringendste limiet bovenaan",
+    color_palette: "Kleurenpalet",
+    palette_standard: "Standaard",
+    palette_deuteranopia: "Deuteranopie (blauw/oranje)",
+    palette_protanopia: "Protanopie (blauw/oranje)",
+    left_click: "Linksklikken",
+    click_show_details: "Details weergeven",
+    click_open_usage_page: "Gebruikspagina openen",
+    open_usage_dashboard: "Gebruiksdashboard openen",
+    click_do_nothing: "Niets doen",
+    show_cost: "Geschatte kosten tonen",
+    show_token_counts: "Tokenaantallen tonen",
+    show_messages_left: "Resterende berichten tonen",
+    notify_usage_jumps: "Waarschuwen bij gebruikspieken",
+    notify_limit_resets: "Melden wanneer limieten resetten",
+    notify_usage_resets: "Melden wanneer gebruik reset",
+    weekly_reminder: "Herinnering wekelijkse reset",
+    reminder_off: "Uit",
+    reminder_hours_before: "{hours} uur ervoor",
+    quiet_when_fullscreen: "Stil bij volledig scherm",
+    pause_on_metered: "Pauzeren bij verbindingen met datalimiet",
+    poll_less_on_battery: "Minder vaak bijwerken bij batterijbesparing",
+    api_key_limits: "API-sleutellimieten tonen",
     language: "Taal",
     system_default: "Systeemstandaard",
     check_for_updates: "Controleren op updates",
@@ -32,9 +80,71 @@ pub(super) const STRINGS: Strings = Strings {
     update_prompt_now: "Versie {version} is beschikbaar. Wil je nu bijwerken?",
     exit: "Afsluiten",
     show_widget: "Widget tonen",
+    badge_mode: "Alleen taakbalkbadge",
     session_window: "5u",
     weekly_window: "7d",
+    opus_window: "7d Opus",
+    requests_window: "Vz",
+    tokens_window: "Tk",
     now: "nu",
+    paused: "Gepauzeerd",
+    none_left: "0 over",
+    burn_rate: "Verbruikstempo",
+    tokens_7d: "7d tokens",
+    estimated_cost: "Geschatte kosten",
+    tokens_today: "Tokens vandaag",
+    tokens_in: "in",
+    tokens_out: "uit",
+    tokens_cached: "gecachet",
+    plan: "Abonnement",
+    plan_session_messages: "~{count} berichten per {window}",
+    messages_left: "~{count} berichten over",
+    usage_by_hour: "Wanneer 5u-gebruik stijgt (laatste 4 weken)",
+    weekday_initials: "MDWDVZZ",
+    recent_resets: "Recente resets",
+    weekday_names: "ma,di,wo,do,vr,za,zo",
+    pace_projection: "In dit tempo",
+    projected_limit: "{window}-limiet bereikt {when}",
+    projected_at_reset: "~{percent} gebruikt bij {window}-reset",
+    usage_jump_title: "Gebruikspiek",
+    usage_jump_body: "{model} {window}-gebruik steeg van {from} naar {to} in {minutes} min",
+    alert_title: "Gebruikswaarschuwing",
+    alert_body: "{model} {window}-gebruik heeft {percent} bereikt",
+    snooze_alerts: "Waarschuwingen uitstellen",
+    snooze_for_hour: "1 uur",
+    snooze_until_reset: "Tot de volgende reset",
+    resume_alerts: "Waarschuwingen hervatten",
+    limit_reset_soon_title: "Limiet reset binnenkort",
+    limit_reset_soon_body: "Het {window}-venster van {model} reset over {time}.",
+    limit_reset_title: "Limiet gereset",
+    limit_reset_body: "Het {window}-venster van {model} is gereset.",
+    usage_reset_title: "Gebruik gereset",
+    usage_reset_body: "{window}-venster van {model} is gereset (was {from}).",
+    poll_failed: "Laatste ophaalpoging mislukt",
+    failure_reason: "Reden",
+    reason_auth_required: "Aanmelden vereist",
+    reason_token_expired: "Token verlopen",
+    reason_no_credentials: "Geen inloggegevens gevonden",
+    reason_request_failed: "Verzoek mislukt",
+    last_response: "Laatste antwoord",
+    credentials_checked: "Gecontroleerde inloggegevens",
+    retry: "Opnieuw",
+    export_history: "Gebruiksgeschiedenis exporteren…",
+    export_history_failed: "Kan gebruiksgeschiedenis niet exporteren",
+    copy_snapshot: "Momentopname kopiëren",
+    save_snapshot: "Momentopname opslaan…",
+    snapshot_failed: "Kan geen momentopname maken",
+    snapshot_empty: "De widget heeft nog niets om te tonen.",
+    interval_suggestion_title: "Vaak vernieuwen?",
+    interval_suggestion_body: "U vernieuwt ongeveer {count} keer per uur handmatig. Klik hier om de updatefrequentie op {interval} te zetten.",
+    welcome_title: "Meld u aan bij Claude Code",
+    welcome_body: "Het gebruik verschijnt zodra Claude Code is aangemeld. Voer claude uit en meld u aan; de aanmelding wordt gelezen uit {path}. Klik hier voor de README.",
+    weekly_reminder_title: "Weeklimiet wordt binnenkort gereset",
+    weekly_reminder_body: "Uw weeklimiet wordt over {time} gereset — {unused}% ongebruikt.",
+    held_notifications_title: "Terwijl Niet storen aan stond",
+    crash_title: "Claude Code Usage Monitor gestopt",
+    crash_body: "Er ging iets mis en de monitor moest worden afgesloten. Details staan in crash.log naast settings.json.",
+    crash_restarted_body: "De monitor is opnieuw gestart na een onverwachte fout. Details staan in crash.log naast settings.json.",
     day_suffix: "d",
     hour_suffix: "u",
     minute_suffix: "m",
@@ -44,7 +154,11 @@ pub(super) const STRINGS: Strings = Strings {
     codex_token_expired_body: "Voer 'codex' uit in een terminal en volg de aanmeldstappen. Ververs of herstart de app daarna.",
     antigravity_token_expired_title: "Antigravity-authenticatiefout",
     antigravity_token_expired_body: "Open Antigravity en meld je opnieuw aan. Ververs of herstart de app daarna.",
+    service_unreachable_title: "Verbruiksdienst onbereikbaar",
+    service_unreachable_body: "De verbruiksdienst was na {count} pogingen niet bereikbaar. De monitor blijft het op de achtergrond proberen; controleer je verbinding of proxy.",
     codex_window_title: "Codex-gebruiksmonitor",
     antigravity_window_title: "Antigravity-gebruiksmonitor",
     second_suffix: "s",
+    backfill_title: "Gebruiksgeschiedenis importeren",
+    backfill_prompt: "Je eerdere Claude Code-activiteit importeren uit lokale sessietranscripten? Dit leest ~/.claude/projects eenmalig, zodat de geschiedenis begint met de afgelopen weken in plaats van leeg.",
 };
@@ -5,18 +5,66 @@ pub(super) const UPDATE_VIA_WINGET_LABEL: &str = "Update via WinGet";
 pub(super) const STRINGS: Strings = Strings {
     window_title: "Claude Code Usage Monitor",
     refresh: "Refresh",
+    pause_polling: "Pause Polling",
     update_frequency: "Update Frequency",
+    thirty_seconds: "30 Seconds",
     one_minute: "1 Minute",
     five_minutes: "5 Minutes",
     fifteen_minutes: "15 Minutes",
+    thirty_minutes: "30 Minutes",
     one_hour: "1 Hour",
+    custom_interval: "Custom…",
+    custom_interval_prompt: "Update every (at least 15 seconds, e.g. 45, 90s, 5m or 1h):",
+    custom_interval_invalid: "Enter an interval of at least 15 seconds, such as 45, 90s, 5m or 1h.",
+    ok: "OK",
+    cancel: "Cancel",
+    smart_polling: "Smart (Adaptive)",
     models: "Models",
     claude_code_model: "Claude Code",
     codex_model: "Codex",
     antigravity_model: "Antigravity",
     settings: "Settings",
     start_with_windows: "Start with Windows",
+    start_with_windows_task: "Start with Windows (Task Scheduler)",
+    startup_task_failed: "Unable to update the Task Scheduler startup task",
     reset_position: "Reset Position",
+    reembed_in_taskbar: "Re-embed in Taskbar",
+    float_over_taskbar: "Float Over Taskbar",
+    restart_widget: "Restart Widget",
+    show_burn_rate: "Show Burn Rate Instead of Countdown",
+    card_background: "Card Background",
+    opacity: "Opacity",
+    show_divider: "Show Divider",
+    emphasize_high_usage: "Emphasize High Usage",
+    mirror_layout: "Right-to-Left Layout",
+    one_decimal: "One Decimal Place",
+    bars_show_time_elapsed: "Bars Show Time Elapsed",
+    show_row: "Show {row} Row",
+    blend_with_taskbar: "Blend with Taskbar",
+    weekly_first: "Weekly Limit on Top",
+    urgent_row_first: "Most Urgent Limit on Top",
+    color_palette: "Color Palette",
+    palette_standard: "Standard",
+    palette_deuteranopia: "Deuteranopia (Blue/Orange)",
+    palette_protanopia: "Protanopia (Blue/Orange)",
+    left_click: "Left Click",
+    click_show_details: "Show Details",
+    click_open_usage_page: "Open Usage Page",
+    open_usage_dashboard: "Open Usage Dashboard",
+    click_do_nothing: "Do Nothing",
+    show_cost: "Show Estimated Cost",
+    show_token_counts: "Show Token Counts",
+    show_messages_left: "Show Messages Left",
+    notify_usage_jumps: "Alert on Usage Spikes",
+    notify_limit_resets: "Notify When Limits Reset",
+    notify_usage_resets: "Notify When Usage Resets",
+    weekly_reminder: "Weekly Reset Reminder",
+    reminder_off: "Off",
+    reminder_hours_before: "{hours} Hours Before",
+    quiet_when_fullscreen: "Quiet When Full-Screen",
+    pause_on_metered: "Pause on Metered Connections",
+    poll_less_on_battery: "Poll Less on Battery Saver",
+    api_key_limits: "Show API Key Limits",
     language: "Language",
     system_default: "System Default",
     check_for_updates: "Check for Updates",
@@ -32,9 +80,71 @@ pub(super) const STRINGS: Strings = Strings {
     update_prompt_now: "Version {version} is available. Do you want to update now?",
     exit: "Exit",
     show_widget: "Show Widget",
+    badge_mode: "Taskbar Badge Only",
     session_window: "5h",
     weekly_window: "7d",
+    opus_window: "7d Opus",
+    requests_window: "Rq",
+    tokens_window: "Tk",
     now: "now",
+    paused: "Paused",
+    none_left: "0 left",
+    burn_rate: "Burn rate",
+    tokens_7d: "7d tokens",
+    estimated_cost: "Est. cost",
+    tokens_today: "Tokens today",
+    tokens_in: "in",
+    tokens_out: "out",
+    tokens_cached: "cached",
+    plan: "Plan",
+    plan_session_messages: "~{count} messages per {window}",
+    messages_left: "~{count} msgs left",
+    usage_by_hour: "When 5h usage climbs (last 4 weeks)",
+    weekday_initials: "MTWTFSS",
+    recent_resets: "Recent resets",
+    weekday_names: "Mon,Tue,Wed,Thu,Fri,Sat,Sun",
+    pace_projection: "At this pace",
+    projected_limit: "{window} limit reached {when}",
+    projected_at_reset: "~{percent} used when {window} resets",
+    usage_jump_title: "Usage Spike",
+    usage_jump_body: "{model} {window} usage rose from {from} to {to} in {minutes} min",
+    alert_title: "Usage Alert",
+    alert_body: "{model} {window} usage reached {percent}",
+    snooze_alerts: "Snooze Alerts",
+    snooze_for_hour: "For 1 Hour",
+    snooze_until_reset: "Until Next Reset",
+    resume_alerts: "Resume Alerts",
+    limit_reset_soon_title: "Limit Resets Soon",
+    limit_reset_soon_body: "{model} {window} window resets in {time}.",
+    limit_reset_title: "Limit Reset",
+    limit_reset_body: "{model} {window} window has reset.",
+    usage_reset_title: "Usage Reset",
+    usage_reset_body: "{model} {window} window has reset (was {from}).",
+    poll_failed: "Last Poll Failed",
+    failure_reason: "Reason",
+    reason_auth_required: "Sign-in required",
+    reason_token_expired: "Token expired",
+    reason_no_credentials: "No credentials found",
+    reason_request_failed: "Request failed",
+    last_response: "Last response",
+    credentials_checked: "Credentials checked",
+    retry: "Retry",
+    export_history: "Export Usage History…",
+    export_history_failed: "Unable to export usage history",
+    copy_snapshot: "Copy Snapshot",
+    save_snapshot: "Save Snapshot…",
+    snapshot_failed: "Unable to create a snapshot",
+    snapshot_empty: "The widget has nothing to show yet.",
+    interval_suggestion_title: "Refreshing Often?",
+    interval_suggestion_body: "You refresh manually about {count} times an hour. Click here to set Update Frequency to {interval}.",
+    welcome_title: "Sign in to Claude Code",
+    welcome_body: "Usage appears once Claude Code is logged in. Run claude and log in; its sign-in is read from {path}. Click here for the README.",
+    weekly_reminder_title: "Weekly Limit Resets Soon",
+    weekly_reminder_body: "Your weekly limit resets in {time} — {unused}% unused.",
+    held_notifications_title: "While Do Not Disturb Was On",
+    crash_title: "Claude Code Usage Monitor Stopped",
+    crash_body: "Something went wrong and the monitor had to close. Details are in crash.log next to settings.json.",
+    crash_restarted_body: "The monitor restarted after an unexpected error. Details are in crash.log next to settings.json.",
     day_suffix: "d",
     hour_suffix: "h",
     minute_suffix: "m",
@@ -44,7 +154,11 @@ pub(super) const STRINGS: Strings = Strings {
     codex_token_expired_body: "Run 'codex' in a terminal and follow the sign-in prompts. After that, refresh or restart this app.",
     antigravity_token_expired_title: "Antigravity Auth Error",
     antigravity_token_expired_body: "Open Antigravity and sign in again. After that, refresh or restart this app.",
+    service_unreachable_title: "Usage Service Unreachable",
+    service_unreachable_body: "The usage service could not be reached after {count} attempts. The monitor keeps retrying in the background; check your connection or proxy.",
     codex_window_title: "Codex Usage Monitor",
     antigravity_window_title: "Antigravity Usage Monitor",
     second_suffix: "s",
+    backfill_title: "Import Usage History",
+    backfill_prompt: "Import your past Claude Code activity from local session transcripts? This reads ~/.claude/projects once so history starts with the last few weeks of usage instead of empty.",
 };
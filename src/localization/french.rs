@@ -5,18 +5,66 @@ pub(super) const UPDATE_VIA_WINGET_LABEL: &str = "Mettre à jour avec WinGet";
 pub(super) const STRINGS: Strings = Strings {
     window_title: "Moniteur d'utilisation Claude Code",
     refresh: "Actualiser",
+    pause_polling: "Suspendre l'actualisation",
     update_frequency: "Fréquence de mise à jour",
+    thirty_seconds: "30 secondes",
     one_minute: "1 minute",
     five_minutes: "5 minutes",
     fifteen_minutes: "15 minutes",
+    thirty_minutes: "30 minutes",
     one_hour: "1 heure",
+    custom_interval: "Personnalisé…",
+    custom_interval_prompt: "Actualiser toutes les (15 secondes minimum, p. ex. 45, 90s, 5m ou 1h) :",
+    custom_interval_invalid: "Saisissez un intervalle d'au moins 15 secondes, comme 45, 90s, 5m ou 1h.",
+    ok: "OK",
+    cancel: "Annuler",
+    smart_polling: "Intelligent (adaptatif)",
     models: "Modeles",
     claude_code_model: "Claude Code",
     codex_model: "Codex",
     antigravity_model: "Antigravity",
     settings: "Paramètres",
     start_with_windows: "Démarrer avec Windows",
+    start_with_windows_task: "Démarrer avec Windows (Planificateur de tâches)",
+    startup_task_failed: "Impossible de mettre à jour la tâche de démarrage du Planificateur de tâches",
     reset_position: "Réinitialiser la position",
+    reembed_in_taskbar: "Réintégrer dans la barre des tâches",
+    float_over_taskbar: "Flotter au-dessus de la barre des tâches",
+    restart_widget: "Redémarrer le widget",
+    show_burn_rate: "Afficher le rythme au lieu du compte à rebours",
+    card_background: "Fond en carte",
+    opacity: "Opacité",
+    show_divider: "Afficher le séparateur",
+    emphasize_high_usage: "Mettre en évidence l'utilisation élevée",
+    mirror_layout: "Disposition de droite à gauche",
+    one_decimal: "Une décimale",
+    bars_show_time_elapsed: "Barres : temps écoulé",
+    show_row: "Afficher la ligne {row}",
+    blend_with_taskbar: "Fondre dans la barre des tâches",
+    weekly_first: "Limite hebdomadaire en haut",
+    urgent_row_first: "Limite la plus urgente en haut",
+    color_palette: "Palette de couleurs",
+    palette_standard: "Standard",
+    palette_deuteranopia: "Deutéranopie (bleu/orange)",
+    palette_protanopia: "Protanopie (bleu/orange)",
+    left_click: "Clic gauche",
+    click_show_details: "Afficher les détails",
+    click_open_usage_page: "Ouvrir la page d'utilisation",
+    open_usage_dashboard: "Ouvrir le tableau de bord d'utilisation",
+    click_do_nothing: "Ne rien faire",
+    show_cost: "Afficher le coût estimé",
+    show_token_counts: "Afficher le nombre de jetons",
+    show_messages_left: "Afficher les messages restants",
+    notify_usage_jumps: "Alerter en cas de pic d'utilisation",
+    notify_limit_resets: "Notifier à la réinitialisation des limites",
+    notify_usage_resets: "Notifier quand l'utilisation est réinitialisée",
+    weekly_reminder: "Rappel de réinitialisation hebdomadaire",
+    reminder_off: "Désactivé",
+    reminder_hours_before: "{hours} heures avant",
+    quiet_when_fullscreen: "Silencieux en plein écran",
+    pause_on_metered: "Suspendre sur les connexions limitées",
+    poll_less_on_battery: "Actualiser moins souvent en économie de batterie",
+    api_key_limits: "Afficher les limites de la clé API",
     language: "Langue",
     system_default: "Par défaut du système",
     check_for_updates: "Vérifier les mises à jour",
@@ -32,9 +80,71 @@ pub(super) const STRINGS: Strings = Strings {
     update_prompt_now: "La version {version} est disponible. Voulez-vous mettre à jour maintenant ?",
     exit: "Quitter",
     show_widget: "Afficher le widget",
+    badge_mode: "Badge de barre des tâches uniquement",
     session_window: "5h",
     weekly_window: "7d",
+    opus_window: "7d Opus",
+    requests_window: "Rq",
+    tokens_window: "Jt",
     now: "maintenant",
+    paused: "En pause",
+    none_left: "0 restant",
+    burn_rate: "Rythme de consommation",
+    tokens_7d: "Jetons 7j",
+    estimated_cost: "Coût estimé",
+    tokens_today: "Jetons aujourd'hui",
+    tokens_in: "entrée",
+    tokens_out: "sortie",
+    tokens_cached: "en cache",
+    plan: "Forfait",
+    plan_session_messages: "~{count} messages par {window}",
+    messages_left: "~{count} messages restants",
+    usage_by_hour: "Quand l'utilisation 5 h augmente (4 dernières semaines)",
+    weekday_initials: "LMMJVSD",
+    recent_resets: "Réinitialisations récentes",
+    weekday_names: "lun.,mar.,mer.,jeu.,ven.,sam.,dim.",
+    pace_projection: "À ce rythme",
+    projected_limit: "limite {window} atteinte {when}",
+    projected_at_reset: "~{percent} utilisé à la réinitialisation {window}",
+    usage_jump_title: "Pic d'utilisation",
+    usage_jump_body: "Utilisation {window} de {model} passée de {from} à {to} en {minutes} min",
+    alert_title: "Alerte d'utilisation",
+    alert_body: "L'utilisation {window} de {model} a atteint {percent}",
+    snooze_alerts: "Suspendre les alertes",
+    snooze_for_hour: "Pendant 1 heure",
+    snooze_until_reset: "Jusqu'à la prochaine réinitialisation",
+    resume_alerts: "Reprendre les alertes",
+    limit_reset_soon_title: "Limite bientôt réinitialisée",
+    limit_reset_soon_body: "La fenêtre {window} de {model} se réinitialise dans {time}.",
+    limit_reset_title: "Limite réinitialisée",
+    limit_reset_body: "La fenêtre {window} de {model} a été réinitialisée.",
+    usage_reset_title: "Utilisation réinitialisée",
+    usage_reset_body: "La fenêtre {window} de {model} a été réinitialisée (était à {from}).",
+    poll_failed: "Échec de la dernière actualisation",
+    failure_reason: "Raison",
+    reason_auth_required: "Connexion requise",
+    reason_token_expired: "Jeton expiré",
+    reason_no_credentials: "Aucun identifiant trouvé",
+    reason_request_failed: "Échec de la requête",
+    last_response: "Dernière réponse",
+    credentials_checked: "Identifiants vérifiés",
+    retry: "Réessayer",
+    export_history: "Exporter l'historique d'utilisation…",
+    export_history_failed: "Impossible d'exporter l'historique d'utilisation",
+    copy_snapshot: "Copier une capture",
+    save_snapshot: "Enregistrer une capture…",
+    snapshot_failed: "Impossible de créer une capture",
+    snapshot_empty: "Le widget n'a encore rien à afficher.",
+    interval_suggestion_title: "Actualisations fréquentes ?",
+    interval_suggestion_body: "Vous actualisez manuellement environ {count} fois par heure. Cliquez ici pour régler la fréquence de mise à jour sur {interval}.",
+    welcome_title: "Connectez-vous à Claude Code",
+    welcome_body: "L'utilisation s'affiche une fois Claude Code connecté. Lancez claude et connectez-vous ; la connexion est lue depuis {path}. Cliquez ici pour le README.",
+    weekly_reminder_title: "Limite hebdomadaire bientôt réinitialisée",
+    weekly_reminder_body: "Votre limite hebdomadaire se réinitialise dans {time} — {unused} % inutilisés.",
+    held_notifications_title: "Pendant le mode Ne pas déranger",
+    crash_title: "Claude Code Usage Monitor s'est arrêté",
+    crash_body: "Une erreur s'est produite et le moniteur a dû se fermer. Les détails sont dans crash.log, à côté de settings.json.",
+    crash_restarted_body: "Le moniteur a redémarré après une erreur inattendue. Les détails sont dans crash.log, à côté de settings.json.",
     day_suffix: "j",
     hour_suffix: "h",
     minute_suffix: "m",
@@ -44,7 +154,11 @@ pub(super) const STRINGS: Strings = Strings {
     codex_token_expired_body: "Executez 'codex' dans un terminal et suivez les instructions de connexion. Ensuite, actualisez ou redemarrez cette application.",
     antigravity_token_expired_title: "Erreur d'authentification Antigravity",
     antigravity_token_expired_body: "Ouvrez Antigravity et reconnectez-vous. Ensuite, actualisez ou redemarrez cette application.",
+    service_unreachable_title: "Service d'utilisation injoignable",
+    service_unreachable_body: "Le service d'utilisation est resté injoignable après {count} tentatives. Le moniteur continue de réessayer en arrière-plan ; vérifiez votre connexion ou votre proxy.",
     codex_window_title: "Moniteur d'utilisation Codex",
     antigravity_window_title: "Moniteur d'utilisation Antigravity",
     second_suffix: "s",
+    backfill_title: "Importer l'historique d'utilisation",
+    backfill_prompt: "Importer votre activité Claude Code passée depuis les transcriptions locales ? ~/.claude/projects est lu une seule fois pour que l'historique commence avec les dernières semaines d'utilisation au lieu d'être vide.",
 };
@@ -5,18 +5,66 @@ pub(super) const UPDATE_VIA_WINGET_LABEL: &str = "Mit WinGet aktualisieren";
 pub(super) const STRINGS: Strings = Strings {
     window_title: "Claude Code Nutzungsmonitor",
     refresh: "Aktualisieren",
+    pause_polling: "Aktualisierung pausieren",
     update_frequency: "Aktualisierungsintervall",
+    thirty_seconds: "30 Sekunden",
     one_minute: "1 Minute",
     five_minutes: "5 Minuten",
     fifteen_minutes: "15 Minuten",
+    thirty_minutes: "30 Minuten",
     one_hour: "1 Stunde",
+    custom_interval: "Benutzerdefiniert…",
+    custom_interval_prompt: "Aktualisieren alle (mindestens 15 Sekunden, z. B. 45, 90s, 5m oder 1h):",
+    custom_interval_invalid: "Geben Sie ein Intervall von mindestens 15 Sekunden ein, z. B. 45, 90s, 5m oder 1h.",
+    ok: "OK",
+    cancel: "Abbrechen",
+    smart_polling: "Intelligent (adaptiv)",
     models: "Modelle",
     claude_code_model: "Claude Code",
     codex_model: "Codex",
     antigravity_model: "Antigravity",
     settings: "Einstellungen",
     start_with_windows: "Mit Windows starten",
+    start_with_windows_task: "Mit Windows starten (Aufgabenplanung)",
+    startup_task_failed: "Die Autostart-Aufgabe der Aufgabenplanung konnte nicht aktualisiert werden",
     reset_position: "Position zurücksetzen",
+    reembed_in_taskbar: "Erneut in Taskleiste einbetten",
+    float_over_taskbar: "Über der Taskleiste schweben",
+    restart_widget: "Widget neu starten",
+    show_burn_rate: "Verbrauchsrate statt Countdown anzeigen",
+    card_background: "Kartenhintergrund",
+    opacity: "Deckkraft",
+    show_divider: "Trennlinie anzeigen",
+    emphasize_high_usage: "Hohe Nutzung hervorheben",
+    mirror_layout: "Layout von rechts nach links",
+    one_decimal: "Eine Nachkommastelle",
+    bars_show_time_elapsed: "Balken zeigen verstrichene Zeit",
+    show_row: "Zeile {row} anzeigen",
+    blend_with_taskbar: "Mit Taskleiste verschmelzen",
+    weekly_first: "Wochenlimit oben",
+    urgent_row_first: "Dringendstes Limit oben",
+    color_palette: "Farbpalette",
+    palette_standard: "Standard",
+    palette_deuteranopia: "Deuteranopie (Blau/Orange)",
+    palette_protanopia: "Protanopie (Blau/Orange)",
+    left_click: "Linksklick",
+    click_show_details: "Details anzeigen",
+    click_open_usage_page: "Nutzungsseite öffnen",
+    open_usage_dashboard: "Nutzungs-Dashboard öffnen",
+    click_do_nothing: "Nichts tun",
+    show_cost: "Geschätzte Kosten anzeigen",
+    show_token_counts: "Token-Anzahl anzeigen",
+    show_messages_left: "Verbleibende Nachrichten anzeigen",
+    notify_usage_jumps: "Bei Nutzungsspitzen warnen",
+    notify_limit_resets: "Bei Limit-Reset benachrichtigen",
+    notify_usage_resets: "Bei Nutzungs-Reset benachrichtigen",
+    weekly_reminder: "Erinnerung an Wochen-Reset",
+    reminder_off: "Aus",
+    reminder_hours_before: "{hours} Stunden vorher",
+    quiet_when_fullscreen: "Im Vollbildmodus ruhig bleiben",
+    pause_on_metered: "Bei getakteten Verbindungen pausieren",
+    poll_less_on_battery: "Im Energiesparmodus seltener aktualisieren",
+    api_key_limits: "API-Schlüssel-Limits anzeigen",
     language: "Sprache",
     system_default: "Systemstandard",
     check_for_updates: "Nach Updates suchen",
@@ -32,9 +80,71 @@ pub(super) const STRINGS: Strings = Strings {
     update_prompt_now: "Version {version} ist verfügbar. Möchten Sie jetzt aktualisieren?",
     exit: "Beenden",
     show_widget: "Widget anzeigen",
+    badge_mode: "Nur Taskleisten-Badge",
     session_window: "5h",
     weekly_window: "7d",
+    opus_window: "7d Opus",
+    requests_window: "Af",
+    tokens_window: "Tk",
     now: "jetzt",
+    paused: "Pausiert",
+    none_left: "0 übrig",
+    burn_rate: "Verbrauchsrate",
+    tokens_7d: "Tokens 7T",
+    estimated_cost: "Geschätzte Kosten",
+    tokens_today: "Tokens heute",
+    tokens_in: "Eingabe",
+    tokens_out: "Ausgabe",
+    tokens_cached: "Cache",
+    plan: "Tarif",
+    plan_session_messages: "~{count} Nachrichten pro {window}",
+    messages_left: "~{count} Nachrichten übrig",
+    usage_by_hour: "Wann die 5h-Nutzung steigt (letzte 4 Wochen)",
+    weekday_initials: "MDMDFSS",
+    recent_resets: "Letzte Zurücksetzungen",
+    weekday_names: "Mo,Di,Mi,Do,Fr,Sa,So",
+    pace_projection: "Bei diesem Tempo",
+    projected_limit: "{window}-Limit erreicht {when}",
+    projected_at_reset: "~{percent} genutzt beim {window}-Reset",
+    usage_jump_title: "Nutzungsspitze",
+    usage_jump_body: "{model} {window}-Nutzung stieg in {minutes} Min. von {from} auf {to}",
+    alert_title: "Nutzungswarnung",
+    alert_body: "{model} {window}-Nutzung hat {percent} erreicht",
+    snooze_alerts: "Warnungen pausieren",
+    snooze_for_hour: "Für 1 Stunde",
+    snooze_until_reset: "Bis zum nächsten Reset",
+    resume_alerts: "Warnungen fortsetzen",
+    limit_reset_soon_title: "Limit wird bald zurückgesetzt",
+    limit_reset_soon_body: "Das {window}-Fenster von {model} wird in {time} zurückgesetzt.",
+    limit_reset_title: "Limit zurückgesetzt",
+    limit_reset_body: "Das {window}-Fenster von {model} wurde zurückgesetzt.",
+    usage_reset_title: "Nutzung zurückgesetzt",
+    usage_reset_body: "{window}-Fenster von {model} wurde zurückgesetzt (war {from}).",
+    poll_failed: "Letzte Abfrage fehlgeschlagen",
+    failure_reason: "Grund",
+    reason_auth_required: "Anmeldung erforderlich",
+    reason_token_expired: "Token abgelaufen",
+    reason_no_credentials: "Keine Anmeldedaten gefunden",
+    reason_request_failed: "Anfrage fehlgeschlagen",
+    last_response: "Letzte Antwort",
+    credentials_checked: "Geprüfte Anmeldedaten",
+    retry: "Erneut versuchen",
+    export_history: "Nutzungsverlauf exportieren…",
+    export_history_failed: "Nutzungsverlauf konnte nicht exportiert werden",
+    copy_snapshot: "Schnappschuss kopieren",
+    save_snapshot: "Schnappschuss speichern…",
+    snapshot_failed: "Schnappschuss konnte nicht erstellt werden",
+    snapshot_empty: "Das Widget hat noch nichts anzuzeigen.",
+    interval_suggestion_title: "Häufig aktualisiert?",
+    interval_suggestion_body: "Sie aktualisieren etwa {count}-mal pro Stunde manuell. Klicken Sie hier, um die Aktualisierungshäufigkeit auf {interval} zu setzen.",
+    welcome_title: "Bei Claude Code anmelden",
+    welcome_body: "Die Nutzung erscheint, sobald Claude Code angemeldet ist. Führen Sie claude aus und melden Sie sich an; die Anmeldung wird aus {path} gelesen. Klicken Sie hier für die README.",
+    weekly_reminder_title: "Wochenlimit wird bald zurückgesetzt",
+    weekly_reminder_body: "Ihr Wochenlimit wird in {time} zurückgesetzt — {unused} % ungenutzt.",
+    held_notifications_title: "Während „Nicht stören“ aktiv war",
+    crash_title: "Claude Code Usage Monitor beendet",
+    crash_body: "Ein Fehler ist aufgetreten und der Monitor musste beendet werden. Details stehen in crash.log neben settings.json.",
+    crash_restarted_body: "Der Monitor wurde nach einem unerwarteten Fehler neu gestartet. Details stehen in crash.log neben settings.json.",
     day_suffix: "T",
     hour_suffix: "h",
     minute_suffix: "m",
@@ -44,7 +154,11 @@ pub(super) const STRINGS: Strings = Strings {
     codex_token_expired_body: "Fuhren Sie 'codex' in einem Terminal aus und folgen Sie den Anmeldeanweisungen. Aktualisieren oder starten Sie diese App anschliessend neu.",
     antigravity_token_expired_title: "Antigravity-Authentifizierungsfehler",
     antigravity_token_expired_body: "Offnen Sie Antigravity und melden Sie sich erneut an. Aktualisieren oder starten Sie diese App anschliessend neu.",
+    service_unreachable_title: "Nutzungsdienst nicht erreichbar",
+    service_unreachable_body: "Der Nutzungsdienst war nach {count} Versuchen nicht erreichbar. Der Monitor versucht es im Hintergrund weiter; prüfe Verbindung oder Proxy.",
     codex_window_title: "Codex-Nutzungsmonitor",
     antigravity_window_title: "Antigravity-Nutzungsmonitor",
     second_suffix: "s",
+    backfill_title: "Nutzungsverlauf importieren",
+    backfill_prompt: "Frühere Claude Code-Aktivität aus lokalen Sitzungsprotokollen importieren? ~/.claude/projects wird einmalig gelesen, damit der Verlauf mit den letzten Wochen beginnt statt leer.",
 };
@@ -5,18 +5,66 @@ pub(super) const UPDATE_VIA_WINGET_LABEL: &str = "WinGet で更新";
 pub(super) const STRINGS: Strings = Strings {
     window_title: "Claude Code 使用量モニター",
     refresh: "更新",
+    pause_polling: "更新を一時停止",
     update_frequency: "更新間隔",
+    thirty_seconds: "30 秒",
     one_minute: "1分",
     five_minutes: "5分",
     fifteen_minutes: "15分",
+    thirty_minutes: "30 分",
     one_hour: "1時間",
+    custom_interval: "カスタム…",
+    custom_interval_prompt: "更新間隔（15 秒以上。例: 45、90s、5m、1h）:",
+    custom_interval_invalid: "15 秒以上の間隔を入力してください（例: 45、90s、5m、1h）。",
+    ok: "OK",
+    cancel: "キャンセル",
+    smart_polling: "スマート (自動調整)",
     models: "モデル",
     claude_code_model: "Claude Code",
     codex_model: "Codex",
     antigravity_model: "Antigravity",
     settings: "設定",
     start_with_windows: "Windows と同時に開始",
+    start_with_windows_task: "Windows と同時に開始 (タスク スケジューラ)",
+    startup_task_failed: "タスク スケジューラのスタートアップ タスクを更新できません",
     reset_position: "位置をリセット",
+    reembed_in_taskbar: "タスクバーに再配置",
+    float_over_taskbar: "タスクバーの上に表示",
+    restart_widget: "ウィジェットを再起動",
+    show_burn_rate: "カウントダウンの代わりに消費ペースを表示",
+    card_background: "カード背景",
+    opacity: "不透明度",
+    show_divider: "区切り線を表示",
+    emphasize_high_usage: "高い使用率を強調表示",
+    mirror_layout: "右から左のレイアウト",
+    one_decimal: "小数点以下 1 桁",
+    bars_show_time_elapsed: "バーに経過時間を表示",
+    show_row: "{row} の行を表示",
+    blend_with_taskbar: "タスクバーになじませる",
+    weekly_first: "週間制限を上に表示",
+    urgent_row_first: "最も差し迫った上限を上に表示",
+    color_palette: "カラーパレット",
+    palette_standard: "標準",
+    palette_deuteranopia: "2 型色覚 (青/オレンジ)",
+    palette_protanopia: "1 型色覚 (青/オレンジ)",
+    left_click: "左クリック",
+    click_show_details: "詳細を表示",
+    click_open_usage_page: "使用状況ページを開く",
+    open_usage_dashboard: "使用状況ダッシュボードを開く",
+    click_do_nothing: "何もしない",
+    show_cost: "推定コストを表示",
+    show_token_counts: "トークン数を表示",
+    show_messages_left: "残りメッセージ数を表示",
+    notify_usage_jumps: "使用量の急増を通知",
+    notify_limit_resets: "上限のリセットを通知",
+    notify_usage_resets: "使用量のリセットを通知",
+    weekly_reminder: "週間リセットのリマインダー",
+    reminder_off: "オフ",
+    reminder_hours_before: "{hours} 時間前",
+    quiet_when_fullscreen: "全画面表示中は通知を控える",
+    pause_on_metered: "従量制課金接続では一時停止",
+    poll_less_on_battery: "バッテリー節約時は更新を減らす",
+    api_key_limits: "API キーの制限を表示",
     language: "言語",
     system_default: "システム既定",
     check_for_updates: "更新を確認",
@@ -32,9 +80,71 @@ pub(super) const STRINGS: Strings = Strings {
     update_prompt_now: "バージョン {version} が利用可能です。今すぐ更新しますか？",
     exit: "終了",
     show_widget: "ウィジェットを表示",
+    badge_mode: "タスクバーのバッジのみ",
     session_window: "5h",
     weekly_window: "7d",
+    opus_window: "7d Opus",
+    requests_window: "要求",
+    tokens_window: "トークン",
     now: "今",
+    paused: "一時停止中",
+    none_left: "残り 0",
+    burn_rate: "消費ペース",
+    tokens_7d: "7日間のトークン",
+    estimated_cost: "推定コスト",
+    tokens_today: "今日のトークン",
+    tokens_in: "入力",
+    tokens_out: "出力",
+    tokens_cached: "キャッシュ",
+    plan: "プラン",
+    plan_session_messages: "{window} あたり約 {count} メッセージ",
+    messages_left: "残り約 {count} メッセージ",
+    usage_by_hour: "5時間の使用量が増える時間帯（過去 4 週間）",
+    weekday_initials: "月火水木金土日",
+    recent_resets: "最近のリセット",
+    weekday_names: "月,火,水,木,金,土,日",
+    pace_projection: "このペースでは",
+    projected_limit: "{when} に {window} の上限に到達",
+    projected_at_reset: "{window} のリセット時に約 {percent} 使用",
+    usage_jump_title: "使用量の急増",
+    usage_jump_body: "{model} の {window} 使用量が {minutes} 分で {from} から {to} に増加しました",
+    alert_title: "使用量アラート",
+    alert_body: "{model} の {window} 使用量が {percent} に達しました",
+    snooze_alerts: "アラートを一時停止",
+    snooze_for_hour: "1 時間",
+    snooze_until_reset: "次のリセットまで",
+    resume_alerts: "アラートを再開",
+    limit_reset_soon_title: "まもなく上限がリセット",
+    limit_reset_soon_body: "{model} の {window} ウィンドウは {time} 後にリセットされます。",
+    limit_reset_title: "上限がリセットされました",
+    limit_reset_body: "{model} の {window} ウィンドウがリセットされました。",
+    usage_reset_title: "使用量がリセットされました",
+    usage_reset_body: "{model} の {window} ウィンドウがリセットされました（{from} でした）。",
+    poll_failed: "前回の取得に失敗しました",
+    failure_reason: "理由",
+    reason_auth_required: "サインインが必要です",
+    reason_token_expired: "トークンの有効期限切れ",
+    reason_no_credentials: "資格情報が見つかりません",
+    reason_request_failed: "リクエストに失敗しました",
+    last_response: "最後の応答",
+    credentials_checked: "確認した資格情報",
+    retry: "再試行",
+    export_history: "使用履歴をエクスポート…",
+    export_history_failed: "使用履歴をエクスポートできません",
+    copy_snapshot: "スナップショットをコピー",
+    save_snapshot: "スナップショットを保存…",
+    snapshot_failed: "スナップショットを作成できません",
+    snapshot_empty: "ウィジェットにはまだ表示する内容がありません。",
+    interval_suggestion_title: "頻繁に更新していますか？",
+    interval_suggestion_body: "1 時間に約 {count} 回手動で更新しています。ここをクリックすると更新頻度を {interval} に設定します。",
+    welcome_title: "Claude Code にログインしてください",
+    welcome_body: "Claude Code にログインすると使用量が表示されます。claude を実行してログインしてください。ログイン情報は {path} から読み込まれます。ここをクリックすると README を開きます。",
+    weekly_reminder_title: "週間上限がまもなくリセット",
+    weekly_reminder_body: "週間上限は {time} 後にリセットされます — {unused}% 未使用です。",
+    held_notifications_title: "応答不可モード中の通知",
+    crash_title: "Claude Code Usage Monitor が停止しました",
+    crash_body: "問題が発生したためモニターを終了しました。詳細は settings.json と同じフォルダーの crash.log にあります。",
+    crash_restarted_body: "予期しないエラーの後にモニターを再起動しました。詳細は settings.json と同じフォルダーの crash.log にあります。",
     day_suffix: "日",
     hour_suffix: "時間",
     minute_suffix: "分",
@@ -44,7 +154,11 @@ pub(super) const STRINGS: Strings = Strings {
     codex_token_expired_body: "ターミナルで 'codex' を実行し、サインインの案内に従ってください。その後、このアプリを更新または再起動してください。",
     antigravity_token_expired_title: "Antigravity 認証エラー",
     antigravity_token_expired_body: "Antigravity を開いて再度サインインしてください。その後、このアプリを更新するか再起動してください。",
+    service_unreachable_title: "使用量サービスに接続できません",
+    service_unreachable_body: "{count} 回試行しても使用量サービスに接続できませんでした。バックグラウンドで再試行を続けます。接続またはプロキシを確認してください。",
     codex_window_title: "Codex 使用量モニター",
     antigravity_window_title: "Antigravity 使用量モニター",
     second_suffix: "秒",
+    backfill_title: "使用履歴のインポート",
+    backfill_prompt: "ローカルのセッション記録から過去の Claude Code の利用状況をインポートしますか？ ~/.claude/projects を一度だけ読み取り、履歴を空ではなく過去数週間分から開始します。",
 };
@@ -5,18 +5,66 @@ pub(super) const UPDATE_VIA_WINGET_LABEL: &str = "WinGet으로 업데이트";
 pub(super) const STRINGS: Strings = Strings {
     window_title: "Claude Code 사용량 모니터",
     refresh: "새로고침",
+    pause_polling: "업데이트 일시 중지",
     update_frequency: "업데이트 주기",
+    thirty_seconds: "30초",
     one_minute: "1분",
     five_minutes: "5분",
     fifteen_minutes: "15분",
+    thirty_minutes: "30분",
     one_hour: "1시간",
+    custom_interval: "사용자 지정…",
+    custom_interval_prompt: "업데이트 간격(최소 15초, 예: 45, 90s, 5m, 1h):",
+    custom_interval_invalid: "15초 이상의 간격을 입력하세요(예: 45, 90s, 5m, 1h).",
+    ok: "확인",
+    cancel: "취소",
+    smart_polling: "스마트 (자동 조정)",
     models: "모델",
     claude_code_model: "Claude Code",
     codex_model: "Codex",
     antigravity_model: "Antigravity",
     settings: "설정",
     start_with_windows: "Windows 시작 시 자동 실행",
+    start_with_windows_task: "Windows 시작 시 자동 실행 (작업 스케줄러)",
+    startup_task_failed: "작업 스케줄러 시작 작업을 업데이트할 수 없습니다",
     reset_position: "위치 초기화",
+    reembed_in_taskbar: "작업 표시줄에 다시 배치",
+    float_over_taskbar: "작업 표시줄 위에 띄우기",
+    restart_widget: "위젯 다시 시작",
+    show_burn_rate: "카운트다운 대신 소모 속도 표시",
+    card_background: "카드 배경",
+    opacity: "불투명도",
+    show_divider: "구분선 표시",
+    emphasize_high_usage: "높은 사용량 강조",
+    mirror_layout: "오른쪽에서 왼쪽 레이아웃",
+    one_decimal: "소수점 한 자리",
+    bars_show_time_elapsed: "막대에 경과 시간 표시",
+    show_row: "{row} 행 표시",
+    blend_with_taskbar: "작업 표시줄과 어우러지게",
+    weekly_first: "주간 한도를 위에 표시",
+    urgent_row_first: "가장 급한 한도를 위에",
+    color_palette: "색상 팔레트",
+    palette_standard: "표준",
+    palette_deuteranopia: "녹색약 (파랑/주황)",
+    palette_protanopia: "적색약 (파랑/주황)",
+    left_click: "왼쪽 클릭",
+    click_show_details: "세부 정보 표시",
+    click_open_usage_page: "사용량 페이지 열기",
+    open_usage_dashboard: "사용량 대시보드 열기",
+    click_do_nothing: "아무 작업 안 함",
+    show_cost: "예상 비용 표시",
+    show_token_counts: "토큰 수 표시",
+    show_messages_left: "남은 메시지 수 표시",
+    notify_usage_jumps: "사용량 급증 알림",
+    notify_limit_resets: "한도 초기화 시 알림",
+    notify_usage_resets: "사용량 초기화 시 알림",
+    weekly_reminder: "주간 초기화 알림",
+    reminder_off: "끄기",
+    reminder_hours_before: "{hours}시간 전",
+    quiet_when_fullscreen: "전체 화면에서 조용히",
+    pause_on_metered: "종량제 연결에서 일시 중지",
+    poll_less_on_battery: "배터리 절약 시 덜 자주 업데이트",
+    api_key_limits: "API 키 한도 표시",
     language: "언어",
     system_default: "시스템 기본값",
     check_for_updates: "업데이트 확인",
@@ -32,9 +80,71 @@ pub(super) const STRINGS: Strings = Strings {
     update_prompt_now: "버전 {version}을 사용할 수 있습니다. 지금 업데이트하시겠습니까?",
     exit: "종료",
     show_widget: "위젯 표시",
+    badge_mode: "작업 표시줄 배지만",
     session_window: "5시간",
     weekly_window: "7일",
+    opus_window: "7일 Opus",
+    requests_window: "요청",
+    tokens_window: "토큰",
     now: "지금",
+    paused: "일시 중지됨",
+    none_left: "0 남음",
+    burn_rate: "소모 속도",
+    tokens_7d: "7일 토큰",
+    estimated_cost: "예상 비용",
+    tokens_today: "오늘 토큰",
+    tokens_in: "입력",
+    tokens_out: "출력",
+    tokens_cached: "캐시",
+    plan: "요금제",
+    plan_session_messages: "{window}당 약 {count}개 메시지",
+    messages_left: "약 {count}개 메시지 남음",
+    usage_by_hour: "5시간 사용량이 오르는 시간대 (최근 4주)",
+    weekday_initials: "월화수목금토일",
+    recent_resets: "최근 초기화",
+    weekday_names: "월,화,수,목,금,토,일",
+    pace_projection: "이 속도라면",
+    projected_limit: "{when}에 {window} 한도 도달",
+    projected_at_reset: "{window} 초기화 시 약 {percent} 사용",
+    usage_jump_title: "사용량 급증",
+    usage_jump_body: "{model} {window} 사용량이 {minutes}분 만에 {from}에서 {to}(으)로 증가했습니다",
+    alert_title: "사용량 알림",
+    alert_body: "{model} {window} 사용량이 {percent}에 도달했습니다",
+    snooze_alerts: "알림 일시 중지",
+    snooze_for_hour: "1시간 동안",
+    snooze_until_reset: "다음 초기화까지",
+    resume_alerts: "알림 다시 시작",
+    limit_reset_soon_title: "곧 한도 초기화",
+    limit_reset_soon_body: "{model} {window} 구간이 {time} 후 초기화됩니다.",
+    limit_reset_title: "한도 초기화됨",
+    limit_reset_body: "{model} {window} 구간이 초기화되었습니다.",
+    usage_reset_title: "사용량 초기화됨",
+    usage_reset_body: "{model} {window} 창이 초기화되었습니다 (이전 {from}).",
+    poll_failed: "마지막 조회 실패",
+    failure_reason: "원인",
+    reason_auth_required: "로그인 필요",
+    reason_token_expired: "토큰 만료",
+    reason_no_credentials: "자격 증명을 찾을 수 없음",
+    reason_request_failed: "요청 실패",
+    last_response: "마지막 응답",
+    credentials_checked: "확인한 자격 증명",
+    retry: "다시 시도",
+    export_history: "사용 기록 내보내기…",
+    export_history_failed: "사용 기록을 내보낼 수 없습니다",
+    copy_snapshot: "스냅샷 복사",
+    save_snapshot: "스냅샷 저장…",
+    snapshot_failed: "스냅샷을 만들 수 없습니다",
+    snapshot_empty: "위젯에 아직 표시할 내용이 없습니다.",
+    interval_suggestion_title: "자주 새로 고치시나요?",
+    interval_suggestion_body: "한 시간에 약 {count}번 수동으로 새로 고칩니다. 여기를 클릭하면 업데이트 빈도를 {interval}(으)로 설정합니다.",
+    welcome_title: "Claude Code에 로그인하세요",
+    welcome_body: "Claude Code에 로그인하면 사용량이 표시됩니다. claude를 실행하고 로그인하세요. 로그인 정보는 {path}에서 읽습니다. 여기를 클릭하면 README를 엽니다.",
+    weekly_reminder_title: "주간 한도가 곧 초기화됨",
+    weekly_reminder_body: "주간 한도가 {time} 후 초기화됩니다 — {unused}% 미사용.",
+    held_notifications_title: "방해 금지 모드 중 알림",
+    crash_title: "Claude Code Usage Monitor가 중지됨",
+    crash_body: "문제가 발생하여 모니터를 종료해야 했습니다. 자세한 내용은 settings.json 옆의 crash.log에 있습니다.",
+    crash_restarted_body: "예기치 않은 오류 후 모니터가 다시 시작되었습니다. 자세한 내용은 settings.json 옆의 crash.log에 있습니다.",
     day_suffix: "일",
     hour_suffix: "시간",
     minute_suffix: "분",
@@ -44,7 +154,11 @@ pub(super) const STRINGS: Strings = Strings {
     codex_token_expired_body: "터미널에서 'codex'를 실행하고 로그인 안내를 따르세요. 그런 다음 이 앱을 새로 고치거나 다시 시작하세요.",
     antigravity_token_expired_title: "Antigravity 인증 오류",
     antigravity_token_expired_body: "Antigravity를 열고 다시 로그인하세요. 그런 다음 이 앱을 새로 고치거나 다시 시작하세요.",
+    service_unreachable_title: "사용량 서비스에 연결할 수 없음",
+    service_unreachable_body: "{count}번 시도했지만 사용량 서비스에 연결할 수 없습니다. 백그라운드에서 계속 재시도합니다. 연결 또는 프록시를 확인하세요.",
     codex_window_title: "Codex 사용량 모니터",
     antigravity_window_title: "Antigravity 사용량 모니터",
     second_suffix: "초",
+    backfill_title: "사용 기록 가져오기",
+    backfill_prompt: "로컬 세션 기록에서 이전 Claude Code 활동을 가져올까요? ~/.claude/projects를 한 번만 읽어 기록이 비어 있지 않고 최근 몇 주의 사용량으로 시작됩니다.",
 };
@@ -139,18 +139,66 @@ impl LanguageId {
 pub struct Strings {
     pub window_title: &'static str,
     pub refresh: &'static str,
+    pub pause_polling: &'static str,
     pub update_frequency: &'static str,
+    pub thirty_seconds: &'static str,
     pub one_minute: &'static str,
     pub five_minutes: &'static str,
     pub fifteen_minutes: &'static str,
+    pub thirty_minutes: &'static str,
     pub one_hour: &'static str,
+    pub custom_interval: &'static str,
+    pub custom_interval_prompt: &'static str,
+    pub custom_interval_invalid: &'static str,
+    pub ok: &'static str,
+    pub cancel: &'static str,
+    pub smart_polling: &'static str,
     pub models: &'static str,
     pub claude_code_model: &'static str,
     pub codex_model: &'static str,
     pub antigravity_model: &'static str,
     pub settings: &'static str,
     pub start_with_windows: &'static str,
+    pub start_with_windows_task: &'static str,
+    pub startup_task_failed: &'static str,
     pub reset_position: &'static str,
+    pub reembed_in_taskbar: &'static str,
+    pub float_over_taskbar: &'static str,
+    pub restart_widget: &'static str,
+    pub show_burn_rate: &'static str,
+    pub card_background: &'static str,
+    pub opacity: &'static str,
+    pub show_divider: &'static str,
+    pub emphasize_high_usage: &'static str,
+    pub mirror_layout: &'static str,
+    pub one_decimal: &'static str,
+    pub bars_show_time_elapsed: &'static str,
+    pub show_row: &'static str,
+    pub blend_with_taskbar: &'static str,
+    pub weekly_first: &'static str,
+    pub urgent_row_first: &'static str,
+    pub color_palette: &'static str,
+    pub palette_standard: &'static str,
+    pub palette_deuteranopia: &'static str,
+    pub palette_protanopia: &'static str,
+    pub left_click: &'static str,
+    pub click_show_details: &'static str,
+    pub click_open_usage_page: &'static str,
+    pub open_usage_dashboard: &'static str,
+    pub click_do_nothing: &'static str,
+    pub show_cost: &'static str,
+    pub show_token_counts: &'static str,
+    pub show_messages_left: &'static str,
+    pub notify_usage_jumps: &'static str,
+    pub notify_limit_resets: &'static str,
+    pub notify_usage_resets: &'static str,
+    pub weekly_reminder: &'static str,
+    pub reminder_off: &'static str,
+    pub reminder_hours_before: &'static str,
+    pub quiet_when_fullscreen: &'static str,
+    pub pause_on_metered: &'static str,
+    pub poll_less_on_battery: &'static str,
+    pub api_key_limits: &'static str,
     pub language: &'static str,
     pub system_default: &'static str,
     pub check_for_updates: &'static str,
@@ -166,9 +214,73 @@ pub struct Strings {
     pub update_prompt_now: &'static str,
     pub exit: &'static str,
     pub show_widget: &'static str,
+    pub badge_mode: &'static str,
     pub session_window: &'static str,
     pub weekly_window: &'static str,
+    pub opus_window: &'static str,
+    pub requests_window: &'static str,
+    pub tokens_window: &'static str,
     pub now: &'static str,
+    pub paused: &'static str,
+    pub none_left: &'static str,
+    pub burn_rate: &'static str,
+    pub tokens_7d: &'static str,
+    pub estimated_cost: &'static str,
+    pub tokens_today: &'static str,
+    pub tokens_in: &'static str,
+    pub tokens_out: &'static str,
+    pub tokens_cached: &'static str,
+    pub plan: &'static str,
+    pub plan_session_messages: &'static str,
+    pub messages_left: &'static str,
+    pub usage_by_hour: &'static str,
+    /// One character per weekday, Monday first.
+    pub weekday_initials: &'static str,
+    pub recent_resets: &'static str,
+    /// Comma-separated short weekday names, Monday first.
+    pub weekday_names: &'static str,
+    pub pace_projection: &'static str,
+    pub projected_limit: &'static str,
+    pub projected_at_reset: &'static str,
+    pub usage_jump_title: &'static str,
+    pub usage_jump_body: &'static str,
+    pub alert_title: &'static str,
+    pub alert_body: &'static str,
+    pub snooze_alerts: &'static str,
+    pub snooze_for_hour: &'static str,
+    pub snooze_until_reset: &'static str,
+    pub resume_alerts: &'static str,
+    pub limit_reset_soon_title: &'static str,
+    pub limit_reset_soon_body: &'static str,
+    pub limit_reset_title: &'static str,
+    pub limit_reset_body: &'static str,
+    pub usage_reset_title: &'static str,
+    pub usage_reset_body: &'static str,
+    pub poll_failed: &'static str,
+    pub failure_reason: &'static str,
+    pub reason_auth_required: &'static str,
+    pub reason_token_expired: &'static str,
+    pub reason_no_credentials: &'static str,
+    pub reason_request_failed: &'static str,
+    pub last_response: &'static str,
+    pub credentials_checked: &'static str,
+    pub retry: &'static str,
+    pub export_history: &'static str,
+    pub export_history_failed: &'static str,
+    pub copy_snapshot: &'static str,
+    pub save_snapshot: &'static str,
+    pub snapshot_failed: &'static str,
+    pub snapshot_empty: &'static str,
+    pub interval_suggestion_title: &'static str,
+    pub interval_suggestion_body: &'static str,
+    pub welcome_title: &'static str,
+    pub welcome_body: &'static str,
+    pub weekly_reminder_title: &'static str,
+    pub weekly_reminder_body: &'static str,
+    pub held_notifications_title: &'static str,
+    pub crash_title: &'static str,
+    pub crash_body: &'static str,
+    pub crash_restarted_body: &'static str,
     pub day_suffix: &'static str,
     pub hour_suffix: &'static str,
     pub minute_suffix: &'static str,
@@ -179,8 +291,12 @@ pub struct Strings {
     pub codex_token_expired_body: &'static str,
     pub antigravity_token_expired_title: &'static str,
     pub antigravity_token_expired_body: &'static str,
+    pub service_unreachable_title: &'static str,
+    pub service_unreachable_body: &'static str,
     pub codex_window_title: &'static str,
     pub antigravity_window_title: &'static str,
+    pub backfill_title: &'static str,
+    pub backfill_prompt: &'static str,
 }
 
 pub fn resolve_language(language_override: Option<LanguageId>) -> LanguageId {
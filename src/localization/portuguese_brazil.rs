@@ -5,18 +5,66 @@ pub(super) const UPDATE_VIA_WINGET_LABEL: &str = "Atualizar via WinGet";
 pub(super) const STRINGS: Strings = Strings {
     window_title: "Monitor de Uso do Claude Code",
     refresh: "Atualizar",
+    pause_polling: "Pausar atualizações",
     update_frequency: "Frequência de Atualização",
+    thirty_seconds: "30 segundos",
     one_minute: "1 Minuto",
     five_minutes: "5 Minutos",
     fifteen_minutes: "15 Minutos",
+    thirty_minutes: "30 minutos",
     one_hour: "1 Hora",
+    custom_interval: "Personalizado…",
+    custom_interval_prompt: "Atualizar a cada (mínimo de 15 segundos, ex.: 45, 90s, 5m ou 1h):",
+    custom_interval_invalid: "Digite um intervalo de pelo menos 15 segundos, como 45, 90s, 5m ou 1h.",
+    ok: "OK",
+    cancel: "Cancelar",
+    smart_polling: "Inteligente (adaptável)",
     models: "Modelos",
     claude_code_model: "Claude Code",
     codex_model: "Codex",
     antigravity_model: "Antigravity",
     settings: "Configurações",
     start_with_windows: "Iniciar com o Windows",
+    start_with_windows_task: "Iniciar com o Windows (Agendador de Tarefas)",
+    startup_task_failed: "Não foi possível atualizar a tarefa de inicialização do Agendador de Tarefas",
     reset_position: "Redefinir Posição",
+    reembed_in_taskbar: "Reinserir na barra de tarefas",
+    float_over_taskbar: "Flutuar sobre a barra de tarefas",
+    restart_widget: "Reiniciar widget",
+    show_burn_rate: "Mostrar ritmo em vez da contagem regressiva",
+    card_background: "Fundo em cartão",
+    opacity: "Opacidade",
+    show_divider: "Mostrar divisor",
+    emphasize_high_usage: "Destacar uso alto",
+    mirror_layout: "Layout da direita para a esquerda",
+    one_decimal: "Uma casa decimal",
+    bars_show_time_elapsed: "Barras mostram tempo decorrido",
+    show_row: "Mostrar linha {row}",
+    blend_with_taskbar: "Mesclar com a barra de tarefas",
+    weekly_first: "Limite semanal no topo",
+    urgent_row_first: "Limite mais urgente no topo",
+    color_palette: "Paleta de cores",
+    palette_standard: "Padrão",
+    palette_deuteranopia: "Deuteranopia (azul/laranja)",
+    palette_protanopia: "Protanopia (azul/laranja)",
+    left_click: "Clique esquerdo",
+    click_show_details: "Mostrar detalhes",
+    click_open_usage_page: "Abrir página de uso",
+    open_usage_dashboard: "Abrir painel de uso",
+    click_do_nothing: "Não fazer nada",
+    show_cost: "Mostrar custo estimado",
+    show_token_counts: "Mostrar contagem de tokens",
+    show_messages_left: "Mostrar mensagens restantes",
+    notify_usage_jumps: "Alertar sobre picos de uso",
+    notify_limit_resets: "Notificar quando os limites forem redefinidos",
+    notify_usage_resets: "Notificar quando o uso for redefinido",
+    weekly_reminder: "Lembrete de redefinição semanal",
+    reminder_off: "Desativado",
+    reminder_hours_before: "{hours} horas antes",
+    quiet_when_fullscreen: "Silencioso em tela cheia",
+    pause_on_metered: "Pausar em conexões limitadas",
+    poll_less_on_battery: "Atualizar menos com economia de bateria",
+    api_key_limits: "Mostrar limites da chave de API",
     language: "Idioma",
     system_default: "Padrão do Sistema",
     check_for_updates: "Busca atualizações",
@@ -32,19 +80,85 @@ pub(super) const STRINGS: Strings = Strings {
     update_prompt_now: "Versão {version} está disponível. Deseja atualizar agora?",
     exit: "Sair",
     show_widget: "Exibir Widget",
+    badge_mode: "Somente selo na barra de tarefas",
     session_window: "5h",
     weekly_window: "7d",
+    opus_window: "7d Opus",
+    requests_window: "Rq",
+    tokens_window: "Tk",
     now: "agora",
+    paused: "Pausado",
+    none_left: "0 restante",
+    burn_rate: "Ritmo de consumo",
+    tokens_7d: "Tokens 7d",
+    estimated_cost: "Custo estimado",
+    tokens_today: "Tokens hoje",
+    tokens_in: "entrada",
+    tokens_out: "saída",
+    tokens_cached: "em cache",
+    plan: "Plano",
+    plan_session_messages: "~{count} mensagens por {window}",
+    messages_left: "~{count} mensagens restantes",
+    usage_by_hour: "Quando o uso de 5h sobe (últimas 4 semanas)",
+    weekday_initials: "STQQSSD",
+    recent_resets: "Redefinições recentes",
+    weekday_names: "seg,ter,qua,qui,sex,sáb,dom",
+    pace_projection: "Neste ritmo",
+    projected_limit: "limite de {window} atingido {when}",
+    projected_at_reset: "~{percent} usado quando {window} redefinir",
+    usage_jump_title: "Pico de uso",
+    usage_jump_body: "O uso {window} do {model} subiu de {from} para {to} em {minutes} min",
+    alert_title: "Alerta de uso",
+    alert_body: "O uso {window} do {model} chegou a {percent}",
+    snooze_alerts: "Adiar alertas",
+    snooze_for_hour: "Por 1 hora",
+    snooze_until_reset: "Até a próxima redefinição",
+    resume_alerts: "Retomar alertas",
+    limit_reset_soon_title: "Limite será redefinido em breve",
+    limit_reset_soon_body: "A janela de {window} do {model} será redefinida em {time}.",
+    limit_reset_title: "Limite redefinido",
+    limit_reset_body: "A janela de {window} do {model} foi redefinida.",
+    usage_reset_title: "Uso redefinido",
+    usage_reset_body: "A janela de {window} do {model} foi redefinida (estava em {from}).",
+    poll_failed: "Última consulta falhou",
+    failure_reason: "Motivo",
+    reason_auth_required: "Login necessário",
+    reason_token_expired: "Token expirado",
+    reason_no_credentials: "Nenhuma credencial encontrada",
+    reason_request_failed: "Falha na requisição",
+    last_response: "Última resposta",
+    credentials_checked: "Credenciais verificadas",
+    retry: "Tentar novamente",
+    export_history: "Exportar histórico de uso…",
+    export_history_failed: "Não foi possível exportar o histórico de uso",
+    copy_snapshot: "Copiar instantâneo",
+    save_snapshot: "Salvar instantâneo…",
+    snapshot_failed: "Não foi possível criar o instantâneo",
+    snapshot_empty: "O widget ainda não tem nada para mostrar.",
+    interval_suggestion_title: "Atualizando com frequência?",
+    interval_suggestion_body: "Você atualiza manualmente cerca de {count} vezes por hora. Clique aqui para definir a frequência de atualização como {interval}.",
+    welcome_title: "Entre no Claude Code",
+    welcome_body: "O uso aparece quando o Claude Code estiver conectado. Execute claude e entre; o login é lido de {path}. Clique aqui para abrir o README.",
+    weekly_reminder_title: "Limite semanal será redefinido em breve",
+    weekly_reminder_body: "Seu limite semanal será redefinido em {time} — {unused}% não utilizado.",
+    held_notifications_title: "Enquanto o Não incomodar estava ativado",
+    crash_title: "Claude Code Usage Monitor parou",
+    crash_body: "Algo deu errado e o monitor precisou fechar. Os detalhes estão em crash.log, ao lado de settings.json.",
+    crash_restarted_body: "O monitor foi reiniciado após um erro inesperado. Os detalhes estão em crash.log, ao lado de settings.json.",
     day_suffix: "d",
     hour_suffix: "h",
     minute_suffix: "m",
     second_suffix: "s",
+    backfill_title: "Importar histórico de uso",
+    backfill_prompt: "Importar sua atividade anterior do Claude Code a partir das transcrições locais? A pasta ~/.claude/projects é lida uma única vez para que o histórico comece com as últimas semanas de uso em vez de vazio.",
     token_expired_title: "Erro de Autenticação do Claude Code",
     token_expired_body: "Execute 'claude' em um terminal, use '/login' e siga as instruções. Depois disso, atualize ou reinicie este aplicativo.",
     codex_token_expired_title: "Erro de Autenticação do Codex",
     codex_token_expired_body: "Execute 'codex' em um terminal e siga as instruções de login. Depois disso, atualize ou reinicie este aplicativo.",
     antigravity_token_expired_title: "Erro de Autenticação do Antigravity",
     antigravity_token_expired_body: "Abra o Antigravity e entre novamente. Depois disso, atualize ou reinicie este aplicativo.",
+    service_unreachable_title: "Serviço de uso inacessível",
+    service_unreachable_body: "O serviço de uso não respondeu após {count} tentativas. O monitor continua tentando em segundo plano; verifique sua conexão ou proxy.",
     codex_window_title: "Monitor de uso do Codex",
     antigravity_window_title: "Monitor de uso do Antigravity",
 };
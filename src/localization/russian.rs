@@ -5,18 +5,66 @@ pub(super) const UPDATE_VIA_WINGET_LABEL: &str = "Обновить через Wi
 pub(super) const STRINGS: Strings = Strings {
     window_title: "Монитор использования Claude Code",
     refresh: "Обновить",
+    pause_polling: "Приостановить обновление",
     update_frequency: "Частота обновления",
+    thirty_seconds: "30 секунд",
     one_minute: "1 минута",
     five_minutes: "5 минут",
     fifteen_minutes: "15 минут",
+    thirty_minutes: "30 минут",
     one_hour: "1 час",
+    custom_interval: "Другое…",
+    custom_interval_prompt: "Обновлять каждые (не менее 15 секунд, например 45, 90s, 5m или 1h):",
+    custom_interval_invalid: "Введите интервал не менее 15 секунд, например 45, 90s, 5m или 1h.",
+    ok: "ОК",
+    cancel: "Отмена",
+    smart_polling: "Умный (адаптивный)",
     models: "Модели",
     claude_code_model: "Claude Code",
     codex_model: "Codex",
     antigravity_model: "Antigravity",
     settings: "Настройки",
     start_with_windows: "Запускать вместе с Windows",
+    start_with_windows_task: "Запускать вместе с Windows (Планировщик заданий)",
+    startup_task_failed: "Не удалось обновить задачу автозапуска в Планировщике заданий",
     reset_position: "Сбросить позицию",
+    reembed_in_taskbar: "Снова встроить в панель задач",
+    float_over_taskbar: "Поверх панели задач",
+    restart_widget: "Перезапустить виджет",
+    show_burn_rate: "Показывать скорость расхода вместо таймера",
+    card_background: "Фон-карточка",
+    opacity: "Непрозрачность",
+    show_divider: "Показывать разделитель",
+    emphasize_high_usage: "Выделять высокое использование",
+    mirror_layout: "Макет справа налево",
+    one_decimal: "Один знак после запятой",
+    bars_show_time_elapsed: "Полосы показывают прошедшее время",
+    show_row: "Показывать строку {row}",
+    blend_with_taskbar: "Сливаться с панелью задач",
+    weekly_first: "Недельный лимит сверху",
+    urgent_row_first: "Самый срочный лимит сверху",
+    color_palette: "Цветовая палитра",
+    palette_standard: "Стандартная",
+    palette_deuteranopia: "Дейтеранопия (синий/оранжевый)",
+    palette_protanopia: "Протанопия (синий/оранжевый)",
+    left_click: "Левый щелчок",
+    click_show_details: "Показать подробности",
+    click_open_usage_page: "Открыть страницу использования",
+    open_usage_dashboard: "Открыть панель использования",
+    click_do_nothing: "Ничего не делать",
+    show_cost: "Показывать оценку стоимости",
+    show_token_counts: "Показывать число токенов",
+    show_messages_left: "Показывать оставшиеся сообщения",
+    notify_usage_jumps: "Предупреждать о скачках использования",
+    notify_limit_resets: "Уведомлять о сбросе лимитов",
+    notify_usage_resets: "Уведомлять о сбросе использования",
+    weekly_reminder: "Напоминание о недельном сбросе",
+    reminder_off: "Выкл.",
+    reminder_hours_before: "За {hours} ч",
+    quiet_when_fullscreen: "Тихий режим в полноэкранных приложениях",
+    pause_on_metered: "Приостанавливать на лимитных подключениях",
+    poll_less_on_battery: "Реже обновлять в режиме экономии заряда",
+    api_key_limits: "Показывать лимиты API-ключа",
     language: "Язык",
     system_default: "Системный по умолчанию",
     check_for_updates: "Проверить обновления",
@@ -32,19 +80,85 @@ pub(super) const STRINGS: Strings = Strings {
     update_prompt_now: "Доступна версия {version}. Обновить сейчас?",
     exit: "Выход",
     show_widget: "Показать виджет",
+    badge_mode: "Только значок на панели задач",
     session_window: "5ч",
     weekly_window: "7д",
+    opus_window: "7д Opus",
+    requests_window: "Зп",
+    tokens_window: "Тк",
     now: "сейчас",
+    paused: "Пауза",
+    none_left: "Осталось 0",
+    burn_rate: "Скорость расхода",
+    tokens_7d: "Токены за 7д",
+    estimated_cost: "Оценка стоимости",
+    tokens_today: "Токены за сегодня",
+    tokens_in: "вход",
+    tokens_out: "выход",
+    tokens_cached: "кэш",
+    plan: "Тариф",
+    plan_session_messages: "~{count} сообщений за {window}",
+    messages_left: "осталось ~{count} сообщ.",
+    usage_by_hour: "Когда растёт использование за 5 ч (4 недели)",
+    weekday_initials: "ПВСЧПСВ",
+    recent_resets: "Последние сбросы",
+    weekday_names: "пн,вт,ср,чт,пт,сб,вс",
+    pace_projection: "В этом темпе",
+    projected_limit: "лимит {window} будет достигнут {when}",
+    projected_at_reset: "~{percent} к сбросу {window}",
+    usage_jump_title: "Скачок использования",
+    usage_jump_body: "Использование {model} за {window} выросло с {from} до {to} за {minutes} мин",
+    alert_title: "Предупреждение об использовании",
+    alert_body: "Использование {model} за {window} достигло {percent}",
+    snooze_alerts: "Отложить предупреждения",
+    snooze_for_hour: "На 1 час",
+    snooze_until_reset: "До следующего сброса",
+    resume_alerts: "Возобновить предупреждения",
+    limit_reset_soon_title: "Лимит скоро сбросится",
+    limit_reset_soon_body: "Окно {window} для {model} сбросится через {time}.",
+    limit_reset_title: "Лимит сброшен",
+    limit_reset_body: "Окно {window} для {model} сброшено.",
+    usage_reset_title: "Использование сброшено",
+    usage_reset_body: "Окно {window} {model} сброшено (было {from}).",
+    poll_failed: "Последний опрос не удался",
+    failure_reason: "Причина",
+    reason_auth_required: "Требуется вход",
+    reason_token_expired: "Срок действия токена истёк",
+    reason_no_credentials: "Учётные данные не найдены",
+    reason_request_failed: "Ошибка запроса",
+    last_response: "Последний ответ",
+    credentials_checked: "Проверенные учётные данные",
+    retry: "Повторить",
+    export_history: "Экспорт истории использования…",
+    export_history_failed: "Не удалось экспортировать историю использования",
+    copy_snapshot: "Копировать снимок",
+    save_snapshot: "Сохранить снимок…",
+    snapshot_failed: "Не удалось создать снимок",
+    snapshot_empty: "Виджету пока нечего показать.",
+    interval_suggestion_title: "Часто обновляете?",
+    interval_suggestion_body: "Вы обновляете вручную примерно {count} раз в час. Нажмите здесь, чтобы установить частоту обновления {interval}.",
+    welcome_title: "Войдите в Claude Code",
+    welcome_body: "Использование появится после входа в Claude Code. Запустите claude и войдите; данные входа читаются из {path}. Нажмите здесь, чтобы открыть README.",
+    weekly_reminder_title: "Недельный лимит скоро сбросится",
+    weekly_reminder_body: "Недельный лимит сбросится через {time} — не использовано {unused}%.",
+    held_notifications_title: "Пока был включён режим «Не беспокоить»",
+    crash_title: "Claude Code Usage Monitor остановлен",
+    crash_body: "Произошла ошибка, и монитор пришлось закрыть. Подробности — в crash.log рядом с settings.json.",
+    crash_restarted_body: "Монитор перезапущен после непредвиденной ошибки. Подробности — в crash.log рядом с settings.json.",
     day_suffix: "д",
     hour_suffix: "ч",
     minute_suffix: "м",
     second_suffix: "с",
+    backfill_title: "Импорт истории использования",
+    backfill_prompt: "Импортировать прошлую активность Claude Code из локальных журналов сеансов? Папка ~/.claude/projects будет прочитана один раз, чтобы история начиналась с последних недель, а не была пустой.",
     token_expired_title: "Ошибка авторизации Claude Code",
     token_expired_body: "Запустите 'claude' в терминале, затем используйте '/login' и следуйте инструкциям. После этого обновите или перезапустите приложение.",
     codex_token_expired_title: "Ошибка авторизации Codex",
     codex_token_expired_body: "Запустите 'codex' в терминале и следуйте инструкциям для входа. После этого обновите или перезапустите приложение.",
     antigravity_token_expired_title: "Ошибка авторизации Antigravity",
     antigravity_token_expired_body: "Откройте Antigravity и войдите снова. После этого обновите или перезапустите приложение.",
+    service_unreachable_title: "Сервис использования недоступен",
+    service_unreachable_body: "Сервис использования недоступен после {count} попыток. Монитор продолжает повторять попытки в фоне; проверьте подключение или прокси.",
     codex_window_title: "Монитор использования Codex",
     antigravity_window_title: "Монитор использования Antigravity",
 };
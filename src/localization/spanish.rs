@@ -5,18 +5,66 @@ pub(super) const UPDATE_VIA_WINGET_LABEL: &str = "Actualizar con WinGet";
 pub(super) const STRINGS: Strings = Strings {
     window_title: "Monitor de uso de Claude Code",
     refresh: "Actualizar",
+    pause_polling: "Pausar actualizaciones",
     update_frequency: "Frecuencia de actualización",
+    thirty_seconds: "30 segundos",
     one_minute: "1 minuto",
     five_minutes: "5 minutos",
     fifteen_minutes: "15 minutos",
+    thirty_minutes: "30 minutos",
     one_hour: "1 hora",
+    custom_interval: "Personalizado…",
+    custom_interval_prompt: "Actualizar cada (mínimo 15 segundos, p. ej. 45, 90s, 5m o 1h):",
+    custom_interval_invalid: "Introduce un intervalo de al menos 15 segundos, como 45, 90s, 5m o 1h.",
+    ok: "Aceptar",
+    cancel: "Cancelar",
+    smart_polling: "Inteligente (adaptativo)",
     models: "Modelos",
     claude_code_model: "Claude Code",
     codex_model: "Codex",
     antigravity_model: "Antigravity",
     settings: "Configuración",
     start_with_windows: "Iniciar con Windows",
+    start_with_windows_task: "Iniciar con Windows (Programador de tareas)",
+    startup_task_failed: "No se pudo actualizar la tarea de inicio del Programador de tareas",
     reset_position: "Restablecer posición",
+    reembed_in_taskbar: "Volver a integrar en la barra de tareas",
+    float_over_taskbar: "Flotar sobre la barra de tareas",
+    restart_widget: "Reiniciar widget",
+    show_burn_rate: "Mostrar ritmo en lugar de la cuenta atrás",
+    card_background: "Fondo de tarjeta",
+    opacity: "Opacidad",
+    show_divider: "Mostrar separador",
+    emphasize_high_usage: "Resaltar uso alto",
+    mirror_layout: "Diseño de derecha a izquierda",
+    one_decimal: "Un decimal",
+    bars_show_time_elapsed: "Barras con tiempo transcurrido",
+    show_row: "Mostrar fila {row}",
+    blend_with_taskbar: "Fundir con la barra de tareas",
+    weekly_first: "Límite semanal arriba",
+    urgent_row_first: "Límite más urgente arriba",
+    color_palette: "Paleta de colores",
+    palette_standard: "Estándar",
+    palette_deuteranopia: "Deuteranopía (azul/naranja)",
+    palette_protanopia: "Protanopía (azul/naranja)",
+    left_click: "Clic izquierdo",
+    click_show_details: "Mostrar detalles",
+    click_open_usage_page: "Abrir página de uso",
+    open_usage_dashboard: "Abrir panel de uso",
+    click_do_nothing: "No hacer nada",
+    show_cost: "Mostrar costo estimado",
+    show_token_counts: "Mostrar recuento de tokens",
+    show_messages_left: "Mostrar mensajes restantes",
+    notify_usage_jumps: "Avisar de picos de uso",
+    notify_limit_resets: "Avisar cuando se reinicien los límites",
+    notify_usage_resets: "Notificar cuando se reinicie el uso",
+    weekly_reminder: "Recordatorio de reinicio semanal",
+    reminder_off: "Desactivado",
+    reminder_hours_before: "{hours} horas antes",
+    quiet_when_fullscreen: "Silencio en pantalla completa",
+    pause_on_metered: "Pausar en conexiones de uso medido",
+    poll_less_on_battery: "Actualizar menos con ahorro de batería",
+    api_key_limits: "Mostrar límites de la clave de API",
     language: "Idioma",
     system_default: "Predeterminado del sistema",
     check_for_updates: "Buscar actualizaciones",
@@ -32,9 +80,71 @@ pub(super) const STRINGS: Strings = Strings {
     update_prompt_now: "La versión {version} está disponible. ¿Quieres actualizar ahora?",
     exit: "Salir",
     show_widget: "Mostrar widget",
+    badge_mode: "Solo insignia en la barra de tareas",
     session_window: "5h",
     weekly_window: "7d",
+    opus_window: "7d Opus",
+    requests_window: "Sl",
+    tokens_window: "Tk",
     now: "ahora",
+    paused: "En pausa",
+    none_left: "0 restante",
+    burn_rate: "Ritmo de consumo",
+    tokens_7d: "Tokens 7d",
+    estimated_cost: "Costo estimado",
+    tokens_today: "Tokens hoy",
+    tokens_in: "entrada",
+    tokens_out: "salida",
+    tokens_cached: "en caché",
+    plan: "Plan",
+    plan_session_messages: "~{count} mensajes por {window}",
+    messages_left: "~{count} mensajes restantes",
+    usage_by_hour: "Cuándo sube el uso de 5 h (últimas 4 semanas)",
+    weekday_initials: "LMXJVSD",
+    recent_resets: "Reinicios recientes",
+    weekday_names: "lun,mar,mié,jue,vie,sáb,dom",
+    pace_projection: "A este ritmo",
+    projected_limit: "límite de {window} alcanzado {when}",
+    projected_at_reset: "~{percent} usado al reiniciarse {window}",
+    usage_jump_title: "Pico de uso",
+    usage_jump_body: "El uso {window} de {model} subió de {from} a {to} en {minutes} min",
+    alert_title: "Alerta de uso",
+    alert_body: "El uso {window} de {model} llegó a {percent}",
+    snooze_alerts: "Posponer alertas",
+    snooze_for_hour: "Durante 1 hora",
+    snooze_until_reset: "Hasta el próximo reinicio",
+    resume_alerts: "Reanudar alertas",
+    limit_reset_soon_title: "El límite se reinicia pronto",
+    limit_reset_soon_body: "La ventana de {window} de {model} se reinicia en {time}.",
+    limit_reset_title: "Límite reiniciado",
+    limit_reset_body: "La ventana de {window} de {model} se ha reiniciado.",
+    usage_reset_title: "Uso reiniciado",
+    usage_reset_body: "La ventana de {window} de {model} se ha reiniciado (estaba en {from}).",
+    poll_failed: "La última consulta falló",
+    failure_reason: "Motivo",
+    reason_auth_required: "Se requiere iniciar sesión",
+    reason_token_expired: "Token caducado",
+    reason_no_credentials: "No se encontraron credenciales",
+    reason_request_failed: "Error en la solicitud",
+    last_response: "Última respuesta",
+    credentials_checked: "Credenciales comprobadas",
+    retry: "Reintentar",
+    export_history: "Exportar historial de uso…",
+    export_history_failed: "No se pudo exportar el historial de uso",
+    copy_snapshot: "Copiar instantánea",
+    save_snapshot: "Guardar instantánea…",
+    snapshot_failed: "No se pudo crear la instantánea",
+    snapshot_empty: "El widget aún no tiene nada que mostrar.",
+    interval_suggestion_title: "¿Actualizas a menudo?",
+    interval_suggestion_body: "Actualizas manualmente unas {count} veces por hora. Haz clic aquí para establecer la frecuencia de actualización en {interval}.",
+    welcome_title: "Inicia sesión en Claude Code",
+    welcome_body: "El uso aparece cuando Claude Code ha iniciado sesión. Ejecuta claude e inicia sesión; el inicio de sesión se lee de {path}. Haz clic aquí para ver el README.",
+    weekly_reminder_title: "El límite semanal se reinicia pronto",
+    weekly_reminder_body: "Tu límite semanal se reinicia en {time} — {unused} % sin usar.",
+    held_notifications_title: "Mientras No molestar estaba activado",
+    crash_title: "Claude Code Usage Monitor se detuvo",
+    crash_body: "Algo salió mal y el monitor tuvo que cerrarse. Los detalles están en crash.log, junto a settings.json.",
+    crash_restarted_body: "El monitor se reinició tras un error inesperado. Los detalles están en crash.log, junto a settings.json.",
     day_suffix: "d",
     hour_suffix: "h",
     minute_suffix: "m",
@@ -44,7 +154,11 @@ pub(super) const STRINGS: Strings = Strings {
     codex_token_expired_body: "Ejecuta 'codex' en una terminal y sigue las indicaciones de inicio de sesion. Despues, actualiza o reinicia esta aplicacion.",
     antigravity_token_expired_title: "Error de autenticacion de Antigravity",
     antigravity_token_expired_body: "Abre Antigravity e inicia sesion otra vez. Despues, actualiza o reinicia esta aplicacion.",
+    service_unreachable_title: "Servicio de uso inaccesible",
+    service_unreachable_body: "No se pudo contactar con el servicio de uso tras {count} intentos. El monitor sigue reintentando en segundo plano; revisa tu conexión o proxy.",
     codex_window_title: "Monitor de uso de Codex",
     antigravity_window_title: "Monitor de uso de Antigravity",
     second_suffix: "s",
+    backfill_title: "Importar historial de uso",
+    backfill_prompt: "¿Importar tu actividad anterior de Claude Code desde las transcripciones locales? Se lee ~/.claude/projects una sola vez para que el historial empiece con las últimas semanas de uso en lugar de vacío.",
 };
@@ -5,18 +5,66 @@ pub(super) const UPDATE_VIA_WINGET_LABEL: &str = "透過 WinGet 更新";
 pub(super) const STRINGS: Strings = Strings {
     window_title: "Claude Code 使用量監控",
     refresh: "重新整理",
+    pause_polling: "暫停更新",
     update_frequency: "更新頻率",
+    thirty_seconds: "30 秒",
     one_minute: "1 分鐘",
     five_minutes: "5 分鐘",
     fifteen_minutes: "15 分鐘",
+    thirty_minutes: "30 分鐘",
     one_hour: "1 小時",
+    custom_interval: "自訂…",
+    custom_interval_prompt: "更新間隔（至少 15 秒，例如 45、90s、5m 或 1h）：",
+    custom_interval_invalid: "請輸入至少 15 秒的間隔，例如 45、90s、5m 或 1h。",
+    ok: "確定",
+    cancel: "取消",
+    smart_polling: "智慧 (自動調整)",
     models: "模型",
     claude_code_model: "Claude Code",
     codex_model: "Codex",
     antigravity_model: "Antigravity",
     settings: "設定",
     start_with_windows: "開機時啟動",
+    start_with_windows_task: "開機時啟動 (工作排程器)",
+    startup_task_failed: "無法更新工作排程器的啟動工作",
     reset_position: "重置位置",
+    reembed_in_taskbar: "重新嵌入工作列",
+    float_over_taskbar: "浮動於工作列上方",
+    restart_widget: "重新啟動小工具",
+    show_burn_rate: "以消耗速度取代倒數計時",
+    card_background: "卡片背景",
+    opacity: "不透明度",
+    show_divider: "顯示分隔線",
+    emphasize_high_usage: "強調高使用量",
+    mirror_layout: "由右至左版面配置",
+    one_decimal: "顯示一位小數",
+    bars_show_time_elapsed: "長條顯示已經過時間",
+    show_row: "顯示 {row} 列",
+    blend_with_taskbar: "與工作列融合",
+    weekly_first: "週限制置頂",
+    urgent_row_first: "最緊迫的上限置頂",
+    color_palette: "色彩配置",
+    palette_standard: "標準",
+    palette_deuteranopia: "綠色弱 (藍/橙)",
+    palette_protanopia: "紅色弱 (藍/橙)",
+    left_click: "左鍵點按",
+    click_show_details: "顯示詳細資料",
+    click_open_usage_page: "開啟使用量頁面",
+    open_usage_dashboard: "開啟使用量儀表板",
+    click_do_nothing: "不執行任何動作",
+    show_cost: "顯示預估費用",
+    show_token_counts: "顯示權杖數量",
+    show_messages_left: "顯示剩餘訊息數",
+    notify_usage_jumps: "使用量暴增時提醒",
+    notify_limit_resets: "上限重設時通知",
+    notify_usage_resets: "使用量重設時通知",
+    weekly_reminder: "每週重設提醒",
+    reminder_off: "關閉",
+    reminder_hours_before: "{hours} 小時前",
+    quiet_when_fullscreen: "全螢幕時保持安靜",
+    pause_on_metered: "計量付費連線時暫停",
+    poll_less_on_battery: "省電模式時減少更新",
+    api_key_limits: "顯示 API 金鑰限制",
     language: "語言",
     system_default: "系統預設",
     check_for_updates: "檢查更新",
@@ -32,9 +80,71 @@ pub(super) const STRINGS: Strings = Strings {
     update_prompt_now: "版本 {version} 已可用。是否立即更新？",
     exit: "結束",
     show_widget: "顯示小工具",
+    badge_mode: "僅工作列徽章",
     session_window: "5h",
     weekly_window: "7d",
+    opus_window: "7d Opus",
+    requests_window: "請求",
+    tokens_window: "Token",
     now: "現在",
+    paused: "已暫停",
+    none_left: "剩餘 0",
+    burn_rate: "消耗速度",
+    tokens_7d: "7 天權杖",
+    estimated_cost: "預估費用",
+    tokens_today: "今日權杖",
+    tokens_in: "輸入",
+    tokens_out: "輸出",
+    tokens_cached: "快取",
+    plan: "方案",
+    plan_session_messages: "每 {window} 約 {count} 則訊息",
+    messages_left: "剩餘約 {count} 則訊息",
+    usage_by_hour: "5 小時用量上升的時段（最近 4 週）",
+    weekday_initials: "一二三四五六日",
+    recent_resets: "最近重設",
+    weekday_names: "週一,週二,週三,週四,週五,週六,週日",
+    pace_projection: "照此速度",
+    projected_limit: "{when} 達到 {window} 上限",
+    projected_at_reset: "{window} 重設時約用 {percent}",
+    usage_jump_title: "使用量暴增",
+    usage_jump_body: "{model} {window} 使用量在 {minutes} 分鐘內從 {from} 增加到 {to}",
+    alert_title: "使用量警示",
+    alert_body: "{model} {window} 使用量已達 {percent}",
+    snooze_alerts: "暫停警示",
+    snooze_for_hour: "1 小時",
+    snooze_until_reset: "直到下次重設",
+    resume_alerts: "恢復警示",
+    limit_reset_soon_title: "上限即將重設",
+    limit_reset_soon_body: "{model} 的 {window} 區間將在 {time} 後重設。",
+    limit_reset_title: "上限已重設",
+    limit_reset_body: "{model} 的 {window} 區間已重設。",
+    usage_reset_title: "使用量已重設",
+    usage_reset_body: "{model} 的 {window} 視窗已重設（先前為 {from}）。",
+    poll_failed: "上次查詢失敗",
+    failure_reason: "原因",
+    reason_auth_required: "需要登入",
+    reason_token_expired: "權杖已過期",
+    reason_no_credentials: "找不到憑證",
+    reason_request_failed: "要求失敗",
+    last_response: "最後回應",
+    credentials_checked: "已檢查的憑證",
+    retry: "重試",
+    export_history: "匯出使用記錄…",
+    export_history_failed: "無法匯出使用記錄",
+    copy_snapshot: "複製快照",
+    save_snapshot: "儲存快照…",
+    snapshot_failed: "無法建立快照",
+    snapshot_empty: "小工具目前還沒有可顯示的內容。",
+    interval_suggestion_title: "經常重新整理？",
+    interval_suggestion_body: "您每小時手動重新整理約 {count} 次。按一下這裡將更新頻率設為 {interval}。",
+    welcome_title: "請登入 Claude Code",
+    welcome_body: "登入 Claude Code 後即會顯示使用量。請執行 claude 並登入；登入資訊讀取自 {path}。按一下這裡開啟 README。",
+    weekly_reminder_title: "每週上限即將重設",
+    weekly_reminder_body: "每週上限將在 {time} 後重設 — 尚有 {unused}% 未使用。",
+    held_notifications_title: "請勿打擾期間的通知",
+    crash_title: "Claude Code Usage Monitor 已停止",
+    crash_body: "發生問題，監視器必須關閉。詳細資訊位於 settings.json 旁的 crash.log。",
+    crash_restarted_body: "監視器在發生非預期錯誤後已重新啟動。詳細資訊位於 settings.json 旁的 crash.log。",
     day_suffix: "天",
     hour_suffix: "時",
     minute_suffix: "分",
@@ -44,7 +154,11 @@ pub(super) const STRINGS: Strings = Strings {
     codex_token_expired_body: "請在終端機中執行 'codex'，並依照登入提示操作。完成後，請重新整理或重新啟動此應用程式。",
     antigravity_token_expired_title: "Antigravity 驗證錯誤",
     antigravity_token_expired_body: "請開啟 Antigravity 並重新登入。完成後，請重新整理或重新啟動此應用程式。",
+    service_unreachable_title: "無法連線到用量服務",
+    service_unreachable_body: "嘗試 {count} 次仍無法連線到用量服務。監視器會在背景持續重試，請檢查網路連線或 Proxy。",
     codex_window_title: "Codex 使用量監控",
     antigravity_window_title: "Antigravity 使用量監控",
     second_suffix: "秒",
+    backfill_title: "匯入使用記錄",
+    backfill_prompt: "要從本機工作階段記錄匯入過去的 Claude Code 活動嗎？這會讀取 ~/.claude/projects 一次，讓記錄從最近幾週的使用量開始，而不是空白。",
 };
@@ -1,13 +1,39 @@
 #![windows_subsystem = "windows"]
 
+mod alerts;
+mod badge;
+mod broadcast;
+mod cleanup;
+mod cost;
 mod diagnose;
+mod events;
+mod export;
+mod flyout;
+mod format;
+mod history;
+mod hotkey;
 mod localization;
+mod messages;
+mod mock_server;
+mod mock_usage;
 mod models;
+mod mqtt;
 mod native_interop;
+mod otel;
+mod palette;
+mod paths;
+mod png;
+mod poll_worker;
 mod poller;
+mod prompt;
+mod render;
+mod shared_memory;
+mod startup_task;
 mod theme;
+mod transcripts;
 mod tray_icon;
 mod updater;
+mod websocket;
 mod window;
 
 fn main() {
@@ -23,15 +49,43 @@ fn main() {
         }
     }
 
-    if let Some(exit_code) = updater::handle_cli_mode(&args) {
+    if let Some(exit_code) = updater::handle_cli_mode(&args)
+        .or_else(|| cleanup::handle_cli_mode(&args))
+        .or_else(|| window::handle_cli_mode(&args))
+    {
         if diagnose_enabled {
             diagnose::log(format!("cli mode exited with code {exit_code}"));
         }
         std::process::exit(exit_code);
     }
 
+    if let Some(index) = args.iter().position(|arg| arg == "--mock-server") {
+        let scenario_path = args
+            .get(index + 1)
+            .filter(|arg| !arg.starts_with("--"))
+            .map(std::path::PathBuf::from);
+        match mock_server::start(scenario_path) {
+            Ok(base_url) => {
+                diagnose::log(format!("mock server listening at {base_url}"));
+                poller::set_api_override(base_url, Some("mock-token".to_string()));
+            }
+            Err(error) => diagnose::log_error("mock server failed to start", error),
+        }
+    }
+
+    if let Some(index) = args.iter().position(|arg| arg == "--mock") {
+        match args.get(index + 1).filter(|arg| !arg.starts_with("--")) {
+            Some(path) => {
+                diagnose::log(format!("serving mock usage from {path}"));
+                mock_usage::start(std::path::PathBuf::from(path));
+            }
+            None => diagnose::log("--mock needs a usage file"),
+        }
+    }
+
     if diagnose_enabled {
         diagnose::log("entering window::run");
     }
+    window::install_panic_hook();
     window::run();
 }
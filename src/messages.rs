@@ -0,0 +1,59 @@
+//! "Messages left" estimate for the Claude 5h window. Percentages are hard to
+//! act on, so the room left in the window is divided by what one message has
+//! cost recently: the session percentage used so far over the responses in the
+//! local transcripts since the window started. Until there are enough of
+//! those, the plan's published message budget stands in.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::history::UsageWindow;
+use crate::models::{PlanTier, UsageData};
+use crate::transcripts;
+
+/// Fewer responses than this say too little about the cost of one.
+const MIN_RESPONSES: usize = 5;
+
+/// Roughly how many more messages fit in the current 5h window of `usage`.
+pub fn estimate(usage: &UsageData) -> Option<u32> {
+    let length = UsageWindow::Session.length();
+    let start = usage
+        .session
+        .window_start(length)
+        .filter(|start| *start <= SystemTime::now())
+        .unwrap_or_else(|| SystemTime::now() - length);
+    let since = start
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let responses = transcripts::usage_since(since).len();
+    messages_left(usage.session.percentage, responses, usage.plan)
+}
+
+fn messages_left(percent: f64, responses: usize, plan: Option<PlanTier>) -> Option<u32> {
+    let per_message = if responses >= MIN_RESPONSES && percent > 0.0 {
+        percent / responses as f64
+    } else {
+        100.0 / plan?.session_messages()? as f64
+    };
+    Some(((100.0 - percent).max(0.0) / per_message).floor() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_responses_set_the_cost_of_a_message() {
+        // 40 responses used 20%, so each costs 0.5% and 80% is 160 more.
+        assert_eq!(messages_left(20.0, 40, None), Some(160));
+        assert_eq!(messages_left(100.0, 40, None), Some(0));
+    }
+
+    #[test]
+    fn plan_budget_stands_in_until_there_are_enough_responses() {
+        assert_eq!(messages_left(0.0, 0, Some(PlanTier::Pro)), Some(45));
+        assert_eq!(messages_left(50.0, 2, Some(PlanTier::Max20x)), Some(450));
+        assert_eq!(messages_left(50.0, 2, Some(PlanTier::Team)), None);
+        assert_eq!(messages_left(50.0, 2, None), None);
+    }
+}
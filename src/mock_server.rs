@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+
+use crate::diagnose;
+
+/// Developer-only scenario served by `--mock-server`.
+///
+/// The file is re-read on every request so a scenario can be edited while the
+/// widget is running. Header values may contain `{now}`, `{now+SECS}` or
+/// `{now-SECS}` placeholders, which are expanded to unix timestamps so reset
+/// times stay relative to the moment of the request.
+#[derive(Deserialize)]
+struct MockScenario {
+    /// Status returned by the Messages endpoint (e.g. 429 for a rejected state,
+    /// 500 to exercise backoff).
+    #[serde(default = "default_messages_status")]
+    status: u16,
+    /// Status returned by the OAuth usage endpoint. Defaults to 404 so the
+    /// poller falls through to the rate-limit header path.
+    #[serde(default = "default_usage_status")]
+    usage_status: u16,
+    /// Raw JSON body returned by the usage endpoint when `usage_status` is 200.
+    #[serde(default)]
+    usage_body: Option<serde_json::Value>,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    delay_ms: u64,
+}
+
+fn default_messages_status() -> u16 {
+    200
+}
+
+fn default_usage_status() -> u16 {
+    404
+}
+
+impl Default for MockScenario {
+    fn default() -> Self {
+        let headers = [
+            ("anthropic-ratelimit-unified-status", "allowed"),
+            ("anthropic-ratelimit-unified-5h-utilization", "0.42"),
+            ("anthropic-ratelimit-unified-5h-reset", "{now+7200}"),
+            ("anthropic-ratelimit-unified-7d-utilization", "0.18"),
+            ("anthropic-ratelimit-unified-7d-reset", "{now+259200}"),
+            (
+                "anthropic-ratelimit-unified-representative-claim",
+                "five_hour",
+            ),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+
+        Self {
+            status: default_messages_status(),
+            usage_status: default_usage_status(),
+            usage_body: None,
+            headers,
+            delay_ms: 0,
+        }
+    }
+}
+
+/// Start the mock API server on a loopback port and return its base URL.
+pub fn start(scenario_path: Option<PathBuf>) -> Result<String, String> {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .map_err(|e| format!("Unable to bind mock API server: {e}"))?;
+    let addr = listener
+        .local_addr()
+        .map_err(|e| format!("Unable to read mock API server address: {e}"))?;
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let scenario_path = scenario_path.clone();
+                    std::thread::spawn(move || handle_connection(stream, scenario_path));
+                }
+                Err(error) => diagnose::log_error("mock server: accept failed", error),
+            }
+        }
+    });
+
+    Ok(format!("http://{addr}"))
+}
+
+fn load_scenario(path: Option<&PathBuf>) -> MockScenario {
+    let Some(path) = path else {
+        return MockScenario::default();
+    };
+
+    match std::fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|error| {
+            diagnose::log_error("mock server: invalid scenario file", error);
+            MockScenario::default()
+        }),
+        Err(error) => {
+            diagnose::log_error(
+                &format!("mock server: unable to read {}", path.display()),
+                error,
+            );
+            MockScenario::default()
+        }
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, scenario_path: Option<PathBuf>) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let Some(request_path) = read_request(&stream) else {
+        return;
+    };
+
+    let scenario = load_scenario(scenario_path.as_ref());
+    if scenario.delay_ms > 0 {
+        std::thread::sleep(Duration::from_millis(scenario.delay_ms));
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or(0);
+
+    let response = if request_path.starts_with("/api/oauth/usage") {
+        let body = match (&scenario.usage_body, scenario.usage_status) {
+            (Some(body), 200) => body.to_string(),
+            _ => "{}".to_string(),
+        };
+        build_response(scenario.usage_status, &[], &body)
+    } else if request_path.starts_with("/v1/messages") {
+        let headers: Vec<(String, String)> = scenario
+            .headers
+            .iter()
+            .map(|(name, value)| (name.clone(), expand_placeholders(value, now)))
+            .collect();
+        build_response(scenario.status, &headers, "{}")
+    } else {
+        build_response(404, &[], "{}")
+    };
+
+    diagnose::log(format!("mock server: served {request_path}"));
+    let _ = stream.write_all(response.as_bytes());
+    let _ = stream.flush();
+}
+
+/// Read the request line and headers, drain any body, and return the path.
+fn read_request(stream: &TcpStream) -> Option<String> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).ok()?;
+    let path = request_line.split_whitespace().nth(1)?.to_string();
+
+    let mut content_length = 0usize;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).ok()? == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    let mut body = vec![0u8; content_length];
+    let _ = reader.read_exact(&mut body);
+    Some(path)
+}
+
+fn build_response(status: u16, headers: &[(String, String)], body: &str) -> String {
+    let mut response = format!(
+        "HTTP/1.1 {status} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+        reason_phrase(status),
+        body.len()
+    );
+    for (name, value) in headers {
+        response.push_str(&format!("{name}: {value}\r\n"));
+    }
+    response.push_str("\r\n");
+    response.push_str(body);
+    response
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        529 => "Overloaded",
+        _ => "Mock",
+    }
+}
+
+fn expand_placeholders(value: &str, now: i64) -> String {
+    let Some(start) = value.find("{now") else {
+        return value.to_string();
+    };
+    let Some(end) = value[start..].find('}').map(|offset| start + offset) else {
+        return value.to_string();
+    };
+
+    let expr = &value[start + 4..end];
+    let offset = match expr.chars().next() {
+        Some('+') => expr[1..].parse::<i64>().unwrap_or(0),
+        Some('-') => -expr[1..].parse::<i64>().unwrap_or(0),
+        _ => 0,
+    };
+
+    format!(
+        "{}{}{}",
+        &value[..start],
+        now + offset,
+        expand_placeholders(&value[end + 1..], now)
+    )
+}
@@ -0,0 +1,282 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+use serde::Deserialize;
+
+use crate::diagnose;
+use crate::models::{AppUsageData, UnifiedLimit, UsageData, UsageSection};
+use crate::poller::{PollError, PollRequest, UsageSource};
+
+/// Developer-only usage file read by `--mock` in place of every provider's API.
+///
+/// Either one set of usage at the top level, or a `steps` list that each poll
+/// advances through, wrapping around. With `step_seconds` the widget polls at
+/// that interval so the steps play back by themselves. The file is re-read on
+/// every poll so it can be edited while the widget is running.
+#[derive(Deserialize)]
+struct MockScript {
+    #[serde(default)]
+    steps: Vec<MockStep>,
+    #[serde(default)]
+    step_seconds: Option<u64>,
+    #[serde(flatten)]
+    step: MockStep,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct MockStep {
+    claude_code: Option<MockUsage>,
+    codex: Option<MockUsage>,
+    antigravity: Option<MockUsage>,
+    secondary: Option<MockUsage>,
+    /// Fail the poll instead, to show the widget's error states.
+    error: Option<MockError>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct MockUsage {
+    session: MockSection,
+    weekly: MockSection,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct MockSection {
+    percent: f64,
+    /// Seconds from the poll until the window resets.
+    resets_in: Option<u64>,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum MockError {
+    AuthRequired,
+    TokenExpired,
+    NoCredentials,
+    RequestFailed,
+}
+
+static MOCK_FILE: OnceLock<PathBuf> = OnceLock::new();
+static STEP_INTERVAL: OnceLock<Option<Duration>> = OnceLock::new();
+static NEXT_STEP: AtomicUsize = AtomicUsize::new(0);
+
+/// Serve usage from `path` instead of polling the real APIs.
+pub fn start(path: PathBuf) {
+    let interval = load(&path)
+        .and_then(|script| script.step_seconds)
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs);
+    let _ = STEP_INTERVAL.set(interval);
+    let _ = MOCK_FILE.set(path);
+}
+
+pub fn is_active() -> bool {
+    MOCK_FILE.get().is_some()
+}
+
+/// Usage from the `--mock` file. It lists every provider itself, so the
+/// request is ignored.
+pub struct MockSource;
+
+impl UsageSource for MockSource {
+    /// The next step's usage, with reset times counted from now.
+    fn poll(&self, _request: &PollRequest) -> Result<AppUsageData, PollError> {
+        let path = MOCK_FILE.get().ok_or(PollError::RequestFailed)?;
+        let script = load(path).ok_or(PollError::RequestFailed)?;
+        let step = if script.steps.is_empty() {
+            &script.step
+        } else {
+            let index = NEXT_STEP.fetch_add(1, Ordering::Relaxed) % script.steps.len();
+            &script.steps[index]
+        };
+        step_usage(step, SystemTime::now())
+    }
+
+    /// Poll interval that plays the steps back, when the file sets one.
+    fn poll_interval(&self) -> Option<Duration> {
+        STEP_INTERVAL.get().copied().flatten()
+    }
+}
+
+/// A canned usage state from the debug menu, for checking alerts, colours and
+/// countdowns end to end without waiting for real usage to get there.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Simulation {
+    /// 95% of the 5h window used, resetting in 70 minutes.
+    NearSessionLimit,
+    /// The 5h limit hit, as a rejected Messages request reports it.
+    LimitHit,
+    /// Every poll fails as a network error would.
+    PollFailure,
+}
+
+/// Serves `Simulation` for every provider the widget shows, in place of the
+/// source chosen at startup.
+pub struct SimulatedSource(pub Simulation);
+
+impl UsageSource for SimulatedSource {
+    fn poll(&self, request: &PollRequest) -> Result<AppUsageData, PollError> {
+        simulated_usage(self.0, request, SystemTime::now())
+    }
+
+    fn is_simulated(&self) -> bool {
+        true
+    }
+}
+
+fn simulated_usage(
+    simulation: Simulation,
+    request: &PollRequest,
+    now: SystemTime,
+) -> Result<AppUsageData, PollError> {
+    let (session_percent, session_resets_in, unified) = match simulation {
+        Simulation::NearSessionLimit => (95.0, 70 * 60, None),
+        Simulation::LimitHit => (
+            100.0,
+            45 * 60,
+            Some(UnifiedLimit {
+                status: Some("rejected".to_string()),
+                representative_claim: Some("five_hour".to_string()),
+            }),
+        ),
+        Simulation::PollFailure => return Err(PollError::RequestFailed),
+    };
+    let usage = |shown: bool| {
+        shown.then(|| UsageData {
+            session: UsageSection {
+                percentage: session_percent,
+                resets_at: Some(now + Duration::from_secs(session_resets_in)),
+            },
+            weekly: UsageSection {
+                percentage: 40.0,
+                resets_at: Some(now + Duration::from_secs(3 * 86_400)),
+            },
+            api_limits: None,
+            plan: None,
+            unified: unified.clone(),
+            per_minute: false,
+            opus: None,
+        })
+    };
+    Ok(AppUsageData {
+        claude_code: usage(request.show_claude_code),
+        codex: usage(request.show_codex),
+        antigravity: usage(request.show_antigravity),
+        secondary: usage(request.secondary_credentials.is_some()),
+    })
+}
+
+fn load(path: &Path) -> Option<MockScript> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(error) => {
+            diagnose::log_error(&format!("mock: unable to read {}", path.display()), error);
+            return None;
+        }
+    };
+    serde_json::from_str(&content)
+        .map_err(|error| diagnose::log_error("mock: invalid usage file", error))
+        .ok()
+}
+
+fn step_usage(step: &MockStep, now: SystemTime) -> Result<AppUsageData, PollError> {
+    if let Some(error) = step.error {
+        return Err(match error {
+            MockError::AuthRequired => PollError::AuthRequired,
+            MockError::TokenExpired => PollError::TokenExpired,
+            MockError::NoCredentials => PollError::NoCredentials,
+            MockError::RequestFailed => PollError::RequestFailed,
+        });
+    }
+
+    let section = |section: &MockSection| UsageSection {
+        percentage: section.percent,
+        resets_at: section
+            .resets_in
+            .map(|secs| now + Duration::from_secs(secs)),
+    };
+    let usage = |usage: &Option<MockUsage>| {
+        usage.as_ref().map(|usage| UsageData {
+            session: section(&usage.session),
+            weekly: section(&usage.weekly),
+            api_limits: None,
+            plan: None,
+            unified: None,
+            per_minute: false,
+            opus: None,
+        })
+    };
+    Ok(AppUsageData {
+        claude_code: usage(&step.claude_code),
+        codex: usage(&step.codex),
+        antigravity: usage(&step.antigravity),
+        secondary: usage(&step.secondary),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_a_single_step_or_a_sequence() {
+        let single: MockScript = serde_json::from_str(
+            r#"{ "claude_code": { "session": { "percent": 42, "resets_in": 60 } } }"#,
+        )
+        .unwrap();
+        let now = SystemTime::now();
+        let data = step_usage(&single.step, now).unwrap();
+        let claude = data.claude_code.unwrap();
+        assert!(single.steps.is_empty());
+        assert_eq!(claude.session.percentage, 42.0);
+        assert_eq!(
+            claude.session.resets_at,
+            Some(now + Duration::from_secs(60))
+        );
+        assert!(data.codex.is_none());
+
+        let sequence: MockScript = serde_json::from_str(
+            r#"{ "step_seconds": 5, "steps": [
+                { "codex": { "weekly": { "percent": 10 } } },
+                { "error": "token_expired" }
+            ] }"#,
+        )
+        .unwrap();
+        assert_eq!(sequence.step_seconds, Some(5));
+        assert!(step_usage(&sequence.steps[0], now).unwrap().codex.is_some());
+        assert!(matches!(
+            step_usage(&sequence.steps[1], now),
+            Err(PollError::TokenExpired)
+        ));
+    }
+
+    #[test]
+    fn simulations_cover_only_the_shown_providers() {
+        let now = SystemTime::now();
+        let request = PollRequest {
+            show_claude_code: true,
+            show_codex: false,
+            show_antigravity: true,
+            secondary_credentials: None,
+        };
+
+        let data = simulated_usage(Simulation::LimitHit, &request, now).unwrap();
+        let claude = data.claude_code.unwrap();
+        assert!(claude.session.limit_reached());
+        assert!(claude.unified.unwrap().is_rejected());
+        assert!(data.codex.is_none());
+        assert!(data.antigravity.is_some());
+        assert!(data.secondary.is_none());
+
+        let data = simulated_usage(Simulation::NearSessionLimit, &request, now).unwrap();
+        assert_eq!(data.claude_code.unwrap().session.percentage, 95.0);
+        assert!(matches!(
+            simulated_usage(Simulation::PollFailure, &request, now),
+            Err(PollError::RequestFailed)
+        ));
+    }
+}
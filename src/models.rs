@@ -1,4 +1,6 @@
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
+
+use crate::history::UsageWindow;
 
 #[derive(Clone, Debug, Default)]
 pub struct UsageSection {
@@ -6,10 +8,172 @@ pub struct UsageSection {
     pub resets_at: Option<SystemTime>,
 }
 
+impl UsageSection {
+    /// When the current window began, for a window `length` long.
+    pub fn window_start(&self, length: Duration) -> Option<SystemTime> {
+        self.resets_at
+            .and_then(|resets_at| resets_at.checked_sub(length))
+    }
+
+    /// How much of the current window has passed at `now`, 0 to 100.
+    pub fn elapsed_percent(&self, length: Duration, now: SystemTime) -> Option<f64> {
+        let elapsed = now.duration_since(self.window_start(length)?).ok()?;
+        Some((elapsed.as_secs_f64() * 100.0 / length.as_secs_f64()).min(100.0))
+    }
+
+    /// Whether the window's limit is used up, so requests are being rejected.
+    pub fn limit_reached(&self) -> bool {
+        self.percentage >= 100.0
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct UsageData {
     pub session: UsageSection,
     pub weekly: UsageSection,
+    /// Per-minute limits reported to API-key accounts, when present.
+    pub api_limits: Option<ApiRateLimits>,
+    /// Subscription of the signed-in Claude account, when its credentials say.
+    pub plan: Option<PlanTier>,
+    /// The unified limit headers, when the poll went through the Messages API.
+    pub unified: Option<UnifiedLimit>,
+    /// `session` and `weekly` hold an API key's per-minute request and token
+    /// buckets, which reset every minute, rather than the 5h and 7d windows.
+    pub per_minute: bool,
+    /// The separate 7-day Claude Opus limit, on plans that have one.
+    pub opus: Option<UsageSection>,
+}
+
+/// `anthropic-ratelimit-unified-status` and `-representative-claim`: whether
+/// requests are let through, and which limit decides it. Kept as sent, so
+/// values this version does not know about still show up in diagnostics.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UnifiedLimit {
+    /// "allowed", "allowed_warning" or "rejected".
+    pub status: Option<String>,
+    /// The binding limit, such as "five_hour" or "seven_day".
+    pub representative_claim: Option<String>,
+}
+
+impl UnifiedLimit {
+    pub fn is_rejected(&self) -> bool {
+        self.status.as_deref() == Some("rejected")
+    }
+
+    /// The widget row of the binding limit. Per-model weekly limits such as
+    /// "seven_day_opus" count towards the weekly row.
+    pub fn binding_window(&self) -> Option<UsageWindow> {
+        match self.representative_claim.as_deref()? {
+            "five_hour" => Some(UsageWindow::Session),
+            claim if claim.starts_with("seven_day") => Some(UsageWindow::Weekly),
+            _ => None,
+        }
+    }
+}
+
+/// Claude subscription, from `subscriptionType` and `rateLimitTier` in the
+/// credentials file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlanTier {
+    Pro,
+    Max5x,
+    Max20x,
+    Team,
+    Enterprise,
+}
+
+impl PlanTier {
+    pub fn from_credentials(
+        subscription_type: Option<&str>,
+        rate_limit_tier: Option<&str>,
+    ) -> Option<Self> {
+        let tier = rate_limit_tier.unwrap_or_default().to_ascii_lowercase();
+        if tier.contains("max_20x") {
+            return Some(PlanTier::Max20x);
+        }
+        if tier.contains("max_5x") {
+            return Some(PlanTier::Max5x);
+        }
+        match subscription_type?.to_ascii_lowercase().as_str() {
+            "pro" => Some(PlanTier::Pro),
+            // Max without a known tier is the smaller one.
+            "max" => Some(PlanTier::Max5x),
+            "team" => Some(PlanTier::Team),
+            "enterprise" => Some(PlanTier::Enterprise),
+            _ => None,
+        }
+    }
+
+    /// Product name; not translated.
+    pub fn label(self) -> &'static str {
+        match self {
+            PlanTier::Pro => "Pro",
+            PlanTier::Max5x => "Max 5x",
+            PlanTier::Max20x => "Max 20x",
+            PlanTier::Team => "Team",
+            PlanTier::Enterprise => "Enterprise",
+        }
+    }
+
+    /// Roughly how many short messages a full 5h window allows, from
+    /// Anthropic's published plan guidance. Real budgets vary with message
+    /// length, model and attachments, and are not published for Team and
+    /// Enterprise seats.
+    pub fn session_messages(self) -> Option<u32> {
+        match self {
+            PlanTier::Pro => Some(45),
+            PlanTier::Max5x => Some(225),
+            PlanTier::Max20x => Some(900),
+            PlanTier::Team | PlanTier::Enterprise => None,
+        }
+    }
+}
+
+/// One `anthropic-ratelimit-<name>-*` header group.
+#[derive(Clone, Debug, Default)]
+pub struct RateLimitBucket {
+    pub limit: u64,
+    pub remaining: u64,
+    pub resets_at: Option<SystemTime>,
+}
+
+impl RateLimitBucket {
+    pub fn used_percentage(&self) -> f64 {
+        if self.limit == 0 {
+            return 0.0;
+        }
+        let used = self.limit.saturating_sub(self.remaining);
+        used as f64 * 100.0 / self.limit as f64
+    }
+
+    pub fn as_section(&self) -> UsageSection {
+        UsageSection {
+            percentage: self.used_percentage(),
+            resets_at: self.resets_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ApiRateLimits {
+    pub requests: Option<RateLimitBucket>,
+    pub tokens: Option<RateLimitBucket>,
+    pub input_tokens: Option<RateLimitBucket>,
+    pub output_tokens: Option<RateLimitBucket>,
+}
+
+impl ApiRateLimits {
+    /// The token bucket closest to its limit, preferring the combined bucket
+    /// when the response reports one.
+    pub fn tightest_tokens(&self) -> Option<&RateLimitBucket> {
+        if self.tokens.is_some() {
+            return self.tokens.as_ref();
+        }
+        [self.input_tokens.as_ref(), self.output_tokens.as_ref()]
+            .into_iter()
+            .flatten()
+            .max_by(|a, b| a.used_percentage().total_cmp(&b.used_percentage()))
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -17,4 +181,6 @@ pub struct AppUsageData {
     pub claude_code: Option<UsageData>,
     pub codex: Option<UsageData>,
     pub antigravity: Option<UsageData>,
+    /// Claude usage of the second account in settings.json, when configured.
+    pub secondary: Option<UsageData>,
 }
@@ -0,0 +1,187 @@
+//! Publishes each successful poll to an MQTT broker, for home automation
+//! dashboards. Speaks just enough MQTT 3.1.1 to connect, publish one QoS 0
+//! message and disconnect, so no client library or persistent connection is
+//! needed.
+
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::diagnose;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const KEEP_ALIVE_SECS: u16 = 30;
+
+/// Set while a publish is under way, so a broker that hangs does not collect
+/// one stuck thread per poll.
+static PUBLISHING: AtomicBool = AtomicBool::new(false);
+
+/// Broker to publish usage to. Only set by editing settings.json.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MqttSettings {
+    pub host: String,
+    /// Defaults to 1883, or 8883 with `tls`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(default = "default_topic")]
+    pub topic: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub tls: bool,
+    /// Keep the last message on the broker for subscribers that connect later.
+    #[serde(default = "default_retain")]
+    pub retain: bool,
+}
+
+fn default_topic() -> String {
+    "claude-code-usage-monitor/usage".to_string()
+}
+
+fn default_retain() -> bool {
+    true
+}
+
+/// Publish `body` to the broker in the background, logging rather than
+/// returning failures so a broker that is slow or down never holds up
+/// polling. Skipped while the previous publish is still under way.
+pub fn publish(settings: &MqttSettings, body: &str) {
+    if PUBLISHING.swap(true, Ordering::AcqRel) {
+        diagnose::log("mqtt: previous publish still running; skipping this one");
+        return;
+    }
+    let settings = settings.clone();
+    let body = body.to_string();
+    std::thread::spawn(move || {
+        if let Err(error) = send(&settings, body.as_bytes()) {
+            diagnose::log(format!(
+                "mqtt: publish to {} failed: {error}",
+                settings.host
+            ));
+        }
+        PUBLISHING.store(false, Ordering::Release);
+    });
+}
+
+fn send(settings: &MqttSettings, body: &[u8]) -> Result<(), String> {
+    let port = settings
+        .port
+        .unwrap_or(if settings.tls { 8883 } else { 1883 });
+    let address = (settings.host.as_str(), port)
+        .to_socket_addrs()
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or("host not found")?;
+    let stream = TcpStream::connect_timeout(&address, TIMEOUT).map_err(|e| e.to_string())?;
+    let _ = stream.set_read_timeout(Some(TIMEOUT));
+    let _ = stream.set_write_timeout(Some(TIMEOUT));
+
+    if settings.tls {
+        let connector = native_tls::TlsConnector::new().map_err(|e| e.to_string())?;
+        let stream = connector
+            .connect(&settings.host, stream)
+            .map_err(|e| e.to_string())?;
+        exchange(stream, settings, body)
+    } else {
+        exchange(stream, settings, body)
+    }
+}
+
+fn exchange(
+    mut stream: impl Read + Write,
+    settings: &MqttSettings,
+    body: &[u8],
+) -> Result<(), String> {
+    stream
+        .write_all(&connect_packet(settings))
+        .map_err(|e| e.to_string())?;
+
+    let mut connack = [0u8; 4];
+    stream.read_exact(&mut connack).map_err(|e| e.to_string())?;
+    if connack[0] != 0x20 {
+        return Err("unexpected reply to CONNECT".to_string());
+    }
+    if connack[3] != 0 {
+        return Err(format!("connection refused (code {})", connack[3]));
+    }
+
+    stream
+        .write_all(&publish_packet(&settings.topic, body, settings.retain))
+        .map_err(|e| e.to_string())?;
+    // DISCONNECT
+    stream.write_all(&[0xE0, 0x00]).map_err(|e| e.to_string())?;
+    stream.flush().map_err(|e| e.to_string())
+}
+
+fn connect_packet(settings: &MqttSettings) -> Vec<u8> {
+    let mut flags = 0x02; // clean session
+    let mut variable = Vec::new();
+    put_string(&mut variable, b"MQTT");
+    variable.push(4); // protocol level 3.1.1
+
+    let mut payload = Vec::new();
+    put_string(&mut payload, b"claude-code-usage-monitor");
+    if let Some(username) = &settings.username {
+        flags |= 0x80;
+        put_string(&mut payload, username.as_bytes());
+        if let Some(password) = &settings.password {
+            flags |= 0x40;
+            put_string(&mut payload, password.as_bytes());
+        }
+    }
+    variable.push(flags);
+    variable.extend_from_slice(&KEEP_ALIVE_SECS.to_be_bytes());
+    variable.extend_from_slice(&payload);
+    packet(0x10, &variable)
+}
+
+fn publish_packet(topic: &str, body: &[u8], retain: bool) -> Vec<u8> {
+    let mut variable = Vec::new();
+    put_string(&mut variable, topic.as_bytes());
+    variable.extend_from_slice(body);
+    packet(0x30 | u8::from(retain), &variable)
+}
+
+fn put_string(buffer: &mut Vec<u8>, value: &[u8]) {
+    buffer.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    buffer.extend_from_slice(value);
+}
+
+/// A fixed header followed by `rest`, with the remaining length encoded seven
+/// bits at a time.
+fn packet(header: u8, rest: &[u8]) -> Vec<u8> {
+    let mut packet = vec![header];
+    let mut length = rest.len();
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if length == 0 {
+            break;
+        }
+    }
+    packet.extend_from_slice(rest);
+    packet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_publish_with_long_remaining_length() {
+        let body = vec![b'x'; 200];
+        let packet = publish_packet("a/b", &body, true);
+        // 2 + 3 bytes of topic plus the body is 205, which takes two bytes.
+        assert_eq!(&packet[..6], &[0x31, 0xCD, 0x01, 0x00, 0x03, b'a']);
+        assert_eq!(packet.len(), 3 + 205);
+    }
+}
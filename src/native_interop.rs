@@ -1,7 +1,41 @@
-use windows::core::PCWSTR;
-use windows::Win32::Foundation::{BOOL, HWND, LPARAM, RECT};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use windows::core::{PCWSTR, PWSTR};
+use windows::Win32::Foundation::{GlobalFree, BOOL, HANDLE, HWND, LPARAM, POINT, RECT, WPARAM};
+use windows::Win32::Graphics::Gdi::{
+    GetMonitorInfoW, GetWindowRgnBox, MonitorFromWindow, COMPLEXREGION, MONITORINFO,
+    MONITOR_DEFAULTTONEAREST, SIMPLEREGION,
+};
+use windows::Win32::Networking::NetworkListManager::{
+    INetworkCostManager, NetworkListManager, NLM_CONNECTION_COST_FIXED,
+    NLM_CONNECTION_COST_OVERDATALIMIT, NLM_CONNECTION_COST_ROAMING, NLM_CONNECTION_COST_VARIABLE,
+};
+use windows::Win32::System::Com::{
+    CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_ALL, CLSCTX_INPROC_SERVER,
+    COINIT_APARTMENTTHREADED, COINIT_MULTITHREADED,
+};
+use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+use windows::Win32::System::DataExchange::{
+    CloseClipboard, EmptyClipboard, OpenClipboard, RegisterClipboardFormatW, SetClipboardData,
+};
+use windows::Win32::System::Diagnostics::Debug::MessageBeep;
+use windows::Win32::System::Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE};
+use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+use windows::Win32::System::Registry::{
+    RegCloseKey, RegOpenKeyExW, HKEY, HKEY_CURRENT_USER, KEY_READ,
+};
+use windows::Win32::System::SystemInformation::{GetLocalTime, GetSystemTime};
 use windows::Win32::UI::Accessibility::{SetWinEventHook, UnhookWinEvent, HWINEVENTHOOK};
-use windows::Win32::UI::Shell::{SHAppBarMessage, ABM_GETTASKBARPOS, APPBARDATA};
+use windows::Win32::UI::Controls::Dialogs::{
+    GetSaveFileNameW, OFN_NOCHANGEDIR, OFN_OVERWRITEPROMPT, OFN_PATHMUSTEXIST, OPENFILENAMEW,
+};
+use windows::Win32::UI::Shell::{
+    ITaskbarList3, SHAppBarMessage, SHQueryUserNotificationState, ShellExecuteW, TaskbarList,
+    ABM_GETSTATE, ABM_GETTASKBARPOS, ABS_AUTOHIDE, APPBARDATA, QUNS_BUSY, QUNS_PRESENTATION_MODE,
+    QUNS_QUIET_TIME, QUNS_RUNNING_D3D_FULL_SCREEN, TBPF_ERROR, TBPF_NOPROGRESS, TBPF_NORMAL,
+    TBPF_PAUSED,
+};
 use windows::Win32::UI::WindowsAndMessaging::*;
 
 // Window style constants
@@ -18,11 +52,29 @@ pub const TIMER_POLL: usize = 1;
 pub const TIMER_COUNTDOWN: usize = 2;
 pub const TIMER_RESET_POLL: usize = 3;
 pub const TIMER_UPDATE_CHECK: usize = 4;
+pub const TIMER_DEFERRED_RENDER: usize = 5;
+pub const TIMER_METERED_CHECK: usize = 6;
+pub const TIMER_WEEKLY_REMINDER: usize = 7;
+pub const TIMER_HELD_NOTIFICATIONS: usize = 8;
+pub const TIMER_ALERT_FLASH: usize = 9;
+pub const TIMER_LIMIT_RESET: usize = 10;
+pub const TIMER_BAR_ANIMATION: usize = 11;
+pub const TIMER_REPOSITION: usize = 12;
 
 // Custom messages
 pub const WM_APP: u32 = 0x8000;
 pub const WM_APP_USAGE_UPDATED: u32 = WM_APP + 1;
 pub const WM_APP_TRAY: u32 = WM_APP + 3;
+// WM_APP + 2 and + 4 are used by window.rs.
+/// A forwarded WM_POWERBROADCAST; wparam is the PBT_* event.
+pub const WM_APP_POWER_BROADCAST: u32 = WM_APP + 5;
+pub const WM_APP_TIME_CHANGED: u32 = WM_APP + 6;
+/// A second instance was started and handed over to this one.
+pub const WM_APP_ACTIVATE: u32 = WM_APP + 7;
+/// The badge mode taskbar button was clicked.
+pub const WM_APP_BADGE_CLICKED: u32 = WM_APP + 8;
+/// The badge mode taskbar button was closed.
+pub const WM_APP_BADGE_CLOSED: u32 = WM_APP + 9;
 
 #[derive(Clone, Copy, Debug)]
 pub struct TaskbarWindow {
@@ -61,19 +113,114 @@ pub fn find_taskbars() -> Vec<TaskbarWindow> {
     taskbars
 }
 
-/// Find a child window by class name
-pub fn find_child_window(parent: HWND, class_name: &str) -> Option<HWND> {
+/// Window classes the widget is placed to the left of, tried in order. The
+/// stock taskbars, and the Windows 10 style ones StartAllBack and
+/// ExplorerPatcher bring back, all have `TrayNotifyWnd`.
+pub const DEFAULT_TRAY_ANCHOR_CLASSES: [&str; 1] = ["TrayNotifyWnd"];
+
+/// Third-party tools known to rework the taskbar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskbarMod {
+    StartAllBack,
+    ExplorerPatcher,
+    /// Clips the taskbar to a rounded or shrunk window region.
+    RoundedTb,
+}
+
+/// The taskbar mods installed or running for this user.
+pub fn detect_taskbar_mods() -> Vec<TaskbarMod> {
+    let mut mods = Vec::new();
+    if has_user_registry_key("Software\\StartIsBack") {
+        mods.push(TaskbarMod::StartAllBack);
+    }
+    if has_user_registry_key("Software\\ExplorerPatcher") {
+        mods.push(TaskbarMod::ExplorerPatcher);
+    }
+    let rounded_tb = wide_str("RoundedTB");
+    let running = unsafe { FindWindowW(PCWSTR::null(), PCWSTR::from_raw(rounded_tb.as_ptr())) };
+    if running.is_ok_and(|hwnd| !hwnd.is_invalid()) {
+        mods.push(TaskbarMod::RoundedTb);
+    }
+    mods
+}
+
+fn has_user_registry_key(path: &str) -> bool {
     unsafe {
-        let class = wide_str(class_name);
-        match FindWindowExW(
-            parent,
-            HWND::default(),
-            PCWSTR::from_raw(class.as_ptr()),
-            PCWSTR::null(),
-        ) {
-            Ok(h) if h != HWND::default() => Some(h),
-            _ => None,
+        let path = wide_str(path);
+        let mut hkey = HKEY::default();
+        let result = RegOpenKeyExW(
+            HKEY_CURRENT_USER,
+            PCWSTR::from_raw(path.as_ptr()),
+            0,
+            KEY_READ,
+            &mut hkey,
+        );
+        if result.is_err() {
+            return false;
+        }
+        let _ = RegCloseKey(hkey);
+        true
+    }
+}
+
+/// The first descendant of `taskbar` whose class is in `classes`, trying the
+/// classes in order. Mods can nest the tray deeper than a direct child.
+pub fn find_tray_anchor<S: AsRef<str>>(taskbar: HWND, classes: &[S]) -> Option<HWND> {
+    struct Search<'a> {
+        class: &'a str,
+        found: Option<HWND>,
+    }
+
+    unsafe extern "system" fn enum_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let search = &mut *(lparam.0 as *mut Search);
+        let mut class_name = [0u16; 256];
+        let len = unsafe { GetClassNameW(hwnd, &mut class_name) };
+        if len > 0 && String::from_utf16_lossy(&class_name[..len as usize]) == search.class {
+            search.found = Some(hwnd);
+            return BOOL(0);
+        }
+        BOOL(1)
+    }
+
+    classes.iter().find_map(|class| {
+        let mut search = Search {
+            class: class.as_ref(),
+            found: None,
+        };
+        unsafe {
+            let _ = EnumChildWindows(
+                taskbar,
+                Some(enum_proc),
+                LPARAM(&mut search as *mut _ as isize),
+            );
         }
+        search.found
+    })
+}
+
+/// The part of `rect`, the taskbar's screen rectangle, left visible by its
+/// window region. Mods such as RoundedTB clip the taskbar with a region
+/// rather than resizing it, so its rectangle alone overstates it.
+pub fn visible_taskbar_rect(taskbar: HWND, rect: RECT) -> RECT {
+    let Some(window) = get_window_rect_safe(taskbar) else {
+        return rect;
+    };
+    let mut bounds = RECT::default();
+    let kind = unsafe { GetWindowRgnBox(taskbar, &mut bounds) };
+    if kind != SIMPLEREGION && kind != COMPLEXREGION {
+        return rect;
+    }
+    // Region coordinates are relative to the window's top-left corner.
+    let visible = RECT {
+        left: rect.left.max(window.left + bounds.left),
+        top: rect.top.max(window.top + bounds.top),
+        right: rect.right.min(window.left + bounds.right),
+        bottom: rect.bottom.min(window.top + bounds.bottom),
+    };
+    if visible.right > visible.left && visible.bottom > visible.top {
+        visible
+    } else {
+        rect
     }
 }
 
@@ -102,6 +249,257 @@ pub fn get_taskbar_rect(taskbar_hwnd: HWND) -> Option<RECT> {
     }
 }
 
+/// Thickness (in pixels) of the strip an auto-hidden taskbar leaves on screen.
+const AUTOHIDE_REVEAL_PX: i32 = 4;
+
+/// True when an auto-hide taskbar has slid off screen, leaving only the thin
+/// reveal strip at the monitor edge.
+pub fn is_taskbar_autohidden(taskbar_hwnd: HWND) -> bool {
+    unsafe {
+        let mut abd = APPBARDATA {
+            cbSize: std::mem::size_of::<APPBARDATA>() as u32,
+            hWnd: taskbar_hwnd,
+            ..Default::default()
+        };
+        let state = SHAppBarMessage(ABM_GETSTATE, &mut abd) as u32;
+        if state & ABS_AUTOHIDE == 0 {
+            return false;
+        }
+
+        let Some(rect) = get_window_rect_safe(taskbar_hwnd) else {
+            return false;
+        };
+        let monitor = MonitorFromWindow(taskbar_hwnd, MONITOR_DEFAULTTONEAREST);
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        if !GetMonitorInfoW(monitor, &mut info).as_bool() {
+            return false;
+        }
+
+        let bounds = info.rcMonitor;
+        let visible_w = rect.right.min(bounds.right) - rect.left.max(bounds.left);
+        let visible_h = rect.bottom.min(bounds.bottom) - rect.top.max(bounds.top);
+        visible_w.min(visible_h) <= AUTOHIDE_REVEAL_PX
+    }
+}
+
+/// True when Windows reports the current internet connection as metered
+/// (fixed or variable cost, roaming, or over its data limit). Any failure to
+/// query the connection cost is treated as unmetered.
+pub fn is_connection_metered() -> bool {
+    unsafe {
+        let com_initialized = CoInitializeEx(None, COINIT_MULTITHREADED).is_ok();
+        let cost =
+            CoCreateInstance::<_, INetworkCostManager>(&NetworkListManager, None, CLSCTX_ALL)
+                .and_then(|manager| {
+                    let mut cost = 0u32;
+                    manager.GetCost(&mut cost, std::ptr::null()).map(|()| cost)
+                })
+                .unwrap_or(0);
+        if com_initialized {
+            CoUninitialize();
+        }
+
+        let metered = NLM_CONNECTION_COST_FIXED.0
+            | NLM_CONNECTION_COST_VARIABLE.0
+            | NLM_CONNECTION_COST_ROAMING.0
+            | NLM_CONNECTION_COST_OVERDATALIMIT.0;
+        cost & metered as u32 != 0
+    }
+}
+
+/// Write stdout and stderr to the console the app was started from. As a GUI
+/// app it gets no console of its own; output is lost when there is none.
+pub fn attach_parent_console() {
+    unsafe {
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+/// True while a full-screen app, Direct3D game or presentation is in the
+/// foreground, as reported by the shell's notification state.
+pub fn is_fullscreen_app_active() -> bool {
+    match unsafe { SHQueryUserNotificationState() } {
+        Ok(state) => [
+            QUNS_BUSY,
+            QUNS_RUNNING_D3D_FULL_SCREEN,
+            QUNS_PRESENTATION_MODE,
+        ]
+        .contains(&state),
+        Err(_) => false,
+    }
+}
+
+/// False when "Animation effects" is turned off in Windows accessibility
+/// settings.
+pub fn animations_enabled() -> bool {
+    let mut enabled = BOOL(1);
+    let result = unsafe {
+        SystemParametersInfoW(
+            SPI_GETCLIENTAREAANIMATION,
+            0,
+            Some(&mut enabled as *mut BOOL as *mut _),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )
+    };
+    result.is_err() || enabled.as_bool()
+}
+
+/// True while Windows Do Not Disturb (Focus Assist) or quiet hours hold back
+/// notifications.
+pub fn is_do_not_disturb_active() -> bool {
+    unsafe { SHQueryUserNotificationState() }.is_ok_and(|state| state == QUNS_QUIET_TIME)
+}
+
+/// True while battery saver is on, or while running on battery at or below
+/// `low_battery_percent` (0 skips the level check).
+pub fn is_saving_power(low_battery_percent: u8) -> bool {
+    let mut status = SYSTEM_POWER_STATUS::default();
+    if unsafe { GetSystemPowerStatus(&mut status) }.is_err() {
+        return false;
+    }
+    let battery_saver = status.SystemStatusFlag == 1;
+    // ACLineStatus 0 is "offline"; a BatteryLifePercent of 255 is unknown.
+    let low_battery = status.ACLineStatus == 0
+        && status.BatteryLifePercent != 255
+        && status.BatteryLifePercent <= low_battery_percent;
+    battery_saver || low_battery
+}
+
+/// Seconds elapsed since local midnight, from the system clock and time zone.
+pub fn secs_since_local_midnight() -> u64 {
+    let now = unsafe { GetLocalTime() };
+    now.wHour as u64 * 3600 + now.wMinute as u64 * 60 + now.wSecond as u64
+}
+
+/// Offset of local time from UTC in seconds, as the time zone applies it now.
+pub fn utc_offset_secs() -> i64 {
+    let (local, utc) = unsafe { (GetLocalTime(), GetSystemTime()) };
+    let minutes = |hour: u16, minute: u16| hour as i64 * 60 + minute as i64;
+    let mut offset = minutes(local.wHour, local.wMinute) - minutes(utc.wHour, utc.wMinute);
+    // The two dates can differ by a day.
+    if offset > 14 * 60 {
+        offset -= 24 * 60;
+    } else if offset < -12 * 60 {
+        offset += 24 * 60;
+    }
+    // Zones are whole quarter hours; this absorbs a minute ticking over
+    // between the two reads.
+    (offset as f64 / 15.0).round() as i64 * 15 * 60
+}
+
+/// Open `url` in the default browser.
+pub fn open_url(url: &str) -> Result<(), String> {
+    let verb = wide_str("open");
+    let url_wide = wide_str(url);
+    let result = unsafe {
+        ShellExecuteW(
+            None,
+            PCWSTR::from_raw(verb.as_ptr()),
+            PCWSTR::from_raw(url_wide.as_ptr()),
+            PCWSTR::null(),
+            PCWSTR::null(),
+            SW_SHOWNORMAL,
+        )
+    };
+    // Values above 32 indicate success.
+    if result.0 as isize > 32 {
+        Ok(())
+    } else {
+        Err(format!("ShellExecuteW returned {}", result.0 as isize))
+    }
+}
+
+/// Show the common Save As dialog. `filters` are (label, pattern) pairs such
+/// as ("CSV (*.csv)", "*.csv"). A name typed without an extension gets the
+/// selected filter's, before the dialog asks about overwriting, so the path
+/// is final. Returns it and the 0-based index of the filter that was
+/// selected, or None when the dialog was cancelled.
+pub fn save_file_dialog(
+    owner: HWND,
+    default_name: &str,
+    filters: &[(&str, &str)],
+) -> Option<(PathBuf, usize)> {
+    let mut filter = Vec::new();
+    for (label, pattern) in filters {
+        filter.extend(label.encode_utf16().chain([0]));
+        filter.extend(pattern.encode_utf16().chain([0]));
+    }
+    filter.push(0);
+    // Any extension works here; the dialog uses the selected filter's.
+    let default_ext = filters
+        .first()
+        .map(|(_, pattern)| wide_str(pattern.trim_start_matches("*.")))
+        .unwrap_or_else(|| wide_str(""));
+
+    let mut file = [0u16; 1024];
+    let name: Vec<u16> = default_name.encode_utf16().collect();
+    let len = name.len().min(file.len() - 1);
+    file[..len].copy_from_slice(&name[..len]);
+
+    let mut dialog = OPENFILENAMEW {
+        lStructSize: std::mem::size_of::<OPENFILENAMEW>() as u32,
+        hwndOwner: owner,
+        lpstrFilter: PCWSTR::from_raw(filter.as_ptr()),
+        nFilterIndex: 1,
+        lpstrFile: PWSTR::from_raw(file.as_mut_ptr()),
+        nMaxFile: file.len() as u32,
+        lpstrDefExt: PCWSTR::from_raw(default_ext.as_ptr()),
+        Flags: OFN_OVERWRITEPROMPT | OFN_PATHMUSTEXIST | OFN_NOCHANGEDIR,
+        ..Default::default()
+    };
+    unsafe {
+        if !GetSaveFileNameW(&mut dialog).as_bool() {
+            return None;
+        }
+    }
+
+    let end = file.iter().position(|&c| c == 0).unwrap_or(file.len());
+    let path = PathBuf::from(String::from_utf16_lossy(&file[..end]));
+    Some((path, dialog.nFilterIndex.saturating_sub(1) as usize))
+}
+
+/// Standard clipboard format for a packed device-independent bitmap.
+pub const CF_DIB: u32 = 8;
+
+/// Clipboard format id for a named format such as "PNG".
+pub fn register_clipboard_format(name: &str) -> u32 {
+    let name = wide_str(name);
+    unsafe { RegisterClipboardFormatW(PCWSTR::from_raw(name.as_ptr())) }
+}
+
+/// Replace the clipboard contents with `items`, each a (format, bytes) pair
+/// offering the same content in a different representation.
+pub fn set_clipboard(owner: HWND, items: &[(u32, &[u8])]) -> Result<(), String> {
+    unsafe {
+        OpenClipboard(owner).map_err(|e| format!("Unable to open the clipboard: {e}"))?;
+        let result = (|| {
+            EmptyClipboard().map_err(|e| format!("Unable to clear the clipboard: {e}"))?;
+            for (format, bytes) in items {
+                let memory = GlobalAlloc(GMEM_MOVEABLE, bytes.len())
+                    .map_err(|e| format!("Unable to allocate clipboard memory: {e}"))?;
+                let target = GlobalLock(memory) as *mut u8;
+                if target.is_null() {
+                    let _ = GlobalFree(memory);
+                    return Err("Unable to lock clipboard memory".to_string());
+                }
+                std::ptr::copy_nonoverlapping(bytes.as_ptr(), target, bytes.len());
+                let _ = GlobalUnlock(memory);
+                // On success the clipboard owns the memory.
+                if let Err(e) = SetClipboardData(*format, HANDLE(memory.0)) {
+                    let _ = GlobalFree(memory);
+                    return Err(format!("Unable to set clipboard data: {e}"));
+                }
+            }
+            Ok(())
+        })();
+        let _ = CloseClipboard();
+        result
+    }
+}
+
 /// Get the bounding rectangle of a window
 pub fn get_window_rect_safe(hwnd: HWND) -> Option<RECT> {
     unsafe {
@@ -117,8 +515,9 @@ pub fn get_window_rect_safe(hwnd: HWND) -> Option<RECT> {
 /// Embed our window as a child of the taskbar
 pub fn embed_in_taskbar(hwnd: HWND, taskbar_hwnd: HWND) {
     unsafe {
-        // Preserve existing extended style, add tool window + no activate
-        let ex_style = GetWindowLongW(hwnd, GWL_EXSTYLE);
+        // Preserve existing extended style, add tool window + no activate and
+        // drop the taskbar button the fallback popup may have had
+        let ex_style = GetWindowLongW(hwnd, GWL_EXSTYLE) & !(WS_EX_APPWINDOW.0 as i32);
         let _ = SetWindowLongW(
             hwnd,
             GWL_EXSTYLE,
@@ -134,6 +533,97 @@ pub fn embed_in_taskbar(hwnd: HWND, taskbar_hwnd: HWND) {
     }
 }
 
+/// Give a hidden top-level popup its own taskbar button, so progress can be
+/// shown on it. Takes effect the next time the window is shown.
+pub fn show_in_taskbar(hwnd: HWND) {
+    unsafe {
+        let ex_style = GetWindowLongW(hwnd, GWL_EXSTYLE) & !(WS_EX_TOOLWINDOW.0 as i32);
+        let _ = SetWindowLongW(hwnd, GWL_EXSTYLE, ex_style | WS_EX_APPWINDOW.0 as i32);
+    }
+}
+
+/// Sent once the shell has created a window's taskbar button; progress set
+/// before then is lost.
+pub fn taskbar_button_created_message() -> u32 {
+    static MESSAGE: OnceLock<u32> = OnceLock::new();
+    *MESSAGE.get_or_init(|| unsafe {
+        let name = wide_str("TaskbarButtonCreated");
+        RegisterWindowMessageW(PCWSTR::from_raw(name.as_ptr()))
+    })
+}
+
+/// Colour of the progress bar on a taskbar button.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskbarProgressState {
+    /// Green.
+    Normal,
+    /// Yellow.
+    Warning,
+    /// Red.
+    Error,
+}
+
+/// Show `percent` on the window's taskbar button, or clear it with None.
+pub fn set_taskbar_progress(hwnd: HWND, progress: Option<(f64, TaskbarProgressState)>) {
+    unsafe {
+        let com_initialized = CoInitializeEx(None, COINIT_APARTMENTTHREADED).is_ok();
+        if let Ok(list) =
+            CoCreateInstance::<_, ITaskbarList3>(&TaskbarList, None, CLSCTX_INPROC_SERVER)
+        {
+            if list.HrInit().is_ok() {
+                match progress {
+                    Some((percent, state)) => {
+                        let flag = match state {
+                            TaskbarProgressState::Normal => TBPF_NORMAL,
+                            TaskbarProgressState::Warning => TBPF_PAUSED,
+                            TaskbarProgressState::Error => TBPF_ERROR,
+                        };
+                        let _ = list.SetProgressState(hwnd, flag);
+                        let completed = percent.clamp(0.0, 100.0).round() as u64;
+                        let _ = list.SetProgressValue(hwnd, completed, 100);
+                    }
+                    None => {
+                        let _ = list.SetProgressState(hwnd, TBPF_NOPROGRESS);
+                    }
+                }
+            }
+        }
+        if com_initialized {
+            CoUninitialize();
+        }
+    }
+}
+
+/// Overlay `icon` on the window's taskbar button, or remove the overlay with
+/// None. `description` is read out by screen readers.
+pub fn set_taskbar_overlay(hwnd: HWND, icon: Option<HICON>, description: &str) {
+    unsafe {
+        let com_initialized = CoInitializeEx(None, COINIT_APARTMENTTHREADED).is_ok();
+        if let Ok(list) =
+            CoCreateInstance::<_, ITaskbarList3>(&TaskbarList, None, CLSCTX_INPROC_SERVER)
+        {
+            if list.HrInit().is_ok() {
+                let description = wide_str(description);
+                let _ = list.SetOverlayIcon(
+                    hwnd,
+                    icon.unwrap_or_default(),
+                    PCWSTR::from_raw(description.as_ptr()),
+                );
+            }
+        }
+        if com_initialized {
+            CoUninitialize();
+        }
+    }
+}
+
+/// Play the Windows warning sound.
+pub fn play_alert_sound() {
+    unsafe {
+        let _ = MessageBeep(MB_ICONWARNING);
+    }
+}
+
 /// Move the window
 pub fn move_window(hwnd: HWND, x: i32, y: i32, w: i32, h: i32) {
     unsafe {
@@ -141,6 +631,38 @@ pub fn move_window(hwnd: HWND, x: i32, y: i32, w: i32, h: i32) {
     }
 }
 
+/// Show `menu` at `pt` and return the chosen command id, or 0 if it was
+/// dismissed. The menu only takes keyboard input and closes on a click
+/// elsewhere while `owner` is in the foreground, so it is brought there for
+/// the duration and the previously active window gets focus back after.
+pub fn track_popup_menu(owner: HWND, menu: HMENU, pt: POINT) -> u32 {
+    unsafe {
+        let previous = GetForegroundWindow();
+        let _ = SetForegroundWindow(owner);
+        let command = TrackPopupMenu(
+            menu,
+            TPM_RETURNCMD | TPM_NONOTIFY | TPM_RIGHTBUTTON,
+            pt.x,
+            pt.y,
+            0,
+            owner,
+            None,
+        );
+        // Without a message after the menu, reopening it can need two clicks.
+        let _ = PostMessageW(owner, WM_NULL, WPARAM(0), LPARAM(0));
+        if should_restore_foreground(previous, owner, GetForegroundWindow()) {
+            let _ = SetForegroundWindow(previous);
+        }
+        command.0 as u32
+    }
+}
+
+/// Hand the foreground back only if the menu left `owner` holding it; a
+/// window the user switched to in the meantime keeps it.
+fn should_restore_foreground(previous: HWND, owner: HWND, current: HWND) -> bool {
+    !previous.is_invalid() && previous != owner && current == owner
+}
+
 /// Set up a WinEvent hook for tray location changes
 pub fn set_tray_event_hook(
     thread_id: u32,
@@ -187,7 +709,7 @@ pub fn colorref(r: u8, g: u8, b: u8) -> u32 {
 }
 
 /// Color helper
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -211,4 +733,47 @@ impl Color {
     pub fn to_colorref(self) -> u32 {
         colorref(self.r, self.g, self.b)
     }
+
+    /// Mix towards `other`; `t` of 0.0 keeps `self`, 1.0 gives `other`.
+    pub fn blend(self, other: Color, t: f64) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        Self {
+            r: mix(self.r, other.r),
+            g: mix(self.g, other.g),
+            b: mix(self.b, other.b),
+        }
+    }
+
+    pub fn is_light(self) -> bool {
+        let luminance = 0.299 * self.r as f64 + 0.587 * self.g as f64 + 0.114 * self.b as f64;
+        luminance > 140.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hwnd(value: usize) -> HWND {
+        HWND(value as *mut _)
+    }
+
+    #[test]
+    fn menu_hands_focus_back_to_the_previous_window() {
+        assert!(should_restore_foreground(hwnd(1), hwnd(2), hwnd(2)));
+    }
+
+    #[test]
+    fn menu_leaves_focus_alone_when_it_moved_on() {
+        // The user switched to another window while the menu was open.
+        assert!(!should_restore_foreground(hwnd(1), hwnd(2), hwnd(3)));
+        // Nothing was active before, or the owner already was.
+        assert!(!should_restore_foreground(
+            HWND::default(),
+            hwnd(2),
+            hwnd(2)
+        ));
+        assert!(!should_restore_foreground(hwnd(2), hwnd(2), hwnd(2)));
+    }
 }
@@ -0,0 +1,457 @@
+//! Receiver for the OpenTelemetry metrics Claude Code exports. With
+//! `OTEL_EXPORTER_OTLP_PROTOCOL=http/json` pointed at this loopback port, the
+//! token and cost counters it reports replace the estimates read from local
+//! transcripts. Only what arrived since the receiver started is known, so
+//! ranges starting earlier still need the estimates.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::Value;
+
+use crate::diagnose;
+use crate::transcripts::TokenTotals;
+
+const METRICS_PATH: &str = "/v1/metrics";
+const TOKEN_METRIC: &str = "claude_code.token.usage";
+const COST_METRIC: &str = "claude_code.cost.usage";
+
+/// Samples older than the weekly window are never summed again.
+const RETENTION: Duration = Duration::from_secs(7 * 86_400);
+
+/// Larger requests are refused before anything is allocated for them. Claude
+/// Code's metric exports are a few kilobytes.
+const MAX_BODY_BYTES: usize = 4 * 1024 * 1024;
+/// Room for the request line and headers.
+const MAX_HEAD_BYTES: u64 = 16 * 1024;
+/// Connections handled at once; more are closed straight away.
+const MAX_CONNECTIONS: usize = 8;
+
+/// OTLP `AggregationTemporality` values.
+const TEMPORALITY_CUMULATIVE: i64 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SampleKind {
+    Input,
+    Output,
+    Cached,
+    Cost,
+}
+
+/// An increase of one counter, at unix seconds `ts`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Sample {
+    ts: u64,
+    kind: SampleKind,
+    value: f64,
+}
+
+#[derive(Default)]
+struct Telemetry {
+    samples: Vec<Sample>,
+    /// Last value of each cumulative series, to turn it into increases.
+    cumulative: HashMap<String, f64>,
+}
+
+static TELEMETRY: Mutex<Option<Telemetry>> = Mutex::new(None);
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// When the receiver started listening, unix seconds.
+static STARTED_AT: AtomicU64 = AtomicU64::new(0);
+static CONNECTIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Counts one open connection for as long as it lives.
+struct ConnectionSlot;
+
+impl ConnectionSlot {
+    fn take() -> Option<ConnectionSlot> {
+        CONNECTIONS
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |open| {
+                (open < MAX_CONNECTIONS).then_some(open + 1)
+            })
+            .ok()
+            .map(|_| ConnectionSlot)
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        CONNECTIONS.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+fn lock_telemetry() -> MutexGuard<'static, Option<Telemetry>> {
+    TELEMETRY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Listen for OTLP/HTTP metrics on loopback `port`.
+pub fn start(port: u16) -> Result<(), String> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .map_err(|e| format!("Unable to listen for telemetry on port {port}: {e}"))?;
+    *lock_telemetry() = Some(Telemetry::default());
+    STARTED_AT.store(unix_now(), Ordering::Relaxed);
+    ENABLED.store(true, Ordering::Relaxed);
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let Some(slot) = ConnectionSlot::take() else {
+                        diagnose::log("telemetry: too many connections; closing one");
+                        continue;
+                    };
+                    std::thread::spawn(move || {
+                        handle_connection(stream);
+                        drop(slot);
+                    });
+                }
+                Err(error) => diagnose::log_error("telemetry: accept failed", error),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// True once the receiver is listening; token and cost figures then come from
+/// telemetry.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Whether the receiver was listening for everything since `since` (unix
+/// seconds). Telemetry is kept in memory only, so after a restart the figures
+/// for a range starting earlier miss what came before.
+pub fn covers(since: u64) -> bool {
+    is_enabled() && since >= STARTED_AT.load(Ordering::Relaxed)
+}
+
+/// Claude Code's reported spend in USD since `since` (unix seconds).
+pub fn cost_since(since: u64) -> f64 {
+    samples_since(since)
+        .filter(|sample| sample.kind == SampleKind::Cost)
+        .map(|sample| sample.value)
+        .sum()
+}
+
+/// Claude Code's reported token counts since `since` (unix seconds).
+pub fn tokens_since(since: u64) -> TokenTotals {
+    let mut totals = TokenTotals::default();
+    for sample in samples_since(since) {
+        let tokens = sample.value.round().max(0.0) as u64;
+        match sample.kind {
+            SampleKind::Input => totals.input += tokens,
+            SampleKind::Output => totals.output += tokens,
+            SampleKind::Cached => totals.cached += tokens,
+            SampleKind::Cost => {}
+        }
+    }
+    totals
+}
+
+fn samples_since(since: u64) -> impl Iterator<Item = Sample> {
+    let samples: Vec<Sample> = lock_telemetry()
+        .as_ref()
+        .map(|telemetry| telemetry.samples.clone())
+        .unwrap_or_default();
+    samples.into_iter().filter(move |sample| sample.ts >= since)
+}
+
+fn handle_connection(mut stream: TcpStream) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let (status, reason) = match read_request(&stream) {
+        Ok((path, content_type, body)) => handle_request(&path, &content_type, &body),
+        Err(Some(refusal)) => refusal,
+        Err(None) => return,
+    };
+
+    let response = format!(
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: application/json\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{{}}"
+    );
+    let _ = stream.write_all(response.as_bytes());
+    let _ = stream.flush();
+}
+
+fn handle_request(path: &str, content_type: &str, body: &[u8]) -> (u16, &'static str) {
+    if !path.starts_with(METRICS_PATH) {
+        (404, "Not Found")
+    } else if !content_type.starts_with("application/json") {
+        diagnose::log(
+            "telemetry: only OTLP/HTTP JSON is supported; set OTEL_EXPORTER_OTLP_PROTOCOL=http/json",
+        );
+        (415, "Unsupported Media Type")
+    } else {
+        match serde_json::from_slice::<Value>(body) {
+            Ok(request) => {
+                let now = unix_now();
+                if let Some(telemetry) = lock_telemetry().as_mut() {
+                    telemetry.record(&request, now);
+                }
+                (200, "OK")
+            }
+            Err(error) => {
+                diagnose::log_error("telemetry: invalid metrics request", error);
+                (400, "Bad Request")
+            }
+        }
+    }
+}
+
+/// The status to refuse a request with, or None when it broke off and there
+/// is no one to answer.
+type Refusal = Option<(u16, &'static str)>;
+
+/// Read the request line, headers and body; returns the path, content type
+/// and body.
+fn read_request(stream: impl Read) -> Result<(String, String, Vec<u8>), Refusal> {
+    let mut reader = BufReader::new(stream).take(MAX_HEAD_BYTES);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).map_err(|_| None)?;
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .ok_or(Some((400, "Bad Request")))?
+        .to_string();
+
+    let mut content_length = None;
+    let mut content_type = String::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).map_err(|_| None)? == 0 {
+            if reader.limit() == 0 {
+                return Err(Some((431, "Request Header Fields Too Large")));
+            }
+            return Err(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let name = name.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = Some(
+                    value
+                        .trim()
+                        .parse::<usize>()
+                        .map_err(|_| Some((400, "Bad Request")))?,
+                );
+            } else if name.eq_ignore_ascii_case("content-type") {
+                content_type = value.trim().to_ascii_lowercase();
+            }
+        }
+    }
+
+    let content_length = content_length.ok_or(Some((411, "Length Required")))?;
+    if content_length > MAX_BODY_BYTES {
+        return Err(Some((413, "Payload Too Large")));
+    }
+    let mut body = vec![0u8; content_length];
+    reader.set_limit(content_length as u64);
+    reader.read_exact(&mut body).map_err(|_| None)?;
+    Ok((path, content_type, body))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+impl Telemetry {
+    /// Add the token and cost increases in an OTLP `ExportMetricsServiceRequest`.
+    fn record(&mut self, request: &Value, now: u64) {
+        let metrics = request["resourceMetrics"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .flat_map(|resource| resource["scopeMetrics"].as_array().into_iter().flatten())
+            .flat_map(|scope| scope["metrics"].as_array().into_iter().flatten());
+
+        for metric in metrics {
+            let name = metric["name"].as_str().unwrap_or_default();
+            if name != TOKEN_METRIC && name != COST_METRIC {
+                continue;
+            }
+            let sum = &metric["sum"];
+            let cumulative = temporality(&sum["aggregationTemporality"]) == TEMPORALITY_CUMULATIVE;
+
+            for point in sum["dataPoints"].as_array().into_iter().flatten() {
+                let attributes = attributes(point);
+                let kind = if name == COST_METRIC {
+                    SampleKind::Cost
+                } else {
+                    match attributes.get("type").map(String::as_str) {
+                        Some("input") => SampleKind::Input,
+                        Some("output") => SampleKind::Output,
+                        Some("cacheRead" | "cacheCreation") => SampleKind::Cached,
+                        _ => continue,
+                    }
+                };
+                let Some(value) = number(&point["asDouble"]).or_else(|| number(&point["asInt"]))
+                else {
+                    continue;
+                };
+
+                let value = if cumulative {
+                    let mut series: Vec<_> = attributes.iter().collect();
+                    series.sort();
+                    let key = format!("{name}{series:?}");
+                    let previous = self.cumulative.insert(key, value);
+                    // A counter that went down was restarted.
+                    match previous {
+                        Some(previous) if previous <= value => value - previous,
+                        _ => value,
+                    }
+                } else {
+                    value
+                };
+                if value > 0.0 {
+                    let ts = number(&point["timeUnixNano"])
+                        .map(|nanos| (nanos / 1e9) as u64)
+                        .filter(|ts| *ts > 0 && *ts <= now)
+                        .unwrap_or(now);
+                    self.samples.push(Sample { ts, kind, value });
+                }
+            }
+        }
+
+        let cutoff = now.saturating_sub(RETENTION.as_secs());
+        self.samples.retain(|sample| sample.ts >= cutoff);
+    }
+}
+
+/// OTLP JSON writes enums as numbers or as their names.
+fn temporality(value: &Value) -> i64 {
+    match value {
+        Value::String(name) if name.ends_with("CUMULATIVE") => TEMPORALITY_CUMULATIVE,
+        value => value.as_i64().unwrap_or(0),
+    }
+}
+
+/// OTLP JSON writes 64-bit integers as strings.
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::String(text) => text.parse().ok(),
+        value => value.as_f64(),
+    }
+}
+
+fn attributes(point: &Value) -> HashMap<String, String> {
+    point["attributes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|attribute| {
+            let key = attribute["key"].as_str()?;
+            let value = attribute["value"]["stringValue"].as_str()?;
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(metric: &str, temporality: Value, points: Value) -> Value {
+        serde_json::json!({
+            "resourceMetrics": [{
+                "scopeMetrics": [{
+                    "metrics": [{
+                        "name": metric,
+                        "sum": {
+                            "aggregationTemporality": temporality,
+                            "dataPoints": points,
+                        }
+                    }]
+                }]
+            }]
+        })
+    }
+
+    #[test]
+    fn delta_points_are_recorded_by_token_type() {
+        let mut telemetry = Telemetry::default();
+        telemetry.record(
+            &request(
+                TOKEN_METRIC,
+                1.into(),
+                serde_json::json!([
+                    {
+                        "asDouble": 1200,
+                        "timeUnixNano": "1700000000000000000",
+                        "attributes": [{ "key": "type", "value": { "stringValue": "input" } }]
+                    },
+                    {
+                        "asInt": "300",
+                        "attributes": [{ "key": "type", "value": { "stringValue": "cacheRead" } }]
+                    }
+                ]),
+            ),
+            1_700_000_100,
+        );
+
+        assert_eq!(
+            telemetry.samples,
+            vec![
+                Sample {
+                    ts: 1_700_000_000,
+                    kind: SampleKind::Input,
+                    value: 1200.0
+                },
+                Sample {
+                    ts: 1_700_000_100,
+                    kind: SampleKind::Cached,
+                    value: 300.0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn cumulative_points_record_the_increase() {
+        let mut telemetry = Telemetry::default();
+        for total in [0.5, 0.75, 0.25] {
+            telemetry.record(
+                &request(
+                    COST_METRIC,
+                    "AGGREGATION_TEMPORALITY_CUMULATIVE".into(),
+                    serde_json::json!([{ "asDouble": total }]),
+                ),
+                1_700_000_000,
+            );
+        }
+
+        let values: Vec<f64> = telemetry.samples.iter().map(|s| s.value).collect();
+        // The drop to 0.25 is a restarted counter, not a refund.
+        assert_eq!(values, vec![0.5, 0.25, 0.25]);
+    }
+
+    #[test]
+    fn requests_without_a_usable_length_are_refused_before_reading_the_body() {
+        let read = |request: &str| read_request(request.as_bytes()).map(|(_, _, body)| body);
+
+        assert_eq!(
+            read("POST /v1/metrics HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}"),
+            Ok(b"{}".to_vec())
+        );
+        assert_eq!(
+            read("POST /v1/metrics HTTP/1.1\r\nContent-Length: 99999999999\r\n\r\n"),
+            Err(Some((413, "Payload Too Large")))
+        );
+        assert_eq!(
+            read("POST /v1/metrics HTTP/1.1\r\nContent-Length: lots\r\n\r\n"),
+            Err(Some((400, "Bad Request")))
+        );
+        assert_eq!(
+            read("POST /v1/metrics HTTP/1.1\r\n\r\n{}"),
+            Err(Some((411, "Length Required")))
+        );
+        // Closed before the headers ended.
+        assert_eq!(read("POST /v1/metrics HTTP/1.1\r\n"), Err(None));
+    }
+}
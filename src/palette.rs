@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+
+use crate::native_interop::Color;
+
+/// Usage at or above which bar segments are hatched in palettes that mark
+/// warnings with a pattern.
+pub const WARNING_PERCENT: f64 = 80.0;
+
+/// Colours for the usage bars and tray badges. The colour-blind palettes
+/// escalate from blue to orange instead of orange to red, and hatch warning
+/// segments so the level never depends on hue alone.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Palette {
+    #[default]
+    Standard,
+    Deuteranopia,
+    Protanopia,
+}
+
+impl Palette {
+    /// Claude bar colour, and the tray badge colour below 50%.
+    pub fn claude_accent(self) -> Color {
+        match self {
+            Palette::Standard => Color::from_hex("#D97757"),
+            Palette::Deuteranopia | Palette::Protanopia => Color::from_hex("#0072B2"),
+        }
+    }
+
+    /// Claude usage text when several models share the widget.
+    pub fn claude_text(self, is_dark: bool) -> Color {
+        match (self, is_dark) {
+            (Palette::Standard, true) => Color::from_hex("#F09A7A"),
+            (Palette::Standard, false) => Color::from_hex("#A94F32"),
+            (_, true) => Color::from_hex("#56B4E9"),
+            (_, false) => Color::from_hex("#005A8C"),
+        }
+    }
+
+    pub fn antigravity_accent(self) -> Color {
+        match self {
+            Palette::Standard => Color::from_hex("#4285F4"),
+            // Kept apart from the blue Claude accent.
+            Palette::Deuteranopia | Palette::Protanopia => Color::from_hex("#CC79A7"),
+        }
+    }
+
+    pub fn antigravity_text(self, is_dark: bool) -> Color {
+        match (self, is_dark) {
+            (Palette::Standard, true) => Color::from_hex("#8AB4F8"),
+            (Palette::Standard, false) => Color::from_hex("#1967D2"),
+            (_, true) => Color::from_hex("#E0A3C6"),
+            (_, false) => Color::from_hex("#9A4C7A"),
+        }
+    }
+
+    /// Usage text at or above the emphasis threshold.
+    pub fn warning_text(self, is_dark: bool) -> Color {
+        match (self, is_dark) {
+            (Palette::Standard, true) => Color::from_hex("#FF6B5B"),
+            (Palette::Standard, false) => Color::from_hex("#C42B1C"),
+            // Orange, as in the badge, rather than a red that reads as brown.
+            (_, true) => Color::from_hex("#F5A623"),
+            (_, false) => Color::from_hex("#B35900"),
+        }
+    }
+
+    /// Bar colour once a limit is reached: the badge colour at 100%, so it
+    /// never reads as the model's own accent.
+    pub fn limit_accent(self) -> Color {
+        self.badge_fill(100.0)
+    }
+
+    /// Whether bar segments at or above `WARNING_PERCENT` are hatched.
+    pub fn patterned_warnings(self) -> bool {
+        self != Palette::Standard
+    }
+
+    /// Claude tray badge colour stops from 50% to 100% usage.
+    fn badge_stops(self) -> [(f64, Color); 5] {
+        match self {
+            Palette::Standard => [
+                (50.0, Color::from_hex("#D97757")),
+                (70.0, Color::from_hex("#D08540")),
+                (85.0, Color::from_hex("#CC8C20")),
+                (95.0, Color::from_hex("#C45020")),
+                (100.0, Color::from_hex("#B82020")),
+            ],
+            Palette::Deuteranopia => [
+                (50.0, Color::from_hex("#0072B2")),
+                (70.0, Color::from_hex("#3A7FA8")),
+                (85.0, Color::from_hex("#E69F00")),
+                (95.0, Color::from_hex("#D55E00")),
+                (100.0, Color::from_hex("#D55E00")),
+            ],
+            // Reds look dark and muddy with protanopia, so stay with orange.
+            Palette::Protanopia => [
+                (50.0, Color::from_hex("#0072B2")),
+                (70.0, Color::from_hex("#3A7FA8")),
+                (85.0, Color::from_hex("#E69F00")),
+                (95.0, Color::from_hex("#E08A00")),
+                (100.0, Color::from_hex("#DB7A00")),
+            ],
+        }
+    }
+
+    /// Claude tray badge colour for `percent` usage.
+    pub fn badge_fill(self, percent: f64) -> Color {
+        let stops = self.badge_stops();
+        if percent <= stops[0].0 {
+            return stops[0].1;
+        }
+
+        for pair in stops.windows(2) {
+            let (start_pct, start_color) = pair[0];
+            let (end_pct, end_color) = pair[1];
+            if percent <= end_pct {
+                let span = (end_pct - start_pct).max(f64::EPSILON);
+                let t = (percent - start_pct) / span;
+                return start_color.blend(end_color, t);
+            }
+        }
+
+        stops[stops.len() - 1].1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_badge_keeps_the_original_gradient() {
+        let palette = Palette::Standard;
+
+        assert_eq!(palette.badge_fill(10.0), Color::from_hex("#D97757"));
+        assert_eq!(palette.badge_fill(85.0), Color::from_hex("#CC8C20"));
+        assert_eq!(palette.badge_fill(120.0), Color::from_hex("#B82020"));
+    }
+
+    #[test]
+    fn colour_blind_badges_never_turn_red() {
+        for palette in [Palette::Deuteranopia, Palette::Protanopia] {
+            for percent in (0..=100).step_by(5) {
+                let fill = palette.badge_fill(percent as f64);
+                // A red badge has almost no green; orange keeps plenty.
+                assert!(
+                    fill.g as f64 >= fill.r as f64 * 0.35,
+                    "{palette:?} at {percent}% is {fill:?}"
+                );
+            }
+        }
+    }
+}
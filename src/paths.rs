@@ -0,0 +1,36 @@
+//! Where the app keeps its files: `%APPDATA%`, or a `data` folder next to the
+//! executable in portable mode.
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Marker file next to the executable that turns on portable mode, as does
+/// starting it with `--portable`.
+const PORTABLE_MARKER: &str = "portable";
+
+static PORTABLE_DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// The `data` folder next to the executable in portable mode.
+fn portable_dir() -> Option<&'static PathBuf> {
+    PORTABLE_DIR
+        .get_or_init(|| {
+            let exe_dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+            let flagged = std::env::args().skip(1).any(|arg| arg == "--portable");
+            (flagged || exe_dir.join(PORTABLE_MARKER).is_file()).then(|| exe_dir.join("data"))
+        })
+        .as_ref()
+}
+
+pub fn is_portable() -> bool {
+    portable_dir().is_some()
+}
+
+/// Where settings, history and other app files live: `%APPDATA%`, or next to
+/// the executable in portable mode.
+pub fn data_dir() -> PathBuf {
+    if let Some(dir) = portable_dir() {
+        return dir.clone();
+    }
+    let appdata = std::env::var("APPDATA").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(appdata).join("ClaudeCodeUsageMonitor")
+}
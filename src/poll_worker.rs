@@ -0,0 +1,111 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::diagnose;
+
+/// How long exit waits for an in-flight poll. Helper processes notice the
+/// cancellation and are killed within this, but a request blocked on the
+/// network cannot be interrupted; it is left behind, cancelled, so it never
+/// writes its result, and dies with the process.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
+enum Command {
+    Poll,
+    Shutdown,
+}
+
+struct PollWorker {
+    sender: Sender<Command>,
+    done: Receiver<()>,
+    handle: JoinHandle<()>,
+}
+
+static WORKER: Mutex<Option<PollWorker>> = Mutex::new(None);
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+/// Sequence number of the newest poll started, by any worker.
+static LATEST_POLL: AtomicU64 = AtomicU64::new(0);
+
+/// Identifies one run of the poll job.
+#[derive(Clone, Copy, Debug)]
+pub struct PollTicket(u64);
+
+impl PollTicket {
+    /// True while no newer poll has started and shutdown has not begun. A
+    /// poll must drop its result once this is false, so a slow response never
+    /// overwrites a newer one.
+    pub fn is_current(self) -> bool {
+        !is_cancelled() && LATEST_POLL.load(Ordering::SeqCst) == self.0
+    }
+}
+
+fn lock_worker() -> MutexGuard<'static, Option<PollWorker>> {
+    WORKER.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Start the single thread that runs `job` for each requested poll. Polls
+/// never overlap, and requests made while one is running are folded into a
+/// single follow-up poll. A poll a previous worker left running is made stale.
+pub fn start(job: impl Fn(PollTicket) + Send + 'static) {
+    let (sender, receiver) = mpsc::channel();
+    let (done_sender, done) = mpsc::channel();
+    LATEST_POLL.fetch_add(1, Ordering::SeqCst);
+    CANCELLED.store(false, Ordering::SeqCst);
+
+    let handle = std::thread::spawn(move || {
+        while let Ok(Command::Poll) = receiver.recv() {
+            let mut shutdown = false;
+            while let Ok(command) = receiver.try_recv() {
+                shutdown |= matches!(command, Command::Shutdown);
+            }
+            if shutdown || is_cancelled() {
+                break;
+            }
+            let ticket = PollTicket(LATEST_POLL.fetch_add(1, Ordering::SeqCst) + 1);
+            job(ticket);
+        }
+        let _ = done_sender.send(());
+    });
+
+    *lock_worker() = Some(PollWorker {
+        sender,
+        done,
+        handle,
+    });
+}
+
+/// Queue a poll on the worker thread.
+pub fn request() {
+    if let Some(worker) = lock_worker().as_ref() {
+        let _ = worker.sender.send(Command::Poll);
+    }
+}
+
+/// True once shutdown has begun; a poll that finishes after this must drop
+/// its result instead of touching window state.
+pub fn is_cancelled() -> bool {
+    CANCELLED.load(Ordering::SeqCst)
+}
+
+/// Cancel outstanding polls and wait for the worker to finish. Must not be
+/// called while holding the window state lock, which the worker may need to
+/// finish a poll.
+pub fn shutdown() {
+    let Some(worker) = lock_worker().take() else {
+        return;
+    };
+    CANCELLED.store(true, Ordering::SeqCst);
+    let _ = worker.sender.send(Command::Shutdown);
+
+    match worker.done.recv_timeout(SHUTDOWN_TIMEOUT) {
+        Ok(()) | Err(RecvTimeoutError::Disconnected) => {
+            let _ = worker.handle.join();
+            diagnose::log("poll worker stopped");
+        }
+        Err(RecvTimeoutError::Timeout) => {
+            diagnose::log("poll worker still waiting on the network at exit; left cancelled");
+        }
+    }
+}
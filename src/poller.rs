@@ -2,19 +2,26 @@ use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::ffi::c_void;
 use std::hash::{Hash, Hasher};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use serde::Deserialize;
 use std::os::windows::process::CommandExt;
 
 use crate::diagnose;
-use crate::localization::Strings;
-use crate::models::{AppUsageData, UsageData, UsageSection};
-
-const USAGE_URL: &str = "https://api.anthropic.com/api/oauth/usage";
-const MESSAGES_URL: &str = "https://api.anthropic.com/v1/messages";
+use crate::history::UsageWindow;
+use crate::mock_usage;
+use crate::models::{
+    ApiRateLimits, AppUsageData, PlanTier, RateLimitBucket, UnifiedLimit, UsageData, UsageSection,
+};
+use crate::poll_worker;
+
+const API_BASE_URL: &str = "https://api.anthropic.com";
+const USAGE_PATH: &str = "/api/oauth/usage";
+const MESSAGES_PATH: &str = "/v1/messages";
 const CODEX_USAGE_URL: &str = "https://chatgpt.com/backend-api/wham/usage";
 const ANTIGRAVITY_CREDENTIAL_TARGET: &str = "gemini:antigravity";
 const ANTIGRAVITY_ENDPOINTS: &[&str] = &[
@@ -43,10 +50,151 @@ pub enum CredentialWatchMode {
 
 pub type CredentialWatchSnapshot = Vec<String>;
 
+/// Claude endpoint override used by developer modes such as `--mock-server`,
+/// or by a gateway configured in settings.json.
+struct ApiOverride {
+    base_url: String,
+    token: Option<String>,
+}
+
+static API_OVERRIDE: OnceLock<ApiOverride> = OnceLock::new();
+
+/// Point the Claude poller at a different API base URL. When `token` is set,
+/// local credentials are not read and the token is sent as-is.
+pub fn set_api_override(base_url: String, token: Option<String>) {
+    let _ = API_OVERRIDE.set(ApiOverride { base_url, token });
+}
+
+/// When set, Claude usage is read from the per-minute limits of the API key in
+/// `ANTHROPIC_API_KEY` instead of the OAuth plan windows.
+static API_KEY_MODE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_api_key_mode(enabled: bool) {
+    API_KEY_MODE.store(enabled, Ordering::Relaxed);
+}
+
+/// Headers added to every Claude usage request, e.g. for an LLM gateway.
+static EXTRA_HEADERS: OnceLock<Vec<(String, String)>> = OnceLock::new();
+
+pub fn set_extra_headers(headers: Vec<(String, String)>) {
+    let _ = EXTRA_HEADERS.set(headers);
+}
+
+/// Apply the configured extra headers last, so a gateway can also replace the
+/// headers set by the poller.
+fn with_extra_headers(mut request: ureq::Request) -> ureq::Request {
+    for (name, value) in EXTRA_HEADERS.get().into_iter().flatten() {
+        request = request.set(name, value);
+    }
+    request
+}
+
+/// Token endpoint and client id for refreshing Claude OAuth tokens directly,
+/// for organisations whose gateway issues its own tokens. Without it, expired
+/// tokens are refreshed by running the Claude Code CLI.
+struct OAuthClient {
+    token_url: String,
+    client_id: String,
+}
+
+static OAUTH_CLIENT: OnceLock<OAuthClient> = OnceLock::new();
+
+pub fn set_oauth_client(token_url: String, client_id: String) {
+    let _ = OAUTH_CLIENT.set(OAuthClient {
+        token_url,
+        client_id,
+    });
+}
+
+/// Claude credentials file set in settings.json, used instead of the one in
+/// the Claude config directory.
+static CREDENTIALS_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn set_credentials_path(path: PathBuf) {
+    let _ = CREDENTIALS_PATH.set(path);
+}
+
+/// WSL distro set in settings.json as the only place Claude Code runs; its
+/// credentials are read instead of probing Windows and every distro.
+static WSL_DISTRO: OnceLock<String> = OnceLock::new();
+
+pub fn set_wsl_distro(distro: String) {
+    let _ = WSL_DISTRO.set(distro);
+}
+
+/// How the last usage request of the current poll ended, e.g. "HTTP 429 Too
+/// Many Requests", for the diagnostics shown after a failed poll.
+static LAST_RESPONSE: Mutex<Option<String>> = Mutex::new(None);
+/// How long the server asked us to wait, when the latest poll was turned away
+/// with 429 or 529 and a `Retry-After`.
+static RETRY_AFTER: Mutex<Option<Duration>> = Mutex::new(None);
+
+fn note_response(result: &Result<ureq::Response, ureq::Error>) {
+    let text = match result {
+        Ok(response) | Err(ureq::Error::Status(_, response)) => {
+            format!("HTTP {} {}", response.status(), response.status_text())
+        }
+        Err(error) => error.to_string(),
+    };
+    *LAST_RESPONSE.lock().unwrap_or_else(|e| e.into_inner()) = Some(text);
+
+    if let Err(ureq::Error::Status(code, response)) = result {
+        if is_overloaded(*code) {
+            if let Some(wait) = response.header("retry-after").and_then(parse_retry_after) {
+                *RETRY_AFTER.lock().unwrap_or_else(|e| e.into_inner()) = Some(wait);
+            }
+        }
+    }
+}
+
+/// Too Many Requests, or Anthropic's Overloaded.
+fn is_overloaded(status: u16) -> bool {
+    status == 429 || status == 529
+}
+
+/// A `Retry-After` in delay-seconds. The HTTP-date form is not used by the
+/// APIs polled here and is ignored.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse::<u64>().ok().map(Duration::from_secs)
+}
+
+/// The wait a 429 or 529 in the latest poll asked for, if any.
+pub fn retry_after() -> Option<Duration> {
+    *RETRY_AFTER.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// How the last usage request ended, if the latest poll sent one.
+pub fn last_response() -> Option<String> {
+    LAST_RESPONSE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// The credentials file inside WSL, following `CLAUDE_CONFIG_DIR` there too.
+const WSL_CREDENTIALS_PATH: &str = "\"${CLAUDE_CONFIG_DIR:-$HOME/.claude}/.credentials.json\"";
+
+/// Claude Code's config directory: `CLAUDE_CONFIG_DIR` when set, otherwise
+/// `~/.claude`.
+pub fn claude_config_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("CLAUDE_CONFIG_DIR").filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    Some(dirs::home_dir()?.join(".claude"))
+}
+
+fn api_base_url() -> &'static str {
+    API_OVERRIDE
+        .get()
+        .map(|value| value.base_url.as_str())
+        .unwrap_or(API_BASE_URL)
+}
+
 #[derive(Deserialize)]
 struct UsageResponse {
     five_hour: Option<UsageBucket>,
     seven_day: Option<UsageBucket>,
+    seven_day_opus: Option<UsageBucket>,
 }
 
 #[derive(Deserialize)]
@@ -171,7 +319,67 @@ extern "system" {
     fn CredFree(buffer: *mut c_void);
 }
 
-pub fn poll(
+/// What one poll should fetch.
+pub struct PollRequest<'a> {
+    pub show_claude_code: bool,
+    pub show_codex: bool,
+    pub show_antigravity: bool,
+    /// Credentials file of a second Claude account to poll as well.
+    pub secondary_credentials: Option<&'a Path>,
+}
+
+/// Where usage comes from. The widget polls one source, chosen at startup, on
+/// the poll worker thread.
+pub trait UsageSource: Send + Sync {
+    fn poll(&self, request: &PollRequest) -> Result<AppUsageData, PollError>;
+
+    /// Poll interval the source needs, overriding the user's choice.
+    fn poll_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Made-up usage from the debug menu, which must not reach the history
+    /// or anything that publishes usage outside the app.
+    fn is_simulated(&self) -> bool {
+        false
+    }
+}
+
+/// The providers' usage APIs, with the local credentials.
+pub struct ApiSource;
+
+impl UsageSource for ApiSource {
+    fn poll(&self, request: &PollRequest) -> Result<AppUsageData, PollError> {
+        *LAST_RESPONSE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        *RETRY_AFTER.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        let mut data = poll(
+            request.show_claude_code,
+            request.show_codex,
+            request.show_antigravity,
+        )?;
+        if let Some(path) = request.secondary_credentials {
+            match poll_claude_account(path) {
+                Ok(usage) => data.secondary = Some(usage),
+                Err(error) => diagnose::log(format!(
+                    "usage poll for {} failed: {error:?}",
+                    path.display()
+                )),
+            }
+        }
+        Ok(data)
+    }
+}
+
+/// The source selected on the command line: `--mock` replaces the APIs.
+pub fn active_source() -> Arc<dyn UsageSource> {
+    if mock_usage::is_active() {
+        Arc::new(mock_usage::MockSource)
+    } else {
+        Arc::new(ApiSource)
+    }
+}
+
+fn poll(
     show_claude_code: bool,
     show_codex: bool,
     show_antigravity: bool,
@@ -242,6 +450,17 @@ fn poll_with(
 }
 
 fn poll_claude_code() -> Result<UsageData, PollError> {
+    if let Some(token) = API_OVERRIDE.get().and_then(|value| value.token.as_deref()) {
+        return fetch_usage_with_fallback(token);
+    }
+
+    if API_KEY_MODE.load(Ordering::Relaxed) {
+        match std::env::var("ANTHROPIC_API_KEY") {
+            Ok(key) if !key.trim().is_empty() => return fetch_usage_via_api_key(key.trim()),
+            _ => diagnose::log("API key limits enabled but ANTHROPIC_API_KEY is not set"),
+        }
+    }
+
     let creds = match read_first_credentials() {
         Some(c) => c,
         None => {
@@ -252,7 +471,43 @@ fn poll_claude_code() -> Result<UsageData, PollError> {
 
     let creds = refresh_or_fallback(creds)?;
 
-    fetch_usage_with_fallback(&creds.access_token)
+    let mut usage = fetch_usage_with_fallback(&creds.access_token)?;
+    usage.plan = creds.plan;
+    Ok(usage)
+}
+
+/// Poll the Claude account whose credentials file is at `credentials_path`,
+/// such as a team organisation's next to a personal plan. Only the configured
+/// OAuth client can refresh its token: the Claude CLI would refresh the
+/// default account instead.
+fn poll_claude_account(credentials_path: &Path) -> Result<UsageData, PollError> {
+    let content = match std::fs::read_to_string(credentials_path) {
+        Ok(content) => content,
+        Err(error) => {
+            diagnose::log_error(
+                &format!(
+                    "unable to read account credentials at {}",
+                    credentials_path.display()
+                ),
+                error,
+            );
+            return Err(PollError::NoCredentials);
+        }
+    };
+    let creds = parse_credentials(
+        &content,
+        CredentialSource::Windows(credentials_path.to_path_buf()),
+    )
+    .ok_or(PollError::NoCredentials)?;
+    let creds = if is_token_expired(creds.expires_at) {
+        oauth_client_refresh(&creds).ok_or(PollError::TokenExpired)?
+    } else {
+        creds
+    };
+
+    let mut usage = fetch_usage_with_fallback(&creds.access_token)?;
+    usage.plan = creds.plan;
+    Ok(usage)
 }
 
 fn poll_codex() -> Result<UsageData, PollError> {
@@ -293,6 +548,10 @@ fn refresh_or_fallback(mut creds: Credentials) -> Result<Credentials, PollError>
             return Ok(creds);
         }
 
+        if let Some(refreshed) = oauth_client_refresh(&creds) {
+            return Ok(refreshed);
+        }
+
         let source = creds.source.clone();
         cli_refresh_token(&source);
 
@@ -313,6 +572,107 @@ fn refresh_or_fallback(mut creds: Credentials) -> Result<Credentials, PollError>
     }
 }
 
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_in: Option<i64>,
+}
+
+/// Refresh through the configured OAuth client, if any. Only Windows
+/// credential files are refreshed this way: refresh tokens may rotate, so the
+/// new tokens must be written back where Claude Code will read them.
+fn oauth_client_refresh(creds: &Credentials) -> Option<Credentials> {
+    let client = OAUTH_CLIENT.get()?;
+    let refresh_token = creds.refresh_token.as_deref()?;
+    let CredentialSource::Windows(path) = &creds.source else {
+        return None;
+    };
+
+    diagnose::log(format!(
+        "attempting Claude token refresh via {}",
+        client.token_url
+    ));
+    let agent = build_agent().ok()?;
+    let request = agent
+        .post(&client.token_url)
+        .set("Content-Type", "application/json");
+    let response: TokenResponse = match with_extra_headers(request).send_json(serde_json::json!({
+        "grant_type": "refresh_token",
+        "refresh_token": refresh_token,
+        "client_id": client.client_id,
+    })) {
+        Ok(response) => match response.into_json() {
+            Ok(tokens) => tokens,
+            Err(error) => {
+                diagnose::log_error("unable to parse token refresh response", error);
+                return None;
+            }
+        },
+        Err(error) => {
+            diagnose::log_error("token refresh request failed", error);
+            return None;
+        }
+    };
+
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64;
+    // Re-read just before writing: Claude Code refreshes the same file, and
+    // overwriting a refresh token it rotated meanwhile would sign it out.
+    let content = std::fs::read_to_string(path).ok()?;
+    let stored = parse_credentials(&content, creds.source.clone());
+    if stored.and_then(|stored| stored.refresh_token).as_deref() != Some(refresh_token) {
+        diagnose::log("credentials changed during the token refresh; leaving them as they are");
+        return None;
+    }
+    let Some(updated) = apply_refreshed_tokens(&content, &response, now_ms) else {
+        diagnose::log("unable to update credentials after token refresh");
+        return None;
+    };
+    if let Err(error) = write_replacing(path, &updated) {
+        // The old refresh token may already be revoked; use the new access
+        // token for this poll anyway.
+        diagnose::log_error("unable to store refreshed credentials", error);
+    }
+
+    Some(Credentials {
+        access_token: response.access_token,
+        refresh_token: response.refresh_token.or(creds.refresh_token.clone()),
+        expires_at: response.expires_in.map(|secs| now_ms + secs * 1000),
+        plan: creds.plan,
+        source: creds.source.clone(),
+    })
+}
+
+/// The credentials file with its OAuth tokens replaced, leaving every other
+/// field as Claude Code wrote it.
+fn apply_refreshed_tokens(content: &str, tokens: &TokenResponse, now_ms: i64) -> Option<String> {
+    let mut json: serde_json::Value = serde_json::from_str(content).ok()?;
+    let oauth = json.get_mut("claudeAiOauth")?.as_object_mut()?;
+    oauth.insert("accessToken".into(), tokens.access_token.clone().into());
+    if let Some(refresh_token) = &tokens.refresh_token {
+        oauth.insert("refreshToken".into(), refresh_token.clone().into());
+    }
+    if let Some(expires_in) = tokens.expires_in {
+        oauth.insert("expiresAt".into(), (now_ms + expires_in * 1000).into());
+    }
+    serde_json::to_string(&json).ok()
+}
+
+/// Replace `path` with `content` through a sibling temporary file, so Claude
+/// Code never reads a half-written credentials file.
+fn write_replacing(path: &Path, content: &str) -> std::io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+    std::fs::write(&temp, content)?;
+    std::fs::rename(&temp, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp);
+    })
+}
+
 /// Invoke the Claude CLI with a minimal prompt to force its internal
 /// OAuth token refresh.
 fn cli_refresh_token(source: &CredentialSource) {
@@ -446,7 +806,8 @@ fn cli_refresh_codex_token() {
 }
 
 /// Spawn a command and wait up to `timeout` for it to finish.
-/// Returns None if the process fails to start or exceeds the deadline.
+/// Returns None if the process fails to start, exceeds the deadline, or is
+/// still running when the app exits.
 fn run_with_timeout(cmd: &mut Command, timeout: Duration) -> Option<std::process::Output> {
     let mut child = cmd.spawn().ok()?;
     let start = std::time::Instant::now();
@@ -454,7 +815,7 @@ fn run_with_timeout(cmd: &mut Command, timeout: Duration) -> Option<std::process
         match child.try_wait() {
             Ok(Some(_)) => return child.wait_with_output().ok(),
             Ok(None) => {
-                if start.elapsed() > timeout {
+                if start.elapsed() > timeout || poll_worker::is_cancelled() {
                     let _ = child.kill();
                     let _ = child.wait();
                     return None;
@@ -467,13 +828,14 @@ fn run_with_timeout(cmd: &mut Command, timeout: Duration) -> Option<std::process
 }
 
 fn wait_for_refresh(child: &mut std::process::Child) {
-    // Wait up to 30 seconds; don't block the poll thread forever.
+    // Wait up to 30 seconds, or until the app exits; don't block the poll
+    // thread forever.
     let start = std::time::Instant::now();
     loop {
         match child.try_wait() {
             Ok(Some(_)) => break,
             Ok(None) => {
-                if start.elapsed() > Duration::from_secs(30) {
+                if start.elapsed() > Duration::from_secs(30) || poll_worker::is_cancelled() {
                     let _ = child.kill();
                     break;
                 }
@@ -585,7 +947,34 @@ pub fn credential_watch_snapshot(mode: CredentialWatchMode) -> CredentialWatchSn
     snapshot
 }
 
+/// Where credentials for the shown providers are looked for, in the order
+/// they are tried. Lists WSL distros, so keep it off the UI thread.
+pub fn credential_locations(show_claude_code: bool, show_codex: bool) -> Vec<String> {
+    let mut locations = Vec::new();
+    if show_claude_code {
+        for source in all_known_credential_sources() {
+            locations.push(match source {
+                CredentialSource::Windows(path) => path.display().to_string(),
+                CredentialSource::Wsl { distro } => {
+                    format!("WSL {distro}: {}", WSL_CREDENTIALS_PATH.trim_matches('"'))
+                }
+            });
+        }
+    }
+    if show_codex {
+        if let Some(path) = codex_auth_path() {
+            locations.push(path.display().to_string());
+        }
+    }
+    locations
+}
+
 fn all_known_credential_sources() -> Vec<CredentialSource> {
+    if let Some(distro) = WSL_DISTRO.get() {
+        return vec![CredentialSource::Wsl {
+            distro: distro.clone(),
+        }];
+    }
     let mut sources = Vec::new();
     if let Some(source) = windows_credential_source() {
         sources.push(source);
@@ -597,10 +986,11 @@ fn all_known_credential_sources() -> Vec<CredentialSource> {
 }
 
 fn windows_credential_source() -> Option<CredentialSource> {
-    let home = dirs::home_dir()?;
-    Some(CredentialSource::Windows(
-        home.join(".claude").join(".credentials.json"),
-    ))
+    let path = match CREDENTIALS_PATH.get() {
+        Some(path) => path.clone(),
+        None => claude_config_dir()?.join(".credentials.json"),
+    };
+    Some(CredentialSource::Windows(path))
 }
 
 fn credential_watch_signature(source: &CredentialSource) -> Option<String> {
@@ -634,11 +1024,11 @@ fn wsl_credential_watch_signature(distro: &str) -> Option<String> {
             .arg("--")
             .arg("sh")
             .arg("-lc")
-            .arg(
-                "if [ -f ~/.claude/.credentials.json ]; then \
-                 stat -c 'present|%s|%Y' ~/.claude/.credentials.json; \
-                 else echo missing; fi",
-            )
+            .arg(format!(
+                "if [ -f {WSL_CREDENTIALS_PATH} ]; then \
+                 stat -c 'present|%s|%Y' {WSL_CREDENTIALS_PATH}; \
+                 else echo missing; fi"
+            ))
             .creation_flags(CREATE_NO_WINDOW)
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::null()),
@@ -668,6 +1058,7 @@ fn fetch_usage_with_fallback(token: &str) -> Result<UsageData, PollError> {
                     if merged.weekly.resets_at.is_none() {
                         merged.weekly.resets_at = fallback.weekly.resets_at;
                     }
+                    merged.unified = fallback.unified;
                     return Ok(merged);
                 }
             }
@@ -687,12 +1078,13 @@ fn fetch_usage_with_fallback(token: &str) -> Result<UsageData, PollError> {
 fn try_usage_endpoint(token: &str) -> Result<Option<UsageData>, PollError> {
     let agent = build_agent()?;
 
-    let resp = match agent
-        .get(USAGE_URL)
+    let request = agent
+        .get(&format!("{}{USAGE_PATH}", api_base_url()))
         .set("Authorization", &format!("Bearer {token}"))
-        .set("anthropic-beta", "oauth-2025-04-20")
-        .call()
-    {
+        .set("anthropic-beta", "oauth-2025-04-20");
+    let result = with_extra_headers(request).call();
+    note_response(&result);
+    let resp = match result {
         Ok(resp) => resp,
         Err(ureq::Error::Status(code, _)) if code == 401 || code == 403 => {
             diagnose::log(format!(
@@ -719,6 +1111,11 @@ fn try_usage_endpoint(token: &str) -> Result<Option<UsageData>, PollError> {
         data.weekly.resets_at = parse_iso8601(bucket.resets_at.as_deref());
     }
 
+    data.opus = response.seven_day_opus.as_ref().map(|bucket| UsageSection {
+        percentage: bucket.utilization,
+        resets_at: parse_iso8601(bucket.resets_at.as_deref()),
+    });
+
     Ok(Some(data))
 }
 
@@ -732,13 +1129,14 @@ fn fetch_usage_via_messages(token: &str) -> Result<UsageData, PollError> {
             "messages": [{"role": "user", "content": "."}]
         });
 
-        let response = match agent
-            .post(MESSAGES_URL)
+        let request = agent
+            .post(&format!("{}{MESSAGES_PATH}", api_base_url()))
             .set("Authorization", &format!("Bearer {token}"))
             .set("anthropic-version", "2023-06-01")
-            .set("anthropic-beta", "oauth-2025-04-20")
-            .send_json(&body)
-        {
+            .set("anthropic-beta", "oauth-2025-04-20");
+        let result = with_extra_headers(request).send_json(&body);
+        note_response(&result);
+        let response = match result {
             Ok(resp) => resp,
             Err(ureq::Error::Status(code, _)) if code == 401 || code == 403 => {
                 diagnose::log(format!(
@@ -746,6 +1144,7 @@ fn fetch_usage_via_messages(token: &str) -> Result<UsageData, PollError> {
                 ));
                 return Err(PollError::AuthRequired);
             }
+            // A rejected request still reports the limits that rejected it.
             Err(ureq::Error::Status(_code, resp)) => resp,
             Err(_) => continue,
         };
@@ -755,47 +1154,224 @@ fn fetch_usage_via_messages(token: &str) -> Result<UsageData, PollError> {
         let hs = response.header("anthropic-ratelimit-unified-status");
 
         if h5.is_some() || h7.is_some() || hs.is_some() {
-            return Ok(parse_rate_limit_headers(&response));
+            return Ok(parse_rate_limit_headers(|name| response.header(name)));
+        }
+        // Every other model would be turned away too.
+        if is_overloaded(response.status()) {
+            break;
         }
     }
 
     Err(PollError::RequestFailed)
 }
 
-fn parse_rate_limit_headers(response: &ureq::Response) -> UsageData {
-    let mut data = UsageData::default();
+/// Send one Messages request with the current credentials and list the HTTP
+/// status and every `anthropic-ratelimit-*` header, for `--debug-headers`.
+pub fn probe_rate_limit_headers() -> Result<Vec<String>, String> {
+    let api_key = std::env::var("ANTHROPIC_API_KEY")
+        .ok()
+        .map(|key| key.trim().to_string())
+        .filter(|key| !key.is_empty() && API_KEY_MODE.load(Ordering::Relaxed));
+    let token = match API_OVERRIDE.get().and_then(|value| value.token.clone()) {
+        Some(token) => token,
+        None if api_key.is_some() => String::new(),
+        None => {
+            let creds = read_first_credentials().ok_or("No Claude credentials found.")?;
+            refresh_or_fallback(creds)
+                .map_err(|error| format!("Unable to use the Claude credentials: {error:?}"))?
+                .access_token
+        }
+    };
 
-    data.session.percentage =
-        get_header_f64(response, "anthropic-ratelimit-unified-5h-utilization") * 100.0;
-    data.session.resets_at = unix_to_system_time(get_header_i64(
-        response,
-        "anthropic-ratelimit-unified-5h-reset",
-    ));
+    let agent = build_agent().map_err(|error| format!("Unable to create a client: {error:?}"))?;
+    let request = agent
+        .post(&format!("{}{MESSAGES_PATH}", api_base_url()))
+        .set("anthropic-version", "2023-06-01");
+    let request = match &api_key {
+        Some(key) => request.set("x-api-key", key),
+        None => request
+            .set("Authorization", &format!("Bearer {token}"))
+            .set("anthropic-beta", "oauth-2025-04-20"),
+    };
+    let response = match with_extra_headers(request).send_json(serde_json::json!({
+        "model": MODEL_FALLBACK_CHAIN[0],
+        "max_tokens": 1,
+        "messages": [{"role": "user", "content": "."}]
+    })) {
+        Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+        Err(error) => return Err(format!("Request failed: {error}")),
+    };
 
-    data.weekly.percentage =
-        get_header_f64(response, "anthropic-ratelimit-unified-7d-utilization") * 100.0;
-    data.weekly.resets_at = unix_to_system_time(get_header_i64(
-        response,
-        "anthropic-ratelimit-unified-7d-reset",
-    ));
+    let mut lines = vec![format!(
+        "HTTP {} {}",
+        response.status(),
+        response.status_text()
+    )];
+    let mut names: Vec<String> = response
+        .headers_names()
+        .into_iter()
+        .filter(|name| {
+            name.to_ascii_lowercase()
+                .starts_with("anthropic-ratelimit-")
+        })
+        .collect();
+    names.sort();
+    for name in names {
+        lines.push(format!(
+            "{name}: {}",
+            response.header(&name).unwrap_or_default()
+        ));
+    }
+    if lines.len() == 1 {
+        lines.push("No anthropic-ratelimit-* headers in the response.".to_string());
+    }
+    Ok(lines)
+}
 
-    let overall_reset = get_header_i64(response, "anthropic-ratelimit-unified-reset");
+/// Send a minimal Messages request with an API key and report its per-minute
+/// limits: requests in the session row, the tightest token bucket in the
+/// weekly row.
+fn fetch_usage_via_api_key(key: &str) -> Result<UsageData, PollError> {
+    let agent = build_agent()?;
+
+    for model in MODEL_FALLBACK_CHAIN {
+        let body = serde_json::json!({
+            "model": model,
+            "max_tokens": 1,
+            "messages": [{"role": "user", "content": "."}]
+        });
 
-    if data.session.percentage == 0.0 && data.weekly.percentage == 0.0 {
-        let status = response.header("anthropic-ratelimit-unified-status");
-        if status == Some("rejected") {
-            let claim = response.header("anthropic-ratelimit-unified-representative-claim");
-            match claim {
-                Some("five_hour") => data.session.percentage = 100.0,
-                Some("seven_day") => data.weekly.percentage = 100.0,
-                _ => {}
+        let request = agent
+            .post(&format!("{}{MESSAGES_PATH}", api_base_url()))
+            .set("x-api-key", key)
+            .set("anthropic-version", "2023-06-01");
+        let result = with_extra_headers(request).send_json(&body);
+        note_response(&result);
+        let response = match result {
+            Ok(resp) => resp,
+            Err(ureq::Error::Status(code, _)) if code == 401 || code == 403 => {
+                diagnose::log(format!(
+                    "messages endpoint rejected the API key with status {code}"
+                ));
+                return Err(PollError::AuthRequired);
             }
+            Err(ureq::Error::Status(_code, resp)) => resp,
+            Err(_) => continue,
+        };
+
+        if let Some(limits) = parse_api_rate_limits(|name| response.header(name)) {
+            return Ok(UsageData {
+                session: limits
+                    .requests
+                    .as_ref()
+                    .map(RateLimitBucket::as_section)
+                    .unwrap_or_default(),
+                weekly: limits
+                    .tightest_tokens()
+                    .map(RateLimitBucket::as_section)
+                    .unwrap_or_default(),
+                api_limits: Some(limits),
+                plan: None,
+                unified: None,
+                per_minute: true,
+                opus: None,
+            });
+        }
+        if is_overloaded(response.status()) {
+            break;
         }
+    }
+
+    Err(PollError::RequestFailed)
+}
+
+/// Read the `anthropic-ratelimit-{requests,tokens,input-tokens,output-tokens}-*`
+/// header groups returned to API-key accounts.
+fn parse_api_rate_limits<'a>(header: impl Fn(&str) -> Option<&'a str>) -> Option<ApiRateLimits> {
+    let bucket = |name: &str| {
+        let value = |suffix: &str| header(&format!("anthropic-ratelimit-{name}-{suffix}"));
+        let limit = value("limit")?.trim().parse::<u64>().ok()?;
+        let remaining = value("remaining")
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .unwrap_or(limit);
+        Some(RateLimitBucket {
+            limit,
+            remaining,
+            resets_at: parse_iso8601(value("reset")),
+        })
+    };
+
+    let limits = ApiRateLimits {
+        requests: bucket("requests"),
+        tokens: bucket("tokens"),
+        input_tokens: bucket("input-tokens"),
+        output_tokens: bucket("output-tokens"),
+    };
+    if limits.requests.is_none()
+        && limits.tokens.is_none()
+        && limits.input_tokens.is_none()
+        && limits.output_tokens.is_none()
+    {
+        return None;
+    }
+    Some(limits)
+}
+
+/// Read the unified `anthropic-ratelimit-unified-*` headers returned to
+/// subscription accounts. `header` looks a header up by name.
+fn parse_rate_limit_headers<'a>(header: impl Fn(&str) -> Option<&'a str>) -> UsageData {
+    let text = |name: &str| {
+        header(&format!("anthropic-ratelimit-unified-{name}"))
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+    // Utilization is a fraction; anything unreadable counts as none used.
+    let utilization = |name: &str| {
+        text(name)
+            .and_then(|value| value.parse::<f64>().ok())
+            .filter(|value| value.is_finite())
+            .map_or(0.0, |value| value.max(0.0) * 100.0)
+    };
+    let reset = |name: &str| text(name).and_then(|value| value.parse::<i64>().ok());
+
+    let mut data = UsageData {
+        api_limits: parse_api_rate_limits(&header),
+        ..Default::default()
+    };
+
+    data.session.percentage = utilization("5h-utilization");
+    data.session.resets_at = unix_to_system_time(reset("5h-reset"));
+
+    data.weekly.percentage = utilization("7d-utilization");
+    data.weekly.resets_at = unix_to_system_time(reset("7d-reset"));
 
-        if data.session.resets_at.is_none() && overall_reset.is_some() {
-            data.session.resets_at = unix_to_system_time(overall_reset);
+    let overall_reset = reset("reset");
+    if data.session.percentage == 0.0
+        && data.weekly.percentage == 0.0
+        && data.session.resets_at.is_none()
+        && overall_reset.is_some()
+    {
+        data.session.resets_at = unix_to_system_time(overall_reset);
+    }
+
+    let unified = UnifiedLimit {
+        status: text("status").map(str::to_string),
+        representative_claim: text("representative-claim").map(str::to_string),
+    };
+    // A rejected request means the binding limit is used up, whatever its
+    // utilization header rounded to.
+    if unified.is_rejected() {
+        match unified.binding_window() {
+            Some(UsageWindow::Session) => {
+                data.session.percentage = data.session.percentage.max(100.0)
+            }
+            Some(UsageWindow::Weekly) => data.weekly.percentage = data.weekly.percentage.max(100.0),
+            None => {}
         }
     }
+    if unified.status.is_some() || unified.representative_claim.is_some() {
+        data.unified = Some(unified);
+    }
 
     data
 }
@@ -811,7 +1387,9 @@ fn fetch_codex_usage(token: &str, account_id: Option<&str>) -> Result<UsageData,
         request = request.set("ChatGPT-Account-Id", account_id);
     }
 
-    let resp = match request.call() {
+    let result = request.call();
+    note_response(&result);
+    let resp = match result {
         Ok(resp) => resp,
         Err(ureq::Error::Status(code, _)) if code == 401 || code == 403 => {
             diagnose::log(format!(
@@ -877,6 +1455,9 @@ fn fetch_antigravity_usage(token: &str) -> Result<UsageData, PollError> {
     let mut last_error = PollError::RequestFailed;
 
     for base_url in ANTIGRAVITY_ENDPOINTS {
+        if poll_worker::is_cancelled() {
+            break;
+        }
         match fetch_antigravity_usage_from_endpoint(base_url, token) {
             Ok(data) => return Ok(data),
             Err(PollError::AuthRequired) => auth_error = true,
@@ -909,7 +1490,15 @@ fn fetch_antigravity_usage_from_endpoint(
     let session = fetch_antigravity_model_quota(base_url, token, project.as_deref())?;
     let weekly = UsageSection::default();
 
-    Ok(UsageData { session, weekly })
+    Ok(UsageData {
+        session,
+        weekly,
+        api_limits: None,
+        plan: None,
+        unified: None,
+        per_minute: false,
+        opus: None,
+    })
 }
 
 fn fetch_antigravity_project(base_url: &str, token: &str) -> Result<Option<String>, PollError> {
@@ -920,13 +1509,14 @@ fn fetch_antigravity_project(base_url: &str, token: &str) -> Result<Option<Strin
         }
     });
 
-    let resp = match agent
+    let result = agent
         .post(&format!("{base_url}/v1internal:loadCodeAssist"))
         .set("Authorization", &format!("Bearer {token}"))
         .set("Content-Type", "application/json")
         .set("User-Agent", "antigravity")
-        .send_json(&body)
-    {
+        .send_json(&body);
+    note_response(&result);
+    let resp = match result {
         Ok(resp) => resp,
         Err(ureq::Error::Status(code, _)) if code == 401 || code == 403 => {
             diagnose::log(format!(
@@ -962,13 +1552,14 @@ fn fetch_antigravity_model_quota(
         None => serde_json::json!({}),
     };
 
-    let resp = match agent
+    let result = agent
         .post(&format!("{base_url}/v1internal:fetchAvailableModels"))
         .set("Authorization", &format!("Bearer {token}"))
         .set("Content-Type", "application/json")
         .set("User-Agent", "antigravity")
-        .send_json(&body)
-    {
+        .send_json(&body);
+    note_response(&result);
+    let resp = match result {
         Ok(resp) => resp,
         Err(ureq::Error::Status(code, _)) if code == 401 || code == 403 => {
             diagnose::log(format!(
@@ -1011,13 +1602,14 @@ fn fetch_antigravity_quota_summary(
     let agent = build_agent()?;
     let body = serde_json::json!({ "project": project });
 
-    let resp = match agent
+    let result = agent
         .post(&format!("{base_url}/v1internal:retrieveUserQuotaSummary"))
         .set("Authorization", &format!("Bearer {token}"))
         .set("Content-Type", "application/json")
         .set("User-Agent", "antigravity")
-        .send_json(&body)
-    {
+        .send_json(&body);
+    note_response(&result);
+    let resp = match result {
         Ok(resp) => resp,
         Err(ureq::Error::Status(code, _)) if code == 401 || code == 403 => {
             return Err(PollError::AuthRequired);
@@ -1147,17 +1739,6 @@ fn is_antigravity_display_model(model: &str) -> bool {
         || model.starts_with("imagen")
 }
 
-fn get_header_f64(response: &ureq::Response, name: &str) -> f64 {
-    response
-        .header(name)
-        .and_then(|s| s.parse::<f64>().ok())
-        .unwrap_or(0.0)
-}
-
-fn get_header_i64(response: &ureq::Response, name: &str) -> Option<i64> {
-    response.header(name).and_then(|s| s.parse::<i64>().ok())
-}
-
 fn unix_to_system_time(unix_secs: Option<i64>) -> Option<SystemTime> {
     let secs = unix_secs?;
     if secs < 0 {
@@ -1168,7 +1749,9 @@ fn unix_to_system_time(unix_secs: Option<i64>) -> Option<SystemTime> {
 
 struct Credentials {
     access_token: String,
+    refresh_token: Option<String>,
     expires_at: Option<i64>,
+    plan: Option<PlanTier>,
     source: CredentialSource,
 }
 
@@ -1179,6 +1762,9 @@ enum CredentialSource {
 }
 
 fn read_first_credentials() -> Option<Credentials> {
+    if let Some(distro) = WSL_DISTRO.get() {
+        return read_wsl_credentials(distro);
+    }
     if let Some(creds) = read_windows_credentials() {
         return Some(creds);
     }
@@ -1308,7 +1894,7 @@ fn read_wsl_credentials(distro: &str) -> Option<Credentials> {
             .arg("--")
             .arg("sh")
             .arg("-lc")
-            .arg("cat ~/.claude/.credentials.json")
+            .arg(format!("cat {WSL_CREDENTIALS_PATH}"))
             .creation_flags(CREATE_NO_WINDOW)
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::null()),
@@ -1340,16 +1926,29 @@ fn parse_credentials(content: &str, source: CredentialSource) -> Option<Credenti
         .get("accessToken")
         .and_then(|v| v.as_str())?
         .to_string();
+    let refresh_token = oauth
+        .get("refreshToken")
+        .and_then(|v| v.as_str())
+        .map(str::to_string);
     let expires_at = oauth.get("expiresAt").and_then(|v| v.as_i64());
+    let plan = PlanTier::from_credentials(
+        oauth.get("subscriptionType").and_then(|v| v.as_str()),
+        oauth.get("rateLimitTier").and_then(|v| v.as_str()),
+    );
 
     Some(Credentials {
         access_token,
+        refresh_token,
         expires_at,
+        plan,
         source,
     })
 }
 
 fn read_next_credentials_after(source: &CredentialSource) -> Option<Credentials> {
+    if WSL_DISTRO.get().is_some() {
+        return None;
+    }
     match source {
         CredentialSource::Windows(_) => {
             for distro in list_wsl_distros() {
@@ -1458,7 +2057,7 @@ fn is_token_expired(expires_at: Option<i64>) -> bool {
 }
 
 /// Parse an ISO 8601 timestamp string into a SystemTime.
-fn parse_iso8601(s: Option<&str>) -> Option<SystemTime> {
+pub fn parse_iso8601(s: Option<&str>) -> Option<SystemTime> {
     let s = s?;
     // Strip timezone offset to get "YYYY-MM-DDTHH:MM:SS" or with fractional seconds
     // The API returns formats like "2026-03-05T08:00:00.321598+00:00"
@@ -1521,72 +2120,6 @@ fn is_leap(y: u64) -> bool {
     (y % 4 == 0 && y % 100 != 0) || y % 400 == 0
 }
 
-/// Format a usage section as "X% · Yh" style text
-pub fn format_line(section: &UsageSection, strings: Strings) -> String {
-    let pct = format!("{:.0}%", section.percentage);
-    let cd = format_countdown(section.resets_at, strings);
-    if cd.is_empty() {
-        pct
-    } else {
-        format!("{pct} \u{00b7} {cd}")
-    }
-}
-
-fn format_countdown(resets_at: Option<SystemTime>, strings: Strings) -> String {
-    let reset = match resets_at {
-        Some(t) => t,
-        None => return String::new(),
-    };
-
-    let remaining = match reset.duration_since(SystemTime::now()) {
-        Ok(d) => d,
-        Err(_) => return strings.now.to_string(),
-    };
-
-    format_countdown_from_secs(remaining.as_secs(), strings)
-}
-
-/// Calculate how long until the display text would change
-pub fn time_until_display_change(resets_at: Option<SystemTime>) -> Option<Duration> {
-    let reset = resets_at?;
-    let remaining = reset.duration_since(SystemTime::now()).ok()?;
-    Some(time_until_display_change_from_secs(remaining.as_secs()))
-}
-
-fn format_countdown_from_secs(total_secs: u64, strings: Strings) -> String {
-    let total_mins = total_secs / 60;
-    let total_hours = total_secs / 3600;
-    let total_days = total_secs / 86400;
-
-    if total_days >= 1 {
-        format!("{total_days}{}", strings.day_suffix)
-    } else if total_hours >= 1 {
-        format!("{total_hours}{}", strings.hour_suffix)
-    } else if total_mins >= 1 {
-        format!("{total_mins}{}", strings.minute_suffix)
-    } else {
-        format!("{total_secs}{}", strings.second_suffix)
-    }
-}
-
-fn time_until_display_change_from_secs(total_secs: u64) -> Duration {
-    let total_mins = total_secs / 60;
-    let total_hours = total_secs / 3600;
-    let total_days = total_secs / 86400;
-
-    let current_bucket_start = if total_days >= 1 {
-        total_days * 86400
-    } else if total_hours >= 1 {
-        total_hours * 3600
-    } else if total_mins >= 1 {
-        total_mins * 60
-    } else {
-        total_secs
-    };
-
-    Duration::from_secs(total_secs.saturating_sub(current_bucket_start) + 1)
-}
-
 /// Returns true if either section has reached "now" (reset time has passed).
 pub fn is_past_reset(data: &UsageData) -> bool {
     let now = SystemTime::now();
@@ -1600,6 +2133,54 @@ pub fn app_is_past_reset(data: &AppUsageData) -> bool {
         || data.antigravity.as_ref().is_some_and(is_past_reset)
 }
 
+const ADAPTIVE_POLL_FAST: Duration = Duration::from_secs(60);
+const ADAPTIVE_POLL_ACTIVE: Duration = Duration::from_secs(5 * 60);
+const ADAPTIVE_POLL_NORMAL: Duration = Duration::from_secs(15 * 60);
+const ADAPTIVE_POLL_IDLE: Duration = Duration::from_secs(60 * 60);
+
+/// Pick the next poll interval for smart polling: poll every minute when a
+/// window is nearly exhausted or about to reset, and back off to hourly while
+/// every window is lightly used and far from its reset.
+pub fn adaptive_poll_interval(data: &AppUsageData) -> Duration {
+    adaptive_poll_interval_at(data, SystemTime::now())
+}
+
+fn adaptive_poll_interval_at(data: &AppUsageData, now: SystemTime) -> Duration {
+    let sections = [&data.claude_code, &data.codex, &data.antigravity]
+        .into_iter()
+        .flatten()
+        .flat_map(|usage| [&usage.session, &usage.weekly]);
+
+    sections
+        .map(|section| {
+            let by_usage = if section.percentage >= 80.0 {
+                ADAPTIVE_POLL_FAST
+            } else if section.percentage >= 50.0 {
+                ADAPTIVE_POLL_ACTIVE
+            } else if section.percentage >= 20.0 {
+                ADAPTIVE_POLL_NORMAL
+            } else {
+                ADAPTIVE_POLL_IDLE
+            };
+
+            let by_reset = match section.resets_at.map(|t| t.duration_since(now)) {
+                Some(Ok(remaining)) if remaining <= Duration::from_secs(10 * 60) => {
+                    ADAPTIVE_POLL_FAST
+                }
+                Some(Ok(remaining)) if remaining <= Duration::from_secs(60 * 60) => {
+                    ADAPTIVE_POLL_ACTIVE
+                }
+                Some(Ok(_)) | None => ADAPTIVE_POLL_IDLE,
+                // Already past the reset; the fast reset poll covers this.
+                Some(Err(_)) => ADAPTIVE_POLL_FAST,
+            };
+
+            by_usage.min(by_reset)
+        })
+        .min()
+        .unwrap_or(ADAPTIVE_POLL_NORMAL)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1611,6 +2192,11 @@ mod tests {
                 resets_at: None,
             },
             weekly: UsageSection::default(),
+            api_limits: None,
+            plan: None,
+            unified: None,
+            per_minute: false,
+            opus: None,
         }
     }
 
@@ -1677,6 +2263,55 @@ mod tests {
         assert_eq!(data.codex.unwrap().session.percentage, 42.0);
     }
 
+    #[test]
+    fn adaptive_interval_backs_off_when_usage_is_low_and_reset_is_far() {
+        let now = SystemTime::now();
+        let data = AppUsageData {
+            claude_code: Some(UsageData {
+                session: UsageSection {
+                    percentage: 5.0,
+                    resets_at: Some(now + Duration::from_secs(4 * 3600)),
+                },
+                weekly: UsageSection {
+                    percentage: 10.0,
+                    resets_at: Some(now + Duration::from_secs(3 * 86400)),
+                },
+                api_limits: None,
+                plan: None,
+                unified: None,
+                per_minute: false,
+                opus: None,
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(adaptive_poll_interval_at(&data, now), ADAPTIVE_POLL_IDLE);
+    }
+
+    #[test]
+    fn adaptive_interval_speeds_up_near_limit_or_reset() {
+        let now = SystemTime::now();
+        let mut data = AppUsageData {
+            codex: Some(usage_with_session_percent(85.0)),
+            ..Default::default()
+        };
+        assert_eq!(adaptive_poll_interval_at(&data, now), ADAPTIVE_POLL_FAST);
+
+        data.codex = Some(UsageData {
+            session: UsageSection {
+                percentage: 10.0,
+                resets_at: Some(now + Duration::from_secs(30 * 60)),
+            },
+            weekly: UsageSection::default(),
+            api_limits: None,
+            plan: None,
+            unified: None,
+            per_minute: false,
+            opus: None,
+        });
+        assert_eq!(adaptive_poll_interval_at(&data, now), ADAPTIVE_POLL_ACTIVE);
+    }
+
     #[test]
     fn antigravity_summary_prefers_gemini_group() {
         let response: AntigravityQuotaSummaryResponse = serde_json::from_str(
@@ -1732,4 +2367,209 @@ mod tests {
         assert!(usage.weekly.resets_at.is_some());
         assert!(usage.session.resets_at.is_some());
     }
+
+    #[test]
+    fn api_key_rate_limit_headers_are_parsed_into_buckets() {
+        let headers: HashMap<&str, &str> = [
+            ("anthropic-ratelimit-requests-limit", "50"),
+            ("anthropic-ratelimit-requests-remaining", "49"),
+            ("anthropic-ratelimit-requests-reset", "2026-06-13T22:08:54Z"),
+            ("anthropic-ratelimit-input-tokens-limit", "40000"),
+            ("anthropic-ratelimit-input-tokens-remaining", "10000"),
+            ("anthropic-ratelimit-output-tokens-limit", "8000"),
+            ("anthropic-ratelimit-output-tokens-remaining", "6000"),
+        ]
+        .into_iter()
+        .collect();
+
+        let limits = parse_api_rate_limits(|name| headers.get(name).copied())
+            .expect("request and token buckets are present");
+
+        let requests = limits.requests.as_ref().expect("requests bucket");
+        assert!((requests.used_percentage() - 2.0).abs() < 0.000001);
+        assert!(requests.resets_at.is_some());
+        assert!(limits.tokens.is_none());
+        let tightest = limits.tightest_tokens().expect("a token bucket");
+        assert!((tightest.used_percentage() - 75.0).abs() < 0.000001);
+    }
+
+    #[test]
+    fn plan_tier_is_read_from_credentials() {
+        let plan = |oauth: &str| {
+            let content = format!(r#"{{"claudeAiOauth":{{"accessToken":"a"{oauth}}}}}"#);
+            parse_credentials(&content, CredentialSource::Windows(PathBuf::new()))
+                .unwrap()
+                .plan
+        };
+
+        assert_eq!(plan(""), None);
+        assert_eq!(plan(r#","subscriptionType":"pro""#), Some(PlanTier::Pro));
+        assert_eq!(plan(r#","subscriptionType":"max""#), Some(PlanTier::Max5x));
+        assert_eq!(
+            plan(r#","subscriptionType":"max","rateLimitTier":"default_claude_max_20x""#),
+            Some(PlanTier::Max20x)
+        );
+    }
+
+    #[test]
+    fn refreshed_tokens_replace_only_the_oauth_fields() {
+        let content = r#"{"claudeAiOauth":{"accessToken":"old","refreshToken":"r1","expiresAt":1,"scopes":["user:inference"]},"other":true}"#;
+        let tokens = TokenResponse {
+            access_token: "new".to_string(),
+            refresh_token: Some("r2".to_string()),
+            expires_in: Some(3600),
+        };
+
+        let updated = apply_refreshed_tokens(content, &tokens, 1_000).unwrap();
+        let creds = parse_credentials(&updated, CredentialSource::Windows(PathBuf::new())).unwrap();
+
+        assert_eq!(creds.access_token, "new");
+        assert_eq!(creds.refresh_token.as_deref(), Some("r2"));
+        assert_eq!(creds.expires_at, Some(3_601_000));
+        assert!(updated.contains(r#""scopes":["user:inference"]"#));
+        assert!(updated.contains(r#""other":true"#));
+    }
+
+    /// `parse_rate_limit_headers` over `anthropic-ratelimit-unified-<name>`
+    /// headers given without their prefix.
+    fn unified_usage(headers: &[(&str, &str)]) -> UsageData {
+        let headers: HashMap<String, &str> = headers
+            .iter()
+            .map(|(name, value)| (format!("anthropic-ratelimit-unified-{name}"), *value))
+            .collect();
+        parse_rate_limit_headers(|name| headers.get(name).copied())
+    }
+
+    fn unix(secs: u64) -> Option<SystemTime> {
+        Some(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    #[test]
+    fn unified_utilization_and_resets_are_read() {
+        let usage = unified_usage(&[
+            ("5h-utilization", "0.25"),
+            ("5h-reset", "1700000000"),
+            ("7d-utilization", " 0.5 "),
+            ("7d-reset", "1700100000"),
+        ]);
+
+        assert_eq!(usage.session.percentage, 25.0);
+        assert_eq!(usage.session.resets_at, unix(1_700_000_000));
+        assert_eq!(usage.weekly.percentage, 50.0);
+        assert_eq!(usage.weekly.resets_at, unix(1_700_100_000));
+        assert!(usage.unified.is_none());
+        assert!(usage.api_limits.is_none());
+    }
+
+    #[test]
+    fn missing_unified_headers_leave_usage_empty() {
+        let usage = unified_usage(&[]);
+
+        assert_eq!(usage.session.percentage, 0.0);
+        assert_eq!(usage.weekly.percentage, 0.0);
+        assert!(usage.session.resets_at.is_none());
+        assert!(usage.weekly.resets_at.is_none());
+        assert!(usage.unified.is_none());
+    }
+
+    #[test]
+    fn rejected_status_uses_up_the_claimed_window() {
+        let usage = unified_usage(&[
+            ("status", "rejected"),
+            ("representative-claim", "five_hour"),
+        ]);
+        assert_eq!(usage.session.percentage, 100.0);
+        assert_eq!(usage.weekly.percentage, 0.0);
+
+        // Even when the utilization headers say a little less.
+        let usage = unified_usage(&[
+            ("status", "rejected"),
+            ("representative-claim", "seven_day_opus"),
+            ("5h-utilization", "0.4"),
+            ("7d-utilization", "0.98"),
+        ]);
+        assert_eq!(usage.session.percentage, 40.0);
+        assert_eq!(usage.weekly.percentage, 100.0);
+        let unified = usage.unified.expect("status and claim are kept");
+        assert!(unified.is_rejected());
+        assert_eq!(unified.binding_window(), Some(UsageWindow::Weekly));
+    }
+
+    #[test]
+    fn allowed_status_keeps_the_claim_without_changing_usage() {
+        let usage = unified_usage(&[
+            ("status", "allowed_warning"),
+            ("representative-claim", "five_hour"),
+            ("5h-utilization", "0.9"),
+        ]);
+
+        assert_eq!(usage.session.percentage, 90.0);
+        let unified = usage.unified.expect("status and claim are kept");
+        assert!(!unified.is_rejected());
+        assert_eq!(unified.status.as_deref(), Some("allowed_warning"));
+        assert_eq!(unified.binding_window(), Some(UsageWindow::Session));
+
+        let unknown = unified_usage(&[("representative-claim", "overage")]);
+        assert_eq!(unknown.unified.unwrap().binding_window(), None);
+    }
+
+    #[test]
+    fn overall_reset_stands_in_only_without_utilization() {
+        let usage = unified_usage(&[("reset", "1700000000")]);
+        assert_eq!(usage.session.resets_at, unix(1_700_000_000));
+
+        let usage = unified_usage(&[("reset", "1700000000"), ("7d-utilization", "0.1")]);
+        assert!(usage.session.resets_at.is_none());
+
+        let usage = unified_usage(&[("reset", "1700000000"), ("5h-reset", "1700000600")]);
+        assert_eq!(usage.session.resets_at, unix(1_700_000_600));
+    }
+
+    #[test]
+    fn negative_resets_are_ignored() {
+        let usage = unified_usage(&[
+            ("5h-reset", "-1"),
+            ("7d-reset", "-1700000000"),
+            ("reset", "-5"),
+        ]);
+
+        assert!(usage.session.resets_at.is_none());
+        assert!(usage.weekly.resets_at.is_none());
+    }
+
+    #[test]
+    fn malformed_unified_numbers_count_as_unknown() {
+        let usage = unified_usage(&[
+            ("5h-utilization", "abc"),
+            ("5h-reset", "soon"),
+            ("7d-utilization", "NaN"),
+            ("7d-reset", "1.7e9"),
+        ]);
+        assert_eq!(usage.session.percentage, 0.0);
+        assert_eq!(usage.weekly.percentage, 0.0);
+        assert!(usage.session.resets_at.is_none());
+        assert!(usage.weekly.resets_at.is_none());
+
+        for value in ["inf", "-0.5", ""] {
+            let usage = unified_usage(&[("5h-utilization", value)]);
+            assert_eq!(usage.session.percentage, 0.0, "{value:?}");
+        }
+        assert!(unified_usage(&[("status", "  ")]).unified.is_none());
+    }
+
+    #[test]
+    fn oauth_responses_have_no_api_key_limits() {
+        let headers: HashMap<&str, &str> = [("anthropic-ratelimit-unified-5h-utilization", "0.42")]
+            .into_iter()
+            .collect();
+
+        assert!(parse_api_rate_limits(|name| headers.get(name).copied()).is_none());
+    }
+
+    #[test]
+    fn retry_after_takes_delay_seconds() {
+        assert_eq!(parse_retry_after(" 120 "), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+        assert!(is_overloaded(429) && is_overloaded(529) && !is_overloaded(503));
+    }
 }
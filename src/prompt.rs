@@ -0,0 +1,337 @@
+//! A small modal window asking for one line of text, for settings that a
+//! menu of presets cannot cover. Enter accepts and Escape cancels, as in a
+//! dialog box.
+
+use std::sync::atomic::{AtomicIsize, Ordering};
+use std::sync::{Mutex, Once};
+
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::*;
+use windows::Win32::Graphics::Gdi::*;
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::HiDpi::GetDpiForWindow;
+use windows::Win32::UI::Input::KeyboardAndMouse::SetFocus;
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use crate::native_interop;
+
+const CLASS_NAME: &str = "ClaudeCodeUsageMonitorPrompt";
+
+const PADDING: i32 = 12;
+const CLIENT_WIDTH: i32 = 320;
+const LABEL_HEIGHT: i32 = 36;
+const EDIT_HEIGHT: i32 = 23;
+const BUTTON_WIDTH: i32 = 80;
+const BUTTON_HEIGHT: i32 = 26;
+const BUTTON_GAP: i32 = 8;
+
+const ID_EDIT: i32 = 100;
+/// Select text in an edit control; declared in the Controls bindings, which
+/// nothing else here needs.
+const EM_SETSEL: u32 = 0x00B1;
+
+/// The open prompt, so a second request brings it forward instead.
+static PROMPT: AtomicIsize = AtomicIsize::new(0);
+/// Set by the window procedure once the prompt closes: the text on OK, None
+/// on Cancel.
+static ANSWER: Mutex<Option<Option<String>>> = Mutex::new(None);
+
+static REGISTER_CLASS: Once = Once::new();
+
+/// What to ask.
+pub struct Prompt<'a> {
+    pub title: &'a str,
+    pub label: &'a str,
+    pub initial: &'a str,
+    pub ok: &'a str,
+    pub cancel: &'a str,
+}
+
+fn register_class() {
+    REGISTER_CLASS.call_once(|| unsafe {
+        let hinstance = GetModuleHandleW(PCWSTR::null()).unwrap_or_default();
+        let class_name = native_interop::wide_str(CLASS_NAME);
+        let wc = WNDCLASSEXW {
+            cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+            lpfnWndProc: Some(prompt_proc),
+            hInstance: HINSTANCE(hinstance.0),
+            lpszClassName: PCWSTR::from_raw(class_name.as_ptr()),
+            hCursor: LoadCursorW(None, IDC_ARROW).unwrap_or_default(),
+            hbrBackground: HBRUSH((COLOR_BTNFACE.0 + 1) as isize as *mut _),
+            ..Default::default()
+        };
+        RegisterClassExW(&wc);
+    });
+}
+
+fn scale(px: i32, dpi: u32) -> i32 {
+    (px as f64 * dpi as f64 / 96.0).round() as i32
+}
+
+fn set_answer(answer: Option<String>) {
+    *ANSWER.lock().unwrap_or_else(|e| e.into_inner()) = Some(answer);
+}
+
+fn take_answer() -> Option<Option<String>> {
+    ANSWER.lock().unwrap_or_else(|e| e.into_inner()).take()
+}
+
+/// Ask `prompt` and wait for the answer, running the message loop meanwhile.
+/// None when cancelled, or when another prompt is already open.
+pub fn ask(prompt: &Prompt) -> Option<String> {
+    let open = HWND(PROMPT.load(Ordering::Relaxed) as *mut _);
+    if !open.is_invalid() {
+        unsafe {
+            let _ = SetForegroundWindow(open);
+        }
+        return None;
+    }
+    register_class();
+    take_answer();
+
+    let hwnd = create(prompt)?;
+    PROMPT.store(hwnd.0 as isize, Ordering::Relaxed);
+    let font = unsafe { create_font(GetDpiForWindow(hwnd).max(96)) };
+    let answer = unsafe {
+        let mut child = GetWindow(hwnd, GW_CHILD).unwrap_or_default();
+        while !child.is_invalid() {
+            SendMessageW(child, WM_SETFONT, WPARAM(font.0 as usize), LPARAM(1));
+            child = GetWindow(child, GW_HWNDNEXT).unwrap_or_default();
+        }
+        let _ = ShowWindow(hwnd, SW_SHOW);
+        let _ = SetForegroundWindow(hwnd);
+        if let Ok(edit) = GetDlgItem(hwnd, ID_EDIT) {
+            let _ = SetFocus(edit);
+            SendMessageW(edit, EM_SETSEL, WPARAM(0), LPARAM(-1));
+        }
+
+        let mut msg = MSG::default();
+        loop {
+            if let Some(answer) = take_answer() {
+                break answer;
+            }
+            let result = GetMessageW(&mut msg, HWND::default(), 0, 0);
+            if result.0 <= 0 {
+                // Leave WM_QUIT for the main loop.
+                if result.0 == 0 {
+                    PostQuitMessage(msg.wParam.0 as i32);
+                }
+                let _ = DestroyWindow(hwnd);
+                break None;
+            }
+            if !IsDialogMessageW(hwnd, &msg).as_bool() {
+                let _ = TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+        }
+    };
+    PROMPT.store(0, Ordering::Relaxed);
+    unsafe {
+        let _ = DeleteObject(font);
+    }
+    answer
+}
+
+/// The prompt window and its controls, centred on the monitor under the
+/// cursor but not yet shown.
+fn create(prompt: &Prompt) -> Option<HWND> {
+    unsafe {
+        let hinstance = GetModuleHandleW(PCWSTR::null()).unwrap_or_default();
+        let class_name = native_interop::wide_str(CLASS_NAME);
+        let title = native_interop::wide_str(prompt.title);
+        let style = WS_POPUP | WS_CAPTION | WS_SYSMENU;
+        let ex_style = WS_EX_DLGMODALFRAME | WS_EX_TOPMOST | WS_EX_CONTROLPARENT;
+
+        let mut cursor = POINT::default();
+        let _ = GetCursorPos(&mut cursor);
+        let monitor = MonitorFromPoint(cursor, MONITOR_DEFAULTTONEAREST);
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        let _ = GetMonitorInfoW(monitor, &mut info);
+        let work = info.rcWork;
+
+        let hwnd = CreateWindowExW(
+            ex_style,
+            PCWSTR::from_raw(class_name.as_ptr()),
+            PCWSTR::from_raw(title.as_ptr()),
+            style,
+            work.left,
+            work.top,
+            0,
+            0,
+            HWND::default(),
+            HMENU::default(),
+            hinstance,
+            None,
+        )
+        .ok()?;
+
+        // Lay out at the DPI of the monitor the window landed on.
+        let dpi = GetDpiForWindow(hwnd).max(96);
+        let s = |px: i32| scale(px, dpi);
+        let client_height =
+            s(PADDING) * 3 + s(LABEL_HEIGHT) + s(EDIT_HEIGHT) + s(BUTTON_HEIGHT) + s(4);
+        let mut frame = RECT {
+            left: 0,
+            top: 0,
+            right: s(CLIENT_WIDTH),
+            bottom: client_height,
+        };
+        let _ = AdjustWindowRectEx(&mut frame, style, false, ex_style);
+        let width = frame.right - frame.left;
+        let height = frame.bottom - frame.top;
+        let _ = SetWindowPos(
+            hwnd,
+            HWND_TOPMOST,
+            work.left + (work.right - work.left - width) / 2,
+            work.top + (work.bottom - work.top - height) / 2,
+            width,
+            height,
+            SWP_NOACTIVATE,
+        );
+
+        let child = |class: &str, text: &str, style: WINDOW_STYLE, id: i32, rect: RECT| {
+            let ex_style = if class == "EDIT" {
+                WS_EX_CLIENTEDGE
+            } else {
+                WINDOW_EX_STYLE(0)
+            };
+            let class = native_interop::wide_str(class);
+            let text = native_interop::wide_str(text);
+            let _ = CreateWindowExW(
+                ex_style,
+                PCWSTR::from_raw(class.as_ptr()),
+                PCWSTR::from_raw(text.as_ptr()),
+                WS_CHILD | WS_VISIBLE | style,
+                rect.left,
+                rect.top,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
+                hwnd,
+                HMENU(id as isize as *mut _),
+                hinstance,
+                None,
+            );
+        };
+
+        let inner_width = s(CLIENT_WIDTH) - s(PADDING) * 2;
+        let label_top = s(PADDING);
+        child(
+            "STATIC",
+            prompt.label,
+            WINDOW_STYLE(0),
+            -1,
+            RECT {
+                left: s(PADDING),
+                top: label_top,
+                right: s(PADDING) + inner_width,
+                bottom: label_top + s(LABEL_HEIGHT),
+            },
+        );
+        let edit_top = label_top + s(LABEL_HEIGHT) + s(4);
+        child(
+            "EDIT",
+            prompt.initial,
+            WS_TABSTOP | WINDOW_STYLE(ES_AUTOHSCROLL as u32),
+            ID_EDIT,
+            RECT {
+                left: s(PADDING),
+                top: edit_top,
+                right: s(PADDING) + inner_width,
+                bottom: edit_top + s(EDIT_HEIGHT),
+            },
+        );
+        let button_top = edit_top + s(EDIT_HEIGHT) + s(PADDING);
+        let cancel_left = s(CLIENT_WIDTH) - s(PADDING) - s(BUTTON_WIDTH);
+        let ok_left = cancel_left - s(BUTTON_GAP) - s(BUTTON_WIDTH);
+        for (text, id, left, default) in [
+            (prompt.ok, IDOK.0, ok_left, true),
+            (prompt.cancel, IDCANCEL.0, cancel_left, false),
+        ] {
+            let style = if default {
+                BS_DEFPUSHBUTTON
+            } else {
+                BS_PUSHBUTTON
+            };
+            child(
+                "BUTTON",
+                text,
+                WS_TABSTOP | WINDOW_STYLE(style as u32),
+                id,
+                RECT {
+                    left,
+                    top: button_top,
+                    right: left + s(BUTTON_WIDTH),
+                    bottom: button_top + s(BUTTON_HEIGHT),
+                },
+            );
+        }
+        Some(hwnd)
+    }
+}
+
+fn create_font(dpi: u32) -> HFONT {
+    let font_name = native_interop::wide_str("Segoe UI");
+    unsafe {
+        CreateFontW(
+            scale(-12, dpi),
+            0,
+            0,
+            0,
+            FW_NORMAL.0 as i32,
+            0,
+            0,
+            0,
+            DEFAULT_CHARSET.0 as u32,
+            OUT_TT_PRECIS.0 as u32,
+            CLIP_DEFAULT_PRECIS.0 as u32,
+            CLEARTYPE_QUALITY.0 as u32,
+            (DEFAULT_PITCH.0 | FF_DONTCARE.0) as u32,
+            PCWSTR::from_raw(font_name.as_ptr()),
+        )
+    }
+}
+
+/// The text in the prompt's edit box.
+fn edit_text(hwnd: HWND) -> String {
+    unsafe {
+        let Ok(edit) = GetDlgItem(hwnd, ID_EDIT) else {
+            return String::new();
+        };
+        let mut buffer = vec![0u16; GetWindowTextLengthW(edit).max(0) as usize + 1];
+        let len = GetWindowTextW(edit, &mut buffer).max(0) as usize;
+        String::from_utf16_lossy(&buffer[..len])
+    }
+}
+
+unsafe extern "system" fn prompt_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_COMMAND => {
+            match (wparam.0 & 0xFFFF) as i32 {
+                id if id == IDOK.0 => {
+                    set_answer(Some(edit_text(hwnd)));
+                    let _ = DestroyWindow(hwnd);
+                }
+                id if id == IDCANCEL.0 => {
+                    set_answer(None);
+                    let _ = DestroyWindow(hwnd);
+                }
+                _ => {}
+            }
+            LRESULT(0)
+        }
+        WM_CLOSE => {
+            set_answer(None);
+            let _ = DestroyWindow(hwnd);
+            LRESULT(0)
+        }
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
@@ -0,0 +1,273 @@
+//! The usage bar in front of each model's text. The widget lays out the area
+//! a bar gets; this draws it in the configured style.
+
+use serde::{Deserialize, Serialize};
+use windows::Win32::Foundation::{COLORREF, RECT};
+use windows::Win32::Graphics::Gdi::*;
+
+use super::Brushes;
+use crate::native_interop::Color;
+use crate::palette::WARNING_PERCENT;
+
+/// How usage bars are drawn, from `"bar_style"` in settings.json.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BarStyle {
+    /// Rounded segments, ten for a single model.
+    #[default]
+    Segmented,
+    /// One rounded bar the width of the segments.
+    Continuous,
+    /// A thin line along the bottom of the row.
+    Underline,
+    /// A pie filling clockwise from the top, as wide as the row is tall.
+    Dial,
+}
+
+impl BarStyle {
+    /// Whether the bar is as wide as the row is tall, whatever the segment
+    /// count.
+    pub fn is_square(self) -> bool {
+        self == BarStyle::Dial
+    }
+}
+
+/// One bar's value and colours.
+pub struct Bar<'a> {
+    pub percent: f64,
+    pub accent: &'a Color,
+    pub track: &'a Color,
+    /// Hatch the filled part from `WARNING_PERCENT` up, for palettes that do
+    /// not rely on colour alone.
+    pub hatch_warnings: bool,
+    /// Fill from the right end. Dials always fill clockwise.
+    pub right_to_left: bool,
+    pub brushes: &'a Brushes,
+}
+
+/// Draw `bar` in `area`. `segments`, `gap` and `radius` give the segmented
+/// layout, whose segments exactly fill `area`; the other styles keep only
+/// the corner radius.
+pub fn draw(
+    hdc: HDC,
+    style: BarStyle,
+    area: &RECT,
+    segments: i32,
+    gap: i32,
+    radius: i32,
+    bar: &Bar,
+) {
+    if segments <= 0 || area.right <= area.left {
+        return;
+    }
+    match style {
+        BarStyle::Segmented => draw_segmented(hdc, area, segments, gap, radius, bar),
+        BarStyle::Continuous => draw_continuous(hdc, area, radius, bar),
+        BarStyle::Underline => {
+            // 2px at 100% scaling, thicker on high-DPI rows.
+            let thickness = ((area.bottom - area.top) / 6).max(2);
+            let line = RECT {
+                top: area.bottom - thickness,
+                ..*area
+            };
+            draw_continuous(hdc, &line, thickness / 2, bar);
+        }
+        BarStyle::Dial => draw_dial(hdc, area, bar),
+    }
+}
+
+fn draw_segmented(hdc: HDC, area: &RECT, segments: i32, gap: i32, radius: i32, bar: &Bar) {
+    let seg_w = (area.right - area.left + gap) / segments - gap;
+    let percent_clamped = bar.percent.clamp(0.0, 100.0);
+    let segment_percent = 100.0 / segments as f64;
+
+    for i in 0..segments {
+        let slot = if bar.right_to_left {
+            segments - 1 - i
+        } else {
+            i
+        };
+        let seg_x = area.left + slot * (seg_w + gap);
+        let seg_start = (i as f64) * segment_percent;
+        let seg_end = seg_start + segment_percent;
+
+        let seg_rect = RECT {
+            left: seg_x,
+            top: area.top,
+            right: seg_x + seg_w,
+            bottom: area.bottom,
+        };
+
+        let filled = if percent_clamped >= seg_end {
+            draw_rounded_rect(hdc, &seg_rect, bar.brushes.solid(bar.accent), radius);
+            Some(seg_rect)
+        } else if percent_clamped <= seg_start {
+            draw_rounded_rect(hdc, &seg_rect, bar.brushes.solid(bar.track), radius);
+            None
+        } else {
+            draw_rounded_rect(hdc, &seg_rect, bar.brushes.solid(bar.track), radius);
+            let fraction = (percent_clamped - seg_start) / segment_percent;
+            fill_part(hdc, &seg_rect, fraction, radius, bar)
+        };
+
+        if let Some(filled) = filled.filter(|_| bar.hatch_warnings && seg_end > WARNING_PERCENT) {
+            draw_warning_hatch(hdc, &seg_rect, &filled, bar.accent, radius);
+        }
+    }
+}
+
+fn draw_continuous(hdc: HDC, area: &RECT, radius: i32, bar: &Bar) {
+    let percent_clamped = bar.percent.clamp(0.0, 100.0);
+    draw_rounded_rect(hdc, area, bar.brushes.solid(bar.track), radius);
+    let Some(filled) = fill_part(hdc, area, percent_clamped / 100.0, radius, bar) else {
+        return;
+    };
+    if bar.hatch_warnings && percent_clamped > WARNING_PERCENT {
+        // Only the stretch past the warning level is hatched.
+        let warning_x =
+            area.left + ((area.right - area.left) as f64 * WARNING_PERCENT / 100.0).round() as i32;
+        let hatched = if bar.right_to_left {
+            RECT {
+                right: area.right - (warning_x - area.left),
+                ..filled
+            }
+        } else {
+            RECT {
+                left: warning_x,
+                ..filled
+            }
+        };
+        draw_warning_hatch(hdc, area, &hatched, bar.accent, radius);
+    }
+}
+
+/// Fill `fraction` of `rect` from its start, clipped to its rounded corners.
+/// Returns the filled part, if any of it was wide enough to draw.
+fn fill_part(hdc: HDC, rect: &RECT, fraction: f64, radius: i32, bar: &Bar) -> Option<RECT> {
+    let fill_width = ((rect.right - rect.left) as f64 * fraction) as i32;
+    if fill_width <= 0 {
+        return None;
+    }
+    let fill_rect = if bar.right_to_left {
+        RECT {
+            left: rect.right - fill_width,
+            ..*rect
+        }
+    } else {
+        RECT {
+            right: rect.left + fill_width,
+            ..*rect
+        }
+    };
+    unsafe {
+        let rgn = rounded_region(rect, radius);
+        let _ = SelectClipRgn(hdc, rgn);
+        FillRect(hdc, &fill_rect, bar.brushes.solid(bar.accent));
+        let _ = SelectClipRgn(hdc, HRGN::default());
+        let _ = DeleteObject(rgn);
+    }
+    Some(fill_rect)
+}
+
+fn draw_dial(hdc: HDC, area: &RECT, bar: &Bar) {
+    let size = (area.bottom - area.top).min(area.right - area.left);
+    let dial = RECT {
+        left: area.left,
+        top: area.top,
+        right: area.left + size,
+        bottom: area.top + size,
+    };
+    let percent_clamped = bar.percent.clamp(0.0, 100.0);
+    // A slice past the warning level would be too small to see, so the
+    // whole filled part is hatched.
+    let warning = bar.hatch_warnings && percent_clamped >= WARNING_PERCENT;
+
+    unsafe {
+        let old_pen = SelectObject(hdc, GetStockObject(NULL_PEN));
+        fill_ellipse(hdc, &dial, bar.brushes.solid(bar.track));
+        let hatch = warning.then(|| {
+            let stripe = bar.accent.blend(Color::from_hex("#000000"), 0.45);
+            CreateHatchBrush(HS_BDIAGONAL, COLORREF(stripe.to_colorref()))
+        });
+        let fill = hatch.unwrap_or_else(|| bar.brushes.solid(bar.accent));
+        // The gaps between hatch lines take the background colour.
+        let old_bk = SetBkColor(hdc, COLORREF(bar.accent.to_colorref()));
+        if percent_clamped >= 100.0 {
+            fill_ellipse(hdc, &dial, fill);
+        } else if percent_clamped > 0.0 {
+            // GDI draws pies counter-clockwise, so go from the end of the
+            // usage back to the top.
+            let angle = percent_clamped / 100.0 * std::f64::consts::TAU;
+            let cx = (dial.left + dial.right) as f64 / 2.0;
+            let cy = (dial.top + dial.bottom) as f64 / 2.0;
+            let reach = size as f64;
+            let end_x = (cx + reach * angle.sin()).round() as i32;
+            let end_y = (cy - reach * angle.cos()).round() as i32;
+            let old_brush = SelectObject(hdc, fill);
+            let _ = Pie(
+                hdc,
+                dial.left,
+                dial.top,
+                dial.right + 1,
+                dial.bottom + 1,
+                end_x,
+                end_y,
+                cx.round() as i32,
+                dial.top,
+            );
+            SelectObject(hdc, old_brush);
+        }
+        SetBkColor(hdc, old_bk);
+        if let Some(hatch) = hatch {
+            let _ = DeleteObject(hatch);
+        }
+        SelectObject(hdc, old_pen);
+    }
+}
+
+fn fill_ellipse(hdc: HDC, rect: &RECT, brush: HBRUSH) {
+    unsafe {
+        let old_brush = SelectObject(hdc, brush);
+        let _ = Ellipse(hdc, rect.left, rect.top, rect.right + 1, rect.bottom + 1);
+        SelectObject(hdc, old_brush);
+    }
+}
+
+fn rounded_region(rect: &RECT, radius: i32) -> HRGN {
+    unsafe {
+        CreateRoundRectRgn(
+            rect.left,
+            rect.top,
+            rect.right + 1,
+            rect.bottom + 1,
+            radius * 2,
+            radius * 2,
+        )
+    }
+}
+
+/// Diagonal stripes over the filled part of a warning segment, so the level
+/// does not depend on telling colours apart.
+fn draw_warning_hatch(hdc: HDC, seg_rect: &RECT, filled: &RECT, accent: &Color, radius: i32) {
+    let stripe = accent.blend(Color::from_hex("#000000"), 0.45);
+    unsafe {
+        let rgn = rounded_region(seg_rect, radius);
+        let _ = SelectClipRgn(hdc, rgn);
+        let brush = CreateHatchBrush(HS_BDIAGONAL, COLORREF(stripe.to_colorref()));
+        // The gaps between hatch lines take the background colour.
+        let old_bk = SetBkColor(hdc, COLORREF(accent.to_colorref()));
+        FillRect(hdc, filled, brush);
+        SetBkColor(hdc, old_bk);
+        let _ = DeleteObject(brush);
+        let _ = SelectClipRgn(hdc, HRGN::default());
+        let _ = DeleteObject(rgn);
+    }
+}
+
+fn draw_rounded_rect(hdc: HDC, rect: &RECT, brush: HBRUSH, radius: i32) {
+    unsafe {
+        let rgn = rounded_region(rect, radius);
+        let _ = FillRgn(hdc, rgn, brush);
+        let _ = DeleteObject(rgn);
+    }
+}
@@ -0,0 +1,54 @@
+//! Drawing for the parts of the taskbar widget that come in more than one
+//! style.
+
+pub mod bars;
+
+use std::cell::RefCell;
+
+use windows::Win32::Foundation::COLORREF;
+use windows::Win32::Graphics::Gdi::{CreateSolidBrush, DeleteObject, HBRUSH};
+
+use crate::native_interop::Color;
+
+/// Beyond this many colours the cache starts over. Bar colours come from
+/// the palette's gradient, so a long session could otherwise collect one
+/// brush per percentage.
+const MAX_BRUSHES: usize = 64;
+
+/// Solid brushes by colour, kept between redraws instead of being created
+/// for every segment.
+#[derive(Default)]
+pub struct Brushes {
+    solid: RefCell<Vec<(u32, HBRUSH)>>,
+}
+
+impl Brushes {
+    /// A solid brush of `color`, owned by the cache.
+    pub fn solid(&self, color: &Color) -> HBRUSH {
+        let colorref = color.to_colorref();
+        let mut solid = self.solid.borrow_mut();
+        if let Some((_, brush)) = solid.iter().find(|(key, _)| *key == colorref) {
+            return *brush;
+        }
+        if solid.len() >= MAX_BRUSHES {
+            for (_, brush) in solid.drain(..) {
+                unsafe {
+                    let _ = DeleteObject(brush);
+                }
+            }
+        }
+        let brush = unsafe { CreateSolidBrush(COLORREF(colorref)) };
+        solid.push((colorref, brush));
+        brush
+    }
+}
+
+impl Drop for Brushes {
+    fn drop(&mut self) {
+        for (_, brush) in self.solid.get_mut().drain(..) {
+            unsafe {
+                let _ = DeleteObject(brush);
+            }
+        }
+    }
+}
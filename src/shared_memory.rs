@@ -0,0 +1,203 @@
+//! The latest poll in a named shared-memory section, for native widgets such
+//! as Rainmeter plugins that want the numbers without a socket or a file. The
+//! section holds one `SharedUsage`; readers check `version` and `size` first.
+//!
+//! Writes are guarded by `sequence`, which is odd while a write is under way:
+//! read it, copy the struct, and read it again, retrying if it was odd or has
+//! changed.
+
+use std::mem::size_of;
+use std::ptr::addr_of_mut;
+use std::sync::atomic::{fence, AtomicBool, AtomicU32, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::INVALID_HANDLE_VALUE;
+use windows::Win32::System::Memory::{
+    CreateFileMappingW, MapViewOfFile, FILE_MAP_WRITE, PAGE_READWRITE,
+};
+
+use crate::diagnose;
+use crate::models::{AppUsageData, UsageData};
+use crate::native_interop;
+
+/// Per session, so each signed-in user sees their own usage.
+pub const SECTION_NAME: &str = "Local\\ClaudeCodeUsageMonitor.Usage";
+
+/// Bumped whenever the layout changes in a way old readers would misread.
+/// Fields added at the end only grow `size`.
+const LAYOUT_VERSION: u32 = 1;
+
+/// The section's contents. All times are unix seconds, 0 when unknown.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct SharedUsage {
+    version: u32,
+    /// Size of this struct in bytes.
+    size: u32,
+    sequence: u32,
+    reserved: u32,
+    updated_at: u64,
+    /// Claude Code, Codex, Antigravity and the secondary Claude account, in
+    /// that order.
+    providers: [SharedProvider; 4],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct SharedProvider {
+    /// 1 when the last poll included this provider.
+    present: u32,
+    reserved: u32,
+    session_percent: f64,
+    session_resets_at: u64,
+    weekly_percent: f64,
+    weekly_resets_at: u64,
+}
+
+/// The mapped view, kept for the life of the process. Windows removes the
+/// section once the last handle to it is closed.
+struct View(*mut SharedUsage);
+
+// Only ever touched under `VIEW`'s lock.
+unsafe impl Send for View {}
+
+static VIEW: Mutex<Option<Result<View, ()>>> = Mutex::new(None);
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+fn lock_view() -> MutexGuard<'static, Option<Result<View, ()>>> {
+    VIEW.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn provider(usage: &Option<UsageData>) -> SharedProvider {
+    let Some(usage) = usage else {
+        return SharedProvider::default();
+    };
+    SharedProvider {
+        present: 1,
+        reserved: 0,
+        session_percent: usage.session.percentage,
+        session_resets_at: usage.session.resets_at.map_or(0, unix_secs),
+        weekly_percent: usage.weekly.percentage,
+        weekly_resets_at: usage.weekly.resets_at.map_or(0, unix_secs),
+    }
+}
+
+fn snapshot(data: &AppUsageData, now: SystemTime) -> SharedUsage {
+    SharedUsage {
+        version: LAYOUT_VERSION,
+        size: size_of::<SharedUsage>() as u32,
+        sequence: 0,
+        reserved: 0,
+        updated_at: unix_secs(now),
+        providers: [
+            provider(&data.claude_code),
+            provider(&data.codex),
+            provider(&data.antigravity),
+            provider(&data.secondary),
+        ],
+    }
+}
+
+fn create_view() -> Result<View, String> {
+    let name = native_interop::wide_str(SECTION_NAME);
+    unsafe {
+        let mapping = CreateFileMappingW(
+            INVALID_HANDLE_VALUE,
+            None,
+            PAGE_READWRITE,
+            0,
+            size_of::<SharedUsage>() as u32,
+            PCWSTR::from_raw(name.as_ptr()),
+        )
+        .map_err(|error| error.to_string())?;
+        let view = MapViewOfFile(mapping, FILE_MAP_WRITE, 0, 0, size_of::<SharedUsage>());
+        if view.Value.is_null() {
+            return Err(windows::core::Error::from_win32().to_string());
+        }
+        // The mapping handle stays open so the section outlives this call.
+        Ok(View(view.Value as *mut SharedUsage))
+    }
+}
+
+/// Publish each successful poll from now on. The section is only created
+/// for users who asked for it.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Write `data` to the shared section, creating it on first use.
+pub fn publish(data: &AppUsageData, now: SystemTime) {
+    let mut view = lock_view();
+    let view = view.get_or_insert_with(|| {
+        create_view().map_err(|error| {
+            diagnose::log(format!("shared memory unavailable: {error}"));
+        })
+    });
+    let Ok(View(ptr)) = view else {
+        return;
+    };
+    let ptr = *ptr;
+    let usage = snapshot(data, now);
+    unsafe {
+        let sequence = &*(addr_of_mut!((*ptr).sequence) as *const AtomicU32);
+        let start = sequence.load(Ordering::Relaxed) | 1;
+        sequence.store(start, Ordering::Relaxed);
+        fence(Ordering::Release);
+        addr_of_mut!((*ptr).version).write_volatile(usage.version);
+        addr_of_mut!((*ptr).size).write_volatile(usage.size);
+        addr_of_mut!((*ptr).reserved).write_volatile(usage.reserved);
+        addr_of_mut!((*ptr).updated_at).write_volatile(usage.updated_at);
+        addr_of_mut!((*ptr).providers).write_volatile(usage.providers);
+        sequence.store(start.wrapping_add(1), Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::UsageSection;
+    use std::time::Duration;
+
+    #[test]
+    fn layout_is_stable() {
+        // Readers in other languages hard-code these offsets.
+        assert_eq!(size_of::<SharedProvider>(), 40);
+        assert_eq!(size_of::<SharedUsage>(), 184);
+        assert_eq!(std::mem::offset_of!(SharedUsage, updated_at), 16);
+        assert_eq!(std::mem::offset_of!(SharedUsage, providers), 24);
+    }
+
+    #[test]
+    fn snapshot_marks_polled_providers() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let data = AppUsageData {
+            codex: Some(UsageData {
+                session: UsageSection {
+                    percentage: 42.0,
+                    resets_at: Some(now + Duration::from_secs(600)),
+                },
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let usage = snapshot(&data, now);
+        assert_eq!(usage.updated_at, 1_700_000_000);
+        assert_eq!(usage.providers[0], SharedProvider::default());
+        assert_eq!(usage.providers[1].present, 1);
+        assert_eq!(usage.providers[1].session_percent, 42.0);
+        assert_eq!(usage.providers[1].session_resets_at, 1_700_000_600);
+        assert_eq!(usage.providers[1].weekly_resets_at, 0);
+    }
+}
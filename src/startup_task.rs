@@ -0,0 +1,169 @@
+//! "Start with Windows (Task Scheduler)": a per-user logon task, for machines
+//! where policy blocks the Run key. Unlike the Run key, the task can wait
+//! before starting and restart the app if it exits with an error.
+
+use std::sync::{Mutex, MutexGuard};
+
+use windows::core::{Interface, BSTR, VARIANT};
+use windows::Win32::Foundation::VARIANT_FALSE;
+use windows::Win32::System::Com::{
+    CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_INPROC_SERVER,
+    COINIT_APARTMENTTHREADED,
+};
+use windows::Win32::System::TaskScheduler::{
+    IExecAction, ILogonTrigger, ITaskFolder, ITaskService, TaskScheduler, TASK_ACTION_EXEC,
+    TASK_CREATE_OR_UPDATE, TASK_INSTANCES_IGNORE_NEW, TASK_LOGON_INTERACTIVE_TOKEN,
+    TASK_RUNLEVEL_LUA, TASK_TRIGGER_LOGON,
+};
+
+use crate::paths;
+
+const TASK_NAME_PREFIX: &str = "ClaudeCodeUsageMonitor";
+/// Wait this long before restarting after a failed run...
+const RESTART_INTERVAL: &str = "PT1M";
+/// ...at most this many times.
+const RESTART_COUNT: i32 = 3;
+
+/// Whether the task is registered, as last queried or changed here. Asking
+/// Task Scheduler takes a COM round trip, too slow for every menu opening.
+static REGISTERED: Mutex<Option<bool>> = Mutex::new(None);
+
+fn lock_registered() -> MutexGuard<'static, Option<bool>> {
+    REGISTERED.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Run `f` against the root task folder of the signed-in user's scheduler,
+/// with the task name for this user.
+fn with_root_folder<T>(
+    f: impl FnOnce(&ITaskService, &ITaskFolder, &BSTR) -> windows::core::Result<T>,
+) -> Result<T, String> {
+    unsafe {
+        let com_initialized = CoInitializeEx(None, COINIT_APARTMENTTHREADED).is_ok();
+        let result = (|| {
+            let service: ITaskService =
+                CoCreateInstance(&TaskScheduler, None, CLSCTX_INPROC_SERVER)?;
+            let none = VARIANT::default();
+            service.Connect(&none, &none, &none, &none)?;
+            let folder = service.GetFolder(&BSTR::from("\\"))?;
+            // The root folder is shared, so each user gets a task of their own.
+            let name = BSTR::from(format!("{TASK_NAME_PREFIX} {}", service.ConnectedUser()?));
+            f(&service, &folder, &name)
+        })();
+        if com_initialized {
+            CoUninitialize();
+        }
+        result.map_err(|error| error.to_string())
+    }
+}
+
+fn current_exe() -> Result<String, String> {
+    std::env::current_exe()
+        .map(|path| path.display().to_string())
+        .map_err(|error| error.to_string())
+}
+
+/// True when this user's logon task exists and starts this executable. Only
+/// the first call asks Task Scheduler; later ones reuse that answer, kept
+/// up to date by `register` and `unregister`.
+pub fn is_registered() -> bool {
+    *lock_registered().get_or_insert_with(query_registered)
+}
+
+fn query_registered() -> bool {
+    let Ok(exe) = current_exe() else {
+        return false;
+    };
+    with_root_folder(|_, folder, name| unsafe {
+        let action = folder
+            .GetTask(name)?
+            .Definition()?
+            .Actions()?
+            .get_Item(1)?
+            .cast::<IExecAction>()?;
+        let mut path = BSTR::new();
+        action.Path(&mut path)?;
+        Ok(path.to_string().eq_ignore_ascii_case(&exe))
+    })
+    .unwrap_or(false)
+}
+
+/// Create or replace this user's logon task, starting `delay_secs` after
+/// sign-in.
+pub fn register(delay_secs: u32) -> Result<(), String> {
+    let exe = current_exe()?;
+    let result = with_root_folder(|service, folder, name| unsafe {
+        let user = BSTR::from(format!(
+            "{}\\{}",
+            service.ConnectedDomain()?,
+            service.ConnectedUser()?
+        ));
+        let task = service.NewTask(0)?;
+
+        task.RegistrationInfo()?
+            .SetDescription(&BSTR::from("Starts Claude Code Usage Monitor at sign-in."))?;
+
+        let principal = task.Principal()?;
+        principal.SetUserId(&user)?;
+        principal.SetLogonType(TASK_LOGON_INTERACTIVE_TOKEN)?;
+        principal.SetRunLevel(TASK_RUNLEVEL_LUA)?;
+
+        let trigger = task
+            .Triggers()?
+            .Create(TASK_TRIGGER_LOGON)?
+            .cast::<ILogonTrigger>()?;
+        trigger.SetUserId(&user)?;
+        if delay_secs > 0 {
+            trigger.SetDelay(&BSTR::from(format!("PT{delay_secs}S")))?;
+        }
+
+        let action = task
+            .Actions()?
+            .Create(TASK_ACTION_EXEC)?
+            .cast::<IExecAction>()?;
+        action.SetPath(&BSTR::from(exe.as_str()))?;
+        if paths::is_portable() {
+            action.SetArguments(&BSTR::from("--portable"))?;
+        }
+
+        let settings = task.Settings()?;
+        settings.SetRestartInterval(&BSTR::from(RESTART_INTERVAL))?;
+        settings.SetRestartCount(RESTART_COUNT)?;
+        // The app runs until sign-out; the default would stop it after 3 days.
+        settings.SetExecutionTimeLimit(&BSTR::from("PT0S"))?;
+        settings.SetDisallowStartIfOnBatteries(VARIANT_FALSE)?;
+        settings.SetStopIfGoingOnBatteries(VARIANT_FALSE)?;
+        settings.SetMultipleInstances(TASK_INSTANCES_IGNORE_NEW)?;
+
+        let none = VARIANT::default();
+        folder.RegisterTaskDefinition(
+            name,
+            &task,
+            TASK_CREATE_OR_UPDATE.0,
+            &VARIANT::from(user),
+            &none,
+            TASK_LOGON_INTERACTIVE_TOKEN,
+            &none,
+        )?;
+        Ok(())
+    });
+    remember(&result, true);
+    result
+}
+
+/// Remove this user's logon task. Succeeds when there is none.
+pub fn unregister() -> Result<(), String> {
+    let result = with_root_folder(|_, folder, name| unsafe {
+        if folder.GetTask(name).is_err() {
+            return Ok(());
+        }
+        folder.DeleteTask(name, 0)
+    });
+    remember(&result, false);
+    result
+}
+
+/// Cache `registered` after a change succeeded; after a failure the state is
+/// unknown, so the next `is_registered` asks again.
+fn remember(result: &Result<(), String>, registered: bool) {
+    *lock_registered() = result.is_ok().then_some(registered);
+}
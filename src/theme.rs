@@ -1,10 +1,17 @@
 use windows::core::PCWSTR;
 use windows::Win32::System::Registry::*;
 
-use crate::native_interop::wide_str;
+use crate::native_interop::{wide_str, Color};
 
 const REGISTRY_PATH: &str = r"Software\Microsoft\Windows\CurrentVersion\Themes\Personalize";
 const REGISTRY_KEY: &str = "SystemUsesLightTheme";
+/// 1 when "Show accent colour on Start and taskbar" is on.
+const COLOR_PREVALENCE_KEY: &str = "ColorPrevalence";
+const ACCENT_PATH: &str = r"Software\Microsoft\Windows\CurrentVersion\Explorer\Accent";
+/// The shade of the accent colour Start and the taskbar are drawn with.
+const START_COLOR_KEY: &str = "StartColorMenu";
+const DWM_PATH: &str = r"Software\Microsoft\Windows\DWM";
+const DWM_ACCENT_KEY: &str = "AccentColor";
 
 /// Check if the system is in dark mode by reading the registry
 pub fn is_dark_mode() -> bool {
@@ -12,9 +19,29 @@ pub fn is_dark_mode() -> bool {
 }
 
 fn is_light_theme() -> bool {
+    // Default to dark mode
+    read_dword(REGISTRY_PATH, REGISTRY_KEY) == Some(1)
+}
+
+/// The taskbar colour when Windows shows the accent colour on the taskbar,
+/// or None when the taskbar uses the plain light or dark theme colour.
+pub fn taskbar_accent_color() -> Option<Color> {
+    if read_dword(REGISTRY_PATH, COLOR_PREVALENCE_KEY) != Some(1) {
+        return None;
+    }
+    read_dword(ACCENT_PATH, START_COLOR_KEY)
+        .or_else(|| read_dword(DWM_PATH, DWM_ACCENT_KEY))
+        .map(|abgr| Color {
+            r: (abgr & 0xFF) as u8,
+            g: ((abgr >> 8) & 0xFF) as u8,
+            b: ((abgr >> 16) & 0xFF) as u8,
+        })
+}
+
+fn read_dword(path: &str, name: &str) -> Option<u32> {
     unsafe {
-        let path = wide_str(REGISTRY_PATH);
-        let key_name = wide_str(REGISTRY_KEY);
+        let path = wide_str(path);
+        let key_name = wide_str(name);
 
         let mut hkey = HKEY::default();
         let result = RegOpenKeyExW(
@@ -26,7 +53,7 @@ fn is_light_theme() -> bool {
         );
 
         if result.is_err() {
-            return false; // Default to dark mode
+            return None;
         }
 
         let mut data: u32 = 0;
@@ -43,9 +70,9 @@ fn is_light_theme() -> bool {
         let _ = RegCloseKey(hkey);
 
         if result.is_err() {
-            return false; // Default to dark mode
+            return None;
         }
 
-        data == 1
+        Some(data)
     }
 }
@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+
+use crate::diagnose;
+use crate::poller;
+
+/// How deep to look below `~/.claude/projects` (project dir, then session
+/// dirs holding sub-agent transcripts).
+const MAX_SCAN_DEPTH: usize = 3;
+
+/// How far back the incremental scan keeps responses in memory. Older ranges
+/// are read from the files again.
+const SCAN_HORIZON_SECS: u64 = 8 * 86_400;
+
+/// Token usage of one assistant response in a Claude Code session transcript.
+#[derive(Clone, Debug)]
+pub struct TranscriptUsage {
+    pub timestamp: u64,
+    pub model: Option<String>,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+}
+
+impl TranscriptUsage {
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens
+            + self.output_tokens
+            + self.cache_creation_input_tokens
+            + self.cache_read_input_tokens
+    }
+}
+
+/// Tokens summed over a range of responses. Cache writes and reads are kept
+/// apart from fresh input since they are usually far larger.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TokenTotals {
+    pub input: u64,
+    pub output: u64,
+    pub cached: u64,
+}
+
+impl TokenTotals {
+    pub fn add(&mut self, usage: &TranscriptUsage) {
+        self.input += usage.input_tokens;
+        self.output += usage.output_tokens;
+        self.cached += usage.cache_creation_input_tokens + usage.cache_read_input_tokens;
+    }
+
+    pub fn total(&self) -> u64 {
+        self.input + self.output + self.cached
+    }
+}
+
+#[derive(Deserialize)]
+struct TranscriptLine {
+    timestamp: Option<String>,
+    #[serde(rename = "requestId")]
+    request_id: Option<String>,
+    message: Option<TranscriptMessage>,
+}
+
+#[derive(Deserialize)]
+struct TranscriptMessage {
+    id: Option<String>,
+    model: Option<String>,
+    usage: Option<TranscriptTokens>,
+}
+
+#[derive(Deserialize)]
+struct TranscriptTokens {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+    #[serde(default)]
+    cache_creation_input_tokens: u64,
+    #[serde(default)]
+    cache_read_input_tokens: u64,
+}
+
+/// Responses from recent transcripts, kept between polls so only what was
+/// appended to each file since is read again.
+#[derive(Default)]
+struct Scan {
+    /// Bytes of each transcript read so far, up to its last complete line.
+    offsets: HashMap<PathBuf, u64>,
+    /// De-duplication keys of the kept responses, with their timestamps.
+    seen: HashMap<String, u64>,
+    /// Oldest first.
+    usage: Vec<TranscriptUsage>,
+    /// Responses before this (unix seconds) are not kept.
+    horizon: u64,
+}
+
+static SCAN: Mutex<Option<Scan>> = Mutex::new(None);
+
+pub fn projects_dir() -> Option<PathBuf> {
+    Some(poller::claude_config_dir()?.join("projects"))
+}
+
+fn now_unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// Read what was appended to the transcripts since the last scan. The first
+/// scan reads the last few days in full.
+pub fn refresh() {
+    let Some(dir) = projects_dir() else {
+        return;
+    };
+    let mut scan = SCAN.lock().unwrap_or_else(|e| e.into_inner());
+    let scan = scan.get_or_insert_with(Scan::default);
+
+    let horizon = now_unix_secs().saturating_sub(SCAN_HORIZON_SECS);
+    scan.horizon = horizon;
+    scan.usage.retain(|entry| entry.timestamp >= horizon);
+    scan.seen.retain(|_, timestamp| *timestamp >= horizon);
+
+    let mut files = Vec::new();
+    collect_transcripts(&dir, horizon, MAX_SCAN_DEPTH, &mut files);
+    let mut offsets = HashMap::with_capacity(files.len());
+    let mut added = Vec::new();
+    for path in files {
+        let offset = scan.offsets.get(&path).copied().unwrap_or(0);
+        let offset = read_appended(&path, offset, horizon, &mut scan.seen, &mut added);
+        offsets.insert(path, offset);
+    }
+    // Transcripts that were deleted or went quiet are dropped; one that is
+    // written to again is read from the start, and its old lines de-duplicated.
+    scan.offsets = offsets;
+
+    if !added.is_empty() {
+        scan.usage.extend(added);
+        scan.usage.sort_by_key(|entry| entry.timestamp);
+    }
+}
+
+/// Every response recorded at or after `since` (unix seconds), oldest first,
+/// as of the last `refresh`. Ranges older than the scan keeps are read from
+/// the files instead.
+pub fn usage_since(since: u64) -> Vec<TranscriptUsage> {
+    {
+        let scan = SCAN.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(scan) = scan.as_ref().filter(|scan| since >= scan.horizon) {
+            let start = scan.usage.partition_point(|entry| entry.timestamp < since);
+            return scan.usage[start..].to_vec();
+        }
+    }
+    read_usage_since(since)
+}
+
+/// Every assistant response recorded at or after `since` (unix seconds),
+/// oldest first. Transcripts last modified before `since` are skipped without
+/// being opened.
+pub fn read_usage_since(since: u64) -> Vec<TranscriptUsage> {
+    let Some(dir) = projects_dir() else {
+        return Vec::new();
+    };
+
+    let mut files = Vec::new();
+    collect_transcripts(&dir, since, MAX_SCAN_DEPTH, &mut files);
+
+    // Claude Code writes one line per content block, each repeating the
+    // response's usage, so responses are de-duplicated by message/request id.
+    let mut seen = HashMap::new();
+    let mut usage = Vec::new();
+    for path in files {
+        read_transcript(&path, since, &mut seen, &mut usage);
+    }
+    usage.sort_by_key(|entry| entry.timestamp);
+    usage
+}
+
+/// Token totals of every response recorded at or after `since` (unix seconds),
+/// as of the last `refresh`.
+pub fn token_totals_since(since: u64) -> TokenTotals {
+    let mut totals = TokenTotals::default();
+    for usage in usage_since(since) {
+        totals.add(&usage);
+    }
+    totals
+}
+
+fn collect_transcripts(dir: &Path, since: u64, depth: usize, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            if depth > 0 {
+                collect_transcripts(&path, since, depth - 1, files);
+            }
+            continue;
+        }
+        if path.extension().and_then(|ext| ext.to_str()) != Some("jsonl") {
+            continue;
+        }
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs())
+            .unwrap_or(u64::MAX);
+        if modified >= since {
+            files.push(path);
+        }
+    }
+}
+
+fn read_transcript(
+    path: &Path,
+    since: u64,
+    seen: &mut HashMap<String, u64>,
+    usage: &mut Vec<TranscriptUsage>,
+) {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(error) => {
+            diagnose::log_error(
+                &format!("unable to read transcript {}", path.display()),
+                error,
+            );
+            return;
+        }
+    };
+
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        parse_line(&line, since, seen, usage);
+    }
+}
+
+/// Read the complete lines written to `path` after `offset` and return the
+/// offset to continue from. A line still being written is left for next time.
+fn read_appended(
+    path: &Path,
+    offset: u64,
+    since: u64,
+    seen: &mut HashMap<String, u64>,
+    usage: &mut Vec<TranscriptUsage>,
+) -> u64 {
+    let read = || -> std::io::Result<(u64, Vec<u8>)> {
+        let mut file = std::fs::File::open(path)?;
+        // A transcript that shrank was rewritten, so start over.
+        let start = if file.metadata()?.len() < offset {
+            0
+        } else {
+            offset
+        };
+        file.seek(SeekFrom::Start(start))?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        Ok((start, bytes))
+    };
+    let (start, bytes) = match read() {
+        Ok(read) => read,
+        Err(error) => {
+            diagnose::log_error(
+                &format!("unable to read transcript {}", path.display()),
+                error,
+            );
+            return offset;
+        }
+    };
+
+    let Some(end) = bytes.iter().rposition(|&byte| byte == b'\n') else {
+        return start;
+    };
+    for line in bytes[..end].split(|&byte| byte == b'\n') {
+        parse_line(&String::from_utf8_lossy(line), since, seen, usage);
+    }
+    start + end as u64 + 1
+}
+
+fn parse_line(
+    line: &str,
+    since: u64,
+    seen: &mut HashMap<String, u64>,
+    usage: &mut Vec<TranscriptUsage>,
+) {
+    // Cheap pre-filter: most lines are user turns and tool output.
+    if !line.contains("\"usage\"") {
+        return;
+    }
+    let Ok(parsed) = serde_json::from_str::<TranscriptLine>(line) else {
+        return;
+    };
+    let Some(message) = parsed.message else {
+        return;
+    };
+    let Some(tokens) = message.usage else {
+        return;
+    };
+    let Some(timestamp) = poller::parse_iso8601(parsed.timestamp.as_deref())
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+    else {
+        return;
+    };
+    if timestamp < since {
+        return;
+    }
+
+    if let Some(id) = message.id.as_ref().or(parsed.request_id.as_ref()) {
+        let key = format!("{id}:{}", parsed.request_id.as_deref().unwrap_or(""));
+        if seen.insert(key, timestamp).is_some() {
+            return;
+        }
+    }
+
+    usage.push(TranscriptUsage {
+        timestamp,
+        model: message.model,
+        input_tokens: tokens.input_tokens,
+        output_tokens: tokens.output_tokens,
+        cache_creation_input_tokens: tokens.cache_creation_input_tokens,
+        cache_read_input_tokens: tokens.cache_read_input_tokens,
+    });
+}
@@ -2,18 +2,32 @@ use windows::core::PCWSTR;
 use windows::Win32::Foundation::*;
 use windows::Win32::Graphics::Gdi::*;
 use windows::Win32::System::LibraryLoader::GetModuleFileNameW;
+use windows::Win32::System::Registry::{
+    RegCloseKey, RegCreateKeyExW, RegDeleteTreeW, RegSetValueExW, HKEY, HKEY_CURRENT_USER,
+    KEY_SET_VALUE, REG_OPTION_NON_VOLATILE, REG_SZ,
+};
 use windows::Win32::UI::Shell::{
-    ExtractIconExW, Shell_NotifyIconW, NIF_ICON, NIF_INFO, NIF_MESSAGE, NIF_TIP, NIIF_WARNING,
-    NIM_ADD, NIM_DELETE, NIM_MODIFY, NOTIFYICONDATAW,
+    ExtractIconExW, SetCurrentProcessExplicitAppUserModelID, Shell_NotifyIconW, NIF_ICON, NIF_INFO,
+    NIF_MESSAGE, NIF_TIP, NIIF_INFO, NIIF_WARNING, NIM_ADD, NIM_DELETE, NIM_MODIFY,
+    NIN_BALLOONHIDE, NIN_BALLOONTIMEOUT, NIN_BALLOONUSERCLICK, NOTIFYICONDATAW,
+    NOTIFY_ICON_INFOTIP_FLAGS,
 };
 use windows::Win32::UI::WindowsAndMessaging::*;
 
+use crate::diagnose;
 use crate::native_interop::{self, Color, WM_APP_TRAY};
+use crate::palette::Palette;
 
 const CLAUDE_TRAY_ICON_ID: u32 = 1;
 const CODEX_TRAY_ICON_ID: u32 = 2;
 const ANTIGRAVITY_TRAY_ICON_ID: u32 = 3;
 
+/// Identity the shell groups our notifications under in Action Center.
+const APP_USER_MODEL_ID: &str = "CodeZeno.ClaudeCodeUsageMonitor";
+const APP_USER_MODEL_ID_REGISTRY_PATH: &str =
+    "Software\\Classes\\AppUserModelId\\CodeZeno.ClaudeCodeUsageMonitor";
+const APP_DISPLAY_NAME: &str = "Claude Code Usage Monitor";
+
 /// Menu item ID for toggling widget visibility (used by window.rs context menu).
 pub const IDM_TOGGLE_WIDGET: u16 = 70;
 
@@ -22,6 +36,10 @@ pub enum TrayAction {
     None,
     ToggleWidget,
     ShowContextMenu,
+    /// A notification balloon was clicked.
+    BalloonClicked,
+    /// A notification balloon timed out or was closed without a click.
+    BalloonDismissed,
 }
 
 #[derive(Clone, Copy)]
@@ -35,6 +53,7 @@ pub struct TrayIconData {
     pub kind: TrayIconKind,
     pub percent: Option<f64>,
     pub tooltip: String,
+    pub palette: Palette,
 }
 
 impl TrayIconKind {
@@ -47,44 +66,6 @@ impl TrayIconKind {
     }
 }
 
-fn lerp_channel(start: u8, end: u8, t: f64) -> u8 {
-    (start as f64 + (end as f64 - start as f64) * t.clamp(0.0, 1.0)).round() as u8
-}
-
-fn lerp_color(start: Color, end: Color, t: f64) -> Color {
-    Color::new(
-        lerp_channel(start.r, end.r, t),
-        lerp_channel(start.g, end.g, t),
-        lerp_channel(start.b, end.b, t),
-    )
-}
-
-fn interpolated_fill(percent: f64) -> Color {
-    if percent <= 50.0 {
-        return Color::from_hex("#D97757");
-    }
-
-    let stops = [
-        (50.0, Color::from_hex("#D97757")),
-        (70.0, Color::from_hex("#D08540")),
-        (85.0, Color::from_hex("#CC8C20")),
-        (95.0, Color::from_hex("#C45020")),
-        (100.0, Color::from_hex("#B82020")),
-    ];
-
-    for pair in stops.windows(2) {
-        let (start_pct, start_color) = pair[0];
-        let (end_pct, end_color) = pair[1];
-        if percent <= end_pct {
-            let span = (end_pct - start_pct).max(f64::EPSILON);
-            let t = (percent - start_pct) / span;
-            return lerp_color(start_color, end_color, t);
-        }
-    }
-
-    stops[stops.len() - 1].1
-}
-
 fn codex_fill(percent: f64) -> Color {
     if percent >= 90.0 {
         Color::from_hex("#FFFFFF")
@@ -93,18 +74,18 @@ fn codex_fill(percent: f64) -> Color {
     }
 }
 
-fn antigravity_fill(percent: f64) -> Color {
+fn antigravity_fill(percent: f64, palette: Palette) -> Color {
     if percent >= 90.0 {
         Color::from_hex("#FFFFFF")
     } else {
-        Color::from_hex("#4285F4")
+        palette.antigravity_accent()
     }
 }
 
 /// Create a rounded-rectangle tray icon badge showing the usage percentage.
 /// For Claude, `percent` = None uses the embedded app icon as the loading state.
 /// For Codex and Antigravity, `percent` = None uses a provider placeholder badge.
-pub fn create_icon(kind: TrayIconKind, percent: Option<f64>) -> HICON {
+pub fn create_icon(kind: TrayIconKind, percent: Option<f64>, palette: Palette) -> HICON {
     if matches!(kind, TrayIconKind::Claude) && percent.is_none() {
         let app_icon = load_embedded_app_icon();
         if !app_icon.is_invalid() {
@@ -122,22 +103,26 @@ pub fn create_icon(kind: TrayIconKind, percent: Option<f64>) -> HICON {
     };
 
     let fill = match kind {
-        TrayIconKind::Claude => interpolated_fill(percent.unwrap_or(0.0)),
+        TrayIconKind::Claude => palette.badge_fill(percent.unwrap_or(0.0)),
         TrayIconKind::Codex => codex_fill(percent.unwrap_or(0.0)),
-        TrayIconKind::Antigravity => antigravity_fill(percent.unwrap_or(0.0)),
+        TrayIconKind::Antigravity => antigravity_fill(percent.unwrap_or(0.0), palette),
     };
     let text_col = match kind {
         TrayIconKind::Claude => Color::from_hex("#FFFFFF"),
         TrayIconKind::Codex if percent.unwrap_or(0.0) >= 90.0 => Color::from_hex("#111111"),
         TrayIconKind::Codex => Color::from_hex("#FFFFFF"),
-        TrayIconKind::Antigravity if percent.unwrap_or(0.0) >= 90.0 => Color::from_hex("#1967D2"),
+        TrayIconKind::Antigravity if percent.unwrap_or(0.0) >= 90.0 => {
+            palette.antigravity_text(false)
+        }
         TrayIconKind::Antigravity => Color::from_hex("#FFFFFF"),
     };
     let outline_col = match kind {
         TrayIconKind::Claude => fill,
         TrayIconKind::Codex if percent.unwrap_or(0.0) >= 90.0 => Color::from_hex("#111111"),
         TrayIconKind::Codex => Color::from_hex("#FFFFFF"),
-        TrayIconKind::Antigravity if percent.unwrap_or(0.0) >= 90.0 => Color::from_hex("#1967D2"),
+        TrayIconKind::Antigravity if percent.unwrap_or(0.0) >= 90.0 => {
+            palette.antigravity_text(false)
+        }
         TrayIconKind::Antigravity => Color::from_hex("#FFFFFF"),
     };
 
@@ -330,16 +315,89 @@ fn load_embedded_app_icon() -> HICON {
     }
 }
 
+/// Give the process an explicit AppUserModelID and register its display name,
+/// so every notification is grouped under "Claude Code Usage Monitor" in
+/// Action Center and can be cleared together.
+pub fn register_app_id() {
+    unsafe {
+        let id = native_interop::wide_str(APP_USER_MODEL_ID);
+        if let Err(error) = SetCurrentProcessExplicitAppUserModelID(PCWSTR::from_raw(id.as_ptr())) {
+            diagnose::log_error("unable to set AppUserModelID", error);
+        }
+
+        let path = native_interop::wide_str(APP_USER_MODEL_ID_REGISTRY_PATH);
+        let mut hkey = HKEY::default();
+        let result = RegCreateKeyExW(
+            HKEY_CURRENT_USER,
+            PCWSTR::from_raw(path.as_ptr()),
+            0,
+            PCWSTR::null(),
+            REG_OPTION_NON_VOLATILE,
+            KEY_SET_VALUE,
+            None,
+            &mut hkey,
+            None,
+        );
+        if result.is_err() {
+            diagnose::log_error("unable to register AppUserModelID", result.0);
+            return;
+        }
+
+        let name = native_interop::wide_str("DisplayName");
+        let value = native_interop::wide_str(APP_DISPLAY_NAME);
+        let _ = RegSetValueExW(
+            hkey,
+            PCWSTR::from_raw(name.as_ptr()),
+            0,
+            REG_SZ,
+            Some(std::slice::from_raw_parts(
+                value.as_ptr() as *const u8,
+                value.len() * 2,
+            )),
+        );
+        let _ = RegCloseKey(hkey);
+    }
+}
+
+/// Remove the registration written by `register_app_id`.
+pub fn unregister_app_id() -> Result<(), String> {
+    let path = native_interop::wide_str(APP_USER_MODEL_ID_REGISTRY_PATH);
+    let result = unsafe { RegDeleteTreeW(HKEY_CURRENT_USER, PCWSTR::from_raw(path.as_ptr())) };
+    if result.is_ok() || result == ERROR_FILE_NOT_FOUND {
+        Ok(())
+    } else {
+        Err(format!(
+            "Unable to remove the AppUserModelID registration: {}",
+            result.0
+        ))
+    }
+}
+
 /// Show a Windows balloon notification from the tray icon.
-/// Used to alert the user when re-authentication is required.
+/// Clicking it sends `TrayAction::BalloonClicked`.
 pub fn notify_balloon(hwnd: HWND, kind: TrayIconKind, title: &str, message: &str) {
+    show_balloon(hwnd, kind, title, message, NIIF_WARNING);
+}
+
+/// Like `notify_balloon`, with the information icon for suggestions.
+pub fn notify_balloon_info(hwnd: HWND, kind: TrayIconKind, title: &str, message: &str) {
+    show_balloon(hwnd, kind, title, message, NIIF_INFO);
+}
+
+fn show_balloon(
+    hwnd: HWND,
+    kind: TrayIconKind,
+    title: &str,
+    message: &str,
+    icon: NOTIFY_ICON_INFOTIP_FLAGS,
+) {
     unsafe {
         let mut nid: NOTIFYICONDATAW = std::mem::zeroed();
         nid.cbSize = std::mem::size_of::<NOTIFYICONDATAW>() as u32;
         nid.hWnd = hwnd;
         nid.uID = kind.id();
         nid.uFlags = NIF_INFO;
-        nid.dwInfoFlags = NIIF_WARNING;
+        nid.dwInfoFlags = icon;
         copy_wide(title, &mut nid.szInfoTitle);
         copy_wide_256(message, &mut nid.szInfo);
         let _ = Shell_NotifyIconW(NIM_MODIFY, &nid);
@@ -360,8 +418,14 @@ fn copy_wide_256(s: &str, buf: &mut [u16; 256]) {
 }
 
 /// Register the tray icon with the shell.
-pub fn add(hwnd: HWND, kind: TrayIconKind, percent: Option<f64>, tooltip: &str) {
-    let hicon = create_icon(kind, percent);
+pub fn add(hwnd: HWND, icon: &TrayIconData) {
+    let TrayIconData {
+        kind,
+        percent,
+        ref tooltip,
+        palette,
+    } = *icon;
+    let hicon = create_icon(kind, percent, palette);
     unsafe {
         let mut nid: NOTIFYICONDATAW = std::mem::zeroed();
         nid.cbSize = std::mem::size_of::<NOTIFYICONDATAW>() as u32;
@@ -379,8 +443,14 @@ pub fn add(hwnd: HWND, kind: TrayIconKind, percent: Option<f64>, tooltip: &str)
 }
 
 /// Update the tray icon colour and tooltip to reflect current usage.
-pub fn update(hwnd: HWND, kind: TrayIconKind, percent: Option<f64>, tooltip: &str) {
-    let hicon = create_icon(kind, percent);
+pub fn update(hwnd: HWND, icon: &TrayIconData) {
+    let TrayIconData {
+        kind,
+        percent,
+        ref tooltip,
+        palette,
+    } = *icon;
+    let hicon = create_icon(kind, percent, palette);
     unsafe {
         let mut nid: NOTIFYICONDATAW = std::mem::zeroed();
         nid.cbSize = std::mem::size_of::<NOTIFYICONDATAW>() as u32;
@@ -419,22 +489,22 @@ pub fn sync(hwnd: HWND, icons: &[TrayIconData]) {
         .find(|icon| matches!(icon.kind, TrayIconKind::Antigravity));
 
     if let Some(icon) = show_claude {
-        add(hwnd, icon.kind, icon.percent, &icon.tooltip);
-        update(hwnd, icon.kind, icon.percent, &icon.tooltip);
+        add(hwnd, icon);
+        update(hwnd, icon);
     } else {
         remove(hwnd, TrayIconKind::Claude);
     }
 
     if let Some(icon) = show_codex {
-        add(hwnd, icon.kind, icon.percent, &icon.tooltip);
-        update(hwnd, icon.kind, icon.percent, &icon.tooltip);
+        add(hwnd, icon);
+        update(hwnd, icon);
     } else {
         remove(hwnd, TrayIconKind::Codex);
     }
 
     if let Some(icon) = show_antigravity {
-        add(hwnd, icon.kind, icon.percent, &icon.tooltip);
-        update(hwnd, icon.kind, icon.percent, &icon.tooltip);
+        add(hwnd, icon);
+        update(hwnd, icon);
     } else {
         remove(hwnd, TrayIconKind::Antigravity);
     }
@@ -452,6 +522,8 @@ pub fn handle_message(lparam: LPARAM) -> TrayAction {
     match mouse_msg {
         WM_LBUTTONUP => TrayAction::ToggleWidget,
         WM_RBUTTONUP => TrayAction::ShowContextMenu,
+        NIN_BALLOONUSERCLICK => TrayAction::BalloonClicked,
+        NIN_BALLOONTIMEOUT | NIN_BALLOONHIDE => TrayAction::BalloonDismissed,
         _ => TrayAction::None,
     }
 }
@@ -11,6 +11,8 @@ use windows::Win32::Foundation::{HWND, WAIT_OBJECT_0, WAIT_TIMEOUT};
 use windows::Win32::System::Threading::{OpenProcess, WaitForSingleObject, PROCESS_SYNCHRONIZE};
 use windows::Win32::UI::WindowsAndMessaging::{MessageBoxW, MB_ICONERROR, MB_OK};
 
+use crate::paths;
+
 const GITHUB_API_ACCEPT: &str = "application/vnd.github+json";
 const GITHUB_API_VERSION: &str = "2022-11-28";
 const RELEASE_ASSET_NAME: &str = "claude-code-usage-monitor.exe";
@@ -309,7 +311,7 @@ fn relaunch_target(target: &Path) -> Result<(), String> {
     Ok(())
 }
 
-fn wait_for_process_exit(pid: u32, timeout: Duration) -> Result<(), String> {
+pub fn wait_for_process_exit(pid: u32, timeout: Duration) -> Result<(), String> {
     if pid == 0 {
         return Ok(());
     }
@@ -332,6 +334,9 @@ fn wait_for_process_exit(pid: u32, timeout: Duration) -> Result<(), String> {
 }
 
 fn updates_dir() -> Result<PathBuf, String> {
+    if paths::is_portable() {
+        return Ok(paths::data_dir().join("updates"));
+    }
     dirs::data_local_dir()
         .map(|dir| dir.join("ClaudeCodeUsageMonitor").join("updates"))
         .or_else(|| {
@@ -0,0 +1,426 @@
+//! Loopback WebSocket feed for companion apps such as Stream Deck plugins and
+//! OBS overlays. Every client gets the latest usage on connect and each
+//! successful poll after that, as one JSON text message.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::diagnose;
+
+/// Appended to the client's key to prove the server speaks WebSocket (RFC 6455).
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// Larger client frames are not expected and end the connection.
+const MAX_CLIENT_FRAME: u64 = 64 * 1024;
+/// Frames queued for a client that is not reading; one more drops it.
+const MAX_QUEUED_FRAMES: usize = 16;
+
+/// A connected client. Its writer thread owns the socket's write side, so a
+/// slow client never holds up the broadcaster.
+struct Client {
+    id: u64,
+    frames: SyncSender<Arc<[u8]>>,
+    stream: TcpStream,
+}
+
+struct Feed {
+    clients: Vec<Client>,
+    latest: Option<String>,
+    /// Browser origins allowed to connect; clients sending no Origin, such as
+    /// native apps, are always allowed.
+    allowed_origins: Vec<String>,
+}
+
+static FEED: Mutex<Feed> = Mutex::new(Feed {
+    clients: Vec::new(),
+    latest: None,
+    allowed_origins: Vec::new(),
+});
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static NEXT_CLIENT_ID: AtomicU64 = AtomicU64::new(0);
+
+fn lock_feed() -> MutexGuard<'static, Feed> {
+    FEED.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Accept WebSocket clients on loopback `port`. Web pages may only connect
+/// from one of `allowed_origins`, so a site open in the browser cannot read
+/// the feed.
+pub fn start(port: u16, allowed_origins: &[String]) -> Result<(), String> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .map_err(|e| format!("Unable to listen for WebSocket clients on port {port}: {e}"))?;
+    lock_feed().allowed_origins = allowed_origins
+        .iter()
+        .map(|origin| origin.trim_end_matches('/').to_string())
+        .collect();
+    ENABLED.store(true, Ordering::Relaxed);
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    std::thread::spawn(move || handle_client(stream));
+                }
+                Err(error) => diagnose::log_error("websocket: accept failed", error),
+            }
+        }
+    });
+    Ok(())
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Queue `message` for every client, dropping the ones that have gone away or
+/// stopped reading. Never waits on a client's socket.
+pub fn broadcast(message: String) {
+    let frame: Arc<[u8]> = frame(OPCODE_TEXT, message.as_bytes()).into();
+    let mut feed = lock_feed();
+    feed.clients
+        .retain(|client| match client.frames.try_send(frame.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                diagnose::log("websocket: dropping a client that stopped reading");
+                let _ = client.stream.shutdown(Shutdown::Both);
+                false
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        });
+    feed.latest = Some(message);
+}
+
+fn handle_client(stream: TcpStream) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let allowed_origins = lock_feed().allowed_origins.clone();
+    let key = match read_handshake(&stream).and_then(|h| accept(h, &allowed_origins)) {
+        Ok(key) => key,
+        Err(status) => {
+            let _ = (&stream)
+                .write_all(format!("HTTP/1.1 {status}\r\nConnection: close\r\n\r\n").as_bytes());
+            return;
+        }
+    };
+
+    let response = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(&key)
+    );
+    let (Ok(writer), Ok(registered)) = (stream.try_clone(), stream.try_clone()) else {
+        return;
+    };
+    let _ = writer.set_write_timeout(Some(Duration::from_secs(2)));
+    if (&writer).write_all(response.as_bytes()).is_err() {
+        return;
+    }
+    let id = NEXT_CLIENT_ID.fetch_add(1, Ordering::Relaxed);
+    let (frames, queue) = mpsc::sync_channel(MAX_QUEUED_FRAMES);
+    {
+        // Queue the latest usage under the lock so no broadcast can slip in
+        // ahead of it.
+        let mut feed = lock_feed();
+        if let Some(latest) = &feed.latest {
+            let _ = frames.try_send(frame(OPCODE_TEXT, latest.as_bytes()).into());
+        }
+        feed.clients.push(Client {
+            id,
+            frames: frames.clone(),
+            stream: registered,
+        });
+    }
+    std::thread::spawn(move || write_frames(writer, queue));
+
+    // Clients only send control frames; answer pings and closes until the
+    // connection ends.
+    let _ = stream.set_read_timeout(None);
+    let mut reader = BufReader::new(&stream);
+    while let Some((opcode, payload)) = read_frame(&mut reader) {
+        match opcode {
+            OPCODE_PING => {
+                let _ = frames.try_send(frame(OPCODE_PONG, &payload).into());
+            }
+            OPCODE_CLOSE => {
+                let _ = frames.try_send(frame(OPCODE_CLOSE, &payload).into());
+                break;
+            }
+            _ => {}
+        }
+    }
+    // Dropping the last sender lets the writer flush what is queued, such as
+    // the close reply, and then shut the connection.
+    lock_feed().clients.retain(|client| client.id != id);
+}
+
+/// Write queued frames to the client until the queue closes or a write fails.
+fn write_frames(stream: TcpStream, queue: Receiver<Arc<[u8]>>) {
+    for frame in queue {
+        if (&stream).write_all(&frame).is_err() {
+            break;
+        }
+    }
+    let _ = stream.shutdown(Shutdown::Both);
+}
+
+/// The parts of an upgrade request the server checks.
+#[derive(Default)]
+struct Handshake {
+    method: String,
+    upgrade: Option<String>,
+    origin: Option<String>,
+    key: Option<String>,
+}
+
+/// Read the upgrade request's request line and headers.
+fn read_handshake(stream: impl Read) -> Result<Handshake, &'static str> {
+    const BAD_REQUEST: &str = "400 Bad Request";
+    let mut reader = BufReader::new(stream);
+    let mut handshake = Handshake::default();
+    let mut request_line = true;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).map_err(|_| BAD_REQUEST)? == 0 {
+            return Err(BAD_REQUEST);
+        }
+        let line = line.trim_end();
+        if request_line {
+            handshake.method = line.split(' ').next().unwrap_or_default().to_string();
+            request_line = false;
+            continue;
+        }
+        if line.is_empty() {
+            return Ok(handshake);
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = Some(value.trim().to_string());
+        match name.trim().to_ascii_lowercase().as_str() {
+            "upgrade" => handshake.upgrade = value,
+            "origin" => handshake.origin = value,
+            "sec-websocket-key" => handshake.key = value,
+            _ => {}
+        }
+    }
+}
+
+/// The `Sec-WebSocket-Key` of a well-formed upgrade from an allowed origin,
+/// or the status to refuse it with.
+fn accept(handshake: Handshake, allowed_origins: &[String]) -> Result<String, &'static str> {
+    if handshake.method != "GET" {
+        return Err("405 Method Not Allowed");
+    }
+    let upgrade = handshake.upgrade.unwrap_or_default();
+    if !upgrade
+        .split(',')
+        .any(|protocol| protocol.trim().eq_ignore_ascii_case("websocket"))
+    {
+        return Err("400 Bad Request");
+    }
+    if let Some(origin) = handshake.origin {
+        let origin = origin.trim_end_matches('/');
+        if !allowed_origins
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(origin))
+        {
+            diagnose::log(format!("websocket: refused a client from origin {origin}"));
+            return Err("403 Forbidden");
+        }
+    }
+    handshake.key.ok_or("400 Bad Request")
+}
+
+/// Read one masked client frame; None when the connection ended or the frame
+/// is malformed.
+fn read_frame(reader: &mut impl Read) -> Option<(u8, Vec<u8>)> {
+    let mut header = [0u8; 2];
+    reader.read_exact(&mut header).ok()?;
+    let opcode = header[0] & 0x0F;
+    let masked = header[1] & 0x80 != 0;
+    let length = match header[1] & 0x7F {
+        126 => {
+            let mut bytes = [0u8; 2];
+            reader.read_exact(&mut bytes).ok()?;
+            u16::from_be_bytes(bytes) as u64
+        }
+        127 => {
+            let mut bytes = [0u8; 8];
+            reader.read_exact(&mut bytes).ok()?;
+            u64::from_be_bytes(bytes)
+        }
+        length => length as u64,
+    };
+    if !masked || length > MAX_CLIENT_FRAME {
+        return None;
+    }
+    let mut mask = [0u8; 4];
+    reader.read_exact(&mut mask).ok()?;
+    let mut payload = vec![0u8; length as usize];
+    reader.read_exact(&mut payload).ok()?;
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+    Some((opcode, payload))
+}
+
+/// An unmasked, unfragmented server frame.
+fn frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{key}{HANDSHAKE_GUID}").as_bytes()))
+}
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [
+        0x6745_2301,
+        0xEFCD_AB89,
+        0x98BA_DCFE,
+        0x1032_5476,
+        0xC3D2_E1F0,
+    ];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (chunk, word) in digest.chunks_mut(4).zip(h) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_key_matches_rfc_example() {
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn refuses_upgrades_from_origins_not_allowed() {
+        let request = |origin: &str| {
+            read_handshake(
+                format!(
+                    "GET / HTTP/1.1\r\nHost: 127.0.0.1\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n{origin}Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n"
+                )
+                .as_bytes(),
+            )
+            .unwrap()
+        };
+        let allowed = vec!["http://localhost:3000".to_string()];
+        assert!(accept(request(""), &allowed).is_ok());
+        assert!(accept(request("Origin: http://localhost:3000\r\n"), &allowed).is_ok());
+        assert_eq!(
+            accept(request("Origin: https://example.com\r\n"), &allowed).unwrap_err(),
+            "403 Forbidden"
+        );
+
+        let post = read_handshake(
+            "POST / HTTP/1.1\r\nUpgrade: websocket\r\nSec-WebSocket-Key: a\r\n\r\n".as_bytes(),
+        )
+        .unwrap();
+        assert!(accept(post, &allowed).is_err());
+        let plain =
+            read_handshake("GET / HTTP/1.1\r\nSec-WebSocket-Key: a\r\n\r\n".as_bytes()).unwrap();
+        assert!(accept(plain, &allowed).is_err());
+    }
+
+    #[test]
+    fn reads_a_masked_close_frame() {
+        let mask = [1, 2, 3, 4];
+        let mut bytes = vec![0x88, 0x82];
+        bytes.extend_from_slice(&mask);
+        bytes.extend_from_slice(&[0x03 ^ 1, 0xE8 ^ 2]);
+        let (opcode, payload) = read_frame(&mut bytes.as_slice()).unwrap();
+        assert_eq!(opcode, OPCODE_CLOSE);
+        // Status 1000, normal closure.
+        assert_eq!(payload, vec![0x03, 0xE8]);
+    }
+}
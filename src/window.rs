@@ -1,6 +1,8 @@
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicU32, Ordering};
-use std::sync::{Mutex, MutexGuard};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicIsize, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
@@ -9,24 +11,57 @@ use windows::Win32::Foundation::*;
 use windows::Win32::Graphics::Gdi::*;
 use windows::Win32::System::LibraryLoader::{GetModuleFileNameW, GetModuleHandleW};
 use windows::Win32::System::Registry::*;
+use windows::Win32::System::RemoteDesktop::{
+    WTSRegisterSessionNotification, WTSUnRegisterSessionNotification, NOTIFY_FOR_THIS_SESSION,
+};
 use windows::Win32::System::Threading::{CreateMutexW, WaitForSingleObject};
 use windows::Win32::UI::Accessibility::HWINEVENTHOOK;
 use windows::Win32::UI::HiDpi::*;
-use windows::Win32::UI::Input::KeyboardAndMouse::{ReleaseCapture, SetCapture};
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetKeyState, ReleaseCapture, SetCapture, VK_SHIFT,
+};
 use windows::Win32::UI::Shell::ExtractIconExW;
 use windows::Win32::UI::WindowsAndMessaging::*;
 
+use crate::alerts::{self, Alert, AlertAction, AlertRule, AlertWindow};
+use crate::badge;
+use crate::broadcast;
+use crate::cost::{self, CostEstimate};
 use crate::diagnose;
+use crate::events::{self, EventKind};
+use crate::export;
+use crate::flyout::{self, FlyoutButton, FlyoutHeatmap, FlyoutSection, FlyoutStrip};
+use crate::format::{self, Style};
+use crate::history::{self, Provider, UsageJump, UsageReset, UsageWindow, WeeklyProjection};
+use crate::hotkey::{self, Hotkey};
 use crate::localization::{self, LanguageId, Strings};
-use crate::models::AppUsageData;
+use crate::messages;
+use crate::mock_usage::{SimulatedSource, Simulation};
+use crate::models::{ApiRateLimits, AppUsageData, UnifiedLimit, UsageData, UsageSection};
+use crate::mqtt::{self, MqttSettings};
 use crate::native_interop::{
-    self, Color, TIMER_COUNTDOWN, TIMER_POLL, TIMER_RESET_POLL, TIMER_UPDATE_CHECK, WM_APP_TRAY,
-    WM_APP_USAGE_UPDATED,
+    self, Color, TaskbarProgressState, TIMER_ALERT_FLASH, TIMER_BAR_ANIMATION, TIMER_COUNTDOWN,
+    TIMER_DEFERRED_RENDER, TIMER_HELD_NOTIFICATIONS, TIMER_LIMIT_RESET, TIMER_METERED_CHECK,
+    TIMER_POLL, TIMER_REPOSITION, TIMER_RESET_POLL, TIMER_UPDATE_CHECK, TIMER_WEEKLY_REMINDER,
+    WM_APP_ACTIVATE, WM_APP_BADGE_CLICKED, WM_APP_BADGE_CLOSED, WM_APP_POWER_BROADCAST,
+    WM_APP_TIME_CHANGED, WM_APP_TRAY, WM_APP_USAGE_UPDATED,
 };
+use crate::otel;
+use crate::palette::{Palette, WARNING_PERCENT};
+use crate::paths;
+use crate::png;
+use crate::poll_worker;
 use crate::poller;
+use crate::prompt;
+use crate::render::bars::{self, Bar, BarStyle};
+use crate::render::Brushes;
+use crate::shared_memory;
+use crate::startup_task;
 use crate::theme;
+use crate::transcripts::{self, TokenTotals};
 use crate::tray_icon;
 use crate::updater::{self, InstallChannel, ReleaseDescriptor, UpdateCheckResult};
+use crate::websocket;
 
 /// Wrapper to make HWND sendable across threads (safe for PostMessage usage)
 #[derive(Clone, Copy)]
@@ -45,16 +80,33 @@ impl SendHwnd {
 
 /// Shared application state
 struct AppState {
+    handles: WindowHandles,
+    ui: UiState,
+    poll: PollState,
+}
+
+/// The Win32 handles behind the widget, and the drag in progress.
+struct WindowHandles {
     hwnd: SendHwnd,
     taskbar_hwnd: Option<HWND>,
     tray_notify_hwnd: Option<HWND>,
     win_event_hook: Option<HWINEVENTHOOK>,
-    is_dark: bool,
     embedded: bool,
+    dragging: bool,
+    drag_start_mouse_x: i32,
+    drag_start_client_x: i32,
+    drag_start_offset: i32,
+}
+
+/// What the widget shows and how: display settings, and the usage values and
+/// text drawn from the last poll.
+struct UiState {
+    is_dark: bool,
+    /// The taskbar colour while Windows shows the accent colour on it.
+    taskbar_accent: Option<Color>,
     language_override: Option<LanguageId>,
     language: LanguageId,
     install_channel: InstallChannel,
-
     session_percent: f64,
     session_text: String,
     weekly_percent: f64,
@@ -67,30 +119,234 @@ struct AppState {
     antigravity_session_text: String,
     antigravity_weekly_percent: f64,
     antigravity_weekly_text: String,
+    secondary_session_percent: f64,
+    secondary_session_text: String,
+    secondary_weekly_percent: f64,
+    secondary_weekly_text: String,
     show_claude_code: bool,
     show_codex: bool,
     show_antigravity: bool,
+    /// Show the recent 5h burn rate in place of the session countdown.
+    show_burn_rate: bool,
+    /// Show the per-minute request/token limits of `ANTHROPIC_API_KEY` instead
+    /// of the OAuth plan windows.
+    api_key_limits: bool,
+    /// Draw the widget on a rounded, slightly raised card.
+    card_background: bool,
+    /// Float over the taskbar as a topmost popup instead of embedding in it.
+    float_widget: bool,
+    /// Whole-widget opacity in percent, `MIN_OPACITY` to 100.
+    opacity: u8,
+    /// Give the widget true per-pixel alpha (with greyscale text smoothing)
+    /// so translucent taskbars show through it. Ignored with the card.
+    blend_with_taskbar: bool,
+    /// Let clicks on empty space around the content reach the taskbar.
+    click_through_background: bool,
+    palette: Palette,
+    /// Usage windows shown as widget rows, top to bottom.
+    row_order: Vec<UsageWindow>,
+    /// Text shown instead of a window's "5h"/"7d" row label.
+    row_labels: BTreeMap<UsageWindow, String>,
+    /// Put the row closest to its limit on top instead of using `row_order`.
+    urgent_row_first: bool,
+    /// Show the Claude 5h percentage as a taskbar button overlay instead of
+    /// the widget.
+    badge_mode: bool,
+    /// Remaining on/off steps of an alert's widget blink.
+    flash_steps: u32,
+    /// Bars easing from their previous values after a poll changed them.
+    bar_animation: Option<BarAnimation>,
+    /// Snap bars to new values. Windows' "Animation effects" switch does
+    /// the same.
+    reduce_motion: bool,
+    /// Draw the left divider (the drag handle).
+    show_divider: bool,
+    /// Draw usage text bold in the palette's warning colour from
+    /// `emphasis_percent` up.
+    emphasize_warnings: bool,
+    emphasis_percent: f64,
+    /// Decimal places on widget percentages, 0 or 1.
+    percent_decimals: usize,
+    /// Rows whose bars fill with the time elapsed in the window rather than
+    /// with usage.
+    elapsed_bars: Vec<UsageWindow>,
+    /// Mirror the widget: divider and labels on the right, text on the left.
+    mirror_layout: bool,
+    /// Fill bars from their right end.
+    bars_right_to_left: bool,
+    bar_style: BarStyle,
+    /// Shrink the widget to one row's height while only one row is shown.
+    compact_single_row: bool,
+    layout_margins: LayoutMargins,
+    display_mode: DisplayMode,
+    /// Wheel movement not yet large enough to change `display_mode`.
+    wheel_delta: i32,
+    left_click: ClickAction,
+    /// Show token counts in full ("1,234,567") rather than abbreviated.
+    exact_token_counts: bool,
+    /// Hours before the weekly reset to show a reminder; 0 turns it off.
+    weekly_reminder_hours: u32,
+    /// Hold back renders and notifications while a full-screen app, game or
+    /// presentation is in the foreground.
+    quiet_when_fullscreen: bool,
+    /// Notifications raised while Do Not Disturb was on, shown together as
+    /// one summary once it ends.
+    held_notifications: Vec<HeldNotification>,
+    /// Notify shortly before and when a window that hit its limit resets.
+    notify_limit_resets: bool,
+    limit_resets: Vec<LimitReset>,
+    /// Weekly reset (unix seconds) the reminder was last shown for, so a
+    /// restart does not repeat it.
+    weekly_reminder_sent_for: Option<u64>,
+    /// Global shortcut for the usage overlay, e.g. "Ctrl+Alt+U"; empty
+    /// disables it.
+    overlay_hotkey: String,
+    /// Window classes inside the taskbar to place the widget left of, for
+    /// taskbar mods with their own tray; empty uses the stock tray.
+    tray_anchor_classes: Vec<String>,
+    history_backfill_offered: bool,
+    /// The faster-polling suggestion is only ever shown once.
+    interval_suggestion_offered: bool,
+    /// So is the sign-in help for a first launch without credentials.
+    welcome_offered: bool,
+    /// Its balloon is up, so clicking it opens the README.
+    welcome_pending: bool,
+    taskbar_index: usize,
+    tray_offset: i32,
+    widget_visible: bool,
+    /// Start a fresh instance after a panic.
+    restart_after_crash: bool,
+    /// Hand-edited; kept so saving settings does not drop it.
+    startup_delay_secs: u32,
+    session_locked: bool,
+    /// Set when a render was skipped because nothing could see it.
+    render_dirty: bool,
+}
 
+/// Background work: where usage comes from, the last result, and the retry,
+/// auth, power and update-check bookkeeping around it.
+struct PollState {
+    /// A second Claude account shown in its own column.
+    secondary_account: Option<SecondaryAccount>,
+    /// Loopback port receiving Claude Code's OpenTelemetry metrics; 0 is off.
+    otel_port: u16,
+    /// Broker each successful poll is published to.
+    mqtt: Option<MqttSettings>,
+    /// Loopback port of the WebSocket usage feed; 0 is off.
+    websocket_port: u16,
+    /// Publish each successful poll in a named shared-memory section.
+    shared_memory: bool,
+    /// Web page origins allowed to connect to the WebSocket feed.
+    websocket_origins: Vec<String>,
+    /// Where polls get usage from.
+    usage_source: Arc<dyn poller::UsageSource>,
+    /// Show the Debug submenu without holding Shift.
+    debug_menu: bool,
+    /// The debug menu's stand-in for real usage, while one is running.
+    simulation: Option<Simulation>,
+    /// Estimate Claude Code spend from local transcripts after each poll.
+    show_cost: bool,
+    cost: Option<CostEstimate>,
+    /// Total today's Claude Code input/output tokens from local transcripts
+    /// after each poll.
+    show_token_counts: bool,
+    tokens_today: Option<TokenTotals>,
+    /// Estimate how many more messages fit in the Claude 5h window after
+    /// each poll.
+    show_messages_left: bool,
+    messages_left: Option<u32>,
+    /// Log a usage-jump event when a window rises by at least this many
+    /// percentage points between two polls; 0 turns detection off.
+    usage_jump_threshold: f64,
+    /// Also show a balloon for usage jumps.
+    notify_usage_jumps: bool,
+    /// Show a balloon when a window resets.
+    notify_usage_resets: bool,
+    /// With `quiet_when_fullscreen`, also skip polls.
+    fullscreen_skips_polls: bool,
+    /// Thresholds checked after each successful poll.
+    alerts: Vec<AlertRule>,
+    /// Alerts stay silent until this unix time.
+    alerts_snoozed_until: Option<u64>,
+    /// Skip polls while the workstation is locked, polling on unlock instead.
+    locked_skips_polls: bool,
     data: Option<AppUsageData>,
-
     poll_interval_ms: u32,
+    /// Derive the poll interval from usage and reset proximity instead of
+    /// `poll_interval_ms`.
+    smart_polling: bool,
     retry_count: u32,
     force_notify_auth_error: bool,
+    /// Polls wait for the credential files to change after an auth error.
     auth_error_paused_polling: bool,
+    /// The usage service was reported unreachable for this run of failures.
+    unreachable_notified: bool,
+    /// The poll timer was stretched to honour a `Retry-After`.
+    retry_after_delayed: bool,
     auth_watch_mode: poller::CredentialWatchMode,
     auth_watch_snapshot: poller::CredentialWatchSnapshot,
     last_poll_ok: bool,
+    last_failure: Option<PollFailure>,
     update_status: UpdateStatus,
     last_update_check_unix: Option<u64>,
+    /// Poll interval applied if the suggestion balloon is clicked.
+    suggested_poll_interval: Option<u32>,
+    /// Hand-edited gateway settings, kept so saving settings does not drop them.
+    api_base_url: Option<String>,
+    api_headers: BTreeMap<String, String>,
+    oauth_token_url: Option<String>,
+    oauth_client_id: Option<String>,
+    credentials_path: Option<PathBuf>,
+    wsl_distro: Option<String>,
+    /// User-requested pause: no polls run and the usage text shows "Paused".
+    polling_paused: bool,
+    /// Skip polls while Windows reports the connection as metered.
+    pause_on_metered: bool,
+    /// A poll was deferred because the connection is metered; the usage text
+    /// shows "Paused" until an unmetered network returns.
+    metered_deferred: bool,
+    /// Poll less often while battery saver is on or the battery is low.
+    poll_less_on_battery: bool,
+    /// Battery percentage at or below which polling slows down even without
+    /// battery saver; 0 only follows battery saver.
+    low_battery_percent: u8,
+    /// `poll_less_on_battery` currently applies.
+    power_saving: bool,
+    /// Our session was switched away from (fast user switching or a dropped
+    /// remote connection); timers and the tray hook are suspended.
+    session_disconnected: bool,
+}
 
-    taskbar_index: usize,
-    tray_offset: i32,
-    dragging: bool,
-    drag_start_mouse_x: i32,
-    drag_start_client_x: i32,
-    drag_start_offset: i32,
+struct HeldNotification {
+    kind: tray_icon::TrayIconKind,
+    title: String,
+    body: String,
+}
 
-    widget_visible: bool,
+/// Why the latest poll failed, shown in the details flyout.
+struct PollFailure {
+    error: poller::PollError,
+    /// How the last request ended, when one was sent.
+    response: Option<String>,
+    credentials: Vec<String>,
+}
+
+/// Where the bars were when a poll moved them, in `bar_percents` order.
+#[derive(Clone, Copy)]
+struct BarAnimation {
+    started: Instant,
+    from: [[f64; 2]; 4],
+}
+
+/// A window that reached its limit, waiting for its reset to be announced.
+#[derive(Clone, Copy)]
+struct LimitReset {
+    provider: Provider,
+    window: UsageWindow,
+    /// Unix seconds.
+    resets_at: u64,
+    /// The "resets soon" notice has been shown.
+    warned: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -103,20 +359,92 @@ enum UpdateStatus {
 }
 
 const RETRY_BASE_MS: u32 = 30_000; // 30 seconds
+/// Consecutive failed polls after which the usage service is reported
+/// unreachable. Retries carry on at the capped backoff.
+const MAX_RETRIES: u32 = 8;
 
+const POLL_30_SEC: u32 = 30_000;
 const POLL_1_MIN: u32 = 60_000;
 const POLL_5_MIN: u32 = 300_000;
 const POLL_15_MIN: u32 = 900_000;
+const POLL_30_MIN: u32 = 1_800_000;
 const POLL_1_HOUR: u32 = 3_600_000;
+/// Bounds of a custom poll interval; faster polling only invites rate limits.
+const MIN_POLL_INTERVAL_MS: u32 = 15_000;
+const MAX_POLL_INTERVAL_MS: u32 = 86_400_000;
+
+/// While saving power, polls are this many times further apart (at most an
+/// hour).
+const POWER_SAVING_POLL_FACTOR: u32 = 4;
+const DEFAULT_LOW_BATTERY_PERCENT: u8 = 20;
+/// Seconds a `--delayed` start waits before creating the widget.
+const DEFAULT_STARTUP_DELAY_SECS: u32 = 15;
+
+/// Percentage points a window may rise between two polls before it is
+/// logged as a usage jump.
+const DEFAULT_USAGE_JUMP_THRESHOLD: f64 = 30.0;
+/// An alert's widget blink: three flashes, each lit and unlit for 400 ms.
+const ALERT_FLASH_STEPS: u32 = 6;
+const ALERT_FLASH_STEP_MS: u32 = 400;
+
+/// Lowest widget opacity in percent, so the widget can't be lost entirely.
+const MIN_OPACITY: u8 = 20;
+
+/// A bar moving to a new value after a poll eases there over this long...
+const BAR_ANIMATION_MS: u32 = 300;
+/// ...redrawn this often, about 60 times a second.
+const BAR_ANIMATION_FRAME_MS: u32 = 16;
+
+/// Manual refreshes within `MANUAL_REFRESH_WINDOW_SECS` that prompt the
+/// one-time suggestion to poll more often.
+const MANUAL_REFRESH_SUGGEST_COUNT: usize = 4;
+const MANUAL_REFRESH_WINDOW_SECS: u64 = 3600;
+
+/// A reminder for a reset within this many seconds of the one it was last
+/// shown for counts as already shown; `resets_at` can drift between polls.
+const WEEKLY_REMINDER_SAME_RESET_SECS: u64 = 3600;
+
+/// How long before a limited window resets the "resets soon" notice is shown.
+const LIMIT_RESET_WARNING_SECS: u64 = 600;
+
+/// Resets listed in the details flyout.
+const RECENT_RESETS: usize = 3;
+
+/// Shown in place of the usage figures while polls fail; clicking the widget
+/// then opens the details flyout with the reason.
+const ERROR_GLYPH: &str = "\u{26A0}";
+
+const DEFAULT_OVERLAY_HOTKEY: &str = "Ctrl+Alt+U";
+const OVERLAY_HOTKEY_ID: i32 = 1;
+/// How long the hotkey overlay stays on screen.
+const OVERLAY_DURATION: Duration = Duration::from_secs(5);
 
 // Menu item IDs for update frequency
 const IDM_FREQ_1MIN: u16 = 10;
 const IDM_FREQ_5MIN: u16 = 11;
 const IDM_FREQ_15MIN: u16 = 12;
 const IDM_FREQ_1HOUR: u16 = 13;
+const IDM_FREQ_SMART: u16 = 14;
+const IDM_FREQ_30SEC: u16 = 15;
+const IDM_FREQ_30MIN: u16 = 16;
+const IDM_FREQ_CUSTOM: u16 = 17;
 const IDM_START_WITH_WINDOWS: u16 = 20;
+const IDM_SHOW_BURN_RATE: u16 = 21;
+const IDM_PAUSE_ON_METERED: u16 = 22;
+const IDM_API_KEY_LIMITS: u16 = 23;
+const IDM_CARD_BACKGROUND: u16 = 24;
+const IDM_SHOW_COST: u16 = 25;
+const IDM_SHOW_TOKEN_COUNTS: u16 = 26;
+const IDM_NOTIFY_USAGE_JUMPS: u16 = 27;
+const IDM_WEEKLY_FIRST: u16 = 28;
+const IDM_QUIET_WHEN_FULLSCREEN: u16 = 29;
 const IDM_RESET_POSITION: u16 = 30;
 const IDM_VERSION_ACTION: u16 = 31;
+const IDM_EXPORT_HISTORY: u16 = 32;
+const IDM_COPY_SNAPSHOT: u16 = 33;
+const IDM_SAVE_SNAPSHOT: u16 = 34;
+const IDM_REEMBED: u16 = 35;
+const IDM_RESTART: u16 = 36;
 const IDM_LANG_SYSTEM: u16 = 40;
 const IDM_LANG_ENGLISH: u16 = 41;
 const IDM_LANG_DUTCH: u16 = 42;
@@ -131,11 +459,70 @@ const IDM_LANG_PORTUGUESE_BRAZIL: u16 = 50;
 const IDM_MODEL_CLAUDE_CODE: u16 = 60;
 const IDM_MODEL_CODEX: u16 = 61;
 const IDM_MODEL_ANTIGRAVITY: u16 = 62;
+const IDM_CLICK_SHOW_DETAILS: u16 = 63;
+const IDM_CLICK_REFRESH: u16 = 64;
+const IDM_CLICK_OPEN_USAGE_PAGE: u16 = 65;
+const IDM_CLICK_NOTHING: u16 = 66;
+const IDM_PALETTE_STANDARD: u16 = 100;
+const IDM_PALETTE_DEUTERANOPIA: u16 = 101;
+const IDM_PALETTE_PROTANOPIA: u16 = 102;
+const IDM_REMINDER_OFF: u16 = 110;
+const IDM_REMINDER_6H: u16 = 111;
+const IDM_REMINDER_12H: u16 = 112;
+const IDM_REMINDER_24H: u16 = 113;
+const IDM_POLL_LESS_ON_BATTERY: u16 = 120;
+const IDM_BLEND_WITH_TASKBAR: u16 = 121;
+const IDM_SHOW_DIVIDER: u16 = 122;
+const IDM_ONE_DECIMAL: u16 = 123;
+const IDM_ELAPSED_SESSION: u16 = 124;
+const IDM_ELAPSED_WEEKLY: u16 = 125;
+const IDM_MIRROR_LAYOUT: u16 = 126;
+const IDM_SHOW_SESSION_ROW: u16 = 127;
+const IDM_SHOW_WEEKLY_ROW: u16 = 128;
+const IDM_URGENT_ROW_FIRST: u16 = 129;
+const IDM_BADGE_MODE: u16 = 130;
+const IDM_SNOOZE_ALERTS_HOUR: u16 = 131;
+const IDM_SNOOZE_ALERTS_RESET: u16 = 132;
+const IDM_RESUME_ALERTS: u16 = 133;
+const IDM_NOTIFY_LIMIT_RESETS: u16 = 134;
+const IDM_SHOW_MESSAGES_LEFT: u16 = 135;
+const IDM_NOTIFY_USAGE_RESETS: u16 = 136;
+const IDM_START_WITH_WINDOWS_TASK: u16 = 137;
+const IDM_OPEN_USAGE_PAGE: u16 = 138;
+const IDM_EMPHASIZE_WARNINGS: u16 = 139;
+const IDM_OPACITY_100: u16 = 140;
+const IDM_OPACITY_85: u16 = 141;
+const IDM_OPACITY_70: u16 = 142;
+const IDM_OPACITY_50: u16 = 143;
+const IDM_FLOAT_WIDGET: u16 = 144;
+const IDM_PAUSE_POLLING: u16 = 80;
+// Developer-only items, shown when Shift is held while opening the menu or
+// "debug_menu" is set in settings.json
+const IDM_SIMULATE_RESET: u16 = 90;
+const IDM_SIMULATE_NEAR_LIMIT: u16 = 91;
+const IDM_SIMULATE_LIMIT_HIT: u16 = 92;
+const IDM_SIMULATE_POLL_FAILURE: u16 = 93;
+const IDM_STOP_SIMULATION: u16 = 94;
 
 const WM_DPICHANGED_MSG: u32 = 0x02E0;
 const WM_APP_UPDATE_CHECK_COMPLETE: u32 = WM_APP + 2;
+const WM_APP_OFFER_BACKFILL: u32 = WM_APP + 4;
 const TRAY_ICON_UPDATE_REPOSITION_SUPPRESS_MS: u64 = 750;
 
+/// How often a metered-deferred poll re-checks the connection cost.
+const METERED_CHECK_MS: u32 = 60_000;
+
+/// How often a deferred render re-checks whether the taskbar is visible again.
+const DEFERRED_RENDER_CHECK_MS: u32 = 1_000;
+
+/// How long the taskbar must stay still before the widget follows it, so a
+/// burst of tray icon changes moves the widget once.
+const REPOSITION_DELAY_MS: u32 = 150;
+
+/// How often a reminder held back by a full-screen app is retried, and how
+/// often held notifications check whether Do Not Disturb has ended.
+const QUIET_RETRY_MS: u32 = 60_000;
+
 /// How often the watchdog thread polls for an explorer.exe restart (which
 /// recreates the taskbar and wipes our tray-icon registration).
 const TASKBAR_WATCH_INTERVAL_SECS: u64 = 2;
@@ -145,10 +532,30 @@ static SUPPRESS_TRAY_REPOSITION_UNTIL: Mutex<Option<Instant>> = Mutex::new(None)
 /// Current system DPI (96 = 100% scaling, 144 = 150%, 192 = 200%, etc.)
 static CURRENT_DPI: AtomicU32 = AtomicU32::new(96);
 
-/// Scale a base pixel value (designed at 96 DPI) to the current DPI.
+/// How much the widget is shrunk to fit a short taskbar, in thousandths.
+static TASKBAR_FIT: AtomicU32 = AtomicU32::new(1000);
+
+/// Taskbar height (at 96 DPI) the widget's rows fit in unscaled: Windows 10's
+/// default. Shorter taskbars, such as ones with small icons, shrink it.
+const FULL_SIZE_TASKBAR_HEIGHT: i32 = 40;
+/// Never shrink further than this, where the text stops being legible.
+const MIN_TASKBAR_FIT: u32 = 600;
+
+/// Scale a base pixel value (designed at 96 DPI) to the current DPI and
+/// taskbar height.
 fn sc(px: i32) -> i32 {
     let dpi = CURRENT_DPI.load(Ordering::Relaxed);
-    (px as f64 * dpi as f64 / 96.0).round() as i32
+    let fit = TASKBAR_FIT.load(Ordering::Relaxed);
+    (px as f64 * dpi as f64 / 96.0 * fit as f64 / 1000.0).round() as i32
+}
+
+/// Shrink the widget, rows, spacing and font alike to fit a taskbar
+/// `taskbar_height` pixels tall. Returns true when the scale changed.
+fn fit_to_taskbar(taskbar_height: i32) -> bool {
+    let dpi = CURRENT_DPI.load(Ordering::Relaxed);
+    let full = FULL_SIZE_TASKBAR_HEIGHT as f64 * dpi as f64 / 96.0;
+    let fit = ((taskbar_height as f64 / full * 1000.0) as u32).clamp(MIN_TASKBAR_FIT, 1000);
+    TASKBAR_FIT.swap(fit, Ordering::Relaxed) != fit
 }
 
 /// Re-query the monitor DPI for our window and update the cached value.
@@ -157,7 +564,7 @@ fn sc(px: i32) -> i32 {
 fn refresh_dpi() {
     let hwnd = {
         let state = lock_state();
-        state.as_ref().map(|s| s.hwnd.to_hwnd())
+        state.as_ref().map(|s| s.handles.hwnd.to_hwnd())
     };
     if let Some(hwnd) = hwnd {
         let dpi = unsafe { GetDpiForWindow(hwnd) };
@@ -177,6 +584,86 @@ const ENV_RELAUNCH: &str = "CCUM_RELAUNCH";
 /// Unix timestamp (seconds) of the relaunch that spawned this process, passed to
 /// the child so it can detect a relaunch storm.
 const ENV_LAST_RELAUNCH_UNIX: &str = "CCUM_LAST_RELAUNCH_UNIX";
+/// Set on an instance started by the panic hook, so it can say why.
+const ENV_CRASH_RESTART: &str = "CCUM_CRASH_RESTART";
+/// Set to anything but `0` to keep the widget out of the taskbar, like
+/// `NO_EMBED_ARG`.
+const ENV_NO_EMBED: &str = "CCUM_NO_EMBED";
+/// Float the widget over the taskbar instead of embedding it, for taskbar
+/// mods that break on a foreign child window.
+const NO_EMBED_ARG: &str = "--no-embed";
+
+/// The main window, for the panic hook, which must not wait on `STATE`.
+static MAIN_HWND: AtomicIsize = AtomicIsize::new(0);
+/// Mirrors the `restart_after_crash` setting for the panic hook.
+static RESTART_AFTER_CRASH: AtomicBool = AtomicBool::new(true);
+/// Mirrors the `tray_anchor_classes` setting, read while positioning.
+static TRAY_ANCHOR_CLASSES: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Record panics in crash.log (next to settings.json) and the diagnostic
+/// log, then start a fresh instance so a panic on any thread does not leave
+/// the process dead or lingering without a window. With `restart_after_crash`
+/// off, or during a relaunch storm, a balloon tells the user instead.
+pub fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        let message = format!("panic: {info}");
+        diagnose::log(&message);
+        record_crash(&message);
+
+        let hwnd = HWND(MAIN_HWND.load(Ordering::SeqCst) as *mut _);
+        if hwnd.is_invalid() {
+            return;
+        }
+        // The panicking thread may hold the state lock, so never wait on it.
+        let (strings, kind) = match STATE.try_lock().as_deref() {
+            Ok(Some(s)) => (s.ui.language.strings(), primary_tray_kind(s)),
+            _ => (
+                LanguageId::English.strings(),
+                tray_icon::TrayIconKind::Claude,
+            ),
+        };
+
+        let now = now_unix_secs();
+        let last = std::env::var(ENV_LAST_RELAUNCH_UNIX)
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(0);
+        let storm = last != 0 && now.saturating_sub(last) < RELAUNCH_THROTTLE_SECS;
+        if RESTART_AFTER_CRASH.load(Ordering::SeqCst) && !storm {
+            if let Ok(exe) = std::env::current_exe() {
+                let args: Vec<String> = std::env::args().skip(1).collect();
+                let spawned = std::process::Command::new(exe)
+                    .args(&args)
+                    .env(ENV_RELAUNCH, "1")
+                    .env(ENV_LAST_RELAUNCH_UNIX, now.to_string())
+                    .env(ENV_CRASH_RESTART, "1")
+                    .spawn();
+                if spawned.is_ok() {
+                    tray_icon::remove_all(hwnd);
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        tray_icon::notify_balloon(hwnd, kind, strings.crash_title, strings.crash_body);
+        // Do not linger without a working UI thread or poll worker.
+        std::process::exit(1);
+    }));
+}
+
+fn record_crash(message: &str) {
+    let path = paths::data_dir().join("crash.log");
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Ok(mut file) = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+    {
+        let _ = writeln!(file, "[{}] {message}", now_unix_secs());
+    }
+}
 
 /// Relaunch the widget as a fresh process after explorer.exe has restarted.
 ///
@@ -200,31 +687,76 @@ fn relaunch_self() {
         std::thread::sleep(Duration::from_secs(RELAUNCH_BACKOFF_SECS));
     }
 
+    spawn_replacement(Some(now));
+}
+
+/// Start a fresh copy of the exe with the same arguments and exit this one.
+/// `last_relaunch_unix` is passed on for relaunch storm detection; a restart
+/// the user asked for leaves it unset so it is never throttled.
+fn spawn_replacement(last_relaunch_unix: Option<u64>) {
     let exe = match std::env::current_exe() {
         Ok(exe) => exe,
         Err(error) => {
-            diagnose::log_error("watchdog: unable to resolve current executable", error);
+            diagnose::log_error("relaunch: unable to resolve current executable", error);
             return;
         }
     };
 
+    // Let an in-flight poll finish (or cancel it) before the new instance
+    // starts writing the same settings and history files.
+    poll_worker::shutdown();
+
     let args: Vec<String> = std::env::args().skip(1).collect();
-    match std::process::Command::new(exe)
-        .args(&args)
-        .env(ENV_RELAUNCH, "1")
-        .env(ENV_LAST_RELAUNCH_UNIX, now.to_string())
-        .spawn()
-    {
+    let mut command = std::process::Command::new(exe);
+    command.args(&args).env(ENV_RELAUNCH, "1");
+    match last_relaunch_unix {
+        Some(now) => command.env(ENV_LAST_RELAUNCH_UNIX, now.to_string()),
+        None => command.env_remove(ENV_LAST_RELAUNCH_UNIX),
+    };
+    match command.spawn() {
         Ok(_) => {
-            diagnose::log("watchdog: relaunched fresh instance, exiting old one");
+            diagnose::log("relaunch: started fresh instance, exiting old one");
             std::process::exit(0);
         }
         Err(error) => {
-            diagnose::log_error("watchdog: unable to spawn relaunched instance", error);
+            diagnose::log_error("relaunch: unable to spawn replacement instance", error);
         }
     }
 }
 
+/// "Restart Widget": replace this process with a fresh one.
+fn restart_widget(hwnd: HWND) {
+    diagnose::log("restart requested from the menu");
+    save_state_settings();
+    // The old process exits without WM_DESTROY, so take its tray icons with
+    // it rather than leaving them behind until hovered.
+    tray_icon::remove_all(hwnd);
+    spawn_replacement(None);
+    // Spawning failed; put the icons back and carry on polling.
+    sync_tray_icons(hwnd);
+    let send_hwnd = SendHwnd::from_hwnd(hwnd);
+    poll_worker::start(move |ticket| do_poll(send_hwnd, ticket));
+}
+
+/// "Re-embed in Taskbar": run the embedding and positioning again, e.g. after
+/// the shell moved or detached the widget.
+fn reembed_in_taskbar(hwnd: HWND) {
+    let taskbar_index = {
+        let state = lock_state();
+        match state.as_ref() {
+            Some(s) => s.ui.taskbar_index,
+            None => return,
+        }
+    };
+    let attached = attach_to_taskbar(hwnd, taskbar_index);
+    diagnose::log(format!(
+        "re-embed requested from the menu: attached={attached}"
+    ));
+    position_at_taskbar();
+    sync_tray_icons(hwnd);
+    render_layered();
+}
+
 /// Detect explorer.exe restarts and recover from them.
 ///
 /// Once explorer destroys the taskbar, our embedded child window is destroyed
@@ -234,9 +766,13 @@ fn relaunch_self() {
 fn spawn_taskbar_watchdog() {
     std::thread::spawn(move || loop {
         std::thread::sleep(Duration::from_secs(TASKBAR_WATCH_INTERVAL_SECS));
+        // A disconnected session is suspended; its taskbar is left alone.
         let stored = {
             let state = lock_state();
-            state.as_ref().and_then(|s| s.taskbar_hwnd)
+            state
+                .as_ref()
+                .filter(|s| !s.poll.session_disconnected)
+                .and_then(|s| s.handles.taskbar_hwnd)
         };
         // Only relevant once we have embedded into a taskbar at least once.
         let Some(old) = stored else {
@@ -280,7 +816,7 @@ fn load_embedded_app_icons() -> (HICON, HICON) {
     }
 }
 
-unsafe impl Send for AppState {}
+unsafe impl Send for WindowHandles {}
 
 static STATE: Mutex<Option<AppState>> = Mutex::new(None);
 
@@ -289,11 +825,84 @@ fn lock_state() -> MutexGuard<'static, Option<AppState>> {
     STATE.lock().unwrap_or_else(|e| e.into_inner())
 }
 
+/// What the widget shows. Scrolling over the widget steps through these in
+/// order.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum DisplayMode {
+    #[default]
+    BothRows,
+    SessionOnly,
+    WeeklyOnly,
+    /// Both rows as text, without bars.
+    TextOnly,
+}
+
+impl DisplayMode {
+    const ALL: [DisplayMode; 4] = [
+        DisplayMode::BothRows,
+        DisplayMode::SessionOnly,
+        DisplayMode::WeeklyOnly,
+        DisplayMode::TextOnly,
+    ];
+
+    fn cycled(self, step: i32) -> DisplayMode {
+        let index = Self::ALL.iter().position(|mode| *mode == self).unwrap_or(0);
+        Self::ALL[(index as i32 + step).rem_euclid(Self::ALL.len() as i32) as usize]
+    }
+
+    fn shows(self, window: UsageWindow) -> bool {
+        !matches!(
+            (self, window),
+            (DisplayMode::SessionOnly, UsageWindow::Weekly)
+                | (DisplayMode::WeeklyOnly, UsageWindow::Session)
+        )
+    }
+
+    /// The mode with `window`'s row shown or hidden. The last row shown
+    /// cannot be hidden, and hiding a row brings the bars back.
+    fn with_row(self, window: UsageWindow, visible: bool) -> DisplayMode {
+        match (window, visible) {
+            _ if self.shows(window) == visible => self,
+            (_, true) => DisplayMode::BothRows,
+            (UsageWindow::Session, false) if self.shows(UsageWindow::Weekly) => {
+                DisplayMode::WeeklyOnly
+            }
+            (UsageWindow::Weekly, false) if self.shows(UsageWindow::Session) => {
+                DisplayMode::SessionOnly
+            }
+            _ => self,
+        }
+    }
+
+    fn row_count(self) -> usize {
+        UsageWindow::ALL
+            .iter()
+            .filter(|window| self.shows(**window))
+            .count()
+    }
+
+    fn text_only(self) -> bool {
+        self == DisplayMode::TextOnly
+    }
+}
+
+const USAGE_PAGE_URL: &str = "https://claude.ai/settings/usage";
+const README_URL: &str = "https://github.com/CodeZeno/Claude-Code-Usage-Monitor#readme";
+
+/// What a left click on the widget does.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ClickAction {
+    #[default]
+    ShowDetails,
+    Refresh,
+    OpenUsagePage,
+    Nothing,
+}
+
 fn settings_path() -> PathBuf {
-    let appdata = std::env::var("APPDATA").unwrap_or_else(|_| ".".to_string());
-    PathBuf::from(appdata)
-        .join("ClaudeCodeUsageMonitor")
-        .join("settings.json")
+    paths::data_dir().join("settings.json")
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -304,6 +913,8 @@ struct SettingsFile {
     taskbar_index: usize,
     #[serde(default = "default_poll_interval")]
     poll_interval_ms: u32,
+    #[serde(default)]
+    smart_polling: bool,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     language: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -316,6 +927,184 @@ struct SettingsFile {
     show_codex: bool,
     #[serde(default = "default_show_antigravity")]
     show_antigravity: bool,
+    #[serde(default)]
+    show_burn_rate: bool,
+    #[serde(default)]
+    pause_on_metered: bool,
+    #[serde(default = "default_poll_less_on_battery")]
+    poll_less_on_battery: bool,
+    /// Only set by editing settings.json.
+    #[serde(default = "default_low_battery_percent")]
+    low_battery_percent: u8,
+    /// Only set by editing settings.json.
+    #[serde(default = "default_restart_after_crash")]
+    restart_after_crash: bool,
+    /// Only set by editing settings.json.
+    #[serde(default = "default_startup_delay_secs")]
+    startup_delay_secs: u32,
+    #[serde(default)]
+    api_key_limits: bool,
+    #[serde(default)]
+    card_background: bool,
+    #[serde(default)]
+    float_widget: bool,
+    #[serde(default = "default_opacity")]
+    opacity: u8,
+    #[serde(default)]
+    blend_with_taskbar: bool,
+    /// Only set by editing settings.json.
+    #[serde(default)]
+    click_through_background: bool,
+    #[serde(default)]
+    palette: Palette,
+    #[serde(default = "default_row_order")]
+    row_order: Vec<UsageWindow>,
+    /// Only set by editing settings.json.
+    #[serde(default)]
+    row_labels: BTreeMap<UsageWindow, String>,
+    #[serde(default)]
+    urgent_row_first: bool,
+    #[serde(default)]
+    badge_mode: bool,
+    #[serde(default = "default_show_divider")]
+    show_divider: bool,
+    #[serde(default)]
+    emphasize_warnings: bool,
+    /// Only set by editing settings.json.
+    #[serde(default = "default_emphasis_percent")]
+    emphasis_percent: f64,
+    #[serde(default)]
+    percent_decimals: usize,
+    #[serde(default)]
+    elapsed_bars: Vec<UsageWindow>,
+    #[serde(default)]
+    mirror_layout: bool,
+    /// Only set by editing settings.json.
+    #[serde(default)]
+    bars_right_to_left: bool,
+    /// Only set by editing settings.json.
+    #[serde(default)]
+    bar_style: BarStyle,
+    /// Only set by editing settings.json.
+    #[serde(default)]
+    reduce_motion: bool,
+    /// Only set by editing settings.json.
+    #[serde(default)]
+    compact_single_row: bool,
+    /// Only set by editing settings.json.
+    #[serde(default)]
+    layout_margins: LayoutMargins,
+    #[serde(default)]
+    display_mode: DisplayMode,
+    #[serde(default)]
+    left_click: ClickAction,
+    #[serde(default)]
+    show_cost: bool,
+    #[serde(default)]
+    show_token_counts: bool,
+    #[serde(default)]
+    show_messages_left: bool,
+    /// Only set by editing settings.json.
+    #[serde(default)]
+    exact_token_counts: bool,
+    /// Only set by editing settings.json.
+    #[serde(default = "default_usage_jump_threshold")]
+    usage_jump_threshold: f64,
+    #[serde(default)]
+    notify_usage_jumps: bool,
+    #[serde(default)]
+    notify_limit_resets: bool,
+    #[serde(default)]
+    notify_usage_resets: bool,
+    #[serde(default)]
+    weekly_reminder_hours: u32,
+    #[serde(default)]
+    quiet_when_fullscreen: bool,
+    /// Only set by editing settings.json.
+    #[serde(default)]
+    fullscreen_skips_polls: bool,
+    /// Only set by editing settings.json.
+    #[serde(
+        default,
+        deserialize_with = "alerts::deserialize_rules",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    alerts: Vec<AlertRule>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    alerts_snoozed_until: Option<u64>,
+    /// Only set by editing settings.json.
+    #[serde(default)]
+    locked_skips_polls: bool,
+    #[serde(default)]
+    weekly_reminder_sent_for: Option<u64>,
+    /// Only set by editing settings.json.
+    #[serde(default = "default_overlay_hotkey")]
+    overlay_hotkey: String,
+    /// Only set by editing settings.json.
+    #[serde(default)]
+    tray_anchor_classes: Vec<String>,
+    #[serde(default)]
+    history_backfill_offered: bool,
+    /// Usage samples still to be estimated from imported transcripts.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    history_estimate_pending: bool,
+    #[serde(default)]
+    interval_suggestion_offered: bool,
+    #[serde(default)]
+    welcome_offered: bool,
+    /// Claude API base URL for users routing through a gateway or proxy.
+    /// Only set by editing settings.json.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    api_base_url: Option<String>,
+    /// Extra headers sent with every Claude usage request.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    api_headers: BTreeMap<String, String>,
+    /// OAuth token endpoint and client id for gateways that issue their own
+    /// tokens. Only set by editing settings.json.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    oauth_token_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    oauth_client_id: Option<String>,
+    /// Claude `.credentials.json` to read instead of the one in
+    /// `CLAUDE_CONFIG_DIR` or `~/.claude`. Only set by editing settings.json.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    credentials_path: Option<PathBuf>,
+    /// WSL distro to read Claude credentials from instead of looking in
+    /// Windows and every distro. Only set by editing settings.json.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    wsl_distro: Option<String>,
+    /// Only set by editing settings.json.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secondary_account: Option<SecondaryAccount>,
+    /// Only set by editing settings.json.
+    #[serde(default)]
+    otel_port: u16,
+    /// Only set by editing settings.json.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mqtt: Option<MqttSettings>,
+    /// Only set by editing settings.json.
+    #[serde(default)]
+    websocket_port: u16,
+    /// Only set by editing settings.json.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    websocket_origins: Vec<String>,
+    /// Only set by editing settings.json.
+    #[serde(default)]
+    shared_memory: bool,
+    /// Only set by editing settings.json.
+    #[serde(default)]
+    debug_menu: bool,
+}
+
+/// A second Claude account, such as a team organisation next to a personal
+/// plan, polled alongside the default one.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct SecondaryAccount {
+    /// Names the account's section in the details flyout.
+    label: String,
+    /// A `.credentials.json` written by Claude Code signed in to the account,
+    /// e.g. with `CLAUDE_CONFIG_DIR` pointing elsewhere.
+    credentials_path: PathBuf,
 }
 
 impl Default for SettingsFile {
@@ -324,12 +1113,77 @@ impl Default for SettingsFile {
             tray_offset: 0,
             taskbar_index: 0,
             poll_interval_ms: default_poll_interval(),
+            smart_polling: false,
             language: None,
             last_update_check_unix: None,
             widget_visible: true,
             show_claude_code: true,
             show_codex: false,
             show_antigravity: false,
+            show_burn_rate: false,
+            pause_on_metered: false,
+            poll_less_on_battery: default_poll_less_on_battery(),
+            low_battery_percent: default_low_battery_percent(),
+            restart_after_crash: default_restart_after_crash(),
+            startup_delay_secs: default_startup_delay_secs(),
+            api_key_limits: false,
+            card_background: false,
+            float_widget: false,
+            opacity: default_opacity(),
+            blend_with_taskbar: false,
+            click_through_background: false,
+            palette: Palette::default(),
+            row_order: default_row_order(),
+            row_labels: BTreeMap::new(),
+            urgent_row_first: false,
+            badge_mode: false,
+            show_divider: default_show_divider(),
+            emphasize_warnings: false,
+            emphasis_percent: default_emphasis_percent(),
+            percent_decimals: 0,
+            elapsed_bars: Vec::new(),
+            mirror_layout: false,
+            bars_right_to_left: false,
+            bar_style: BarStyle::default(),
+            reduce_motion: false,
+            compact_single_row: false,
+            layout_margins: LayoutMargins::default(),
+            display_mode: DisplayMode::default(),
+            left_click: ClickAction::default(),
+            show_cost: false,
+            show_token_counts: false,
+            show_messages_left: false,
+            exact_token_counts: false,
+            usage_jump_threshold: default_usage_jump_threshold(),
+            notify_usage_jumps: false,
+            notify_limit_resets: false,
+            notify_usage_resets: false,
+            weekly_reminder_hours: 0,
+            quiet_when_fullscreen: false,
+            fullscreen_skips_polls: false,
+            alerts: Vec::new(),
+            alerts_snoozed_until: None,
+            locked_skips_polls: false,
+            weekly_reminder_sent_for: None,
+            overlay_hotkey: default_overlay_hotkey(),
+            tray_anchor_classes: Vec::new(),
+            history_backfill_offered: false,
+            history_estimate_pending: false,
+            interval_suggestion_offered: false,
+            welcome_offered: false,
+            api_base_url: None,
+            api_headers: BTreeMap::new(),
+            oauth_token_url: None,
+            oauth_client_id: None,
+            credentials_path: None,
+            wsl_distro: None,
+            secondary_account: None,
+            otel_port: 0,
+            mqtt: None,
+            websocket_port: 0,
+            websocket_origins: Vec::new(),
+            shared_memory: false,
+            debug_menu: false,
         }
     }
 }
@@ -338,6 +1192,46 @@ fn default_poll_interval() -> u32 {
     POLL_15_MIN
 }
 
+fn default_row_order() -> Vec<UsageWindow> {
+    UsageWindow::ALL.to_vec()
+}
+
+fn default_usage_jump_threshold() -> f64 {
+    DEFAULT_USAGE_JUMP_THRESHOLD
+}
+
+fn default_overlay_hotkey() -> String {
+    DEFAULT_OVERLAY_HOTKEY.to_string()
+}
+
+fn default_poll_less_on_battery() -> bool {
+    true
+}
+
+fn default_low_battery_percent() -> u8 {
+    DEFAULT_LOW_BATTERY_PERCENT
+}
+
+fn default_opacity() -> u8 {
+    100
+}
+
+fn default_show_divider() -> bool {
+    true
+}
+
+fn default_emphasis_percent() -> f64 {
+    WARNING_PERCENT
+}
+
+fn default_restart_after_crash() -> bool {
+    true
+}
+
+fn default_startup_delay_secs() -> u32 {
+    DEFAULT_STARTUP_DELAY_SECS
+}
+
 fn default_widget_visible() -> bool {
     true
 }
@@ -366,6 +1260,103 @@ fn load_settings() -> SettingsFile {
     settings
 }
 
+/// Point the poller at the configured endpoint, headers and OAuth client.
+fn apply_api_settings(settings: &SettingsFile) {
+    poller::set_api_key_mode(settings.api_key_limits);
+    // `--mock-server` installs its override first and takes precedence.
+    if let Some(base_url) = settings
+        .api_base_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+    {
+        diagnose::log(format!("using configured Claude API base URL {base_url}"));
+        poller::set_api_override(base_url.trim_end_matches('/').to_string(), None);
+    }
+    poller::set_extra_headers(
+        settings
+            .api_headers
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect(),
+    );
+    match (
+        settings.oauth_token_url.as_deref().map(str::trim),
+        settings.oauth_client_id.as_deref().map(str::trim),
+    ) {
+        (Some(token_url), Some(client_id))
+            if token_url.starts_with("https://") && !client_id.is_empty() =>
+        {
+            diagnose::log(format!("using configured OAuth token endpoint {token_url}"));
+            poller::set_oauth_client(token_url.to_string(), client_id.to_string());
+        }
+        (None, None) => {}
+        // A half-set or malformed client would fail every refresh and keep
+        // the Claude CLI from refreshing instead.
+        _ => diagnose::log(
+            "ignoring oauth_token_url and oauth_client_id: both are needed, and the URL must use https://",
+        ),
+    }
+    if let Some(path) = settings
+        .credentials_path
+        .as_ref()
+        .filter(|path| !path.as_os_str().is_empty())
+    {
+        diagnose::log(format!(
+            "using configured credentials file {}",
+            path.display()
+        ));
+        poller::set_credentials_path(path.clone());
+    }
+    if let Some(distro) = settings
+        .wsl_distro
+        .as_deref()
+        .map(str::trim)
+        .filter(|distro| !distro.is_empty())
+    {
+        diagnose::log(format!(
+            "reading Claude credentials from WSL distro {distro}"
+        ));
+        poller::set_wsl_distro(distro.to_string());
+    }
+}
+
+/// Console modes: `--debug-headers` and `--render-golden <dir>`.
+pub fn handle_cli_mode(args: &[String]) -> Option<i32> {
+    if args.iter().skip(1).any(|arg| arg == "--debug-headers") {
+        native_interop::attach_parent_console();
+        return Some(print_debug_headers());
+    }
+    if let Some(index) = args.iter().position(|arg| arg == "--render-golden") {
+        native_interop::attach_parent_console();
+        let Some(dir) = args.get(index + 1).filter(|arg| !arg.starts_with("--")) else {
+            eprintln!("--render-golden needs an output folder");
+            return Some(1);
+        };
+        return Some(render_golden_images(&PathBuf::from(dir)));
+    }
+    None
+}
+
+/// Print the HTTP status and rate-limit headers of one probe request made with
+/// the widget's credentials and API settings, to see what the widget parsed
+/// when it shows 0%. Returns 1 if the request fails.
+fn print_debug_headers() -> i32 {
+    apply_api_settings(&load_settings());
+    match poller::probe_rate_limit_headers() {
+        Ok(lines) => {
+            for line in lines {
+                println!("{line}");
+            }
+            0
+        }
+        Err(error) => {
+            eprintln!("{error}");
+            1
+        }
+    }
+}
+
 fn save_settings(settings: &SettingsFile) {
     let path = settings_path();
     if let Some(parent) = path.parent() {
@@ -380,17 +1371,83 @@ fn save_state_settings() {
     let state = lock_state();
     if let Some(s) = state.as_ref() {
         save_settings(&SettingsFile {
-            tray_offset: s.tray_offset,
-            taskbar_index: s.taskbar_index,
-            poll_interval_ms: s.poll_interval_ms,
+            tray_offset: s.ui.tray_offset,
+            taskbar_index: s.ui.taskbar_index,
+            poll_interval_ms: s.poll.poll_interval_ms,
+            smart_polling: s.poll.smart_polling,
             language: s
+                .ui
                 .language_override
                 .map(|language| language.code().to_string()),
-            last_update_check_unix: s.last_update_check_unix,
-            widget_visible: s.widget_visible,
-            show_claude_code: s.show_claude_code,
-            show_codex: s.show_codex,
-            show_antigravity: s.show_antigravity,
+            last_update_check_unix: s.poll.last_update_check_unix,
+            widget_visible: s.ui.widget_visible,
+            show_claude_code: s.ui.show_claude_code,
+            show_codex: s.ui.show_codex,
+            show_antigravity: s.ui.show_antigravity,
+            show_burn_rate: s.ui.show_burn_rate,
+            pause_on_metered: s.poll.pause_on_metered,
+            poll_less_on_battery: s.poll.poll_less_on_battery,
+            low_battery_percent: s.poll.low_battery_percent,
+            restart_after_crash: s.ui.restart_after_crash,
+            startup_delay_secs: s.ui.startup_delay_secs,
+            api_key_limits: s.ui.api_key_limits,
+            card_background: s.ui.card_background,
+            float_widget: s.ui.float_widget,
+            opacity: s.ui.opacity,
+            blend_with_taskbar: s.ui.blend_with_taskbar,
+            click_through_background: s.ui.click_through_background,
+            palette: s.ui.palette,
+            row_order: s.ui.row_order.clone(),
+            row_labels: s.ui.row_labels.clone(),
+            urgent_row_first: s.ui.urgent_row_first,
+            badge_mode: s.ui.badge_mode,
+            show_divider: s.ui.show_divider,
+            emphasize_warnings: s.ui.emphasize_warnings,
+            emphasis_percent: s.ui.emphasis_percent,
+            percent_decimals: s.ui.percent_decimals,
+            elapsed_bars: s.ui.elapsed_bars.clone(),
+            mirror_layout: s.ui.mirror_layout,
+            bars_right_to_left: s.ui.bars_right_to_left,
+            bar_style: s.ui.bar_style,
+            reduce_motion: s.ui.reduce_motion,
+            compact_single_row: s.ui.compact_single_row,
+            layout_margins: s.ui.layout_margins,
+            display_mode: s.ui.display_mode,
+            left_click: s.ui.left_click,
+            show_cost: s.poll.show_cost,
+            show_token_counts: s.poll.show_token_counts,
+            show_messages_left: s.poll.show_messages_left,
+            exact_token_counts: s.ui.exact_token_counts,
+            usage_jump_threshold: s.poll.usage_jump_threshold,
+            notify_usage_jumps: s.poll.notify_usage_jumps,
+            notify_limit_resets: s.ui.notify_limit_resets,
+            notify_usage_resets: s.poll.notify_usage_resets,
+            weekly_reminder_hours: s.ui.weekly_reminder_hours,
+            quiet_when_fullscreen: s.ui.quiet_when_fullscreen,
+            fullscreen_skips_polls: s.poll.fullscreen_skips_polls,
+            alerts: s.poll.alerts.clone(),
+            alerts_snoozed_until: s.poll.alerts_snoozed_until,
+            locked_skips_polls: s.poll.locked_skips_polls,
+            weekly_reminder_sent_for: s.ui.weekly_reminder_sent_for,
+            overlay_hotkey: s.ui.overlay_hotkey.clone(),
+            tray_anchor_classes: s.ui.tray_anchor_classes.clone(),
+            history_backfill_offered: s.ui.history_backfill_offered,
+            history_estimate_pending: history::estimate_pending(),
+            interval_suggestion_offered: s.ui.interval_suggestion_offered,
+            welcome_offered: s.ui.welcome_offered,
+            api_base_url: s.poll.api_base_url.clone(),
+            api_headers: s.poll.api_headers.clone(),
+            oauth_token_url: s.poll.oauth_token_url.clone(),
+            oauth_client_id: s.poll.oauth_client_id.clone(),
+            credentials_path: s.poll.credentials_path.clone(),
+            wsl_distro: s.poll.wsl_distro.clone(),
+            secondary_account: s.poll.secondary_account.clone(),
+            otel_port: s.poll.otel_port,
+            mqtt: s.poll.mqtt.clone(),
+            websocket_port: s.poll.websocket_port,
+            websocket_origins: s.poll.websocket_origins.clone(),
+            shared_memory: s.poll.shared_memory,
+            debug_menu: s.poll.debug_menu,
         });
     }
 }
@@ -398,67 +1455,102 @@ fn save_state_settings() {
 fn tray_icon_data_from_state() -> Vec<tray_icon::TrayIconData> {
     let state = lock_state();
     match state.as_ref() {
-        Some(s) if s.last_poll_ok => {
+        Some(s) if s.poll.last_poll_ok => {
             let mut icons = Vec::new();
-            if s.show_claude_code {
-                icons.push(tray_icon::TrayIconData {
-                    kind: tray_icon::TrayIconKind::Claude,
-                    percent: Some(s.session_percent),
-                    tooltip: format!(
+            if s.ui.show_claude_code {
+                let model = s.ui.language.strings().claude_code_model;
+                let plan = s
+                    .poll
+                    .data
+                    .as_ref()
+                    .and_then(|data| data.claude_code.as_ref()?.plan);
+                let model = match plan {
+                    Some(plan) => format!("{model} ({})", plan.label()),
+                    None => model.to_string(),
+                };
+                let mut tooltip = match s.poll.cost {
+                    Some(cost) => format!(
+                        "{} 5h: {} | 7d: {} | {} / {}",
+                        model,
+                        s.ui.session_text,
+                        s.ui.weekly_text,
+                        format::usd(cost.session, Style::WIDGET),
+                        format::usd(cost.weekly, Style::WIDGET)
+                    ),
+                    None => format!(
                         "{} 5h: {} | 7d: {}",
-                        s.language.strings().claude_code_model,
-                        s.session_text,
-                        s.weekly_text
+                        model, s.ui.session_text, s.ui.weekly_text
                     ),
+                };
+                if let Some(count) = s.poll.messages_left {
+                    tooltip.push_str(" | ");
+                    tooltip.push_str(
+                        &s.ui
+                            .language
+                            .strings()
+                            .messages_left
+                            .replace("{count}", &count.to_string()),
+                    );
+                }
+                icons.push(tray_icon::TrayIconData {
+                    kind: tray_icon::TrayIconKind::Claude,
+                    percent: Some(s.ui.session_percent),
+                    tooltip,
+                    palette: s.ui.palette,
                 });
             }
-            if s.show_codex {
+            if s.ui.show_codex {
                 icons.push(tray_icon::TrayIconData {
                     kind: tray_icon::TrayIconKind::Codex,
-                    percent: Some(s.codex_session_percent),
+                    percent: Some(s.ui.codex_session_percent),
                     tooltip: format!(
                         "{} 5h: {} | 7d: {}",
-                        s.language.strings().codex_model,
-                        s.codex_session_text,
-                        s.codex_weekly_text
+                        s.ui.language.strings().codex_model,
+                        s.ui.codex_session_text,
+                        s.ui.codex_weekly_text
                     ),
+                    palette: s.ui.palette,
                 });
             }
-            if s.show_antigravity {
+            if s.ui.show_antigravity {
                 icons.push(tray_icon::TrayIconData {
                     kind: tray_icon::TrayIconKind::Antigravity,
-                    percent: Some(s.antigravity_session_percent),
+                    percent: Some(s.ui.antigravity_session_percent),
                     tooltip: format!(
                         "{} 5h: {} | 7d: {}",
-                        s.language.strings().antigravity_model,
-                        s.antigravity_session_text,
-                        s.antigravity_weekly_text
+                        s.ui.language.strings().antigravity_model,
+                        s.ui.antigravity_session_text,
+                        s.ui.antigravity_weekly_text
                     ),
+                    palette: s.ui.palette,
                 });
             }
             icons
         }
         Some(s) => {
             let mut icons = Vec::new();
-            if s.show_claude_code {
+            if s.ui.show_claude_code {
                 icons.push(tray_icon::TrayIconData {
                     kind: tray_icon::TrayIconKind::Claude,
                     percent: None,
-                    tooltip: s.language.strings().window_title.to_string(),
+                    tooltip: s.ui.language.strings().window_title.to_string(),
+                    palette: s.ui.palette,
                 });
             }
-            if s.show_codex {
+            if s.ui.show_codex {
                 icons.push(tray_icon::TrayIconData {
                     kind: tray_icon::TrayIconKind::Codex,
                     percent: None,
-                    tooltip: s.language.strings().codex_window_title.to_string(),
+                    tooltip: s.ui.language.strings().codex_window_title.to_string(),
+                    palette: s.ui.palette,
                 });
             }
-            if s.show_antigravity {
+            if s.ui.show_antigravity {
                 icons.push(tray_icon::TrayIconData {
                     kind: tray_icon::TrayIconKind::Antigravity,
                     percent: None,
-                    tooltip: s.language.strings().antigravity_window_title.to_string(),
+                    tooltip: s.ui.language.strings().antigravity_window_title.to_string(),
+                    palette: s.ui.palette,
                 });
             }
             icons
@@ -472,12 +1564,70 @@ fn sync_tray_icons(hwnd: HWND) {
     tray_icon::sync(hwnd, &icons);
 }
 
+/// Badge mode replaces the widget with a taskbar button badge; turning it off
+/// brings the widget back.
+fn toggle_badge_mode(hwnd: HWND) {
+    let (badge_mode, title) = {
+        let mut state = lock_state();
+        let Some(s) = state.as_mut() else {
+            return;
+        };
+        s.ui.badge_mode = !s.ui.badge_mode;
+        s.ui.widget_visible = !s.ui.badge_mode;
+        (s.ui.badge_mode, s.ui.language.strings().window_title)
+    };
+    save_state_settings();
+    unsafe {
+        if badge_mode {
+            let _ = ShowWindow(hwnd, SW_HIDE);
+            if let Err(error) = badge::show(hwnd, title) {
+                diagnose::log_error("unable to create the taskbar badge", error);
+            }
+            sync_badge();
+        } else {
+            badge::hide();
+            position_at_taskbar();
+            let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+            render_layered();
+        }
+    }
+}
+
+/// Mirror the Claude tray badge on the badge mode taskbar button.
+fn sync_badge() {
+    let badge_mode = {
+        let state = lock_state();
+        state.as_ref().is_some_and(|s| s.ui.badge_mode)
+    };
+    if !badge_mode {
+        return;
+    }
+    let icons = tray_icon_data_from_state();
+    match icons
+        .iter()
+        .find(|icon| matches!(icon.kind, tray_icon::TrayIconKind::Claude))
+    {
+        Some(icon) => badge::update(icon.percent, icon.palette, &icon.tooltip),
+        None => {
+            let palette = icons.first().map(|icon| icon.palette).unwrap_or_default();
+            let title = {
+                let state = lock_state();
+                state
+                    .as_ref()
+                    .map(|s| s.ui.language.strings().window_title)
+                    .unwrap_or_default()
+            };
+            badge::update(None, palette, title);
+        }
+    }
+}
+
 fn toggle_widget_visibility(hwnd: HWND) {
     let new_visible = {
         let mut state = lock_state();
         if let Some(s) = state.as_mut() {
-            s.widget_visible = !s.widget_visible;
-            s.widget_visible
+            s.ui.widget_visible = !s.ui.widget_visible;
+            s.ui.widget_visible
         } else {
             return;
         }
@@ -494,7 +1644,20 @@ fn toggle_widget_visibility(hwnd: HWND) {
     }
 }
 
+/// True when `--no-embed` or `CCUM_NO_EMBED` keeps the widget floating,
+/// whatever the setting says.
+fn embedding_forced_off() -> bool {
+    std::env::args().skip(1).any(|arg| arg == NO_EMBED_ARG)
+        || std::env::var_os(ENV_NO_EMBED).is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+/// Follow taskbar `requested_index`, embedding in it unless the widget is set
+/// to float. Returns false when there is no taskbar to follow.
 fn attach_to_taskbar(hwnd: HWND, requested_index: usize) -> bool {
+    let embed = {
+        let state = lock_state();
+        state.as_ref().is_some_and(|s| !s.ui.float_widget)
+    } && !embedding_forced_off();
     let taskbars = native_interop::find_taskbars();
     if taskbars.is_empty() {
         diagnose::log("taskbar not found; using fallback popup window");
@@ -515,25 +1678,33 @@ fn attach_to_taskbar(hwnd: HWND, requested_index: usize) -> bool {
 
     let old_hook = {
         let mut state = lock_state();
-        state.as_mut().and_then(|s| s.win_event_hook.take())
+        state.as_mut().and_then(|s| s.handles.win_event_hook.take())
     };
     if let Some(hook) = old_hook {
         native_interop::unhook_win_event(hook);
     }
 
-    native_interop::embed_in_taskbar(hwnd, taskbar.hwnd);
+    if embed {
+        native_interop::embed_in_taskbar(hwnd, taskbar.hwnd);
+    } else {
+        diagnose::log("embedding disabled; floating over the taskbar");
+    }
 
-    let tray_notify = native_interop::find_child_window(taskbar.hwnd, "TrayNotifyWnd");
+    let mods = native_interop::detect_taskbar_mods();
+    if !mods.is_empty() {
+        diagnose::log(format!("taskbar mods detected: {mods:?}"));
+    }
+    let tray_notify = tray_anchor(taskbar.hwnd);
     if tray_notify.is_some() {
-        diagnose::log("TrayNotifyWnd found");
+        diagnose::log("tray anchor found");
     } else {
-        diagnose::log("TrayNotifyWnd not found");
+        diagnose::log("tray anchor not found");
     }
 
-    let hook = tray_notify.and_then(|tray_hwnd| {
-        let thread_id = native_interop::get_window_thread_id(tray_hwnd);
-        native_interop::set_tray_event_hook(thread_id, on_tray_location_changed)
-    });
+    // The taskbar and its tray share a thread, so one hook sees both move.
+    // Secondary taskbars have no tray but still move between edges.
+    let thread_id = native_interop::get_window_thread_id(tray_notify.unwrap_or(taskbar.hwnd));
+    let hook = native_interop::set_tray_event_hook(thread_id, on_taskbar_location_changed);
     if hook.is_some() {
         diagnose::log("tray event hook installed");
     } else {
@@ -542,11 +1713,11 @@ fn attach_to_taskbar(hwnd: HWND, requested_index: usize) -> bool {
 
     let mut state = lock_state();
     if let Some(s) = state.as_mut() {
-        s.taskbar_hwnd = Some(taskbar.hwnd);
-        s.tray_notify_hwnd = tray_notify;
-        s.win_event_hook = hook;
-        s.taskbar_index = index;
-        s.embedded = true;
+        s.handles.taskbar_hwnd = Some(taskbar.hwnd);
+        s.handles.tray_notify_hwnd = tray_notify;
+        s.handles.win_event_hook = hook;
+        s.ui.taskbar_index = index;
+        s.handles.embedded = embed;
     }
     true
 }
@@ -563,14 +1734,27 @@ fn taskbar_at_point(pt: POINT) -> Option<(usize, native_interop::TaskbarWindow)>
         })
 }
 
-fn tray_left_for_taskbar(taskbar_hwnd: HWND, taskbar_rect: RECT) -> i32 {
-    let mut tray_left = taskbar_rect.right;
-    if let Some(tray_hwnd) = native_interop::find_child_window(taskbar_hwnd, "TrayNotifyWnd") {
-        if let Some(tray_rect) = native_interop::get_window_rect_safe(tray_hwnd) {
-            tray_left = tray_rect.left;
+/// The window the widget sits to the left of: the first configured
+/// `tray_anchor_classes` match, or the stock tray.
+fn tray_anchor(taskbar_hwnd: HWND) -> Option<HWND> {
+    match TRAY_ANCHOR_CLASSES.get() {
+        Some(classes) if !classes.is_empty() => {
+            native_interop::find_tray_anchor(taskbar_hwnd, classes)
         }
+        _ => native_interop::find_tray_anchor(
+            taskbar_hwnd,
+            &native_interop::DEFAULT_TRAY_ANCHOR_CLASSES,
+        ),
     }
-    tray_left
+}
+
+/// Right edge of the space the widget can use: the tray's left edge, or the
+/// end of the visible taskbar when there is no tray or a mod has clipped it.
+fn tray_left_for_taskbar(taskbar_hwnd: HWND, taskbar_rect: RECT) -> i32 {
+    let visible = native_interop::visible_taskbar_rect(taskbar_hwnd, taskbar_rect);
+    tray_anchor(taskbar_hwnd)
+        .and_then(native_interop::get_window_rect_safe)
+        .map_or(visible.right, |tray| tray.left.min(visible.right))
 }
 
 fn clamp_offset_for_taskbar(taskbar_hwnd: HWND, taskbar_rect: RECT, offset: i32) -> i32 {
@@ -617,10 +1801,11 @@ fn schedule_auto_update_check(hwnd: HWND) {
             return;
         };
 
-        if auto_update_check_due(s.last_update_check_unix) {
+        if auto_update_check_due(s.poll.last_update_check_unix) {
             None
         } else {
-            let elapsed = now_unix_secs().saturating_sub(s.last_update_check_unix.unwrap_or(0));
+            let elapsed =
+                now_unix_secs().saturating_sub(s.poll.last_update_check_unix.unwrap_or(0));
             let remaining_secs = update_check_interval().as_secs().saturating_sub(elapsed);
             Some((remaining_secs.saturating_mul(1000)).min(u32::MAX as u64) as u32)
         }
@@ -634,43 +1819,551 @@ fn schedule_auto_update_check(hwnd: HWND) {
     }
 }
 
-fn refresh_usage_texts(state: &mut AppState) {
-    if !state.last_poll_ok {
-        return;
+/// The interval the regular poll timer should use right now.
+fn effective_poll_interval(state: &AppState) -> u32 {
+    if let Some(interval) = state.poll.usage_source.poll_interval() {
+        return interval.as_millis() as u32;
     }
-
-    let strings = state.language.strings();
-    let Some(data) = state.data.as_ref() else {
+    let interval = match state.poll.data.as_ref() {
+        Some(data) if state.poll.smart_polling => {
+            poller::adaptive_poll_interval(data).as_millis() as u32
+        }
+        _ => state.poll.poll_interval_ms,
+    };
+    if state.poll.power_saving {
+        interval
+            .saturating_mul(POWER_SAVING_POLL_FACTOR)
+            .min(POLL_1_HOUR)
+            .max(interval)
+    } else {
+        interval
+    }
+}
+
+/// Re-check battery saver and the battery level, and move the poll timer to
+/// the new interval when that changes whether power is being saved.
+fn update_power_saving(hwnd: HWND) {
+    let (enabled, low_battery_percent) = {
+        let state = lock_state();
+        match state.as_ref() {
+            Some(s) => (s.poll.poll_less_on_battery, s.poll.low_battery_percent),
+            None => return,
+        }
+    };
+    let saving = enabled && native_interop::is_saving_power(low_battery_percent);
+
+    let interval = {
+        let mut state = lock_state();
+        let Some(s) = state.as_mut() else {
+            return;
+        };
+        if s.poll.power_saving == saving {
+            return;
+        }
+        s.poll.power_saving = saving;
+        (!s.poll.polling_paused && !s.poll.session_disconnected).then(|| effective_poll_interval(s))
+    };
+    diagnose::log(format!("power saving={saving}"));
+    if let Some(interval) = interval {
+        unsafe {
+            SetTimer(hwnd, TIMER_POLL, interval, None);
+        }
+    }
+}
+
+/// Strings for the widget rows. API-key limits are per minute rather than 5h/7d,
+/// so when they fill both rows the labels switch to requests/tokens.
+fn row_strings(state: &AppState) -> Strings {
+    let mut strings = state.ui.language.strings();
+    if state.ui.api_key_limits && !state.ui.show_codex && !state.ui.show_antigravity {
+        strings.session_window = strings.requests_window;
+        strings.weekly_window = strings.tokens_window;
+    }
+    strings
+}
+
+/// One line of the widget: a usage window's label and each provider's
+/// bar and text in that window.
+struct WidgetRow {
+    label: String,
+    claude: RowValue,
+    codex: RowValue,
+    antigravity: RowValue,
+    /// The second Claude account, when one is configured.
+    secondary: Option<RowValue>,
+}
+
+/// One model's bar and usage text in a widget row.
+struct RowValue {
+    fill: f64,
+    text: String,
+    /// Bold warning text, at or above the emphasis threshold.
+    emphasized: bool,
+    /// The window's limit is used up: a full bar in the limit colour.
+    limit_reached: bool,
+    /// The API names this window's limit as the binding one: the text is
+    /// underlined.
+    binding: bool,
+}
+
+/// Whether `section`'s limit is used up and has not reset since. Stays true
+/// when a later poll fails, since nothing but the reset lifts a limit.
+fn limit_in_force(section: &UsageSection, now: SystemTime) -> bool {
+    section.limit_reached() && section.resets_at.is_none_or(|resets_at| resets_at > now)
+}
+
+/// The widget rows top to bottom, for the current display mode.
+fn widget_rows(state: &AppState) -> Vec<WidgetRow> {
+    let now = SystemTime::now();
+    let [claude_bars, codex_bars, antigravity_bars, secondary_bars] = shown_bar_percents(&state.ui);
+    row_display_order(state)
+        .iter()
+        .filter(|window| state.ui.display_mode.shows(**window))
+        .map(|&window| {
+            // Bars show usage, or how far into the window we are.
+            let fill = |usage: Option<&UsageData>, percent: f64| {
+                if !state.ui.elapsed_bars.contains(&window) {
+                    return percent;
+                }
+                usage
+                    .and_then(|usage| {
+                        let section = match window {
+                            UsageWindow::Session => &usage.session,
+                            UsageWindow::Weekly => &usage.weekly,
+                        };
+                        section.elapsed_percent(window.length(), now)
+                    })
+                    .unwrap_or(0.0)
+            };
+            let value = |usage: Option<&UsageData>, bar: f64, text: &String, percent: f64| {
+                let limit_reached = usage.is_some_and(|usage| {
+                    let section = match window {
+                        UsageWindow::Session => &usage.session,
+                        UsageWindow::Weekly => &usage.weekly,
+                    };
+                    limit_in_force(section, now)
+                });
+                let binding = usage
+                    .and_then(|usage| usage.unified.as_ref())
+                    .and_then(UnifiedLimit::binding_window)
+                    == Some(window);
+                RowValue {
+                    fill: if limit_reached {
+                        100.0
+                    } else {
+                        fill(usage, bar)
+                    },
+                    text: text.clone(),
+                    emphasized: state.ui.emphasize_warnings && percent >= state.ui.emphasis_percent,
+                    limit_reached,
+                    binding,
+                }
+            };
+            let data = state.poll.data.as_ref();
+            let claude = data.and_then(|d| d.claude_code.as_ref());
+            let codex = data.and_then(|d| d.codex.as_ref());
+            let antigravity = data.and_then(|d| d.antigravity.as_ref());
+            let secondary = data.and_then(|d| d.secondary.as_ref());
+            let show_secondary = state.poll.secondary_account.is_some();
+            match window {
+                UsageWindow::Session => WidgetRow {
+                    label: row_label(state, window),
+                    claude: value(
+                        claude,
+                        claude_bars[0],
+                        &state.ui.session_text,
+                        state.ui.session_percent,
+                    ),
+                    codex: value(
+                        codex,
+                        codex_bars[0],
+                        &state.ui.codex_session_text,
+                        state.ui.codex_session_percent,
+                    ),
+                    antigravity: value(
+                        antigravity,
+                        antigravity_bars[0],
+                        &state.ui.antigravity_session_text,
+                        state.ui.antigravity_session_percent,
+                    ),
+                    secondary: show_secondary.then(|| {
+                        value(
+                            secondary,
+                            secondary_bars[0],
+                            &state.ui.secondary_session_text,
+                            state.ui.secondary_session_percent,
+                        )
+                    }),
+                },
+                UsageWindow::Weekly => WidgetRow {
+                    label: row_label(state, window),
+                    claude: value(
+                        claude,
+                        claude_bars[1],
+                        &state.ui.weekly_text,
+                        state.ui.weekly_percent,
+                    ),
+                    codex: value(
+                        codex,
+                        codex_bars[1],
+                        &state.ui.codex_weekly_text,
+                        state.ui.codex_weekly_percent,
+                    ),
+                    antigravity: value(
+                        antigravity,
+                        antigravity_bars[1],
+                        &state.ui.antigravity_weekly_text,
+                        state.ui.antigravity_weekly_percent,
+                    ),
+                    secondary: show_secondary.then(|| {
+                        value(
+                            secondary,
+                            secondary_bars[1],
+                            &state.ui.secondary_weekly_text,
+                            state.ui.secondary_weekly_percent,
+                        )
+                    }),
+                },
+            }
+        })
+        .collect()
+}
+
+/// Every bar's usage: 5h and 7d for Claude, Codex, Antigravity and the
+/// second Claude account.
+fn bar_percents(ui: &UiState) -> [[f64; 2]; 4] {
+    [
+        [ui.session_percent, ui.weekly_percent],
+        [ui.codex_session_percent, ui.codex_weekly_percent],
+        [
+            ui.antigravity_session_percent,
+            ui.antigravity_weekly_percent,
+        ],
+        [ui.secondary_session_percent, ui.secondary_weekly_percent],
+    ]
+}
+
+/// `bar_percents` as drawn right now: part-way from the previous values
+/// while an animation runs, easing out so the bar settles gently.
+fn shown_bar_percents(ui: &UiState) -> [[f64; 2]; 4] {
+    let target = bar_percents(ui);
+    let Some(animation) = ui.bar_animation else {
+        return target;
+    };
+    let t = (animation.started.elapsed().as_secs_f64() * 1000.0 / BAR_ANIMATION_MS as f64).min(1.0);
+    let eased = 1.0 - (1.0 - t).powi(3);
+    std::array::from_fn(|model| {
+        std::array::from_fn(|window| {
+            let from = animation.from[model][window];
+            from + (target[model][window] - from) * eased
+        })
+    })
+}
+
+/// The label for `window`'s row: the custom one from settings.json, if set.
+fn row_label(state: &AppState, window: UsageWindow) -> String {
+    if let Some(label) = state
+        .ui
+        .row_labels
+        .get(&window)
+        .filter(|l| !l.trim().is_empty())
+    {
+        return label.clone();
+    }
+    let strings = row_strings(state);
+    match window {
+        UsageWindow::Session => strings.session_window,
+        UsageWindow::Weekly => strings.weekly_window,
+    }
+    .to_string()
+}
+
+/// Label column width in 96 DPI pixels. Every window's label is measured,
+/// shown or not, so the widget does not change width with the display mode.
+fn row_label_width(state: &AppState) -> i32 {
+    label_column_width(&UsageWindow::ALL.map(|window| row_label(state, window)))
+}
+
+/// The widest of `labels` in the widget font, and never narrower than the
+/// default column.
+fn label_column_width(labels: &[String]) -> i32 {
+    widest_text(labels, false).max(LABEL_WIDTH)
+}
+
+/// Width of the usage text column: room for the longest text `style` gives
+/// a widget row, in bold when that text can be emphasized, and never
+/// narrower than the default column.
+fn usage_text_width(style: Style, bold: bool) -> i32 {
+    let longest = format!("{} \u{00b7} 59m", format::percent(100.0, style));
+    widest_text(&[longest], bold).max(TEXT_WIDTH)
+}
+
+/// The widest of `texts` in the widget font at 96 DPI. Measurements are
+/// remembered, as only a handful of different sets are ever asked for.
+fn widest_text(texts: &[String], bold: bool) -> i32 {
+    static MEASURED: Mutex<BTreeMap<(Vec<String>, bool), i32>> = Mutex::new(BTreeMap::new());
+
+    let mut measured = MEASURED.lock().unwrap_or_else(|e| e.into_inner());
+    let key = (texts.to_vec(), bold);
+    if let Some(width) = measured.get(&key) {
+        return *width;
+    }
+    let width = texts
+        .iter()
+        .map(|text| measure_widget_text(text, bold))
+        .max()
+        .unwrap_or(0);
+    measured.insert(key, width);
+    width
+}
+
+/// Width of `text` in the widget font at 96 DPI.
+fn measure_widget_text(text: &str, bold: bool) -> i32 {
+    unsafe {
+        let screen_dc = GetDC(None);
+        let font = create_widget_font(-12, bold, CLEARTYPE_QUALITY);
+        let old_font = SelectObject(screen_dc, font);
+        let wide: Vec<u16> = text.encode_utf16().collect();
+        let mut size = SIZE::default();
+        let _ = GetTextExtentPoint32W(screen_dc, &wide, &mut size);
+        SelectObject(screen_dc, old_font);
+        let _ = DeleteObject(font);
+        ReleaseDC(None, screen_dc);
+        size.cx
+    }
+}
+
+/// `row_order`, or with `urgent_row_first` the windows by how close they are
+/// to their limit, the sooner reset first on a tie.
+fn row_display_order(state: &AppState) -> Vec<UsageWindow> {
+    let mut order = state.ui.row_order.clone();
+    if state.ui.urgent_row_first {
+        // Stable, so equal rows keep the manual order.
+        order.sort_by(|a, b| {
+            let (a_percent, a_reset) = row_urgency(state, *a);
+            let (b_percent, b_reset) = row_urgency(state, *b);
+            b_percent
+                .total_cmp(&a_percent)
+                .then_with(|| match (a_reset, b_reset) {
+                    (Some(a), Some(b)) => a.cmp(&b),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
+                })
+        });
+    }
+    order
+}
+
+/// The highest usage in `window` among the shown models, and the earliest
+/// reset among them.
+fn row_urgency(state: &AppState, window: UsageWindow) -> (f64, Option<SystemTime>) {
+    let (claude, codex, antigravity, secondary) = match window {
+        UsageWindow::Session => (
+            state.ui.session_percent,
+            state.ui.codex_session_percent,
+            state.ui.antigravity_session_percent,
+            state.ui.secondary_session_percent,
+        ),
+        UsageWindow::Weekly => (
+            state.ui.weekly_percent,
+            state.ui.codex_weekly_percent,
+            state.ui.antigravity_weekly_percent,
+            state.ui.secondary_weekly_percent,
+        ),
+    };
+    let data = state.poll.data.as_ref();
+    let models = [
+        (
+            state.ui.show_claude_code,
+            claude,
+            data.and_then(|d| d.claude_code.as_ref()),
+        ),
+        (
+            state.ui.show_codex,
+            codex,
+            data.and_then(|d| d.codex.as_ref()),
+        ),
+        (
+            state.ui.show_antigravity,
+            antigravity,
+            data.and_then(|d| d.antigravity.as_ref()),
+        ),
+        (
+            state.poll.secondary_account.is_some(),
+            secondary,
+            data.and_then(|d| d.secondary.as_ref()),
+        ),
+    ];
+
+    let mut percent = 0.0_f64;
+    let mut resets_at: Option<SystemTime> = None;
+    for (_, model_percent, usage) in models.into_iter().filter(|(shown, _, _)| *shown) {
+        percent = percent.max(model_percent);
+        let section = usage.map(|usage| match window {
+            UsageWindow::Session => &usage.session,
+            UsageWindow::Weekly => &usage.weekly,
+        });
+        if let Some(reset) = section.and_then(|section| section.resets_at) {
+            resets_at = Some(resets_at.map_or(reset, |earliest| earliest.min(reset)));
+        }
+    }
+    (percent, resets_at)
+}
+
+/// `order` without duplicates and with any missing window appended, so a
+/// hand-edited list cannot drop a row.
+fn normalized_row_order(order: &[UsageWindow]) -> Vec<UsageWindow> {
+    let mut normalized = Vec::with_capacity(UsageWindow::ALL.len());
+    for window in order.iter().chain(UsageWindow::ALL.iter()) {
+        if !normalized.contains(window) {
+            normalized.push(*window);
+        }
+    }
+    normalized
+}
+
+/// `Style::WIDGET` with the chosen percentage precision.
+fn widget_style(state: &AppState) -> Style {
+    Style {
+        percent_decimals: state.ui.percent_decimals.min(1),
+        ..Style::WIDGET
+    }
+}
+
+fn refresh_usage_texts(state: &mut AppState) {
+    let strings = state.ui.language.strings();
+    if state.poll.polling_paused || state.poll.metered_deferred {
+        for text in [
+            &mut state.ui.session_text,
+            &mut state.ui.weekly_text,
+            &mut state.ui.codex_session_text,
+            &mut state.ui.codex_weekly_text,
+            &mut state.ui.antigravity_session_text,
+            &mut state.ui.antigravity_weekly_text,
+            &mut state.ui.secondary_session_text,
+            &mut state.ui.secondary_weekly_text,
+        ] {
+            *text = strings.paused.to_string();
+        }
+        return;
+    }
+
+    if !state.poll.last_poll_ok {
+        show_reached_limits(state);
+        return;
+    }
+
+    let Some(data) = state.poll.data.as_ref() else {
         return;
     };
 
+    let style = widget_style(state);
+    let show_burn_rate = state.ui.show_burn_rate;
+    let session_line =
+        |usage: &UsageData, provider: Provider| match history::session_burn_rate(provider)
+            .filter(|_| show_burn_rate)
+        {
+            Some(rate) if !usage.session.limit_reached() => {
+                format::usage_line_with_rate(&usage.session, rate, style)
+            }
+            _ => format::usage_line(&usage.session, style, strings),
+        };
+
     if let Some(claude_code) = data.claude_code.as_ref() {
-        state.session_text = poller::format_line(&claude_code.session, strings);
-        state.weekly_text = poller::format_line(&claude_code.weekly, strings);
-    } else if state.show_claude_code {
-        state.session_text = "!".to_string();
-        state.weekly_text = "!".to_string();
+        state.ui.session_text = session_line(claude_code, Provider::ClaudeCode);
+        state.ui.weekly_text = format::usage_line(&claude_code.weekly, style, strings);
+    } else if state.ui.show_claude_code {
+        state.ui.session_text = "!".to_string();
+        state.ui.weekly_text = "!".to_string();
     }
 
     if let Some(codex) = data.codex.as_ref() {
-        state.codex_session_text = poller::format_line(&codex.session, strings);
-        state.codex_weekly_text = poller::format_line(&codex.weekly, strings);
-    } else if state.show_codex {
-        state.codex_session_text = "!".to_string();
-        state.codex_weekly_text = "!".to_string();
+        state.ui.codex_session_text = session_line(codex, Provider::Codex);
+        state.ui.codex_weekly_text = format::usage_line(&codex.weekly, style, strings);
+    } else if state.ui.show_codex {
+        state.ui.codex_session_text = "!".to_string();
+        state.ui.codex_weekly_text = "!".to_string();
     }
 
     if let Some(antigravity) = data.antigravity.as_ref() {
-        state.antigravity_session_text = poller::format_line(&antigravity.session, strings);
-        state.antigravity_weekly_text =
+        state.ui.antigravity_session_text = session_line(antigravity, Provider::Antigravity);
+        state.ui.antigravity_weekly_text =
             if antigravity.weekly.resets_at.is_none() && antigravity.weekly.percentage == 0.0 {
                 "--".to_string()
             } else {
-                poller::format_line(&antigravity.weekly, strings)
+                format::usage_line(&antigravity.weekly, style, strings)
             };
-    } else if state.show_antigravity {
-        state.antigravity_session_text = "!".to_string();
-        state.antigravity_weekly_text = "!".to_string();
+    } else if state.ui.show_antigravity {
+        state.ui.antigravity_session_text = "!".to_string();
+        state.ui.antigravity_weekly_text = "!".to_string();
+    }
+
+    // Burn rates are only tracked for the default account.
+    if let Some(secondary) = data.secondary.as_ref() {
+        state.ui.secondary_session_text = format::usage_line(&secondary.session, style, strings);
+        state.ui.secondary_weekly_text = format::usage_line(&secondary.weekly, style, strings);
+    } else if state.poll.secondary_account.is_some() {
+        state.ui.secondary_session_text = "!".to_string();
+        state.ui.secondary_weekly_text = "!".to_string();
+    }
+}
+
+/// Mark every usage text as failed, apart from limits that are still in
+/// force.
+fn show_poll_error(state: &mut AppState) {
+    for text in [
+        &mut state.ui.session_text,
+        &mut state.ui.weekly_text,
+        &mut state.ui.codex_session_text,
+        &mut state.ui.codex_weekly_text,
+        &mut state.ui.antigravity_session_text,
+        &mut state.ui.antigravity_weekly_text,
+        &mut state.ui.secondary_session_text,
+        &mut state.ui.secondary_weekly_text,
+    ] {
+        *text = ERROR_GLYPH.to_string();
+    }
+    show_reached_limits(state);
+}
+
+/// Show "0 left" for windows whose limit the last good poll found used up
+/// and which have not reset yet. A failed poll says nothing new about them,
+/// so the error glyph must not hide them.
+fn show_reached_limits(state: &mut AppState) {
+    let strings = state.ui.language.strings();
+    let style = widget_style(state);
+    let now = SystemTime::now();
+    let Some(data) = state.poll.data.as_ref() else {
+        return;
+    };
+    let ui = &mut state.ui;
+    for (usage, session_text, weekly_text) in [
+        (&data.claude_code, &mut ui.session_text, &mut ui.weekly_text),
+        (
+            &data.codex,
+            &mut ui.codex_session_text,
+            &mut ui.codex_weekly_text,
+        ),
+        (
+            &data.antigravity,
+            &mut ui.antigravity_session_text,
+            &mut ui.antigravity_weekly_text,
+        ),
+        (
+            &data.secondary,
+            &mut ui.secondary_session_text,
+            &mut ui.secondary_weekly_text,
+        ),
+    ] {
+        let Some(usage) = usage else {
+            continue;
+        };
+        for (section, text) in [(&usage.session, session_text), (&usage.weekly, weekly_text)] {
+            if limit_in_force(section, now) {
+                *text = format::usage_line(section, style, strings);
+            }
+        }
     }
 }
 
@@ -707,6 +2400,41 @@ fn show_error_message(hwnd: HWND, title: &str, message: &str) {
     }
 }
 
+/// Save every retained usage sample to a CSV or JSON file chosen by the user.
+fn export_history(hwnd: HWND) {
+    let strings = {
+        let state = lock_state();
+        state
+            .as_ref()
+            .map(|s| s.ui.language.strings())
+            .unwrap_or_else(|| LanguageId::English.strings())
+    };
+    let Some((path, filter)) = native_interop::save_file_dialog(
+        hwnd,
+        "usage-history.csv",
+        &[("CSV (*.csv)", "*.csv"), ("JSON (*.json)", "*.json")],
+    ) else {
+        return;
+    };
+
+    // The dialog already confirmed this exact path, so it is written as-is.
+    let samples = history::all_samples();
+    let content = if filter == 1 {
+        history::samples_to_json(&samples)
+    } else {
+        history::samples_to_csv(&samples)
+    };
+
+    if let Err(error) = std::fs::write(&path, content) {
+        diagnose::log_error("unable to export usage history", &error);
+        show_error_message(
+            hwnd,
+            strings.export_history_failed,
+            &format!("{}\n\n{error}", path.display()),
+        );
+    }
+}
+
 fn show_update_prompt(hwnd: HWND, strings: Strings, release: &ReleaseDescriptor) -> bool {
     let message = strings
         .update_prompt_now
@@ -724,10 +2452,57 @@ fn show_update_prompt(hwnd: HWND, strings: Strings, release: &ReleaseDescriptor)
     }
 }
 
+/// Ask once whether to seed history from existing Claude Code transcripts.
+/// Only offered when there are transcripts to import.
+fn offer_transcript_backfill(hwnd: HWND) {
+    let strings = {
+        let mut state = lock_state();
+        match state.as_mut() {
+            Some(s) if !s.ui.history_backfill_offered => {
+                s.ui.history_backfill_offered = true;
+                s.ui.language.strings()
+            }
+            _ => return,
+        }
+    };
+    save_state_settings();
+
+    let has_transcripts = transcripts::projects_dir().is_some_and(|dir| dir.is_dir());
+    if !has_transcripts || history::has_activity() {
+        return;
+    }
+
+    let accepted = unsafe {
+        let title_wide = native_interop::wide_str(strings.backfill_title);
+        let message_wide = native_interop::wide_str(strings.backfill_prompt);
+        MessageBoxW(
+            hwnd,
+            PCWSTR::from_raw(message_wide.as_ptr()),
+            PCWSTR::from_raw(title_wide.as_ptr()),
+            MB_YESNO | MB_ICONQUESTION,
+        ) == IDYES
+    };
+    if !accepted {
+        diagnose::log("transcript backfill declined");
+        return;
+    }
+
+    let sh = SendHwnd::from_hwnd(hwnd);
+    std::thread::spawn(move || {
+        history::backfill_from_transcripts();
+        if history::estimate_pending() {
+            save_state_settings();
+        }
+        unsafe {
+            let _ = PostMessageW(sh.to_hwnd(), WM_APP_USAGE_UPDATED, WPARAM(0), LPARAM(0));
+        }
+    });
+}
+
 fn apply_language_to_state(state: &mut AppState, language_override: Option<LanguageId>) {
-    state.language_override = language_override;
-    state.language = localization::resolve_language(language_override);
-    set_window_title(state.hwnd.to_hwnd(), state.language.strings());
+    state.ui.language_override = language_override;
+    state.ui.language = localization::resolve_language(language_override);
+    set_window_title(state.handles.hwnd.to_hwnd(), state.ui.language.strings());
     refresh_usage_texts(state);
 }
 
@@ -737,12 +2512,12 @@ fn update_language_change() -> bool {
         return false;
     };
 
-    if app_state.language_override.is_some() {
+    if app_state.ui.language_override.is_some() {
         return false;
     }
 
     let new_language = localization::detect_system_language();
-    if new_language == app_state.language {
+    if new_language == app_state.ui.language {
         return false;
     }
 
@@ -787,21 +2562,24 @@ fn begin_update_check(hwnd: HWND, interactive: bool) {
         };
 
         if matches!(
-            app_state.update_status,
+            app_state.poll.update_status,
             UpdateStatus::Checking | UpdateStatus::Applying
         ) {
             if interactive {
                 show_info_message(
                     hwnd,
-                    app_state.language.strings().updates,
-                    app_state.language.strings().update_in_progress,
+                    app_state.ui.language.strings().updates,
+                    app_state.ui.language.strings().update_in_progress,
                 );
             }
             return;
         }
 
-        app_state.update_status = UpdateStatus::Checking;
-        (app_state.language.strings(), app_state.install_channel)
+        app_state.poll.update_status = UpdateStatus::Checking;
+        (
+            app_state.ui.language.strings(),
+            app_state.ui.install_channel,
+        )
     };
 
     std::thread::spawn(move || {
@@ -812,8 +2590,8 @@ fn begin_update_check(hwnd: HWND, interactive: bool) {
                 {
                     let mut state = lock_state();
                     if let Some(s) = state.as_mut() {
-                        s.update_status = UpdateStatus::UpToDate;
-                        s.last_update_check_unix = Some(checked_at);
+                        s.poll.update_status = UpdateStatus::UpToDate;
+                        s.poll.last_update_check_unix = Some(checked_at);
                     }
                 }
                 save_state_settings();
@@ -828,8 +2606,8 @@ fn begin_update_check(hwnd: HWND, interactive: bool) {
                 {
                     let mut state = lock_state();
                     if let Some(s) = state.as_mut() {
-                        s.update_status = UpdateStatus::Available(release.clone());
-                        s.last_update_check_unix = Some(checked_at);
+                        s.poll.update_status = UpdateStatus::Available(release.clone());
+                        s.poll.last_update_check_unix = Some(checked_at);
                     }
                 }
                 save_state_settings();
@@ -847,8 +2625,8 @@ fn begin_update_check(hwnd: HWND, interactive: bool) {
                 {
                     let mut state = lock_state();
                     if let Some(s) = state.as_mut() {
-                        s.update_status = UpdateStatus::Idle;
-                        s.last_update_check_unix = Some(checked_at);
+                        s.poll.update_status = UpdateStatus::Idle;
+                        s.poll.last_update_check_unix = Some(checked_at);
                     }
                 }
                 save_state_settings();
@@ -873,19 +2651,19 @@ fn begin_update_apply(hwnd: HWND, release: ReleaseDescriptor) {
         };
 
         if matches!(
-            app_state.update_status,
+            app_state.poll.update_status,
             UpdateStatus::Checking | UpdateStatus::Applying
         ) {
             show_info_message(
                 hwnd,
-                app_state.language.strings().updates,
-                app_state.language.strings().update_in_progress,
+                app_state.ui.language.strings().updates,
+                app_state.ui.language.strings().update_in_progress,
             );
             return;
         }
 
-        app_state.update_status = UpdateStatus::Applying;
-        app_state.language.strings()
+        app_state.poll.update_status = UpdateStatus::Applying;
+        app_state.ui.language.strings()
     };
 
     std::thread::spawn(move || {
@@ -898,7 +2676,7 @@ fn begin_update_apply(hwnd: HWND, release: ReleaseDescriptor) {
                 {
                     let mut state = lock_state();
                     if let Some(s) = state.as_mut() {
-                        s.update_status = UpdateStatus::Available(release);
+                        s.poll.update_status = UpdateStatus::Available(release);
                     }
                 }
                 let message = format!("{}.\n\n{}", strings.update_failed, error);
@@ -914,7 +2692,7 @@ fn begin_update_apply(hwnd: HWND, release: ReleaseDescriptor) {
 fn begin_winget_update(hwnd: HWND) {
     let strings = {
         let state = lock_state();
-        state.as_ref().map(|s| s.language.strings())
+        state.as_ref().map(|s| s.ui.language.strings())
     }
     .unwrap_or(LanguageId::English.strings());
 
@@ -931,6 +2709,18 @@ fn begin_winget_update(hwnd: HWND) {
 
 const STARTUP_REGISTRY_PATH: &str = r"Software\Microsoft\Windows\CurrentVersion\Run";
 const STARTUP_REGISTRY_KEY: &str = "ClaudeCodeUsageMonitor";
+/// Passed by the Run entry so a start at sign-in waits for the taskbar and
+/// network to come up.
+const STARTUP_ARG: &str = "--delayed";
+
+/// The Run entry for `exe`.
+fn startup_command(exe: &str) -> String {
+    if paths::is_portable() {
+        format!("\"{exe}\" {STARTUP_ARG} --portable")
+    } else {
+        format!("\"{exe}\" {STARTUP_ARG}")
+    }
+}
 
 /// Returns true only if the startup registry value points to this executable.
 fn is_startup_enabled() -> bool {
@@ -995,12 +2785,14 @@ fn is_startup_enabled() -> bool {
         }
         let current_exe = String::from_utf16_lossy(&exe_buf[..len]);
 
-        // Case-insensitive comparison (Windows paths are case-insensitive)
-        reg_value.eq_ignore_ascii_case(&current_exe)
+        // Case-insensitive comparison (Windows paths are case-insensitive).
+        // Entries written before `--delayed` hold just the path.
+        reg_value.eq_ignore_ascii_case(&startup_command(&current_exe))
+            || reg_value.eq_ignore_ascii_case(&current_exe)
     }
 }
 
-fn set_startup_enabled(enable: bool) {
+pub fn set_startup_enabled(enable: bool) {
     unsafe {
         let path = native_interop::wide_str(STARTUP_REGISTRY_PATH);
 
@@ -1022,16 +2814,17 @@ fn set_startup_enabled(enable: bool) {
             let mut exe_buf = [0u16; 260];
             let len = GetModuleFileNameW(None, &mut exe_buf) as usize;
             if len > 0 {
+                let exe = String::from_utf16_lossy(&exe_buf[..len]);
+                let command = native_interop::wide_str(&startup_command(&exe));
                 // Write the wide string including null terminator
-                let byte_len = ((len + 1) * 2) as u32;
                 let _ = RegSetValueExW(
                     hkey,
                     PCWSTR::from_raw(key_name.as_ptr()),
                     0,
                     REG_SZ,
                     Some(std::slice::from_raw_parts(
-                        exe_buf.as_ptr() as *const u8,
-                        byte_len as usize,
+                        command.as_ptr() as *const u8,
+                        command.len() * 2,
                     )),
                 );
             }
@@ -1043,11 +2836,27 @@ fn set_startup_enabled(enable: bool) {
     }
 }
 
+/// Log a failed change to the logon task and tell the user, who otherwise
+/// sees only the menu check mark not changing.
+fn report_startup_task_error(hwnd: HWND, error: &str) {
+    diagnose::log_error("unable to update the startup task", error);
+    let strings = {
+        let state = lock_state();
+        state
+            .as_ref()
+            .map(|s| s.ui.language.strings())
+            .unwrap_or_else(|| LanguageId::English.strings())
+    };
+    show_error_message(hwnd, strings.startup_task_failed, error);
+}
+
 // Dimensions matching the C# version
 const SEGMENT_W: i32 = 10;
 const SEGMENT_H: i32 = 13;
 const SEGMENT_GAP: i32 = 1;
 const SEGMENT_COUNT: i32 = 10;
+const ROW_GAP: i32 = 10;
+const ROW_BOTTOM_MARGIN: i32 = 5;
 const CORNER_RADIUS: i32 = 2;
 
 const LEFT_DIVIDER_W: i32 = 3;
@@ -1059,10 +2868,127 @@ const TEXT_WIDTH: i32 = 62;
 const MODEL_RIGHT_MARGIN: i32 = 3;
 const RIGHT_MARGIN: i32 = 1;
 const WIDGET_HEIGHT: i32 = 46;
+const CARD_INSET_LEFT: i32 = 4;
+const CARD_INSET_Y: i32 = 2;
+const CARD_RADIUS: i32 = 6;
+const CARD_SHADOW_SIZE: i32 = 3;
+
+/// Gaps around the widget columns in 96 DPI pixels, for tightening the widget
+/// on a crowded taskbar.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct LayoutMargins {
+    /// Between the divider and the labels.
+    divider_right: i32,
+    /// Between the labels and the first bar.
+    label_right: i32,
+    /// Between one model's usage and the next.
+    model_gap: i32,
+    /// After the last model's usage.
+    right: i32,
+}
+
+impl Default for LayoutMargins {
+    fn default() -> Self {
+        LayoutMargins {
+            divider_right: DIVIDER_RIGHT_MARGIN,
+            label_right: LABEL_RIGHT_MARGIN,
+            model_gap: MODEL_RIGHT_MARGIN,
+            right: RIGHT_MARGIN,
+        }
+    }
+}
+
+impl LayoutMargins {
+    /// Hand-edited values kept within a sensible range.
+    fn clamped(self) -> Self {
+        let clamp = |margin: i32| margin.clamp(0, MAX_LAYOUT_MARGIN);
+        LayoutMargins {
+            divider_right: clamp(self.divider_right),
+            label_right: clamp(self.label_right),
+            model_gap: clamp(self.model_gap),
+            right: clamp(self.right),
+        }
+    }
+}
+
+const MAX_LAYOUT_MARGIN: i32 = 40;
+
+/// Where the widget columns go, in 96 DPI pixels.
+#[derive(Clone, Copy, Debug)]
+struct WidgetLayout {
+    show_divider: bool,
+    /// The card replaces the divider, but keeps its space.
+    card_background: bool,
+    margins: LayoutMargins,
+    label_width: i32,
+    /// Width of each model's usage text.
+    text_width: i32,
+    /// Drawn right to left, as a mirror image apart from the text itself.
+    mirrored: bool,
+    bars_right_to_left: bool,
+    bar_style: BarStyle,
+}
+
+impl Default for WidgetLayout {
+    fn default() -> Self {
+        WidgetLayout {
+            show_divider: true,
+            card_background: false,
+            margins: LayoutMargins::default(),
+            label_width: LABEL_WIDTH,
+            text_width: TEXT_WIDTH,
+            mirrored: false,
+            bars_right_to_left: false,
+            bar_style: BarStyle::default(),
+        }
+    }
+}
+
+impl WidgetLayout {
+    fn draws_divider(&self) -> bool {
+        self.show_divider && !self.card_background
+    }
+
+    /// Left edge of the label column, scaled.
+    fn content_x(&self) -> i32 {
+        let divider = if self.show_divider { LEFT_DIVIDER_W } else { 0 };
+        sc(divider) + sc(self.margins.divider_right)
+    }
+}
+
+fn widget_layout(state: &AppState) -> WidgetLayout {
+    WidgetLayout {
+        show_divider: state.ui.show_divider,
+        card_background: state.ui.card_background,
+        margins: state.ui.layout_margins.clamped(),
+        label_width: row_label_width(state),
+        text_width: usage_text_width(widget_style(state), state.ui.emphasize_warnings),
+        mirrored: state.ui.mirror_layout,
+        bars_right_to_left: state.ui.bars_right_to_left,
+        bar_style: state.ui.bar_style,
+    }
+}
 
+/// The divider doubles as the drag handle. Without it the same strip at the
+/// edge still drags the widget. It is on the right in the mirrored layout.
 fn is_drag_handle_point(client_x: i32, client_y: i32) -> bool {
-    let divider_h = sc(25);
-    let divider_top = (sc(WIDGET_HEIGHT) - divider_h) / 2;
+    let (mirrored_width, height) = {
+        let state = lock_state();
+        match state.as_ref() {
+            Some(s) => (
+                s.ui.mirror_layout.then(|| total_widget_width_for_state(s)),
+                widget_height_for_state(s),
+            ),
+            None => (None, sc(WIDGET_HEIGHT)),
+        }
+    };
+    let client_x = match mirrored_width {
+        Some(width) => width - 1 - client_x,
+        None => client_x,
+    };
+    let divider_h = sc(25).min(height);
+    let divider_top = (height - divider_h) / 2;
     client_x >= 0
         && client_x < sc(LEFT_DIVIDER_W)
         && client_y >= divider_top
@@ -1079,77 +3005,123 @@ fn cursor_is_on_drag_handle(hwnd: HWND) -> bool {
     }
 }
 
-fn active_model_count(show_claude_code: bool, show_codex: bool, show_antigravity: bool) -> i32 {
-    (show_claude_code as i32 + show_codex as i32 + show_antigravity as i32).max(1)
+fn active_model_count(
+    show_claude_code: bool,
+    show_codex: bool,
+    show_antigravity: bool,
+    show_secondary: bool,
+) -> i32 {
+    (show_claude_code as i32 + show_codex as i32 + show_antigravity as i32 + show_secondary as i32)
+        .max(1)
 }
 
-fn row_bar_segment_count(active_models: i32) -> i32 {
+/// Bar segments per model in a row; none when the widget shows text only.
+fn row_bar_segment_count(active_models: i32, text_only: bool) -> i32 {
     match active_models {
+        _ if text_only => 0,
         1 => SEGMENT_COUNT,
         2 => 5,
         _ => 4,
     }
 }
 
-fn total_widget_width_for(active_models: i32) -> i32 {
-    let bar_segments = row_bar_segment_count(active_models);
-    let model_width = (sc(SEGMENT_W) + sc(SEGMENT_GAP)) * bar_segments - sc(SEGMENT_GAP)
-        + sc(BAR_RIGHT_MARGIN)
-        + sc(TEXT_WIDTH);
+fn total_widget_width_for(active_models: i32, text_only: bool, layout: &WidgetLayout) -> i32 {
+    let model_width = model_usage_width(row_bar_segment_count(active_models, text_only), layout);
 
-    sc(LEFT_DIVIDER_W)
-        + sc(DIVIDER_RIGHT_MARGIN)
-        + sc(LABEL_WIDTH)
-        + sc(LABEL_RIGHT_MARGIN)
+    layout.content_x()
+        + sc(layout.label_width)
+        + sc(layout.margins.label_right)
         + model_width * active_models
-        + sc(MODEL_RIGHT_MARGIN) * (active_models - 1)
-        + sc(RIGHT_MARGIN)
+        + sc(layout.margins.model_gap) * (active_models - 1)
+        + sc(layout.margins.right)
 }
 
 fn total_widget_width_for_state(state: &AppState) -> i32 {
-    total_widget_width_for(active_model_count(
-        state.show_claude_code,
-        state.show_codex,
-        state.show_antigravity,
-    ))
+    total_widget_width_for(
+        active_model_count(
+            state.ui.show_claude_code,
+            state.ui.show_codex,
+            state.ui.show_antigravity,
+            state.poll.secondary_account.is_some(),
+        ),
+        state.ui.display_mode.text_only(),
+        &widget_layout(state),
+    )
 }
 
-fn total_widget_width() -> i32 {
-    let active_models = {
-        let state = lock_state();
-        state
-            .as_ref()
-            .map(|s| active_model_count(s.show_claude_code, s.show_codex, s.show_antigravity))
-            .unwrap_or(1)
-    };
-    total_widget_width_for(active_models)
+/// The full height, or one row's height with `compact_single_row` while only
+/// one row is shown.
+fn widget_height_for_state(state: &AppState) -> i32 {
+    if state.ui.compact_single_row && state.ui.display_mode.row_count() == 1 {
+        sc(WIDGET_HEIGHT - SEGMENT_H - ROW_GAP)
+    } else {
+        sc(WIDGET_HEIGHT)
+    }
 }
 
-fn claude_accent_color() -> Color {
-    Color::from_hex("#D97757")
+fn widget_height() -> i32 {
+    let state = lock_state();
+    state
+        .as_ref()
+        .map(widget_height_for_state)
+        .unwrap_or_else(|| sc(WIDGET_HEIGHT))
 }
 
-fn codex_accent_color(is_dark: bool) -> Color {
-    if is_dark {
-        Color::from_hex("#F5F5F5")
-    } else {
-        Color::from_hex("#1F1F1F")
-    }
+fn total_widget_width() -> i32 {
+    let state = lock_state();
+    state
+        .as_ref()
+        .map(total_widget_width_for_state)
+        .unwrap_or_else(|| total_widget_width_for(1, false, &WidgetLayout::default()))
 }
 
-fn antigravity_accent_color() -> Color {
-    Color::from_hex("#4285F4")
+/// Colours of the bare taskbar the widget sits on, and of the text and empty
+/// bar tracks drawn over it.
+struct TaskbarColors {
+    is_dark: bool,
+    backdrop: Color,
+    text: Color,
+    track: Color,
 }
 
-fn claude_usage_text_color(is_dark: bool) -> Color {
-    if is_dark {
-        Color::from_hex("#F09A7A")
-    } else {
-        Color::from_hex("#A94F32")
+impl TaskbarColors {
+    /// The light or dark theme colours, or ones derived from `accent` when
+    /// Windows shows the accent colour on the taskbar.
+    fn new(is_dark: bool, accent: Option<Color>) -> TaskbarColors {
+        let Some(accent) = accent else {
+            return if is_dark {
+                TaskbarColors {
+                    is_dark,
+                    backdrop: Color::from_hex("#1C1C1C"),
+                    text: Color::from_hex("#888888"),
+                    track: Color::from_hex("#444444"),
+                }
+            } else {
+                TaskbarColors {
+                    is_dark,
+                    backdrop: Color::from_hex("#F3F3F3"),
+                    text: Color::from_hex("#404040"),
+                    track: Color::from_hex("#AAAAAA"),
+                }
+            };
+        };
+
+        let is_dark = !accent.is_light();
+        let contrast = if is_dark {
+            Color::from_hex("#FFFFFF")
+        } else {
+            Color::from_hex("#000000")
+        };
+        TaskbarColors {
+            is_dark,
+            backdrop: accent,
+            text: accent.blend(contrast, 0.6),
+            track: accent.blend(contrast, 0.25),
+        }
     }
 }
 
-fn codex_usage_text_color(is_dark: bool) -> Color {
+fn codex_accent_color(is_dark: bool) -> Color {
     if is_dark {
         Color::from_hex("#F5F5F5")
     } else {
@@ -1157,14 +3129,19 @@ fn codex_usage_text_color(is_dark: bool) -> Color {
     }
 }
 
-fn antigravity_usage_text_color(is_dark: bool) -> Color {
+fn codex_usage_text_color(is_dark: bool) -> Color {
     if is_dark {
-        Color::from_hex("#8AB4F8")
+        Color::from_hex("#F5F5F5")
     } else {
-        Color::from_hex("#1967D2")
+        Color::from_hex("#1F1F1F")
     }
 }
 
+/// Extended styles of the widget window. With WS_EX_NOACTIVATE, showing or
+/// clicking the widget never takes focus from the app in use.
+const WIDGET_EX_STYLE: WINDOW_EX_STYLE =
+    WINDOW_EX_STYLE(WS_EX_TOOLWINDOW.0 | WS_EX_LAYERED.0 | WS_EX_NOACTIVATE.0);
+
 pub fn run() {
     // Enable Per-Monitor DPI Awareness V2 for crisp rendering at any scale factor
     unsafe {
@@ -1193,7 +3170,12 @@ pub fn run() {
                             return;
                         }
                     } else {
-                        diagnose::log("startup aborted: another instance is already running");
+                        // Exiting silently looks like a crash, so have the
+                        // running instance show itself instead.
+                        let activated = broadcast::activate_running_instance();
+                        diagnose::log(format!(
+                            "startup aborted: another instance is already running (activated={activated})"
+                        ));
                         return;
                     }
                 }
@@ -1209,10 +3191,13 @@ pub fn run() {
         }
     };
 
+    // Must happen before the first tray icon or window is created.
+    tray_icon::register_app_id();
+
     let class_name = native_interop::wide_str("ClaudeCodeUsageMonitor");
 
     unsafe {
-        let hinstance = GetModuleHandleW(PCWSTR::null()).unwrap();
+        let hinstance = GetModuleHandleW(PCWSTR::null()).unwrap_or_default();
         let (large_icon, small_icon) = load_embedded_app_icons();
 
         let wc = WNDCLASSEXW {
@@ -1233,33 +3218,82 @@ pub fn run() {
             diagnose::log("RegisterClassExW returned 0");
         }
 
+        // Settings are written from the first launch on, so a missing file
+        // means this is it. Upgrades from before `welcome_offered` are not.
+        let first_run = !settings_path().exists();
         let settings = load_settings();
+        // Started at sign-in: give Explorer and the network time to come up
+        // so embedding and the first poll do not fail. Relaunches skip this.
+        let delayed = std::env::args().skip(1).any(|arg| arg == STARTUP_ARG);
+        if delayed && !is_relaunch && settings.startup_delay_secs > 0 {
+            diagnose::log(format!(
+                "delayed start: waiting {}s",
+                settings.startup_delay_secs
+            ));
+            std::thread::sleep(Duration::from_secs(settings.startup_delay_secs as u64));
+        }
+        // Move an entry written before `--delayed` over to the new form.
+        if is_startup_enabled() {
+            set_startup_enabled(true);
+        }
         let language_override = settings.language.as_deref().and_then(LanguageId::from_code);
         let language = localization::resolve_language(language_override);
         let install_channel = updater::current_install_channel();
-
-        // Create as layered popup (will be reparented into taskbar)
-        let title = native_interop::wide_str(language.strings().window_title);
-        let initial_model_count = active_model_count(
+        apply_api_settings(&settings);
+        if settings.otel_port != 0 {
+            if let Err(error) = otel::start(settings.otel_port) {
+                diagnose::log(&error);
+            }
+        }
+        if settings.websocket_port != 0 {
+            if let Err(error) =
+                websocket::start(settings.websocket_port, &settings.websocket_origins)
+            {
+                diagnose::log(&error);
+            }
+        }
+        if settings.shared_memory {
+            shared_memory::enable();
+        }
+        if settings.history_estimate_pending {
+            history::resume_pending_estimate();
+        }
+
+        // Create as layered popup (will be reparented into taskbar)
+        let title = native_interop::wide_str(language.strings().window_title);
+        let initial_model_count = active_model_count(
             settings.show_claude_code,
             settings.show_codex,
             settings.show_antigravity,
+            settings.secondary_account.is_some(),
         );
         let hwnd = CreateWindowExW(
-            WS_EX_TOOLWINDOW | WS_EX_LAYERED | WS_EX_NOACTIVATE,
+            WIDGET_EX_STYLE,
             PCWSTR::from_raw(class_name.as_ptr()),
             PCWSTR::from_raw(title.as_ptr()),
             WS_POPUP,
             0,
             0,
-            total_widget_width_for(initial_model_count),
+            total_widget_width_for(
+                initial_model_count,
+                settings.display_mode.text_only(),
+                &WidgetLayout::default(),
+            ),
             sc(WIDGET_HEIGHT),
             HWND::default(),
             HMENU::default(),
             hinstance,
             None,
-        )
-        .unwrap();
+        );
+        let hwnd = match hwnd {
+            Ok(hwnd) => hwnd,
+            Err(error) => {
+                diagnose::log_error("startup aborted: unable to create the main window", error);
+                return;
+            }
+        };
+        MAIN_HWND.store(hwnd.0 as isize, Ordering::SeqCst);
+        RESTART_AFTER_CRASH.store(settings.restart_after_crash, Ordering::SeqCst);
 
         if !large_icon.is_invalid() {
             let _ = SendMessageW(
@@ -1280,64 +3314,182 @@ pub fn run() {
 
         diagnose::log(format!("main window created hwnd={:?}", hwnd));
 
+        if let Err(error) = WTSRegisterSessionNotification(hwnd, NOTIFY_FOR_THIS_SESSION) {
+            diagnose::log_error("unable to register for session notifications", error);
+        }
+
+        register_overlay_hotkey(hwnd, &settings.overlay_hotkey);
+        let _ = TRAY_ANCHOR_CLASSES.set(settings.tray_anchor_classes.clone());
+
+        if let Err(error) = broadcast::listen(hwnd) {
+            diagnose::log_error("unable to create the broadcast listener", error);
+        }
+
         let is_dark = theme::is_dark_mode();
-        let mut embedded = false;
 
         {
             let mut state = lock_state();
             *state = Some(AppState {
-                hwnd: SendHwnd::from_hwnd(hwnd),
-                taskbar_hwnd: None,
-                tray_notify_hwnd: None,
-                win_event_hook: None,
-                is_dark,
-                embedded: false,
-                language_override,
-                language,
-                install_channel,
-                session_percent: 0.0,
-                session_text: "--".to_string(),
-                weekly_percent: 0.0,
-                weekly_text: "--".to_string(),
-                codex_session_percent: 0.0,
-                codex_session_text: "--".to_string(),
-                codex_weekly_percent: 0.0,
-                codex_weekly_text: "--".to_string(),
-                antigravity_session_percent: 0.0,
-                antigravity_session_text: "--".to_string(),
-                antigravity_weekly_percent: 0.0,
-                antigravity_weekly_text: "--".to_string(),
-                show_claude_code: settings.show_claude_code,
-                show_codex: settings.show_codex,
-                show_antigravity: settings.show_antigravity,
-                data: None,
-                poll_interval_ms: settings.poll_interval_ms,
-                retry_count: 0,
-                force_notify_auth_error: false,
-                auth_error_paused_polling: false,
-                auth_watch_mode: poller::CredentialWatchMode::ActiveSource,
-                auth_watch_snapshot: Vec::new(),
-                last_poll_ok: false,
-                update_status: UpdateStatus::Idle,
-                last_update_check_unix: settings.last_update_check_unix,
-                taskbar_index: settings.taskbar_index,
-                tray_offset: settings.tray_offset,
-                dragging: false,
-                drag_start_mouse_x: 0,
-                drag_start_client_x: 0,
-                drag_start_offset: 0,
-                widget_visible: settings.widget_visible,
+                handles: WindowHandles {
+                    hwnd: SendHwnd::from_hwnd(hwnd),
+                    taskbar_hwnd: None,
+                    tray_notify_hwnd: None,
+                    win_event_hook: None,
+                    embedded: false,
+                    dragging: false,
+                    drag_start_mouse_x: 0,
+                    drag_start_client_x: 0,
+                    drag_start_offset: 0,
+                },
+                ui: UiState {
+                    is_dark,
+                    taskbar_accent: theme::taskbar_accent_color(),
+                    language_override,
+                    language,
+                    install_channel,
+                    session_percent: 0.0,
+                    session_text: "--".to_string(),
+                    weekly_percent: 0.0,
+                    weekly_text: "--".to_string(),
+                    codex_session_percent: 0.0,
+                    codex_session_text: "--".to_string(),
+                    codex_weekly_percent: 0.0,
+                    codex_weekly_text: "--".to_string(),
+                    antigravity_session_percent: 0.0,
+                    antigravity_session_text: "--".to_string(),
+                    antigravity_weekly_percent: 0.0,
+                    antigravity_weekly_text: "--".to_string(),
+                    secondary_session_percent: 0.0,
+                    secondary_session_text: "--".to_string(),
+                    secondary_weekly_percent: 0.0,
+                    secondary_weekly_text: "--".to_string(),
+                    show_claude_code: settings.show_claude_code,
+                    show_codex: settings.show_codex,
+                    show_antigravity: settings.show_antigravity,
+                    show_burn_rate: settings.show_burn_rate,
+                    api_key_limits: settings.api_key_limits,
+                    card_background: settings.card_background,
+                    float_widget: settings.float_widget,
+                    opacity: settings.opacity.clamp(MIN_OPACITY, 100),
+                    blend_with_taskbar: settings.blend_with_taskbar,
+                    click_through_background: settings.click_through_background,
+                    palette: settings.palette,
+                    row_order: normalized_row_order(&settings.row_order),
+                    row_labels: settings.row_labels.clone(),
+                    urgent_row_first: settings.urgent_row_first,
+                    badge_mode: settings.badge_mode,
+                    flash_steps: 0,
+                    bar_animation: None,
+                    reduce_motion: settings.reduce_motion,
+                    show_divider: settings.show_divider,
+                    emphasize_warnings: settings.emphasize_warnings,
+                    emphasis_percent: settings.emphasis_percent,
+                    percent_decimals: settings.percent_decimals.min(1),
+                    elapsed_bars: settings.elapsed_bars.clone(),
+                    mirror_layout: settings.mirror_layout,
+                    bars_right_to_left: settings.bars_right_to_left,
+                    bar_style: settings.bar_style,
+                    compact_single_row: settings.compact_single_row,
+                    layout_margins: settings.layout_margins,
+                    display_mode: settings.display_mode,
+                    wheel_delta: 0,
+                    left_click: settings.left_click,
+                    exact_token_counts: settings.exact_token_counts,
+                    overlay_hotkey: settings.overlay_hotkey.clone(),
+                    tray_anchor_classes: settings.tray_anchor_classes.clone(),
+                    weekly_reminder_hours: settings.weekly_reminder_hours,
+                    quiet_when_fullscreen: settings.quiet_when_fullscreen,
+                    held_notifications: Vec::new(),
+                    notify_limit_resets: settings.notify_limit_resets,
+                    limit_resets: Vec::new(),
+                    weekly_reminder_sent_for: settings.weekly_reminder_sent_for,
+                    history_backfill_offered: settings.history_backfill_offered,
+                    interval_suggestion_offered: settings.interval_suggestion_offered,
+                    welcome_offered: settings.welcome_offered || !first_run,
+                    welcome_pending: false,
+                    taskbar_index: settings.taskbar_index,
+                    tray_offset: settings.tray_offset,
+                    widget_visible: settings.widget_visible,
+                    restart_after_crash: settings.restart_after_crash,
+                    startup_delay_secs: settings.startup_delay_secs,
+                    session_locked: false,
+                    render_dirty: false,
+                },
+                poll: PollState {
+                    show_cost: settings.show_cost,
+                    cost: None,
+                    show_token_counts: settings.show_token_counts,
+                    tokens_today: None,
+                    show_messages_left: settings.show_messages_left,
+                    messages_left: None,
+                    usage_jump_threshold: settings.usage_jump_threshold,
+                    notify_usage_jumps: settings.notify_usage_jumps,
+                    notify_usage_resets: settings.notify_usage_resets,
+                    fullscreen_skips_polls: settings.fullscreen_skips_polls,
+                    alerts: settings.alerts.clone(),
+                    alerts_snoozed_until: settings.alerts_snoozed_until,
+                    locked_skips_polls: settings.locked_skips_polls,
+                    data: None,
+                    poll_interval_ms: settings
+                        .poll_interval_ms
+                        .clamp(MIN_POLL_INTERVAL_MS, MAX_POLL_INTERVAL_MS),
+                    smart_polling: settings.smart_polling,
+                    retry_count: 0,
+                    force_notify_auth_error: false,
+                    auth_error_paused_polling: false,
+                    unreachable_notified: false,
+                    retry_after_delayed: false,
+                    auth_watch_mode: poller::CredentialWatchMode::ActiveSource,
+                    auth_watch_snapshot: Vec::new(),
+                    last_poll_ok: false,
+                    last_failure: None,
+                    update_status: UpdateStatus::Idle,
+                    last_update_check_unix: settings.last_update_check_unix,
+                    suggested_poll_interval: None,
+                    api_base_url: settings.api_base_url.clone(),
+                    api_headers: settings.api_headers.clone(),
+                    oauth_token_url: settings.oauth_token_url.clone(),
+                    oauth_client_id: settings.oauth_client_id.clone(),
+                    credentials_path: settings.credentials_path.clone(),
+                    wsl_distro: settings.wsl_distro.clone(),
+                    secondary_account: settings.secondary_account.clone(),
+                    otel_port: settings.otel_port,
+                    mqtt: settings.mqtt.clone(),
+                    websocket_port: settings.websocket_port,
+                    websocket_origins: settings.websocket_origins.clone(),
+                    shared_memory: settings.shared_memory,
+                    usage_source: poller::active_source(),
+                    debug_menu: settings.debug_menu,
+                    simulation: None,
+                    polling_paused: false,
+                    pause_on_metered: settings.pause_on_metered,
+                    metered_deferred: false,
+                    poll_less_on_battery: settings.poll_less_on_battery,
+                    low_battery_percent: settings.low_battery_percent,
+                    power_saving: settings.poll_less_on_battery
+                        && native_interop::is_saving_power(settings.low_battery_percent),
+                    session_disconnected: false,
+                },
             });
         }
 
         // Try to embed in taskbar
-        if attach_to_taskbar(hwnd, settings.taskbar_index) {
-            embedded = true;
-        }
+        attach_to_taskbar(hwnd, settings.taskbar_index);
+        let embedded = {
+            let state = lock_state();
+            state.as_ref().is_some_and(|s| s.handles.embedded)
+        };
 
-        // If not embedded, fall back to topmost popup with SetLayeredWindowAttributes
+        // If not embedded, fall back to topmost popup with SetLayeredWindowAttributes,
+        // and give it a taskbar button that shows the 5h usage
         if !embedded {
-            let _ = SetLayeredWindowAttributes(hwnd, COLORREF(0), 255, LWA_ALPHA);
+            native_interop::show_in_taskbar(hwnd);
+            let _ = SetLayeredWindowAttributes(
+                hwnd,
+                COLORREF(0),
+                opacity_alpha(settings.opacity.clamp(MIN_OPACITY, 100)),
+                LWA_ALPHA,
+            );
             let _ = SetWindowPos(
                 hwnd,
                 HWND_TOPMOST,
@@ -1351,6 +3503,27 @@ pub fn run() {
 
         // Register system tray icon(s)
         sync_tray_icons(hwnd);
+        if settings.badge_mode {
+            if let Err(error) = badge::show(hwnd, language.strings().window_title) {
+                diagnose::log_error("unable to create the taskbar badge", error);
+            }
+            sync_badge();
+        }
+        if std::env::var_os(ENV_CRASH_RESTART).is_some() {
+            let kind = {
+                let state = lock_state();
+                state.as_ref().map(primary_tray_kind)
+            };
+            let strings = language.strings();
+            if let Some(kind) = kind {
+                tray_icon::notify_balloon(
+                    hwnd,
+                    kind,
+                    strings.crash_title,
+                    strings.crash_restarted_body,
+                );
+            }
+        }
 
         // Position and show (only if widget_visible preference is true)
         position_at_taskbar();
@@ -1367,7 +3540,7 @@ pub fn run() {
             let state = lock_state();
             state
                 .as_ref()
-                .map(|s| s.poll_interval_ms)
+                .map(effective_poll_interval)
                 .unwrap_or(POLL_15_MIN)
         };
         SetTimer(hwnd, TIMER_POLL, initial_poll_ms, None);
@@ -1381,23 +3554,26 @@ pub fn run() {
 
         // Initial poll
         let send_hwnd = SendHwnd::from_hwnd(hwnd);
-        std::thread::spawn(move || {
-            diagnose::log("initial poll thread started");
-            do_poll(send_hwnd);
-        });
+        poll_worker::start(move |ticket| do_poll(send_hwnd, ticket));
+        diagnose::log("initial poll requested");
+        poll_worker::request();
 
         schedule_auto_update_check(hwnd);
         let should_check_updates = {
             let state = lock_state();
             state
                 .as_ref()
-                .map(|s| auto_update_check_due(s.last_update_check_unix))
+                .map(|s| auto_update_check_due(s.poll.last_update_check_unix))
                 .unwrap_or(false)
         };
         if should_check_updates {
             begin_update_check(hwnd, false);
         }
 
+        if !settings.history_backfill_offered {
+            let _ = PostMessageW(hwnd, WM_APP_OFFER_BACKFILL, WPARAM(0), LPARAM(0));
+        }
+
         // Initial theme check
         check_theme_change();
 
@@ -1407,6 +3583,9 @@ pub fn run() {
             let _ = TranslateMessage(&msg);
             DispatchMessageW(&msg);
         }
+
+        // Any other way out of the loop has not stopped the worker yet.
+        poll_worker::shutdown();
     }
 }
 
@@ -1415,57 +3594,21 @@ pub fn run() {
 /// ClearType sub-pixel font rendering can be used for crisp, OS-native text.
 fn render_layered() {
     refresh_dpi();
-    let (
-        hwnd_val,
-        is_dark,
-        embedded,
-        strings,
-        session_pct,
-        session_text,
-        weekly_pct,
-        weekly_text,
-        codex_session_pct,
-        codex_session_text,
-        codex_weekly_pct,
-        codex_weekly_text,
-        antigravity_session_pct,
-        antigravity_session_text,
-        antigravity_weekly_pct,
-        antigravity_weekly_text,
-        show_claude_code,
-        show_codex,
-        show_antigravity,
-    ) = {
+    if defer_render_if_hidden() {
+        return;
+    }
+    let (hwnd_val, embedded, opacity) = {
         let state = lock_state();
         match state.as_ref() {
-            Some(s) => (
-                s.hwnd,
-                s.is_dark,
-                s.embedded,
-                s.language.strings(),
-                s.session_percent,
-                s.session_text.clone(),
-                s.weekly_percent,
-                s.weekly_text.clone(),
-                s.codex_session_percent,
-                s.codex_session_text.clone(),
-                s.codex_weekly_percent,
-                s.codex_weekly_text.clone(),
-                s.antigravity_session_percent,
-                s.antigravity_session_text.clone(),
-                s.antigravity_weekly_percent,
-                s.antigravity_weekly_text.clone(),
-                s.show_claude_code,
-                s.show_codex,
-                s.show_antigravity,
-            ),
+            Some(s) => (s.handles.hwnd, s.handles.embedded, s.ui.opacity),
             None => return,
         }
     };
 
     let hwnd = hwnd_val.to_hwnd();
 
-    // For non-embedded fallback, just invalidate and let WM_PAINT handle it
+    // For non-embedded fallback, just invalidate and let WM_PAINT handle it;
+    // its opacity is the window's layered attribute.
     if !embedded {
         unsafe {
             let _ = InvalidateRect(hwnd, None, false);
@@ -1473,1390 +3616,5161 @@ fn render_layered() {
         return;
     }
 
-    let width = total_widget_width();
-    let height = sc(WIDGET_HEIGHT);
-
-    let accent = claude_accent_color();
-    let codex_accent = codex_accent_color(is_dark);
-    let antigravity_accent = antigravity_accent_color();
-    let track = if is_dark {
-        Color::from_hex("#444444")
-    } else {
-        Color::from_hex("#AAAAAA")
-    };
-    let text_color = if is_dark {
-        Color::from_hex("#888888")
-    } else {
-        Color::from_hex("#404040")
-    };
-    let bg_color = if is_dark {
-        Color::from_hex("#1C1C1C")
-    } else {
-        Color::from_hex("#F3F3F3")
-    };
-
     unsafe {
         let screen_dc = GetDC(hwnd);
+        if let Some(frame) = render_widget_frame(screen_dc) {
+            // Push to window via UpdateLayeredWindow
+            let pt_src = POINT { x: 0, y: 0 };
+            let sz = SIZE {
+                cx: frame.width,
+                cy: frame.height,
+            };
+            let blend = BLENDFUNCTION {
+                BlendOp: 0, // AC_SRC_OVER
+                BlendFlags: 0,
+                SourceConstantAlpha: opacity_alpha(opacity),
+                AlphaFormat: 1, // AC_SRC_ALPHA
+            };
 
-        let bmi = BITMAPINFO {
-            bmiHeader: BITMAPINFOHEADER {
-                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
-                biWidth: width,
-                biHeight: -height, // top-down
-                biPlanes: 1,
-                biBitCount: 32,
-                biCompression: 0, // BI_RGB
-                ..Default::default()
-            },
-            ..Default::default()
-        };
+            let _ = UpdateLayeredWindow(
+                hwnd,
+                screen_dc,
+                None,
+                Some(&sz),
+                frame.mem_dc,
+                Some(&pt_src),
+                COLORREF(0),
+                Some(&blend),
+                ULW_ALPHA,
+            );
+            recycle_widget_frame(frame);
+        }
+        ReleaseDC(hwnd, screen_dc);
+    }
+}
 
-        let mut bits: *mut std::ffi::c_void = std::ptr::null_mut();
-        let mem_dc = CreateCompatibleDC(screen_dc);
-        let dib =
-            CreateDIBSection(mem_dc, &bmi, DIB_RGB_COLORS, &mut bits, None, 0).unwrap_or_default();
+/// `opacity` percent as a 0-255 alpha.
+fn opacity_alpha(opacity: u8) -> u8 {
+    (u32::from(opacity.min(100)) * 255 / 100) as u8
+}
 
-        if dib.is_invalid() || bits.is_null() {
-            let _ = DeleteDC(mem_dc);
-            ReleaseDC(hwnd, screen_dc);
-            return;
+/// Apply the opacity setting. The embedded widget picks it up on its next
+/// `UpdateLayeredWindow`; the floating one keeps it as a window attribute.
+fn apply_opacity() {
+    let (hwnd, embedded, opacity) = {
+        let state = lock_state();
+        match state.as_ref() {
+            Some(s) => (s.handles.hwnd.to_hwnd(), s.handles.embedded, s.ui.opacity),
+            None => return,
+        }
+    };
+    if !embedded {
+        unsafe {
+            let _ =
+                SetLayeredWindowAttributes(hwnd, COLORREF(0), opacity_alpha(opacity), LWA_ALPHA);
         }
+    }
+    render_layered();
+}
 
-        let old_bmp = SelectObject(mem_dc, dib);
-        let pixel_count = (width * height) as usize;
+/// The widget painted into a 32-bit top-down DIB selected into its own memory
+/// DC. Pixels are premultiplied BGRA, ready for `UpdateLayeredWindow`.
+struct WidgetFrame {
+    mem_dc: HDC,
+    dib: HBITMAP,
+    old_bmp: HGDIOBJ,
+    bits: *mut u32,
+    width: i32,
+    height: i32,
+}
 
-        // Render once with the actual taskbar background colour.
-        // Using an opaque background lets us use CLEARTYPE_QUALITY for
-        // sub-pixel font rendering that matches the rest of the OS.
-        paint_content(
-            mem_dc,
-            width,
-            height,
-            is_dark,
-            &bg_color,
-            &text_color,
-            &accent,
-            &track,
-            strings,
-            session_pct,
-            &session_text,
-            weekly_pct,
-            &weekly_text,
-            codex_session_pct,
-            &codex_session_text,
-            codex_weekly_pct,
-            &codex_weekly_text,
-            antigravity_session_pct,
-            &antigravity_session_text,
-            antigravity_weekly_pct,
-            &antigravity_weekly_text,
-            show_claude_code,
-            show_codex,
-            show_antigravity,
-            &codex_accent,
-            &antigravity_accent,
-        );
+impl WidgetFrame {
+    /// Create a `width` x `height` frame compatible with `screen_dc`.
+    fn new(screen_dc: HDC, width: i32, height: i32) -> Option<WidgetFrame> {
+        unsafe {
+            let bmi = BITMAPINFO {
+                bmiHeader: BITMAPINFOHEADER {
+                    biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                    biWidth: width,
+                    biHeight: -height, // top-down
+                    biPlanes: 1,
+                    biBitCount: 32,
+                    biCompression: 0, // BI_RGB
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
 
-        // Background pixels → alpha 1 (nearly invisible but still hittable for right-click).
-        // Content pixels → fully opaque (preserves ClearType sub-pixel rendering).
-        let bg_bgr = bg_color.to_colorref();
-        let pixel_data = std::slice::from_raw_parts_mut(bits as *mut u32, pixel_count);
-        for px in pixel_data.iter_mut() {
-            let rgb = *px & 0x00FFFFFF;
-            if rgb == bg_bgr {
-                *px = 0x01000000;
-            } else {
-                *px = rgb | 0xFF000000;
+            let mut bits: *mut std::ffi::c_void = std::ptr::null_mut();
+            let mem_dc = CreateCompatibleDC(screen_dc);
+            let dib = CreateDIBSection(mem_dc, &bmi, DIB_RGB_COLORS, &mut bits, None, 0)
+                .unwrap_or_default();
+
+            if dib.is_invalid() || bits.is_null() {
+                let _ = DeleteDC(mem_dc);
+                return None;
             }
-        }
 
-        // Push to window via UpdateLayeredWindow
-        let pt_src = POINT { x: 0, y: 0 };
-        let sz = SIZE {
-            cx: width,
-            cy: height,
-        };
-        let blend = BLENDFUNCTION {
-            BlendOp: 0, // AC_SRC_OVER
-            BlendFlags: 0,
-            SourceConstantAlpha: 255,
-            AlphaFormat: 1, // AC_SRC_ALPHA
-        };
+            let old_bmp = SelectObject(mem_dc, dib);
+            Some(WidgetFrame {
+                mem_dc,
+                dib,
+                old_bmp,
+                bits: bits as *mut u32,
+                width,
+                height,
+            })
+        }
+    }
 
-        let _ = UpdateLayeredWindow(
-            hwnd,
-            screen_dc,
-            None,
-            Some(&sz),
-            mem_dc,
-            Some(&pt_src),
-            COLORREF(0),
-            Some(&blend),
-            ULW_ALPHA,
-        );
+    fn pixels(&mut self) -> &mut [u32] {
+        unsafe { std::slice::from_raw_parts_mut(self.bits, (self.width * self.height) as usize) }
+    }
+}
 
-        // Cleanup
-        SelectObject(mem_dc, old_bmp);
-        let _ = DeleteObject(dib);
-        let _ = DeleteDC(mem_dc);
-        ReleaseDC(hwnd, screen_dc);
+impl Drop for WidgetFrame {
+    fn drop(&mut self) {
+        unsafe {
+            SelectObject(self.mem_dc, self.old_bmp);
+            let _ = DeleteObject(self.dib);
+            let _ = DeleteDC(self.mem_dc);
+        }
     }
 }
 
-/// Paint all widget content onto a DC with a given background color.
-fn paint_content(
-    hdc: HDC,
-    width: i32,
-    height: i32,
+/// The fonts and brushes `paint_content` draws with, for one widget scale
+/// and background colour.
+struct PaintResources {
+    /// Follows the DPI and the taskbar fit.
+    font_height: i32,
     is_dark: bool,
-    bg: &Color,
-    text_color: &Color,
-    accent: &Color,
-    track: &Color,
-    strings: Strings,
-    session_pct: f64,
-    session_text: &str,
-    weekly_pct: f64,
-    weekly_text: &str,
-    codex_session_pct: f64,
-    codex_session_text: &str,
-    codex_weekly_pct: f64,
-    codex_weekly_text: &str,
-    antigravity_session_pct: f64,
-    antigravity_session_text: &str,
-    antigravity_weekly_pct: f64,
-    antigravity_weekly_text: &str,
-    show_claude_code: bool,
-    show_codex: bool,
-    show_antigravity: bool,
-    codex_accent: &Color,
-    antigravity_accent: &Color,
-) {
-    unsafe {
-        let client_rect = RECT {
-            left: 0,
-            top: 0,
-            right: width,
-            bottom: height,
-        };
-
-        let bg_brush = CreateSolidBrush(COLORREF(bg.to_colorref()));
-        FillRect(hdc, &client_rect, bg_brush);
-        let _ = DeleteObject(bg_brush);
-
-        // Left divider
-        let divider_h = sc(25);
-        let divider_top = (height - divider_h) / 2;
-        let divider_bottom = divider_top + divider_h;
+    bg: Color,
+    /// Greyscale instead of ClearType text, which only looks right over an
+    /// opaque background.
+    greyscale: bool,
+    font: HFONT,
+    /// For emphasized usage text.
+    bold_font: HFONT,
+    background: HBRUSH,
+    divider_left: HBRUSH,
+    divider_right: HBRUSH,
+    /// Bar, track and underline colours, filled in as they are drawn.
+    brushes: Brushes,
+}
 
+impl PaintResources {
+    fn new(is_dark: bool, bg: &Color, greyscale: bool) -> PaintResources {
+        let quality = if greyscale {
+            ANTIALIASED_QUALITY
+        } else {
+            CLEARTYPE_QUALITY
+        };
         let (div_left, div_right) = if is_dark {
             ((80, 80, 80), (40, 40, 40))
         } else {
             ((160, 160, 160), (230, 230, 230))
         };
 
-        let left_brush = CreateSolidBrush(COLORREF(native_interop::colorref(
-            div_left.0, div_left.1, div_left.2,
-        )));
-        let left_rect = RECT {
-            left: 0,
-            top: divider_top,
-            right: sc(2),
-            bottom: divider_bottom,
-        };
-        FillRect(hdc, &left_rect, left_brush);
-        let _ = DeleteObject(left_brush);
-
-        let right_brush = CreateSolidBrush(COLORREF(native_interop::colorref(
-            div_right.0,
-            div_right.1,
-            div_right.2,
-        )));
-        let right_rect = RECT {
-            left: sc(2),
-            top: divider_top,
-            right: sc(3),
-            bottom: divider_bottom,
-        };
-        FillRect(hdc, &right_rect, right_brush);
-        let _ = DeleteObject(right_brush);
-
-        let content_x = sc(LEFT_DIVIDER_W) + sc(DIVIDER_RIGHT_MARGIN);
-        let row2_y = height - sc(5) - sc(SEGMENT_H);
-        let row1_y = row2_y - sc(10) - sc(SEGMENT_H);
+        unsafe {
+            let font_height = sc(-12);
+            let font = create_widget_font(font_height, false, quality);
+            let bold_font = create_widget_font(font_height, true, quality);
 
-        let _ = SetBkMode(hdc, TRANSPARENT);
-        let _ = SetTextColor(hdc, COLORREF(text_color.to_colorref()));
+            PaintResources {
+                font_height,
+                is_dark,
+                bg: *bg,
+                greyscale,
+                font,
+                bold_font,
+                background: CreateSolidBrush(COLORREF(bg.to_colorref())),
+                divider_left: CreateSolidBrush(COLORREF(native_interop::colorref(
+                    div_left.0, div_left.1, div_left.2,
+                ))),
+                divider_right: CreateSolidBrush(COLORREF(native_interop::colorref(
+                    div_right.0,
+                    div_right.1,
+                    div_right.2,
+                ))),
+                brushes: Brushes::default(),
+            }
+        }
+    }
+
+    fn matches(&self, is_dark: bool, bg: &Color, greyscale: bool) -> bool {
+        self.font_height == sc(-12)
+            && self.is_dark == is_dark
+            && self.bg == *bg
+            && self.greyscale == greyscale
+    }
+}
+
+impl Drop for PaintResources {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = DeleteObject(self.font);
+            let _ = DeleteObject(self.bold_font);
+            let _ = DeleteObject(self.background);
+            let _ = DeleteObject(self.divider_left);
+            let _ = DeleteObject(self.divider_right);
+        }
+    }
+}
 
+/// The Segoe UI font the widget is drawn with, `height` pixels tall.
+fn create_widget_font(height: i32, bold: bool, quality: FONT_QUALITY) -> HFONT {
+    let weight = if bold { FW_BOLD } else { FW_MEDIUM };
+    unsafe {
         let font_name = native_interop::wide_str("Segoe UI");
-        let font = CreateFontW(
-            sc(-12),
+        CreateFontW(
+            height,
             0,
             0,
             0,
-            FW_MEDIUM.0 as i32,
+            weight.0 as i32,
             0,
             0,
             0,
             DEFAULT_CHARSET.0 as u32,
             OUT_TT_PRECIS.0 as u32,
             CLIP_DEFAULT_PRECIS.0 as u32,
-            CLEARTYPE_QUALITY.0 as u32,
+            quality.0 as u32,
             (DEFAULT_PITCH.0 | FF_DONTCARE.0) as u32,
             PCWSTR::from_raw(font_name.as_ptr()),
-        );
-        let old_font = SelectObject(hdc, font);
+        )
+    }
+}
 
-        draw_row(
-            hdc,
-            content_x,
-            row1_y,
-            is_dark,
-            text_color,
-            strings.session_window,
-            session_pct,
-            session_text,
-            codex_session_pct,
-            codex_session_text,
-            antigravity_session_pct,
-            antigravity_session_text,
-            show_claude_code,
-            show_codex,
-            show_antigravity,
-            accent,
-            codex_accent,
-            antigravity_accent,
-            track,
-        );
-        draw_row(
+/// GDI objects kept between redraws, since the countdown can repaint every
+/// second. The frame is recreated when the widget size changes and the paint
+/// resources when the DPI or theme does.
+struct RenderCache {
+    frame: Option<WidgetFrame>,
+    resources: Option<PaintResources>,
+}
+
+unsafe impl Send for RenderCache {}
+
+impl RenderCache {
+    fn resources(&mut self, is_dark: bool, bg: &Color, greyscale: bool) -> &PaintResources {
+        if !self
+            .resources
+            .as_ref()
+            .is_some_and(|resources| resources.matches(is_dark, bg, greyscale))
+        {
+            self.resources = None;
+        }
+        self.resources
+            .get_or_insert_with(|| PaintResources::new(is_dark, bg, greyscale))
+    }
+}
+
+static RENDER_CACHE: Mutex<RenderCache> = Mutex::new(RenderCache {
+    frame: None,
+    resources: None,
+});
+
+fn lock_render_cache() -> MutexGuard<'static, RenderCache> {
+    RENDER_CACHE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Hand a frame from `render_widget_frame` back for the next redraw.
+fn recycle_widget_frame(frame: WidgetFrame) {
+    lock_render_cache().frame = Some(frame);
+}
+
+/// Everything a render needs, captured under the state lock so painting runs
+/// without it.
+struct RenderSnapshot {
+    colors: TaskbarColors,
+    rows: Vec<WidgetRow>,
+    layout: WidgetLayout,
+    show_claude_code: bool,
+    show_codex: bool,
+    show_antigravity: bool,
+    /// Paint with per-pixel alpha so a translucent taskbar shows through.
+    blend: bool,
+    click_through: bool,
+    palette: Palette,
+    text_only: bool,
+    /// In the lit step of an alert blink.
+    flash: bool,
+    width: i32,
+    height: i32,
+}
+
+impl RenderSnapshot {
+    fn capture(state: &AppState) -> RenderSnapshot {
+        RenderSnapshot {
+            colors: TaskbarColors::new(state.ui.is_dark, state.ui.taskbar_accent),
+            rows: widget_rows(state),
+            layout: widget_layout(state),
+            show_claude_code: state.ui.show_claude_code,
+            show_codex: state.ui.show_codex,
+            show_antigravity: state.ui.show_antigravity,
+            blend: state.ui.blend_with_taskbar && !state.ui.card_background,
+            click_through: state.ui.click_through_background,
+            palette: state.ui.palette,
+            text_only: state.ui.display_mode.text_only(),
+            flash: state.ui.flash_steps % 2 == 1,
+            width: total_widget_width_for_state(state),
+            height: widget_height_for_state(state),
+        }
+    }
+
+    /// The colour behind the content: the card's, or the taskbar's.
+    fn background(&self) -> Color {
+        if self.flash {
+            return self.palette.claude_accent();
+        }
+        match (self.layout.card_background, self.colors.is_dark) {
+            (true, true) => Color::from_hex("#2C2C2C"),
+            (true, false) => Color::from_hex("#FFFFFF"),
+            (false, _) => self.colors.backdrop,
+        }
+    }
+
+    /// Paint the widget into `hdc` over `background`.
+    fn paint(&self, hdc: HDC, resources: &PaintResources, background: HBRUSH) {
+        let is_dark = self.colors.is_dark;
+        paint_content(
             hdc,
-            content_x,
-            row2_y,
-            is_dark,
-            text_color,
-            strings.weekly_window,
-            weekly_pct,
-            weekly_text,
-            codex_weekly_pct,
-            codex_weekly_text,
-            antigravity_weekly_pct,
-            antigravity_weekly_text,
-            show_claude_code,
-            show_codex,
-            show_antigravity,
-            accent,
-            codex_accent,
-            antigravity_accent,
-            track,
+            self.width,
+            self.height,
+            resources,
+            background,
+            &self.colors.text,
+            &self.palette.claude_accent(),
+            &self.colors.track,
+            &self.rows,
+            self.show_claude_code,
+            self.show_codex,
+            self.show_antigravity,
+            &codex_accent_color(is_dark),
+            &self.palette.antigravity_accent(),
+            self.palette,
+            self.text_only,
+            &self.layout,
         );
-
-        SelectObject(hdc, old_font);
-        let _ = DeleteObject(font);
     }
 }
 
-fn do_poll(send_hwnd: SendHwnd) {
-    let hwnd = send_hwnd.to_hwnd();
-    let (show_claude_code, show_codex, show_antigravity) = {
+/// Paint the current widget contents off-screen, compatible with `screen_dc`.
+/// The frame is taken out of the cache so the caller can use it without
+/// holding the lock; pass it to `recycle_widget_frame` when done.
+fn render_widget_frame(screen_dc: HDC) -> Option<WidgetFrame> {
+    let snapshot = {
         let state = lock_state();
-        state
-            .as_ref()
-            .map(|s| (s.show_claude_code, s.show_codex, s.show_antigravity))
-            .unwrap_or((true, false, false))
+        RenderSnapshot::capture(state.as_ref()?)
     };
+    let (width, height) = (snapshot.width, snapshot.height);
+    let bg_color = snapshot.background();
 
-    match poller::poll(show_claude_code, show_codex, show_antigravity) {
-        Ok(data) => {
-            let mut state = lock_state();
-            if let Some(s) = state.as_mut() {
-                if let Some(claude_code) = data.claude_code.as_ref() {
-                    s.session_percent = claude_code.session.percentage;
-                    s.weekly_percent = claude_code.weekly.percentage;
-                } else if s.show_claude_code {
-                    s.session_percent = 0.0;
-                    s.weekly_percent = 0.0;
-                }
-                if let Some(codex) = data.codex.as_ref() {
-                    s.codex_session_percent = codex.session.percentage;
-                    s.codex_weekly_percent = codex.weekly.percentage;
-                } else if s.show_codex {
-                    s.codex_session_percent = 0.0;
-                    s.codex_weekly_percent = 0.0;
-                }
-                if let Some(antigravity) = data.antigravity.as_ref() {
-                    s.antigravity_session_percent = antigravity.session.percentage;
-                    s.antigravity_weekly_percent = antigravity.weekly.percentage;
-                } else if s.show_antigravity {
-                    s.antigravity_session_percent = 0.0;
-                    s.antigravity_weekly_percent = 0.0;
-                }
-                // Stop fast-poll if reset data is now fresh
-                if !poller::app_is_past_reset(&data) {
-                    unsafe {
-                        let _ = KillTimer(hwnd, TIMER_RESET_POLL);
-                    }
-                }
+    {
+        let mut cache = lock_render_cache();
+        let mut frame = match cache.frame.take() {
+            Some(frame) if frame.width == width && frame.height == height => frame,
+            _ => WidgetFrame::new(screen_dc, width, height)?,
+        };
+        let resources = cache.resources(snapshot.colors.is_dark, &bg_color, snapshot.blend);
+        let mem_dc = frame.mem_dc;
+        let paint = |background: HBRUSH| snapshot.paint(mem_dc, resources, background);
+
+        if snapshot.blend {
+            // Paint over black and then white: how far each pixel moves
+            // between the two gives its coverage.
+            let (black, white) =
+                unsafe { (GetStockObject(BLACK_BRUSH), GetStockObject(WHITE_BRUSH)) };
+            paint(HBRUSH(black.0));
+            let over_black = frame.pixels().to_vec();
+            paint(HBRUSH(white.0));
+            drop(cache);
+            apply_blended_alpha(frame.pixels(), &over_black);
+            if snapshot.click_through {
+                clear_empty_background(frame.pixels(), width, height, snapshot.layout.mirrored);
+            }
+            return Some(frame);
+        }
 
-                s.data = Some(data);
-                s.last_poll_ok = true;
-                refresh_usage_texts(s);
+        // Render once with the actual taskbar background colour.
+        // Using an opaque background lets us use CLEARTYPE_QUALITY for
+        // sub-pixel font rendering that matches the rest of the OS.
+        paint(resources.background);
+        drop(cache);
 
-                // Recovered from errors — restore normal poll interval
-                if s.retry_count > 0 {
-                    s.retry_count = 0;
-                    let interval = s.poll_interval_ms;
-                    unsafe {
-                        SetTimer(hwnd, TIMER_POLL, interval, None);
-                    }
+        // Background pixels → alpha 1 (nearly invisible but still hittable for right-click).
+        // Content pixels → fully opaque (preserves ClearType sub-pixel rendering).
+        let bg_bgr = bg_color.to_colorref();
+        let pixel_data = frame.pixels();
+        if snapshot.layout.card_background {
+            apply_card_alpha(
+                pixel_data,
+                width,
+                height,
+                bg_color,
+                snapshot.colors.is_dark,
+                snapshot.layout.mirrored,
+            );
+        } else {
+            for px in pixel_data.iter_mut() {
+                let rgb = *px & 0x00FFFFFF;
+                if rgb == bg_bgr {
+                    *px = 0x01000000;
+                } else {
+                    *px = rgb | 0xFF000000;
                 }
-                s.force_notify_auth_error = false;
-                s.auth_error_paused_polling = false;
-                s.auth_watch_mode = poller::CredentialWatchMode::ActiveSource;
-                s.auth_watch_snapshot.clear();
-            }
-
-            unsafe {
-                let _ = PostMessageW(hwnd, WM_APP_USAGE_UPDATED, WPARAM(0), LPARAM(0));
             }
         }
-        Err(e) => {
-            let auth_watch = match e {
-                poller::PollError::AuthRequired | poller::PollError::TokenExpired
-                    if show_antigravity && !show_claude_code && !show_codex =>
-                {
-                    Some((
-                        poller::CredentialWatchMode::Antigravity,
-                        poller::credential_watch_snapshot(poller::CredentialWatchMode::Antigravity),
-                    ))
-                }
-                poller::PollError::AuthRequired | poller::PollError::TokenExpired => Some((
-                    poller::CredentialWatchMode::ActiveSource,
-                    poller::credential_watch_snapshot(poller::CredentialWatchMode::ActiveSource),
-                )),
-                poller::PollError::NoCredentials => Some((
-                    poller::CredentialWatchMode::AllSources,
-                    poller::credential_watch_snapshot(poller::CredentialWatchMode::AllSources),
-                )),
-                poller::PollError::RequestFailed => None,
-            };
-            // Distinguish auth-required errors from transient errors.
-            let notify_auth_error = {
-                let mut state = lock_state();
-                let mut should_notify = false;
-                if let Some(s) = state.as_mut() {
-                    s.last_poll_ok = false;
-                    match auth_watch {
-                        Some((watch_mode, watch_snapshot)) => {
-                            // Only show the balloon on the first failure so it doesn't spam.
-                            if s.retry_count == 0 || s.force_notify_auth_error {
-                                should_notify = true;
-                            }
-                            s.force_notify_auth_error = false;
-                            s.auth_error_paused_polling = true;
-                            s.auth_watch_mode = watch_mode;
-                            s.auth_watch_snapshot = watch_snapshot;
-                            s.session_text = "!".to_string();
-                            s.weekly_text = "!".to_string();
-                            s.codex_session_text = "!".to_string();
-                            s.codex_weekly_text = "!".to_string();
-                            s.antigravity_session_text = "!".to_string();
-                            s.antigravity_weekly_text = "!".to_string();
-                            s.retry_count = s.retry_count.saturating_add(1);
-                            unsafe {
-                                let _ = KillTimer(hwnd, TIMER_POLL);
-                                let _ = KillTimer(hwnd, TIMER_RESET_POLL);
-                                let _ = KillTimer(hwnd, TIMER_COUNTDOWN);
-                                SetTimer(hwnd, TIMER_POLL, s.poll_interval_ms, None);
-                            }
-                        }
-                        _ => {
-                            // Transient network / credential-missing errors: exponential backoff.
-                            s.force_notify_auth_error = false;
-                            s.auth_error_paused_polling = false;
-                            s.auth_watch_mode = poller::CredentialWatchMode::ActiveSource;
-                            s.auth_watch_snapshot.clear();
-                            s.session_text = "...".to_string();
-                            s.weekly_text = "...".to_string();
-                            s.codex_session_text = "...".to_string();
-                            s.codex_weekly_text = "...".to_string();
-                            s.antigravity_session_text = "...".to_string();
-                            s.antigravity_weekly_text = "...".to_string();
-                            s.retry_count = s.retry_count.saturating_add(1);
-                            let backoff = RETRY_BASE_MS.saturating_mul(
-                                1u32.checked_shl(s.retry_count - 1).unwrap_or(u32::MAX),
-                            );
-                            let retry_ms = backoff.min(s.poll_interval_ms);
-                            unsafe {
-                                let _ = KillTimer(hwnd, TIMER_RESET_POLL);
-                                SetTimer(hwnd, TIMER_POLL, retry_ms, None);
-                            }
-                        }
-                    }
-                }
-                should_notify
-            };
-
-            if notify_auth_error {
-                let balloon = {
-                    let state = lock_state();
-                    state.as_ref().map(|s| {
-                        if s.show_claude_code {
-                            (
-                                s.language.strings(),
-                                tray_icon::TrayIconKind::Claude,
-                                s.language.strings().token_expired_title,
-                                s.language.strings().token_expired_body,
-                            )
-                        } else if s.show_codex {
-                            (
-                                s.language.strings(),
-                                tray_icon::TrayIconKind::Codex,
-                                s.language.strings().codex_token_expired_title,
-                                s.language.strings().codex_token_expired_body,
-                            )
-                        } else {
-                            (
-                                s.language.strings(),
-                                tray_icon::TrayIconKind::Antigravity,
-                                s.language.strings().antigravity_token_expired_title,
-                                s.language.strings().antigravity_token_expired_body,
-                            )
-                        }
-                    })
-                };
-                if let Some((_strings, kind, title, body)) = balloon {
-                    tray_icon::notify_balloon(hwnd, kind, title, body);
-                }
-            }
-
-            unsafe {
-                let _ = PostMessageW(hwnd, WM_APP_USAGE_UPDATED, WPARAM(0), LPARAM(0));
-            }
+        if snapshot.click_through {
+            clear_empty_background(pixel_data, width, height, snapshot.layout.mirrored);
         }
+
+        Some(frame)
     }
 }
 
-fn schedule_countdown_timer() {
-    let state = lock_state();
-    let s = match state.as_ref() {
-        Some(s) => s,
-        None => return,
+/// The widget as it currently looks, composited onto the plain taskbar colour
+/// so the card and anti-aliased edges read the same outside the taskbar.
+/// Returns (width, height, straight RGBA pixels).
+fn widget_snapshot() -> Option<(u32, u32, Vec<u8>)> {
+    let backdrop = lock_state()
+        .as_ref()
+        .map(|s| TaskbarColors::new(s.ui.is_dark, s.ui.taskbar_accent).backdrop)?;
+
+    let mut frame = unsafe {
+        let screen_dc = GetDC(None);
+        let frame = render_widget_frame(screen_dc);
+        ReleaseDC(None, screen_dc);
+        frame?
     };
-
-    let hwnd = s.hwnd.to_hwnd();
-    if !s.last_poll_ok {
-        unsafe {
-            let _ = KillTimer(hwnd, TIMER_COUNTDOWN);
-            let _ = KillTimer(hwnd, TIMER_RESET_POLL);
-        }
-        return;
+    let (width, height) = (frame.width as u32, frame.height as u32);
+
+    let mut rgba = Vec::with_capacity(frame.pixels().len() * 4);
+    for &px in frame.pixels().iter() {
+        // Premultiplied source over the opaque backdrop.
+        let alpha = (px >> 24) & 0xFF;
+        let over = |channel: u32, backdrop: u8| {
+            (channel + backdrop as u32 * (255 - alpha) / 255).min(255) as u8
+        };
+        rgba.extend_from_slice(&[
+            over((px >> 16) & 0xFF, backdrop.r),
+            over((px >> 8) & 0xFF, backdrop.g),
+            over(px & 0xFF, backdrop.b),
+            255,
+        ]);
     }
+    recycle_widget_frame(frame);
+    Some((width, height, rgba))
+}
 
-    let data = match &s.data {
-        Some(d) => d,
-        None => return,
-    };
+/// Render the widget for each theme, DPI and usage level in the golden-image
+/// matrix, off-screen and without a taskbar. Images missing from `dir` are
+/// written there; existing ones are compared, and a differing render is saved
+/// next to its golden image as `.actual.png`. Returns 1 if any image differs.
+fn render_golden_images(dir: &Path) -> i32 {
+    if let Err(error) = std::fs::create_dir_all(dir) {
+        eprintln!("Unable to create {}: {error}", dir.display());
+        return 1;
+    }
 
-    // If a reset time has passed, poll every 5s to pick up fresh data
-    if poller::app_is_past_reset(data) {
-        unsafe {
-            SetTimer(hwnd, TIMER_RESET_POLL, 5_000, None);
+    let mut failures = 0;
+    for dpi in [96, 120, 144, 192] {
+        CURRENT_DPI.store(dpi, Ordering::Relaxed);
+        for is_dark in [true, false] {
+            for percent in [0.0, 42.0, 85.0, 100.0] {
+                let name = format!(
+                    "widget-{}-{dpi}dpi-{percent:.0}.png",
+                    if is_dark { "dark" } else { "light" }
+                );
+                let Some((width, height, rgba)) = render_golden_frame(is_dark, percent) else {
+                    eprintln!("{name}: unable to render");
+                    failures += 1;
+                    continue;
+                };
+                let png = png::encode_rgba(width, height, &rgba);
+                let path = dir.join(&name);
+                let result = match std::fs::read(&path) {
+                    // The encoder does not compress, so equal bytes are equal pixels.
+                    Ok(golden) if golden == png => Ok("matches"),
+                    Ok(_) => {
+                        std::fs::write(path.with_extension("actual.png"), &png).map(|_| "DIFFERS")
+                    }
+                    Err(_) => std::fs::write(&path, &png).map(|_| "written"),
+                };
+                match result {
+                    Ok(status) => {
+                        println!("{name}: {status}");
+                        failures += (status == "DIFFERS") as i32;
+                    }
+                    Err(error) => {
+                        eprintln!("{name}: {error}");
+                        failures += 1;
+                    }
+                }
+            }
         }
     }
+    (failures > 0) as i32
+}
 
-    let delays = [
-        data.claude_code
-            .as_ref()
-            .and_then(|usage| poller::time_until_display_change(usage.session.resets_at)),
-        data.claude_code
-            .as_ref()
-            .and_then(|usage| poller::time_until_display_change(usage.weekly.resets_at)),
-        data.codex
-            .as_ref()
-            .and_then(|usage| poller::time_until_display_change(usage.session.resets_at)),
-        data.codex
-            .as_ref()
-            .and_then(|usage| poller::time_until_display_change(usage.weekly.resets_at)),
-        data.antigravity
-            .as_ref()
-            .and_then(|usage| poller::time_until_display_change(usage.session.resets_at)),
-        data.antigravity
-            .as_ref()
-            .and_then(|usage| poller::time_until_display_change(usage.weekly.resets_at)),
-    ];
-    let min_delay = delays.into_iter().flatten().min();
-
-    let ms = min_delay
-        .unwrap_or(Duration::from_secs(60))
-        .as_millis()
-        .max(1000) as u32;
+/// One golden image: both rows of a single Claude column at `percent`, with
+/// fixed countdown text so the image never depends on the clock. Fonts are
+/// greyscale anti-aliased, as ClearType follows each user's display tuning.
+fn render_golden_frame(is_dark: bool, percent: f64) -> Option<(u32, u32, Vec<u8>)> {
+    let strings = LanguageId::English.strings();
+    let value = |fill: f64, text: String| RowValue {
+        fill,
+        text,
+        emphasized: false,
+        limit_reached: false,
+        binding: false,
+    };
+    let row = |label: &str, countdown: &str| WidgetRow {
+        label: label.to_string(),
+        claude: value(percent, format!("{percent:.0}% · {countdown}")),
+        codex: value(0.0, String::new()),
+        antigravity: value(0.0, String::new()),
+        secondary: None,
+    };
+    let layout = WidgetLayout::default();
+    let snapshot = RenderSnapshot {
+        colors: TaskbarColors::new(is_dark, None),
+        rows: vec![
+            row(strings.session_window, "2h 10m"),
+            row(strings.weekly_window, "3d 4h"),
+        ],
+        width: total_widget_width_for(1, false, &layout),
+        height: sc(WIDGET_HEIGHT),
+        layout,
+        show_claude_code: true,
+        show_codex: false,
+        show_antigravity: false,
+        blend: false,
+        click_through: false,
+        palette: Palette::Standard,
+        text_only: false,
+        flash: false,
+    };
+    let (width, height) = (snapshot.width, snapshot.height);
 
-    unsafe {
-        SetTimer(hwnd, TIMER_COUNTDOWN, ms, None);
-    }
+    let mut frame = unsafe {
+        let screen_dc = GetDC(None);
+        let frame = WidgetFrame::new(screen_dc, width, height);
+        ReleaseDC(None, screen_dc);
+        frame?
+    };
+    let resources = PaintResources::new(is_dark, &snapshot.background(), true);
+    snapshot.paint(frame.mem_dc, &resources, resources.background);
+
+    // GDI leaves the alpha byte undefined; the background is opaque.
+    let rgba = frame
+        .pixels()
+        .iter()
+        .flat_map(|&px| [(px >> 16) as u8, (px >> 8) as u8, px as u8, 255])
+        .collect();
+    Some((width as u32, height as u32, rgba))
 }
 
-fn check_theme_change() {
-    let new_dark = theme::is_dark_mode();
-    let changed = {
-        let mut state = lock_state();
-        if let Some(s) = state.as_mut() {
-            if s.is_dark != new_dark {
-                s.is_dark = new_dark;
-                true
-            } else {
-                false
-            }
-        } else {
-            false
-        }
+/// A packed, top-down 32-bit DIB for the CF_DIB clipboard format.
+fn snapshot_dib(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let header = BITMAPINFOHEADER {
+        biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+        biWidth: width as i32,
+        biHeight: -(height as i32),
+        biPlanes: 1,
+        biBitCount: 32,
+        biCompression: 0, // BI_RGB
+        ..Default::default()
     };
-    if changed {
-        render_layered();
+    let header_bytes = unsafe {
+        std::slice::from_raw_parts(
+            &header as *const BITMAPINFOHEADER as *const u8,
+            std::mem::size_of::<BITMAPINFOHEADER>(),
+        )
+    };
+    let mut dib = header_bytes.to_vec();
+    for px in rgba.chunks_exact(4) {
+        dib.extend_from_slice(&[px[2], px[1], px[0], px[3]]);
     }
+    dib
 }
 
-fn check_language_change() {
-    if update_language_change() {
-        render_layered();
+fn snapshot_strings() -> Strings {
+    let state = lock_state();
+    state
+        .as_ref()
+        .map(|s| s.ui.language.strings())
+        .unwrap_or_else(|| LanguageId::English.strings())
+}
+
+/// Put a snapshot of the widget on the clipboard, as PNG for apps that keep
+/// transparency-aware images and as a DIB for everything else.
+fn copy_snapshot(hwnd: HWND) {
+    let strings = snapshot_strings();
+    let result = widget_snapshot()
+        .ok_or_else(|| strings.snapshot_empty.to_string())
+        .and_then(|(width, height, rgba)| {
+            let png = png::encode_rgba(width, height, &rgba);
+            let dib = snapshot_dib(width, height, &rgba);
+            native_interop::set_clipboard(
+                hwnd,
+                &[
+                    (native_interop::register_clipboard_format("PNG"), &png),
+                    (native_interop::CF_DIB, &dib),
+                ],
+            )
+        });
+    if let Err(error) = result {
+        diagnose::log_error("unable to copy snapshot", &error);
+        show_error_message(hwnd, strings.snapshot_failed, &error);
     }
 }
 
-fn update_display() {
-    let mut state = lock_state();
-    let s = match state.as_mut() {
-        Some(s) => s,
-        None => return,
+/// Save a PNG snapshot of the widget to a file chosen by the user.
+fn save_snapshot(hwnd: HWND) {
+    let strings = snapshot_strings();
+    let Some((width, height, rgba)) = widget_snapshot() else {
+        show_error_message(hwnd, strings.snapshot_failed, strings.snapshot_empty);
+        return;
     };
-
-    // Don't overwrite error text with stale cached data
-    if !s.last_poll_ok {
+    let Some((path, _)) =
+        native_interop::save_file_dialog(hwnd, "claude-usage.png", &[("PNG (*.png)", "*.png")])
+    else {
         return;
-    }
+    };
 
-    refresh_usage_texts(s);
+    if let Err(error) = std::fs::write(&path, png::encode_rgba(width, height, &rgba)) {
+        diagnose::log_error("unable to save snapshot", &error);
+        show_error_message(
+            hwnd,
+            strings.snapshot_failed,
+            &format!("{}\n\n{error}", path.display()),
+        );
+    }
 }
 
-fn suppress_tray_reposition_for(duration: Duration) {
-    let mut until = SUPPRESS_TRAY_REPOSITION_UNTIL
-        .lock()
-        .unwrap_or_else(|e| e.into_inner());
-    *until = Some(Instant::now() + duration);
-}
+/// Give the background pixels of a frame rendered on `card` the shape of a
+/// rounded card with a soft shadow below it. Content pixels stay opaque, as in
+/// the plain layout, so text keeps its ClearType rendering.
+fn apply_card_alpha(
+    pixels: &mut [u32],
+    width: i32,
+    height: i32,
+    card: Color,
+    is_dark: bool,
+    mirrored: bool,
+) {
+    let card_bgr = card.to_colorref();
+    let (inset_left, inset_right) = if mirrored {
+        (RIGHT_MARGIN, CARD_INSET_LEFT)
+    } else {
+        (CARD_INSET_LEFT, RIGHT_MARGIN)
+    };
+    let left = sc(inset_left) as f64;
+    let top = sc(CARD_INSET_Y) as f64;
+    let right = (width - sc(inset_right)) as f64;
+    let bottom = (height - sc(CARD_INSET_Y)) as f64;
+    let radius = sc(CARD_RADIUS) as f64;
+    let shadow_size = sc(CARD_SHADOW_SIZE).max(1) as f64;
+    let shadow_offset = sc(1) as f64;
+    let shadow_alpha = if is_dark { 96.0 } else { 40.0 };
+
+    // Distance from a pixel centre to the rounded rectangle (negative inside).
+    let distance = |x: f64, y: f64| {
+        let half_w = (right - left) / 2.0 - radius;
+        let half_h = (bottom - top) / 2.0 - radius;
+        let dx = ((x - (left + right) / 2.0).abs() - half_w).max(0.0);
+        let dy = ((y - (top + bottom) / 2.0).abs() - half_h).max(0.0);
+        (dx * dx + dy * dy).sqrt() - radius
+    };
 
-fn tray_reposition_is_suppressed() -> bool {
-    let now = Instant::now();
-    let mut until = SUPPRESS_TRAY_REPOSITION_UNTIL
-        .lock()
-        .unwrap_or_else(|e| e.into_inner());
+    for y in 0..height {
+        for x in 0..width {
+            let px = &mut pixels[(y * width + x) as usize];
+            let rgb = *px & 0x00FFFFFF;
+            if rgb != card_bgr {
+                *px = rgb | 0xFF000000;
+                continue;
+            }
 
-    match *until {
-        Some(deadline) if now < deadline => true,
-        Some(_) => {
-            *until = None;
-            false
+            let (cx, cy) = (x as f64 + 0.5, y as f64 + 0.5);
+            let coverage = (0.5 - distance(cx, cy)).clamp(0.0, 1.0);
+            let shadow = (1.0 - distance(cx, cy - shadow_offset) / shadow_size).clamp(0.0, 1.0)
+                * shadow_alpha
+                * (1.0 - coverage);
+            let alpha = (coverage * 255.0 + shadow).round().clamp(1.0, 255.0) as u32;
+            // Premultiplied: the shadow is black, so only the card adds colour.
+            let premultiply = |channel: u32| (channel as f64 * coverage).round() as u32;
+            let b = premultiply(rgb & 0xFF);
+            let g = premultiply((rgb >> 8) & 0xFF);
+            let r = premultiply((rgb >> 16) & 0xFF);
+            *px = (alpha << 24) | (r << 16) | (g << 8) | b;
         }
-        None => false,
     }
 }
 
-fn position_at_taskbar() {
-    refresh_dpi();
-    // Drop the app-state lock before any Win32 call that may synchronously
-    // re-enter our window procedure.
-    let (hwnd, embedded, tray_offset, taskbar_hwnd) = {
-        let state = lock_state();
-        let s = match state.as_ref() {
-            Some(s) => s,
-            None => return,
-        };
-
-        // Don't fight the user's drag
-        if s.dragging {
-            return;
+/// Turn a frame painted over white into premultiplied per-pixel alpha, given
+/// the same frame painted over black. Fully transparent pixels keep alpha 1 so
+/// the widget still receives clicks there.
+fn apply_blended_alpha(over_white: &mut [u32], over_black: &[u32]) {
+    for (px, &black) in over_white.iter_mut().zip(over_black) {
+        let channels = [0, 8, 16].map(|shift| {
+            let on_black = (black >> shift) & 0xFF;
+            let on_white = (*px >> shift) & 0xFF;
+            (on_black, 255 - on_white.saturating_sub(on_black))
+        });
+        let alpha = channels.iter().map(|&(_, alpha)| alpha).max().unwrap_or(0);
+        if alpha == 0 {
+            *px = 0x01000000;
+            continue;
         }
+        // Over black the painted colour is already premultiplied.
+        let [b, g, r] = channels.map(|(on_black, _)| on_black.min(alpha));
+        *px = (alpha << 24) | (r << 16) | (g << 8) | b;
+    }
+}
 
-        let taskbar_hwnd = match s.taskbar_hwnd {
-            Some(h) => h,
-            None => {
-                diagnose::log("position_at_taskbar skipped: no taskbar handle");
-                return;
+/// Make the near-invisible background outside the drawn content fully
+/// transparent, so Windows passes clicks there through to the taskbar. The
+/// box around the content stays clickable, gaps between glyphs included, as
+/// does the drag strip at the divider edge.
+fn clear_empty_background(pixels: &mut [u32], width: i32, height: i32, mirrored: bool) {
+    let is_content = |px: u32| px >> 24 > 1;
+    let mut content: Option<RECT> = None;
+    for y in 0..height {
+        for x in 0..width {
+            if is_content(pixels[(y * width + x) as usize]) {
+                let rect = content.get_or_insert(RECT {
+                    left: x,
+                    top: y,
+                    right: x + 1,
+                    bottom: y + 1,
+                });
+                rect.left = rect.left.min(x);
+                rect.right = rect.right.max(x + 1);
+                rect.bottom = y + 1;
             }
-        };
-
-        (s.hwnd.to_hwnd(), s.embedded, s.tray_offset, taskbar_hwnd)
-    };
+        }
+    }
+    let content = content.unwrap_or_default();
+    let handle = sc(LEFT_DIVIDER_W);
+    for y in 0..height {
+        for x in 0..width {
+            let on_handle = if mirrored {
+                x >= width - handle
+            } else {
+                x < handle
+            };
+            let inside =
+                x >= content.left && x < content.right && y >= content.top && y < content.bottom;
+            let px = &mut pixels[(y * width + x) as usize];
+            if !on_handle && !inside && !is_content(*px) {
+                *px = 0;
+            }
+        }
+    }
+}
 
-    let taskbar_rect = match native_interop::get_taskbar_rect(taskbar_hwnd) {
-        Some(r) => r,
-        None => {
-            diagnose::log("position_at_taskbar skipped: unable to query taskbar rect");
-            return;
+/// Skip painting while nothing can see the widget: the session is locked or
+/// disconnected, or the auto-hide taskbar we are embedded in has slid off
+/// screen. The frame is marked dirty and rendered once when visibility returns.
+fn defer_render_if_hidden() -> bool {
+    let (hwnd, taskbar_hwnd, session_locked, quiet_when_fullscreen) = {
+        let state = lock_state();
+        match state.as_ref() {
+            Some(s) if s.handles.embedded => (
+                s.handles.hwnd.to_hwnd(),
+                s.handles.taskbar_hwnd,
+                s.ui.session_locked || s.poll.session_disconnected,
+                s.ui.quiet_when_fullscreen,
+            ),
+            _ => return false,
         }
     };
 
-    let taskbar_height = taskbar_rect.bottom - taskbar_rect.top;
-    let mut tray_left = taskbar_rect.right;
-    let anchor_top = taskbar_rect.top;
-    let anchor_height = taskbar_height;
-
-    if let Some(tray_hwnd) = native_interop::find_child_window(taskbar_hwnd, "TrayNotifyWnd") {
-        if let Some(tray_rect) = native_interop::get_window_rect_safe(tray_hwnd) {
-            tray_left = tray_rect.left;
+    // A full-screen app covers the taskbar, so treat it like an auto-hidden
+    // one and render once it is gone.
+    let taskbar_hidden = taskbar_hwnd.is_some_and(native_interop::is_taskbar_autohidden)
+        || (quiet_when_fullscreen && native_interop::is_fullscreen_app_active());
+    let hidden = session_locked || taskbar_hidden;
+    {
+        let mut state = lock_state();
+        if let Some(s) = state.as_mut() {
+            s.ui.render_dirty = hidden;
         }
     }
 
-    let widget_width = total_widget_width();
-    let max_offset = (tray_left - taskbar_rect.left - widget_width).max(0);
-    let tray_offset = tray_offset.clamp(0, max_offset);
-    let offset_changed = {
-        let mut state = lock_state();
-        if let Some(s) = state.as_mut() {
-            if s.tray_offset != tray_offset {
-                s.tray_offset = tray_offset;
-                true
-            } else {
-                false
-            }
+    unsafe {
+        if taskbar_hidden && !session_locked {
+            SetTimer(hwnd, TIMER_DEFERRED_RENDER, DEFERRED_RENDER_CHECK_MS, None);
         } else {
-            false
+            let _ = KillTimer(hwnd, TIMER_DEFERRED_RENDER);
         }
+    }
+    hidden
+}
+
+/// True while `quiet_when_fullscreen` is on and a full-screen app is in the
+/// foreground, i.e. notifications should be held back.
+fn fullscreen_quiet() -> bool {
+    let enabled = {
+        let state = lock_state();
+        state.as_ref().is_some_and(|s| s.ui.quiet_when_fullscreen)
     };
-    if offset_changed {
-        save_state_settings();
+    enabled && native_interop::is_fullscreen_app_active()
+}
+
+/// Show a warning balloon, or hold it for the summary shown once Windows
+/// Do Not Disturb (Focus Assist) ends. Held notifications are delivered by
+/// `deliver_held_notifications`.
+fn notify(hwnd: HWND, kind: tray_icon::TrayIconKind, title: &str, body: &str) {
+    if !native_interop::is_do_not_disturb_active() {
+        tray_icon::notify_balloon(hwnd, kind, title, body);
+        return;
+    }
+    diagnose::log(format!("notification held for do not disturb: {title}"));
+    let mut state = lock_state();
+    if let Some(s) = state.as_mut() {
+        s.ui.held_notifications.push(HeldNotification {
+            kind,
+            title: title.to_string(),
+            body: body.to_string(),
+        });
     }
+}
 
-    let widget_height = sc(WIDGET_HEIGHT);
-    let y = compute_anchor_y(anchor_top, anchor_height, widget_height);
-    if embedded {
-        // Child window: coordinates relative to parent (taskbar)
-        let x = tray_left - taskbar_rect.left - widget_width - tray_offset;
-        native_interop::move_window(hwnd, x, y - taskbar_rect.top, widget_width, widget_height);
-        diagnose::log(format!(
-            "positioned embedded widget at x={x} y={} w={widget_width} h={widget_height}",
-            y - taskbar_rect.top
-        ));
-    } else {
-        // Topmost popup: screen coordinates
-        let x = tray_left - widget_width - tray_offset;
-        native_interop::move_window(hwnd, x, y, widget_width, widget_height);
-        diagnose::log(format!(
-            "positioned fallback widget at x={x} y={y} w={widget_width} h={widget_height}"
-        ));
+/// Show one summary of the held notifications once Do Not Disturb is off,
+/// otherwise check again later. Runs on the UI thread, which owns the timer.
+fn deliver_held_notifications(hwnd: HWND) {
+    let held_any = {
+        let state = lock_state();
+        state
+            .as_ref()
+            .is_some_and(|s| !s.ui.held_notifications.is_empty())
+    };
+    if held_any && native_interop::is_do_not_disturb_active() {
+        unsafe {
+            SetTimer(hwnd, TIMER_HELD_NOTIFICATIONS, QUIET_RETRY_MS, None);
+        }
+        return;
+    }
+    unsafe {
+        let _ = KillTimer(hwnd, TIMER_HELD_NOTIFICATIONS);
     }
-}
 
-fn compute_anchor_y(anchor_top: i32, anchor_height: i32, widget_height: i32) -> i32 {
-    let anchor_bottom = anchor_top + anchor_height;
-    (anchor_bottom - widget_height).max(anchor_top)
+    let (strings, held) = {
+        let mut state = lock_state();
+        match state.as_mut() {
+            Some(s) => (
+                s.ui.language.strings(),
+                std::mem::take(&mut s.ui.held_notifications),
+            ),
+            None => return,
+        }
+    };
+    let Some(first) = held.first() else {
+        return;
+    };
+    if held.len() == 1 {
+        tray_icon::notify_balloon(hwnd, first.kind, &first.title, &first.body);
+        return;
+    }
+    let body = held
+        .iter()
+        .map(|n| format!("{}: {}", n.title, n.body))
+        .collect::<Vec<_>>()
+        .join("\n");
+    tray_icon::notify_balloon(hwnd, first.kind, strings.held_notifications_title, &body);
 }
 
-/// WinEvent callback for tray icon location changes
-unsafe extern "system" fn on_tray_location_changed(
-    _hook: HWINEVENTHOOK,
-    _event: u32,
-    hwnd: HWND,
-    _id_object: i32,
-    _id_child: i32,
-    _thread: u32,
-    _time: u32,
-) {
-    static LAST_REPOSITION: Mutex<Option<std::time::Instant>> = Mutex::new(None);
-
-    let is_tray = {
+fn render_if_dirty() {
+    let dirty = {
         let state = lock_state();
-        state
-            .as_ref()
-            .and_then(|s| s.tray_notify_hwnd)
-            .map(|h| h == hwnd)
-            .unwrap_or(false)
+        state.as_ref().map(|s| s.ui.render_dirty).unwrap_or(false)
     };
+    if dirty {
+        render_layered();
+    }
+}
 
-    if is_tray {
-        if tray_reposition_is_suppressed() {
+/// Fast user switching: another user now owns the console (or our remote
+/// connection dropped), so nobody can see the widget. Stop every timer and
+/// drop the tray location hook until the session is reconnected.
+fn suspend_for_disconnect(hwnd: HWND) {
+    let hook = {
+        let mut state = lock_state();
+        let Some(s) = state.as_mut() else {
+            return;
+        };
+        if s.poll.session_disconnected {
             return;
         }
+        s.poll.session_disconnected = true;
+        s.handles.win_event_hook.take()
+    };
+    if let Some(hook) = hook {
+        native_interop::unhook_win_event(hook);
+    }
 
-        let should_reposition = {
-            let mut last = LAST_REPOSITION.lock().unwrap_or_else(|e| e.into_inner());
-            let now = std::time::Instant::now();
-            if last
-                .map(|t| now.duration_since(t).as_millis() > 500)
-                .unwrap_or(true)
-            {
-                *last = Some(now);
-                true
-            } else {
-                false
-            }
-        };
-        if should_reposition {
-            position_at_taskbar();
-            render_layered();
+    unsafe {
+        for timer in [
+            TIMER_POLL,
+            TIMER_COUNTDOWN,
+            TIMER_RESET_POLL,
+            TIMER_UPDATE_CHECK,
+            TIMER_DEFERRED_RENDER,
+            TIMER_METERED_CHECK,
+        ] {
+            let _ = KillTimer(hwnd, timer);
         }
     }
+    diagnose::log("session disconnected: timers and tray hook suspended");
 }
 
-/// Main window procedure
-unsafe extern "system" fn wnd_proc(
-    hwnd: HWND,
-    msg: u32,
-    wparam: WPARAM,
-    lparam: LPARAM,
-) -> LRESULT {
-    match msg {
-        WM_PAINT => {
-            // For non-embedded fallback, paint normally
-            let embedded = {
-                let state = lock_state();
-                state.as_ref().map(|s| s.embedded).unwrap_or(false)
-            };
-            if embedded {
-                // Layered windows don't use WM_PAINT; just validate the region
-                let mut ps = PAINTSTRUCT::default();
-                let _ = BeginPaint(hwnd, &mut ps);
-                let _ = EndPaint(hwnd, &ps);
-            } else {
-                let mut ps = PAINTSTRUCT::default();
-                let hdc = BeginPaint(hwnd, &mut ps);
-                paint(hdc, hwnd);
-                let _ = EndPaint(hwnd, &ps);
-            }
-            LRESULT(0)
-        }
-        WM_ERASEBKGND => LRESULT(1),
-        WM_DISPLAYCHANGE | WM_DPICHANGED_MSG | WM_SETTINGCHANGE => {
-            if msg == WM_DPICHANGED_MSG {
-                let new_dpi = (wparam.0 & 0xFFFF) as u32;
-                CURRENT_DPI.store(new_dpi, Ordering::Relaxed);
-            }
-            if msg == WM_SETTINGCHANGE {
-                check_theme_change();
-                check_language_change();
-            }
-            refresh_dpi();
-            position_at_taskbar();
-            render_layered();
-            LRESULT(0)
-        }
-        WM_TIMER => {
-            let timer_id = wparam.0;
-            match timer_id {
-                TIMER_POLL => {
-                    let auth_watch = {
-                        let state = lock_state();
-                        state.as_ref().map(|s| {
-                            (
-                                s.auth_error_paused_polling,
-                                s.auth_watch_mode,
-                                s.auth_watch_snapshot.clone(),
-                            )
-                        })
-                    };
-                    match auth_watch {
-                        Some((true, watch_mode, previous_snapshot)) => {
-                            let current_snapshot = poller::credential_watch_snapshot(watch_mode);
-                            if current_snapshot != previous_snapshot {
-                                let mut state = lock_state();
-                                if let Some(s) = state.as_mut() {
-                                    if s.auth_error_paused_polling
-                                        && s.auth_watch_mode == watch_mode
-                                    {
-                                        s.auth_watch_snapshot = current_snapshot;
-                                    }
-                                }
-                                drop(state);
-                                let sh = SendHwnd::from_hwnd(hwnd);
-                                std::thread::spawn(move || {
-                                    do_poll(sh);
-                                });
-                            }
-                        }
-                        Some((false, _, _)) => {
-                            let sh = SendHwnd::from_hwnd(hwnd);
-                            std::thread::spawn(move || {
-                                do_poll(sh);
-                            });
-                        }
-                        None => {}
-                    }
-                }
-                TIMER_COUNTDOWN => {
-                    update_display();
-                    render_layered();
-                    schedule_countdown_timer();
-                }
-                TIMER_RESET_POLL => {
-                    let should_poll = {
-                        let state = lock_state();
-                        state
-                            .as_ref()
-                            .map(|s| !s.auth_error_paused_polling)
-                            .unwrap_or(false)
-                    };
-                    if should_poll {
-                        let sh = SendHwnd::from_hwnd(hwnd);
-                        std::thread::spawn(move || {
-                            do_poll(sh);
-                        });
-                    }
-                }
-                TIMER_UPDATE_CHECK => {
-                    begin_update_check(hwnd, false);
-                }
-                _ => {}
-            }
-            LRESULT(0)
-        }
-        WM_APP_USAGE_UPDATED => {
-            check_theme_change();
-            check_language_change();
-            render_layered();
-            schedule_countdown_timer();
-            suppress_tray_reposition_for(Duration::from_millis(
-                TRAY_ICON_UPDATE_REPOSITION_SUPPRESS_MS,
-            ));
-            sync_tray_icons(hwnd);
-            LRESULT(0)
-        }
-        WM_APP_UPDATE_CHECK_COMPLETE => {
-            schedule_auto_update_check(hwnd);
-            LRESULT(0)
-        }
-        WM_SETCURSOR => {
-            let is_dragging = {
-                let state = lock_state();
-                state.as_ref().map(|s| s.dragging).unwrap_or(false)
-            };
-            if is_dragging {
-                let cursor = LoadCursorW(HINSTANCE::default(), IDC_SIZEWE).unwrap_or_default();
-                SetCursor(cursor);
-                return LRESULT(1);
-            }
-            if cursor_is_on_drag_handle(hwnd) {
-                let cursor = LoadCursorW(HINSTANCE::default(), IDC_SIZEWE).unwrap_or_default();
-                SetCursor(cursor);
-                return LRESULT(1);
+/// Undo `suspend_for_disconnect`: re-embed into the (possibly re-laid-out)
+/// taskbar and refresh immediately rather than waiting a full interval.
+fn resume_after_reconnect(hwnd: HWND) {
+    let resume = {
+        let mut state = lock_state();
+        match state.as_mut() {
+            Some(s) if s.poll.session_disconnected => {
+                s.poll.session_disconnected = false;
+                Some((
+                    s.handles.embedded,
+                    s.ui.taskbar_index,
+                    s.poll.polling_paused,
+                ))
             }
-            DefWindowProcW(hwnd, msg, wparam, lparam)
+            _ => None,
         }
-        WM_LBUTTONDOWN => {
-            let client_x = (lparam.0 & 0xFFFF) as i16 as i32;
-            let client_y = ((lparam.0 >> 16) & 0xFFFF) as i16 as i32;
-            if !is_drag_handle_point(client_x, client_y) {
-                return LRESULT(0);
-            }
+    };
+    let Some((embedded, taskbar_index, polling_paused)) = resume else {
+        return;
+    };
+    diagnose::log("session reconnected: re-embedding and refreshing");
 
-            let mut pt = POINT::default();
-            let _ = GetCursorPos(&mut pt);
-            let mut state = lock_state();
-            if let Some(s) = state.as_mut() {
-                s.dragging = true;
-                s.drag_start_mouse_x = pt.x;
-                s.drag_start_client_x = client_x;
-                s.drag_start_offset = s.tray_offset;
-            }
-            SetCapture(hwnd);
-            LRESULT(0)
+    if embedded {
+        attach_to_taskbar(hwnd, taskbar_index);
+    }
+    position_at_taskbar();
+    sync_tray_icons(hwnd);
+    render_layered();
+    schedule_auto_update_check(hwnd);
+
+    if !polling_paused {
+        let interval = {
+            let state = lock_state();
+            state
+                .as_ref()
+                .map(effective_poll_interval)
+                .unwrap_or(POLL_15_MIN)
+        };
+        unsafe {
+            SetTimer(hwnd, TIMER_POLL, interval, None);
         }
-        WM_MOUSEMOVE => {
-            let is_dragging = {
-                let state = lock_state();
-                state.as_ref().map(|s| s.dragging).unwrap_or(false)
-            };
-            if is_dragging {
-                let mut pt = POINT::default();
-                let _ = GetCursorPos(&mut pt);
-                let move_target = {
-                    let mut state = lock_state();
-                    let s = match state.as_mut() {
-                        Some(s) => s,
-                        None => return LRESULT(0),
-                    };
+        poll_worker::request();
+    }
+}
 
-                    // Moving mouse left = positive delta = larger offset (further left)
-                    let delta = s.drag_start_mouse_x - pt.x;
-                    let mut new_offset = s.drag_start_offset + delta;
+/// Timers do not run during sleep, so on wake the countdowns and usage are
+/// as old as the sleep was long. Redraw the countdowns straight away and poll
+/// now rather than when the poll timer next fires.
+fn resume_after_sleep(hwnd: HWND) {
+    let resume = {
+        let state = lock_state();
+        state.as_ref().map(|s| {
+            (
+                s.poll.polling_paused || s.poll.auth_error_paused_polling,
+                s.poll.session_disconnected,
+            )
+        })
+    };
+    let Some((polling_paused, session_disconnected)) = resume else {
+        return;
+    };
+    diagnose::log("resumed from sleep: refreshing");
 
-                    // Clamp: offset >= 0 (can't go right of default)
-                    if new_offset < 0 {
-                        new_offset = 0;
-                    }
+    update_power_saving(hwnd);
+    refresh_countdowns();
 
-                    let taskbar_hwnd = s.taskbar_hwnd;
-                    let embedded = s.embedded;
-                    let hwnd_val = s.hwnd.to_hwnd();
+    // A disconnected session polls again from resume_after_reconnect.
+    if polling_paused || session_disconnected {
+        return;
+    }
+    let interval = {
+        let state = lock_state();
+        state
+            .as_ref()
+            .map(effective_poll_interval)
+            .unwrap_or(POLL_15_MIN)
+    };
+    unsafe {
+        SetTimer(hwnd, TIMER_POLL, interval, None);
+    }
+    poll_worker::request();
+}
 
-                    // Clamp: don't go past left edge of taskbar
-                    if let Some(taskbar_hwnd) = taskbar_hwnd {
-                        if let Some(taskbar_rect) = native_interop::get_taskbar_rect(taskbar_hwnd) {
-                            let mut tray_left = taskbar_rect.right;
-                            if let Some(tray_hwnd) =
-                                native_interop::find_child_window(taskbar_hwnd, "TrayNotifyWnd")
-                            {
-                                if let Some(tray_rect) =
-                                    native_interop::get_window_rect_safe(tray_hwnd)
-                                {
-                                    tray_left = tray_rect.left;
-                                }
-                            }
-                            let widget_width = total_widget_width_for_state(s);
-                            let max_offset = (tray_left - taskbar_rect.left - widget_width).max(0);
-                            if new_offset > max_offset {
-                                new_offset = max_offset;
-                            }
+/// Re-derive every countdown from the wall clock and re-arm the timers that
+/// were scheduled from it.
+fn refresh_countdowns() {
+    update_display();
+    render_layered();
+    refresh_flyout();
+    schedule_countdown_timer();
+    schedule_weekly_reminder();
+    schedule_limit_resets();
+}
 
-                            s.tray_offset = new_offset;
+fn set_session_locked(locked: bool) {
+    let mut state = lock_state();
+    if let Some(s) = state.as_mut() {
+        s.ui.session_locked = locked;
+    }
+    diagnose::log(format!("session locked={locked}"));
+}
 
-                            let taskbar_height = taskbar_rect.bottom - taskbar_rect.top;
-                            let anchor_top = taskbar_rect.top;
-                            let anchor_height = taskbar_height;
-                            let widget_height = sc(WIDGET_HEIGHT);
-                            let y = compute_anchor_y(anchor_top, anchor_height, widget_height);
-                            let x = if embedded {
-                                tray_left - taskbar_rect.left - widget_width - new_offset
-                            } else {
-                                tray_left - widget_width - new_offset
-                            };
-                            Some((
-                                hwnd_val,
-                                embedded,
-                                x,
-                                y,
-                                taskbar_rect.top,
-                                widget_width,
-                                widget_height,
-                            ))
-                        } else {
-                            s.tray_offset = new_offset;
-                            None
-                        }
-                    } else {
-                        s.tray_offset = new_offset;
-                        None
-                    }
-                };
+/// With `locked_skips_polls`, polls were skipped while locked, so the usage is
+/// as old as the lock was long. Poll now rather than when the timer next fires.
+fn poll_after_unlock() {
+    let poll_now = {
+        let state = lock_state();
+        state.as_ref().is_some_and(|s| {
+            s.poll.locked_skips_polls
+                && !s.poll.polling_paused
+                && !s.poll.auth_error_paused_polling
+                && !s.poll.session_disconnected
+        })
+    };
+    if poll_now {
+        refresh_countdowns();
+        poll_worker::request();
+    }
+}
 
-                if let Some((hwnd_val, embedded, x, y, taskbar_top, widget_width, widget_height)) =
-                    move_target
-                {
-                    if embedded {
-                        native_interop::move_window(
-                            hwnd_val,
-                            x,
-                            y - taskbar_top,
-                            widget_width,
-                            widget_height,
-                        );
-                    } else {
-                        native_interop::move_window(hwnd_val, x, y, widget_width, widget_height);
-                    }
-                }
-            }
-            LRESULT(0)
+/// Paint all widget content onto a DC with a given background color.
+fn paint_content(
+    hdc: HDC,
+    width: i32,
+    height: i32,
+    resources: &PaintResources,
+    background: HBRUSH,
+    text_color: &Color,
+    accent: &Color,
+    track: &Color,
+    rows: &[WidgetRow],
+    show_claude_code: bool,
+    show_codex: bool,
+    show_antigravity: bool,
+    codex_accent: &Color,
+    antigravity_accent: &Color,
+    palette: Palette,
+    text_only: bool,
+    layout: &WidgetLayout,
+) {
+    unsafe {
+        let client_rect = RECT {
+            left: 0,
+            top: 0,
+            right: width,
+            bottom: height,
+        };
+
+        FillRect(hdc, &client_rect, background);
+
+        // GDI mirrors everything drawn from here on except the text itself.
+        if layout.mirrored {
+            SetLayout(hdc, LAYOUT_RTL);
         }
-        WM_LBUTTONUP => {
-            let mut pt = POINT::default();
-            let _ = GetCursorPos(&mut pt);
-            let drag_result = {
-                let mut state = lock_state();
-                if let Some(s) = state.as_mut() {
-                    if s.dragging {
-                        s.dragging = false;
-                        Some((s.taskbar_index, s.drag_start_client_x))
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
+
+        // Left divider (the card background replaces it)
+        if layout.draws_divider() {
+            let divider_h = sc(25);
+            let divider_top = (height - divider_h) / 2;
+            let divider_bottom = divider_top + divider_h;
+
+            let left_rect = RECT {
+                left: 0,
+                top: divider_top,
+                right: sc(2),
+                bottom: divider_bottom,
             };
-            if let Some((current_taskbar_index, drag_start_client_x)) = drag_result {
-                let _ = ReleaseCapture();
-                if let Some((target_index, target_taskbar)) = taskbar_at_point(pt) {
-                    if target_index != current_taskbar_index {
-                        let new_offset = offset_for_drop_point(
-                            target_taskbar.hwnd,
-                            target_taskbar.rect,
-                            pt,
-                            drag_start_client_x,
-                        );
-                        {
-                            let mut state = lock_state();
-                            if let Some(s) = state.as_mut() {
-                                s.tray_offset = new_offset;
-                            }
-                        }
-                        if attach_to_taskbar(hwnd, target_index) {
-                            position_at_taskbar();
-                            render_layered();
-                        }
-                    }
-                }
-                save_state_settings();
-            }
-            LRESULT(0)
+            FillRect(hdc, &left_rect, resources.divider_left);
+
+            let right_rect = RECT {
+                left: sc(2),
+                top: divider_top,
+                right: sc(3),
+                bottom: divider_bottom,
+            };
+            FillRect(hdc, &right_rect, resources.divider_right);
         }
-        WM_RBUTTONUP => {
-            show_context_menu(hwnd);
-            LRESULT(0)
+
+        let content_x = layout.content_x();
+
+        let _ = SetBkMode(hdc, TRANSPARENT);
+        let _ = SetTextColor(hdc, COLORREF(text_color.to_colorref()));
+
+        let old_font = SelectObject(hdc, resources.font);
+
+        // Rows are centred on where the usual two sit, so a single row stays
+        // in the middle of the widget, compact or not.
+        let row_step = sc(SEGMENT_H) + sc(ROW_GAP);
+        let block_height = |count: i32| sc(SEGMENT_H) + row_step * (count - 1);
+        let full_height = sc(WIDGET_HEIGHT);
+        let two_row_top = full_height
+            - sc(ROW_BOTTOM_MARGIN)
+            - block_height(2)
+            - (full_height - height).max(0) / 2;
+        let mut row_y = two_row_top + (block_height(2) - block_height(rows.len() as i32)) / 2;
+        for row in rows {
+            draw_row(
+                hdc,
+                content_x,
+                row_y,
+                resources,
+                text_color,
+                row,
+                show_claude_code,
+                show_codex,
+                show_antigravity,
+                accent,
+                codex_accent,
+                antigravity_accent,
+                track,
+                palette,
+                text_only,
+                layout,
+            );
+            row_y += row_step;
         }
-        WM_COMMAND => {
-            let id = wparam.0 as u16;
-            match id {
-                1 => {
-                    {
-                        let mut state = lock_state();
-                        if let Some(s) = state.as_mut() {
-                            s.session_text = "...".to_string();
-                            s.weekly_text = "...".to_string();
-                            s.codex_session_text = "...".to_string();
-                            s.codex_weekly_text = "...".to_string();
-                            s.force_notify_auth_error = true;
-                        }
-                    }
-                    render_layered();
-                    let sh = SendHwnd::from_hwnd(hwnd);
-                    std::thread::spawn(move || {
-                        do_poll(sh);
-                    });
+
+        SelectObject(hdc, old_font);
+        if layout.mirrored {
+            SetLayout(hdc, DC_LAYOUT(0));
+        }
+    }
+}
+
+/// Claude Code's tokens since `since` (unix seconds): as reported over
+/// telemetry, or read from the transcripts when the receiver is off.
+/// Telemetry that started after `since` misses the time before, so the
+/// transcripts win when they count more.
+fn claude_code_tokens_since(since: u64) -> TokenTotals {
+    if !otel::is_enabled() {
+        return transcripts::token_totals_since(since);
+    }
+    let reported = otel::tokens_since(since);
+    if otel::covers(since) {
+        return reported;
+    }
+    let estimated = transcripts::token_totals_since(since);
+    if reported.total() >= estimated.total() {
+        reported
+    } else {
+        estimated
+    }
+}
+
+fn do_poll(send_hwnd: SendHwnd, ticket: poll_worker::PollTicket) {
+    let hwnd = send_hwnd.to_hwnd();
+    let (skip_polls, skip_locked) = {
+        let state = lock_state();
+        state
+            .as_ref()
+            .map(|s| {
+                (
+                    s.poll.fullscreen_skips_polls,
+                    s.poll.locked_skips_polls && s.ui.session_locked,
+                )
+            })
+            .unwrap_or((false, false))
+    };
+    if skip_locked {
+        diagnose::log("poll skipped: workstation is locked");
+        return;
+    }
+    if skip_polls && fullscreen_quiet() {
+        diagnose::log("poll skipped: full-screen app in the foreground");
+        return;
+    }
+    let (
+        show_claude_code,
+        show_codex,
+        show_antigravity,
+        secondary_account,
+        usage_source,
+        pause_on_metered,
+        show_cost,
+        show_token_counts,
+        usage_jump_threshold,
+        notify_usage_jumps,
+        mqtt_settings,
+        alert_rules,
+        alerts_snoozed,
+        show_messages_left,
+        notify_usage_resets,
+    ) = {
+        let state = lock_state();
+        state
+            .as_ref()
+            .map(|s| {
+                (
+                    s.ui.show_claude_code,
+                    s.ui.show_codex,
+                    s.ui.show_antigravity,
+                    s.poll.secondary_account.clone(),
+                    Arc::clone(&s.poll.usage_source),
+                    s.poll.pause_on_metered,
+                    s.poll.show_cost,
+                    s.poll.show_token_counts,
+                    s.poll.usage_jump_threshold,
+                    s.poll.notify_usage_jumps,
+                    s.poll.mqtt.clone(),
+                    s.poll.alerts.clone(),
+                    alerts_snoozed(s),
+                    s.poll.show_messages_left,
+                    s.poll.notify_usage_resets,
+                )
+            })
+            .unwrap_or((
+                true,
+                false,
+                false,
+                None,
+                poller::active_source(),
+                false,
+                false,
+                false,
+                DEFAULT_USAGE_JUMP_THRESHOLD,
+                false,
+                None,
+                Vec::new(),
+                false,
+                false,
+                false,
+            ))
+    };
+
+    let metered = pause_on_metered && native_interop::is_connection_metered();
+    let was_deferred = {
+        let mut state = lock_state();
+        state
+            .as_mut()
+            .map(|s| {
+                let was_deferred = s.poll.metered_deferred;
+                s.poll.metered_deferred = metered;
+                if metered {
+                    refresh_usage_texts(s);
                 }
-                IDM_VERSION_ACTION => {
-                    let (install_channel, release) = {
-                        let state = lock_state();
-                        match state.as_ref() {
-                            Some(s) => (
-                                s.install_channel,
-                                match &s.update_status {
-                                    UpdateStatus::Available(release) => Some(release.clone()),
-                                    _ => None,
-                                },
-                            ),
-                            None => (InstallChannel::Portable, None),
-                        }
-                    };
+                was_deferred
+            })
+            .unwrap_or(false)
+    };
+    if metered {
+        if !was_deferred {
+            diagnose::log("poll deferred: connection is metered");
+        }
+        unsafe {
+            let _ = PostMessageW(hwnd, WM_APP_USAGE_UPDATED, WPARAM(0), LPARAM(0));
+        }
+        return;
+    }
 
-                    match install_channel {
-                        InstallChannel::Winget => {
-                            if release.is_some() {
-                                begin_winget_update(hwnd);
-                            } else {
-                                begin_update_check(hwnd, true);
-                            }
-                        }
-                        InstallChannel::Portable => {
-                            if let Some(release) = release {
-                                begin_update_apply(hwnd, release);
-                            } else {
-                                begin_update_check(hwnd, true);
-                            }
-                        }
-                    }
+    let simulated = usage_source.is_simulated();
+    let result = usage_source.poll(&poller::PollRequest {
+        show_claude_code,
+        show_codex,
+        show_antigravity,
+        secondary_credentials: secondary_account
+            .as_ref()
+            .map(|account| account.credentials_path.as_path()),
+    });
+
+    // A poll that was already in flight at exit must not write into state
+    // that is being torn down. Nor may one that a newer poll has overtaken,
+    // e.g. one left running when the worker was restarted.
+    if poll_worker::is_cancelled() {
+        diagnose::log("poll result discarded: shutting down");
+        return;
+    }
+    if !ticket.is_current() {
+        diagnose::log(format!("poll result discarded: {ticket:?} is stale"));
+        return;
+    }
+
+    // A poll that was already in flight when the user paused (or the session
+    // was switched away) must not re-arm the timers or overwrite the paused
+    // indicator.
+    let paused = {
+        let state = lock_state();
+        state
+            .as_ref()
+            .map(|s| s.poll.polling_paused || s.poll.session_disconnected)
+            .unwrap_or(false)
+    };
+    if paused {
+        diagnose::log("poll result discarded: polling is paused");
+        return;
+    }
+
+    match result {
+        Ok(data) => {
+            // Simulated usage measured against the real history would log
+            // and announce jumps and resets that never happened.
+            let jumps = if usage_jump_threshold > 0.0 && !simulated {
+                history::detect_jumps(&data, usage_jump_threshold)
+            } else {
+                Vec::new()
+            };
+            let resets = if simulated {
+                Vec::new()
+            } else {
+                history::detect_resets(&data)
+            };
+            let fired_alerts = alerts::evaluate(&alert_rules, &data, alerts_snoozed);
+            if !simulated {
+                let estimate_pending = history::estimate_pending();
+                history::record(&data);
+                if estimate_pending && !history::estimate_pending() {
+                    save_state_settings();
                 }
-                2 => {
-                    let hook = {
-                        let state = lock_state();
-                        state.as_ref().and_then(|s| s.win_event_hook)
-                    };
-                    if let Some(h) = hook {
-                        native_interop::unhook_win_event(h);
-                    }
-                    PostQuitMessage(0);
+                if shared_memory::is_enabled() {
+                    shared_memory::publish(&data, SystemTime::now());
                 }
-                IDM_RESET_POSITION => {
-                    {
-                        let mut state = lock_state();
-                        if let Some(s) = state.as_mut() {
-                            s.tray_offset = 0;
-                        }
-                    }
-                    save_state_settings();
-                    position_at_taskbar();
+            }
+            if !simulated && (mqtt_settings.is_some() || websocket::is_enabled()) {
+                let message = export::usage_json(&data, SystemTime::now());
+                if let Some(mqtt_settings) = &mqtt_settings {
+                    mqtt::publish(mqtt_settings, &message);
                 }
-                IDM_START_WITH_WINDOWS => {
-                    set_startup_enabled(!is_startup_enabled());
-                }
-                IDM_FREQ_1MIN | IDM_FREQ_5MIN | IDM_FREQ_15MIN | IDM_FREQ_1HOUR => {
-                    let new_interval = match id {
-                        IDM_FREQ_1MIN => POLL_1_MIN,
-                        IDM_FREQ_5MIN => POLL_5_MIN,
-                        IDM_FREQ_15MIN => POLL_15_MIN,
-                        IDM_FREQ_1HOUR => POLL_1_HOUR,
-                        _ => POLL_15_MIN,
-                    };
-                    {
-                        let mut state = lock_state();
-                        if let Some(s) = state.as_mut() {
-                            s.poll_interval_ms = new_interval;
-                        }
-                    }
-                    save_state_settings();
-                    // Reset the poll timer with the new interval
-                    SetTimer(hwnd, TIMER_POLL, new_interval, None);
+                if websocket::is_enabled() {
+                    websocket::broadcast(message);
                 }
-                IDM_MODEL_CLAUDE_CODE | IDM_MODEL_CODEX | IDM_MODEL_ANTIGRAVITY => {
-                    {
-                        let mut state = lock_state();
-                        if let Some(s) = state.as_mut() {
-                            match id {
-                                IDM_MODEL_CLAUDE_CODE => {
-                                    if s.show_codex || s.show_antigravity || !s.show_claude_code {
-                                        s.show_claude_code = !s.show_claude_code;
-                                    }
-                                }
-                                IDM_MODEL_CODEX => {
-                                    if s.show_claude_code || s.show_antigravity || !s.show_codex {
-                                        s.show_codex = !s.show_codex;
-                                    }
-                                }
-                                IDM_MODEL_ANTIGRAVITY => {
-                                    if s.show_claude_code || s.show_codex || !s.show_antigravity {
-                                        s.show_antigravity = !s.show_antigravity;
-                                    }
-                                }
-                                _ => {}
-                            }
-                            s.session_text = "...".to_string();
-                            s.weekly_text = "...".to_string();
-                            s.codex_session_text = "...".to_string();
-                            s.codex_weekly_text = "...".to_string();
-                            s.antigravity_session_text = "...".to_string();
-                            s.antigravity_weekly_text = "...".to_string();
-                        }
+            }
+            for jump in &jumps {
+                events::record(
+                    EventKind::UsageJump,
+                    usage_jump_text(jump, LanguageId::English.strings()),
+                );
+            }
+            for reset in &resets {
+                events::record(
+                    EventKind::Reset,
+                    reset_text(reset, LanguageId::English.strings()),
+                );
+            }
+            // One pass over what Claude Code appended since the last poll
+            // serves the activity log and every transcript figure below.
+            if show_cost || show_token_counts || show_messages_left || history::has_activity() {
+                transcripts::refresh();
+            }
+            history::sync_activity();
+            let cost = data
+                .claude_code
+                .as_ref()
+                .filter(|_| show_cost)
+                .map(|usage| {
+                    if otel::is_enabled() {
+                        cost::from_telemetry(usage)
+                    } else {
+                        cost::estimate(usage)
                     }
-                    save_state_settings();
-                    position_at_taskbar();
-                    render_layered();
-                    sync_tray_icons(hwnd);
-                    let sh = SendHwnd::from_hwnd(hwnd);
-                    std::thread::spawn(move || {
-                        do_poll(sh);
+                });
+            let tokens_today = data
+                .claude_code
+                .as_ref()
+                .filter(|_| show_token_counts)
+                .map(|_| {
+                    let midnight =
+                        now_unix_secs().saturating_sub(native_interop::secs_since_local_midnight());
+                    claude_code_tokens_since(midnight)
+                });
+            let messages_left = data
+                .claude_code
+                .as_ref()
+                .filter(|_| show_messages_left)
+                .and_then(messages::estimate);
+            let mut state = lock_state();
+            // Checked again under the lock: a newer poll may have started
+            // while the transcripts were read, and must not be overwritten.
+            if !ticket.is_current() {
+                diagnose::log(format!("poll result discarded: {ticket:?} is stale"));
+                return;
+            }
+            if let Some(s) = state.as_mut() {
+                s.poll.cost = cost;
+                s.poll.tokens_today = tokens_today;
+                s.poll.messages_left = messages_left;
+                let bars_before = bar_percents(&s.ui);
+                // Where the bars are drawn, which is mid-way when the
+                // previous animation has not ended.
+                let bars_shown = shown_bar_percents(&s.ui);
+                if let Some(claude_code) = data.claude_code.as_ref() {
+                    s.ui.session_percent = claude_code.session.percentage;
+                    s.ui.weekly_percent = claude_code.weekly.percentage;
+                } else if s.ui.show_claude_code {
+                    s.ui.session_percent = 0.0;
+                    s.ui.weekly_percent = 0.0;
+                }
+                if let Some(codex) = data.codex.as_ref() {
+                    s.ui.codex_session_percent = codex.session.percentage;
+                    s.ui.codex_weekly_percent = codex.weekly.percentage;
+                } else if s.ui.show_codex {
+                    s.ui.codex_session_percent = 0.0;
+                    s.ui.codex_weekly_percent = 0.0;
+                }
+                if let Some(antigravity) = data.antigravity.as_ref() {
+                    s.ui.antigravity_session_percent = antigravity.session.percentage;
+                    s.ui.antigravity_weekly_percent = antigravity.weekly.percentage;
+                } else if s.ui.show_antigravity {
+                    s.ui.antigravity_session_percent = 0.0;
+                    s.ui.antigravity_weekly_percent = 0.0;
+                }
+                if let Some(secondary) = data.secondary.as_ref() {
+                    s.ui.secondary_session_percent = secondary.session.percentage;
+                    s.ui.secondary_weekly_percent = secondary.weekly.percentage;
+                } else if s.poll.secondary_account.is_some() {
+                    s.ui.secondary_session_percent = 0.0;
+                    s.ui.secondary_weekly_percent = 0.0;
+                }
+                if bar_percents(&s.ui) != bars_before
+                    && !s.ui.reduce_motion
+                    && native_interop::animations_enabled()
+                {
+                    s.ui.bar_animation = Some(BarAnimation {
+                        started: Instant::now(),
+                        from: bars_shown,
                     });
                 }
-                IDM_LANG_SYSTEM
-                | IDM_LANG_ENGLISH
-                | IDM_LANG_DUTCH
-                | IDM_LANG_SPANISH
-                | IDM_LANG_FRENCH
-                | IDM_LANG_GERMAN
-                | IDM_LANG_JAPANESE
-                | IDM_LANG_KOREAN
-                | IDM_LANG_TRADITIONAL_CHINESE
-                | IDM_LANG_RUSSIAN
-                | IDM_LANG_PORTUGUESE_BRAZIL => {
-                    let language_override = match id {
-                        IDM_LANG_SYSTEM => None,
-                        IDM_LANG_ENGLISH => Some(LanguageId::English),
-                        IDM_LANG_DUTCH => Some(LanguageId::Dutch),
-                        IDM_LANG_SPANISH => Some(LanguageId::Spanish),
-                        IDM_LANG_FRENCH => Some(LanguageId::French),
-                        IDM_LANG_GERMAN => Some(LanguageId::German),
-                        IDM_LANG_JAPANESE => Some(LanguageId::Japanese),
-                        IDM_LANG_KOREAN => Some(LanguageId::Korean),
-                        IDM_LANG_TRADITIONAL_CHINESE => Some(LanguageId::TraditionalChinese),
-                        IDM_LANG_RUSSIAN => Some(LanguageId::Russian),
-                        IDM_LANG_PORTUGUESE_BRAZIL => Some(LanguageId::PortugueseBrazil),
-                        _ => None,
-                    };
-                    {
-                        let mut state = lock_state();
-                        if let Some(s) = state.as_mut() {
-                            apply_language_to_state(s, language_override);
-                        }
+                // Stop fast-poll if reset data is now fresh
+                if !poller::app_is_past_reset(&data) {
+                    unsafe {
+                        let _ = KillTimer(hwnd, TIMER_RESET_POLL);
                     }
-                    save_state_settings();
-                    render_layered();
                 }
-                id if id == tray_icon::IDM_TOGGLE_WIDGET => {
-                    toggle_widget_visibility(hwnd);
+
+                s.poll.data = Some(data);
+                s.poll.last_poll_ok = true;
+                s.poll.last_failure = None;
+                refresh_usage_texts(s);
+
+                // Recovered from errors — restore normal poll interval. Smart
+                // polling picks a new interval after every successful poll.
+                // A rejected request that still reported usage holds the next
+                // poll back for as long as the server asked.
+                let retry_after = poller::retry_after();
+                if s.poll.retry_count > 0
+                    || s.poll.smart_polling
+                    || s.poll.retry_after_delayed
+                    || retry_after.is_some()
+                {
+                    s.poll.retry_count = 0;
+                    s.poll.retry_after_delayed = retry_after.is_some();
+                    let interval = retry_after.map_or(effective_poll_interval(s), |wait| {
+                        effective_poll_interval(s).max(retry_after_ms(wait))
+                    });
+                    unsafe {
+                        SetTimer(hwnd, TIMER_POLL, interval, None);
+                    }
                 }
-                _ => {}
+                s.poll.force_notify_auth_error = false;
+                s.poll.auth_error_paused_polling = false;
+                s.poll.unreachable_notified = false;
+                s.poll.auth_watch_mode = poller::CredentialWatchMode::ActiveSource;
+                s.poll.auth_watch_snapshot.clear();
+            }
+            drop(state);
+
+            if let Some(jump) = jumps.first().filter(|_| notify_usage_jumps) {
+                notify_usage_jump(hwnd, jump);
+            }
+            if notify_usage_resets {
+                notify_usage_reset(hwnd, &resets);
+            }
+            for alert in &fired_alerts {
+                fire_alert(hwnd, alert);
+            }
+
+            unsafe {
+                let _ = PostMessageW(hwnd, WM_APP_USAGE_UPDATED, WPARAM(0), LPARAM(0));
             }
-            LRESULT(0)
         }
-        _ if msg == WM_APP_TRAY => {
-            match tray_icon::handle_message(lparam) {
-                tray_icon::TrayAction::ToggleWidget => {
-                    toggle_widget_visibility(hwnd);
+        Err(e) => {
+            let auth_watch = match e {
+                poller::PollError::AuthRequired | poller::PollError::TokenExpired
+                    if show_antigravity && !show_claude_code && !show_codex =>
+                {
+                    Some((
+                        poller::CredentialWatchMode::Antigravity,
+                        poller::credential_watch_snapshot(poller::CredentialWatchMode::Antigravity),
+                    ))
                 }
-                tray_icon::TrayAction::ShowContextMenu => {
-                    show_context_menu(hwnd);
+                poller::PollError::AuthRequired | poller::PollError::TokenExpired => Some((
+                    poller::CredentialWatchMode::ActiveSource,
+                    poller::credential_watch_snapshot(poller::CredentialWatchMode::ActiveSource),
+                )),
+                poller::PollError::NoCredentials => Some((
+                    poller::CredentialWatchMode::AllSources,
+                    poller::credential_watch_snapshot(poller::CredentialWatchMode::AllSources),
+                )),
+                poller::PollError::RequestFailed => None,
+            };
+            let welcome = matches!(e, poller::PollError::NoCredentials) && take_welcome();
+            let retry_after = poller::retry_after();
+            let failure = PollFailure {
+                error: e,
+                response: poller::last_response(),
+                credentials: poller::credential_locations(show_claude_code, show_codex),
+            };
+            let welcome_path = welcome
+                .then(|| failure.credentials.first().cloned())
+                .flatten();
+            // Distinguish auth-required errors from transient errors.
+            let (notify_auth_error, notify_unreachable) = {
+                let mut state = lock_state();
+                if !ticket.is_current() {
+                    diagnose::log(format!("poll failure discarded: {ticket:?} is stale"));
+                    return;
                 }
-                tray_icon::TrayAction::None => {}
-            }
-            LRESULT(0)
-        }
-        WM_DESTROY => {
-            let hook = {
-                let state = lock_state();
-                state.as_ref().and_then(|s| s.win_event_hook)
+                let mut should_notify = false;
+                let mut unreachable = false;
+                if let Some(s) = state.as_mut() {
+                    s.poll.last_poll_ok = false;
+                    s.poll.last_failure = Some(failure);
+                    match auth_watch {
+                        Some((watch_mode, watch_snapshot)) => {
+                            // Only show the balloon on the first failure so it doesn't spam.
+                            if s.poll.retry_count == 0 || s.poll.force_notify_auth_error {
+                                should_notify = true;
+                            }
+                            s.poll.force_notify_auth_error = false;
+                            s.poll.auth_error_paused_polling = true;
+                            s.poll.auth_watch_mode = watch_mode;
+                            s.poll.auth_watch_snapshot = watch_snapshot;
+                            show_poll_error(s);
+                            s.poll.retry_count = s.poll.retry_count.saturating_add(1);
+                            unsafe {
+                                let _ = KillTimer(hwnd, TIMER_POLL);
+                                let _ = KillTimer(hwnd, TIMER_RESET_POLL);
+                                let _ = KillTimer(hwnd, TIMER_COUNTDOWN);
+                                SetTimer(hwnd, TIMER_POLL, effective_poll_interval(s), None);
+                            }
+                        }
+                        _ => {
+                            // Transient network errors: exponential backoff,
+                            // capped at the poll interval and jittered so
+                            // clients that failed together do not all retry
+                            // together. Only credential errors pause polling,
+                            // since retrying cannot fix those. Being rate
+                            // limited does not make the service unreachable.
+                            if retry_after.is_none()
+                                && s.poll.retry_count + 1 >= MAX_RETRIES
+                                && (!s.poll.unreachable_notified || s.poll.force_notify_auth_error)
+                            {
+                                unreachable = true;
+                                s.poll.unreachable_notified = true;
+                                diagnose::log(format!(
+                                    "usage service unreachable after {} failed polls; still retrying",
+                                    s.poll.retry_count + 1
+                                ));
+                            }
+                            s.poll.force_notify_auth_error = false;
+                            s.poll.auth_error_paused_polling = false;
+                            s.poll.auth_watch_mode = poller::CredentialWatchMode::ActiveSource;
+                            s.poll.auth_watch_snapshot.clear();
+                            show_poll_error(s);
+                            s.poll.retry_count = s.poll.retry_count.saturating_add(1);
+                            let backoff = RETRY_BASE_MS.saturating_mul(
+                                1u32.checked_shl(s.poll.retry_count - 1).unwrap_or(u32::MAX),
+                            );
+                            let retry_ms = match retry_after {
+                                Some(wait) => {
+                                    diagnose::log(format!(
+                                        "rate limited; retrying after {}s",
+                                        wait.as_secs()
+                                    ));
+                                    s.poll.retry_after_delayed = true;
+                                    retry_after_ms(wait)
+                                }
+                                None => with_jitter(backoff.min(effective_poll_interval(s))),
+                            };
+                            unsafe {
+                                let _ = KillTimer(hwnd, TIMER_RESET_POLL);
+                                SetTimer(hwnd, TIMER_POLL, retry_ms, None);
+                            }
+                        }
+                    }
+                }
+                (should_notify, unreachable)
             };
-            if let Some(h) = hook {
-                native_interop::unhook_win_event(h);
+
+            if welcome {
+                show_welcome(hwnd, welcome_path.as_deref());
+            } else if notify_auth_error {
+                let balloon = {
+                    let state = lock_state();
+                    state.as_ref().map(|s| {
+                        if s.ui.show_claude_code {
+                            (
+                                s.ui.language.strings(),
+                                tray_icon::TrayIconKind::Claude,
+                                s.ui.language.strings().token_expired_title,
+                                s.ui.language.strings().token_expired_body,
+                            )
+                        } else if s.ui.show_codex {
+                            (
+                                s.ui.language.strings(),
+                                tray_icon::TrayIconKind::Codex,
+                                s.ui.language.strings().codex_token_expired_title,
+                                s.ui.language.strings().codex_token_expired_body,
+                            )
+                        } else {
+                            (
+                                s.ui.language.strings(),
+                                tray_icon::TrayIconKind::Antigravity,
+                                s.ui.language.strings().antigravity_token_expired_title,
+                                s.ui.language.strings().antigravity_token_expired_body,
+                            )
+                        }
+                    })
+                };
+                if let Some((_strings, kind, title, body)) = balloon.filter(|_| !fullscreen_quiet())
+                {
+                    notify(hwnd, kind, title, body);
+                }
+            }
+            if notify_unreachable && !fullscreen_quiet() {
+                let strings = {
+                    let state = lock_state();
+                    state.as_ref().map(|s| s.ui.language.strings())
+                };
+                if let Some(strings) = strings {
+                    notify(
+                        hwnd,
+                        tray_icon::TrayIconKind::Claude,
+                        strings.service_unreachable_title,
+                        &strings
+                            .service_unreachable_body
+                            .replace("{count}", &MAX_RETRIES.to_string()),
+                    );
+                }
+            }
+
+            unsafe {
+                let _ = PostMessageW(hwnd, WM_APP_USAGE_UPDATED, WPARAM(0), LPARAM(0));
             }
-            tray_icon::remove_all(hwnd);
-            PostQuitMessage(0);
-            LRESULT(0)
         }
-        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
     }
 }
 
-fn show_context_menu(hwnd: HWND) {
+/// A `Retry-After` as a poll timer delay, within reason.
+fn retry_after_ms(wait: Duration) -> u32 {
+    (wait.as_millis() as u64).clamp(1_000, POLL_1_HOUR as u64) as u32
+}
+
+/// `ms` moved at random by up to a fifth either way.
+fn with_jitter(ms: u32) -> u32 {
+    let spread = ms / 5;
+    if spread == 0 {
+        return ms;
+    }
+    use std::hash::BuildHasher;
+    let random = std::collections::hash_map::RandomState::new().hash_one(ms);
+    ms - spread + (random % (2 * u64::from(spread) + 1)) as u32
+}
+
+/// While a poll is deferred for a metered connection, re-check the connection
+/// cost every minute so polling resumes soon after an unmetered network returns.
+fn schedule_metered_check() {
+    let (hwnd, deferred) = {
+        let state = lock_state();
+        match state.as_ref() {
+            Some(s) => (s.handles.hwnd.to_hwnd(), s.poll.metered_deferred),
+            None => return,
+        }
+    };
     unsafe {
-        let (
-            current_interval,
-            strings,
-            language,
-            language_override,
-            install_channel,
-            update_status,
-            widget_visible,
-            show_claude_code,
-            show_codex,
-            show_antigravity,
-        ) = {
-            let state = lock_state();
-            match state.as_ref() {
-                Some(s) => (
-                    s.poll_interval_ms,
-                    s.language.strings(),
-                    s.language,
-                    s.language_override,
-                    s.install_channel,
-                    s.update_status.clone(),
-                    s.widget_visible,
-                    s.show_claude_code,
-                    s.show_codex,
-                    s.show_antigravity,
-                ),
-                None => (
-                    POLL_15_MIN,
-                    LanguageId::English.strings(),
-                    LanguageId::English,
-                    None,
-                    InstallChannel::Portable,
-                    UpdateStatus::Idle,
-                    true,
-                    true,
-                    false,
-                    false,
-                ),
-            }
-        };
+        if deferred {
+            SetTimer(hwnd, TIMER_METERED_CHECK, METERED_CHECK_MS, None);
+        } else {
+            let _ = KillTimer(hwnd, TIMER_METERED_CHECK);
+        }
+    }
+}
 
-        let menu = CreatePopupMenu().unwrap();
+/// Seconds from `now` until the reminder for a weekly window resetting at
+/// `resets_at` is due (0 when it is due already), or None when there is
+/// nothing left to remind about.
+fn weekly_reminder_delay(
+    resets_at: u64,
+    now: u64,
+    hours_before: u32,
+    sent_for: Option<u64>,
+) -> Option<u64> {
+    let already_sent =
+        sent_for.is_some_and(|sent| sent.abs_diff(resets_at) < WEEKLY_REMINDER_SAME_RESET_SECS);
+    if hours_before == 0 || already_sent || now >= resets_at {
+        return None;
+    }
+    let due = resets_at.saturating_sub(u64::from(hours_before) * 3600);
+    Some(due.saturating_sub(now))
+}
 
-        let refresh_str = native_interop::wide_str(strings.refresh);
+fn claude_weekly_reset(state: &AppState) -> Option<SystemTime> {
+    state
+        .poll
+        .data
+        .as_ref()?
+        .claude_code
+        .as_ref()?
+        .weekly
+        .resets_at
+}
+
+/// Arm (or show) the weekly reset reminder for the latest poll. Called after
+/// every successful poll, so the timer follows `resets_at` and survives
+/// restarts without a separate schedule file.
+fn schedule_weekly_reminder() {
+    let (hwnd, delay) = {
+        let state = lock_state();
+        let Some(s) = state.as_ref() else {
+            return;
+        };
+        let delay = claude_weekly_reset(s)
+            .and_then(|resets_at| resets_at.duration_since(UNIX_EPOCH).ok())
+            .and_then(|resets_at| {
+                weekly_reminder_delay(
+                    resets_at.as_secs(),
+                    now_unix_secs(),
+                    s.ui.weekly_reminder_hours,
+                    s.ui.weekly_reminder_sent_for,
+                )
+            });
+        (s.handles.hwnd.to_hwnd(), delay)
+    };
+
+    unsafe {
+        match delay {
+            Some(0) if fullscreen_quiet() => {
+                SetTimer(hwnd, TIMER_WEEKLY_REMINDER, QUIET_RETRY_MS, None);
+            }
+            Some(0) => {
+                let _ = KillTimer(hwnd, TIMER_WEEKLY_REMINDER);
+                show_weekly_reminder(hwnd);
+            }
+            Some(secs) => {
+                let ms = secs.saturating_mul(1000).min(u32::MAX as u64) as u32;
+                SetTimer(hwnd, TIMER_WEEKLY_REMINDER, ms, None);
+            }
+            None => {
+                let _ = KillTimer(hwnd, TIMER_WEEKLY_REMINDER);
+            }
+        }
+    }
+}
+
+fn show_weekly_reminder(hwnd: HWND) {
+    let (strings, percent, resets_at) = {
+        let mut state = lock_state();
+        let Some(s) = state.as_mut() else {
+            return;
+        };
+        let Some(resets_at) = claude_weekly_reset(s) else {
+            return;
+        };
+        s.ui.weekly_reminder_sent_for = resets_at
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|d| d.as_secs());
+        (s.ui.language.strings(), s.ui.weekly_percent, resets_at)
+    };
+    save_state_settings();
+
+    let body = strings
+        .weekly_reminder_body
+        .replace(
+            "{time}",
+            &format::time_until(resets_at, Style::DETAIL, strings),
+        )
+        .replace("{unused}", &format!("{:.0}", (100.0 - percent).max(0.0)));
+    diagnose::log(format!("weekly reset reminder: {body}"));
+    if native_interop::is_do_not_disturb_active() {
+        notify(
+            hwnd,
+            tray_icon::TrayIconKind::Claude,
+            strings.weekly_reminder_title,
+            &body,
+        );
+    } else {
+        tray_icon::notify_balloon_info(
+            hwnd,
+            tray_icon::TrayIconKind::Claude,
+            strings.weekly_reminder_title,
+            &body,
+        );
+    }
+}
+
+/// Start tracking each window of the latest poll that is at its limit, keeping
+/// `resets_at` current for the ones already tracked.
+fn track_limit_resets(state: &mut AppState, now: u64) {
+    let Some(data) = state.poll.data.as_ref() else {
+        return;
+    };
+    let providers = [
+        (Provider::ClaudeCode, data.claude_code.as_ref()),
+        (Provider::Codex, data.codex.as_ref()),
+        (Provider::Antigravity, data.antigravity.as_ref()),
+    ];
+    for (provider, usage) in providers {
+        let Some(usage) = usage else {
+            continue;
+        };
+        for window in UsageWindow::ALL {
+            let section = match window {
+                UsageWindow::Session => &usage.session,
+                UsageWindow::Weekly => &usage.weekly,
+            };
+            let resets_at = section
+                .resets_at
+                .and_then(|resets_at| resets_at.duration_since(UNIX_EPOCH).ok())
+                .map(|resets_at| resets_at.as_secs());
+            let Some(resets_at) = resets_at.filter(|resets_at| *resets_at > now) else {
+                continue;
+            };
+            let tracked = state
+                .ui
+                .limit_resets
+                .iter_mut()
+                .find(|reset| reset.provider == provider && reset.window == window);
+            match tracked {
+                Some(reset) => reset.resets_at = resets_at,
+                None if section.limit_reached() => {
+                    diagnose::log(format!("{provider:?} {window:?} limit reached"));
+                    state.ui.limit_resets.push(LimitReset {
+                        provider,
+                        window,
+                        resets_at,
+                        warned: false,
+                    });
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+/// Show the limit reset notices that are due and arm the timer for the next
+/// one. Called after every successful poll and from its own timer.
+fn schedule_limit_resets() {
+    if fullscreen_quiet() {
+        let state = lock_state();
+        if let Some(s) = state.as_ref().filter(|s| !s.ui.limit_resets.is_empty()) {
+            unsafe {
+                SetTimer(
+                    s.handles.hwnd.to_hwnd(),
+                    TIMER_LIMIT_RESET,
+                    QUIET_RETRY_MS,
+                    None,
+                );
+            }
+        }
+        return;
+    }
+
+    let (hwnd, strings, due, next) = {
+        let mut state = lock_state();
+        let Some(s) = state.as_mut() else {
+            return;
+        };
+        let now = now_unix_secs();
+        if s.ui.notify_limit_resets {
+            track_limit_resets(s, now);
+        } else {
+            s.ui.limit_resets.clear();
+        }
+
+        let mut due = Vec::new();
+        s.ui.limit_resets.retain_mut(|reset| {
+            if now >= reset.resets_at {
+                due.push((*reset, true));
+                return false;
+            }
+            if !reset.warned && now + LIMIT_RESET_WARNING_SECS >= reset.resets_at {
+                reset.warned = true;
+                due.push((*reset, false));
+            }
+            true
+        });
+        let next =
+            s.ui.limit_resets
+                .iter()
+                .map(|reset| {
+                    if reset.warned {
+                        reset.resets_at
+                    } else {
+                        reset.resets_at - LIMIT_RESET_WARNING_SECS
+                    }
+                })
+                .min()
+                .map(|at| at.saturating_sub(now));
+        (s.handles.hwnd.to_hwnd(), s.ui.language.strings(), due, next)
+    };
+
+    for (reset, has_reset) in due {
+        let window = match reset.window {
+            UsageWindow::Session => strings.session_window,
+            UsageWindow::Weekly => strings.weekly_window,
+        };
+        let (title, body) = if has_reset {
+            (
+                strings.limit_reset_title,
+                strings.limit_reset_body.to_string(),
+            )
+        } else {
+            let resets_at = UNIX_EPOCH + Duration::from_secs(reset.resets_at);
+            (
+                strings.limit_reset_soon_title,
+                strings.limit_reset_soon_body.replace(
+                    "{time}",
+                    &format::time_until(resets_at, Style::DETAIL, strings),
+                ),
+            )
+        };
+        let body = body
+            .replace("{model}", provider_name(reset.provider, strings))
+            .replace("{window}", window);
+        let kind = match reset.provider {
+            Provider::ClaudeCode => tray_icon::TrayIconKind::Claude,
+            Provider::Codex => tray_icon::TrayIconKind::Codex,
+            Provider::Antigravity => tray_icon::TrayIconKind::Antigravity,
+        };
+        diagnose::log(format!("limit reset notice: {body}"));
+        notify(hwnd, kind, title, &body);
+    }
+
+    unsafe {
+        match next {
+            Some(secs) => {
+                let ms = secs.saturating_mul(1000).clamp(1000, u32::MAX as u64) as u32;
+                SetTimer(hwnd, TIMER_LIMIT_RESET, ms, None);
+            }
+            None => {
+                let _ = KillTimer(hwnd, TIMER_LIMIT_RESET);
+            }
+        }
+    }
+}
+
+fn schedule_countdown_timer() {
+    let state = lock_state();
+    let s = match state.as_ref() {
+        Some(s) => s,
+        None => return,
+    };
+
+    let hwnd = s.handles.hwnd.to_hwnd();
+    if !s.poll.last_poll_ok || s.poll.polling_paused || s.poll.metered_deferred {
+        unsafe {
+            let _ = KillTimer(hwnd, TIMER_COUNTDOWN);
+            let _ = KillTimer(hwnd, TIMER_RESET_POLL);
+        }
+        return;
+    }
+
+    let data = match &s.poll.data {
+        Some(d) => d,
+        None => return,
+    };
+
+    // If a reset time has passed, poll every 5s to pick up fresh data
+    if poller::app_is_past_reset(data) {
+        unsafe {
+            SetTimer(hwnd, TIMER_RESET_POLL, 5_000, None);
+        }
+    }
+
+    let delays = [
+        data.claude_code.as_ref().and_then(|usage| {
+            format::time_until_display_change(usage.session.resets_at, Style::WIDGET)
+        }),
+        data.claude_code.as_ref().and_then(|usage| {
+            format::time_until_display_change(usage.weekly.resets_at, Style::WIDGET)
+        }),
+        data.codex.as_ref().and_then(|usage| {
+            format::time_until_display_change(usage.session.resets_at, Style::WIDGET)
+        }),
+        data.codex.as_ref().and_then(|usage| {
+            format::time_until_display_change(usage.weekly.resets_at, Style::WIDGET)
+        }),
+        data.antigravity.as_ref().and_then(|usage| {
+            format::time_until_display_change(usage.session.resets_at, Style::WIDGET)
+        }),
+        data.antigravity.as_ref().and_then(|usage| {
+            format::time_until_display_change(usage.weekly.resets_at, Style::WIDGET)
+        }),
+    ];
+    let min_delay = delays.into_iter().flatten().min();
+
+    let ms = min_delay
+        .unwrap_or(Duration::from_secs(60))
+        .as_millis()
+        .max(1000) as u32;
+
+    unsafe {
+        SetTimer(hwnd, TIMER_COUNTDOWN, ms, None);
+    }
+}
+
+/// Pretend every known reset time passed a second ago, driving the same
+/// fast-poll path a real window rollover takes.
+fn simulate_reset() {
+    let past = SystemTime::now() - Duration::from_secs(1);
+    {
+        let mut state = lock_state();
+        let Some(s) = state.as_mut() else {
+            return;
+        };
+        let Some(data) = s.poll.data.as_mut() else {
+            diagnose::log("simulate reset skipped: no usage data yet");
+            return;
+        };
+
+        for usage in [
+            data.claude_code.as_mut(),
+            data.codex.as_mut(),
+            data.antigravity.as_mut(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            usage.session.resets_at = Some(past);
+            usage.weekly.resets_at = Some(past);
+        }
+        refresh_usage_texts(s);
+    }
+    diagnose::log("simulated reset of all usage windows");
+    render_layered();
+    schedule_countdown_timer();
+}
+
+/// Poll `simulation` instead of the real source from now on, or go back to
+/// the real source, and poll straight away so alerts, colours and countdowns
+/// all follow.
+fn set_simulation(simulation: Option<Simulation>) {
+    {
+        let mut state = lock_state();
+        let Some(s) = state.as_mut() else {
+            return;
+        };
+        s.poll.simulation = simulation;
+        s.poll.usage_source = match simulation {
+            Some(simulation) => Arc::new(SimulatedSource(simulation)),
+            None => poller::active_source(),
+        };
+    }
+    match simulation {
+        Some(simulation) => diagnose::log(format!("simulating {simulation:?}")),
+        None => diagnose::log("simulation stopped"),
+    }
+    poll_worker::request();
+}
+
+/// Pause or resume background polling. Pausing stops every poll-related timer;
+/// resuming restarts the poll timer and refreshes immediately.
+fn set_polling_paused(hwnd: HWND, paused: bool) {
+    let interval = {
+        let mut state = lock_state();
+        let Some(s) = state.as_mut() else {
+            return;
+        };
+        if s.poll.polling_paused == paused {
+            return;
+        }
+        s.poll.polling_paused = paused;
+        if paused {
+            refresh_usage_texts(s);
+        } else {
+            s.ui.session_text = "...".to_string();
+            s.ui.weekly_text = "...".to_string();
+            s.ui.codex_session_text = "...".to_string();
+            s.ui.codex_weekly_text = "...".to_string();
+            s.ui.antigravity_session_text = "...".to_string();
+            s.ui.antigravity_weekly_text = "...".to_string();
+        }
+        effective_poll_interval(s)
+    };
+    diagnose::log(format!("polling paused={paused}"));
+
+    unsafe {
+        if paused {
+            let _ = KillTimer(hwnd, TIMER_POLL);
+            let _ = KillTimer(hwnd, TIMER_RESET_POLL);
+            let _ = KillTimer(hwnd, TIMER_COUNTDOWN);
+        } else {
+            SetTimer(hwnd, TIMER_POLL, interval, None);
+        }
+    }
+
+    render_layered();
+    sync_tray_icons(hwnd);
+
+    if !paused {
+        poll_worker::request();
+    }
+}
+
+/// Build the details flyout content: current usage and burn rate per model.
+fn flyout_sections(state: &AppState) -> Vec<FlyoutSection> {
+    let strings = state.ui.language.strings();
+    let data = state.poll.data.as_ref();
+    let models = [
+        (
+            state.ui.show_claude_code,
+            strings.claude_code_model,
+            Provider::ClaudeCode,
+            data.and_then(|d| d.claude_code.as_ref()),
+            state.ui.palette.claude_accent(),
+            &state.ui.session_text,
+            &state.ui.weekly_text,
+        ),
+        (
+            state.ui.show_codex,
+            strings.codex_model,
+            Provider::Codex,
+            data.and_then(|d| d.codex.as_ref()),
+            codex_accent_color(state.ui.is_dark),
+            &state.ui.codex_session_text,
+            &state.ui.codex_weekly_text,
+        ),
+        (
+            state.ui.show_antigravity,
+            strings.antigravity_model,
+            Provider::Antigravity,
+            data.and_then(|d| d.antigravity.as_ref()),
+            state.ui.palette.antigravity_accent(),
+            &state.ui.antigravity_session_text,
+            &state.ui.antigravity_weekly_text,
+        ),
+    ];
+
+    let utc_offset = native_interop::utc_offset_secs();
+    let mut sections: Vec<FlyoutSection> = models
+        .into_iter()
+        .filter(|(visible, ..)| *visible)
+        .map(
+            |(_, title, provider, usage, accent, session_text, weekly_text)| {
+                let (lines, strip, heatmap) = match usage.filter(|_| state.poll.last_poll_ok) {
+                    Some(UsageData {
+                        api_limits: Some(limits),
+                        ..
+                    }) if state.ui.api_key_limits => (
+                        api_limit_lines(limits, flyout_style(state), strings),
+                        None,
+                        None,
+                    ),
+                    Some(usage) => (
+                        usage_lines(state, provider, usage, strings),
+                        history::weekly_day_breakdown(provider).map(|days| FlyoutStrip {
+                            values: days.to_vec(),
+                            accent,
+                        }),
+                        history::hourly_heatmap(provider, utc_offset).map(|cells| FlyoutHeatmap {
+                            caption: strings.usage_by_hour.to_string(),
+                            row_labels: strings
+                                .weekday_initials
+                                .chars()
+                                .map(String::from)
+                                .collect(),
+                            values: cells.iter().map(|hours| hours.to_vec()).collect(),
+                            accent,
+                        }),
+                    ),
+                    None => (
+                        vec![
+                            format!("{}: {session_text}", strings.session_window),
+                            format!("{}: {weekly_text}", strings.weekly_window),
+                        ],
+                        None,
+                        None,
+                    ),
+                };
+                FlyoutSection {
+                    title: title.to_string(),
+                    lines,
+                    strip,
+                    heatmap,
+                    button: None,
+                }
+            },
+        )
+        .collect();
+
+    if let Some(account) = &state.poll.secondary_account {
+        sections.push(FlyoutSection {
+            title: account.label.clone(),
+            lines: vec![
+                format!(
+                    "{}: {}",
+                    strings.session_window, state.ui.secondary_session_text
+                ),
+                format!(
+                    "{}: {}",
+                    strings.weekly_window, state.ui.secondary_weekly_text
+                ),
+            ],
+            strip: None,
+            heatmap: None,
+            button: None,
+        });
+    }
+    if let Some(failure) = poll_failure(state) {
+        sections.insert(0, failure_section(failure, strings));
+    }
+    sections
+}
+
+fn poll_failure(state: &AppState) -> Option<&PollFailure> {
+    state
+        .poll
+        .last_failure
+        .as_ref()
+        .filter(|_| !state.poll.last_poll_ok)
+}
+
+/// What went wrong with the latest poll, with a button to try again.
+fn failure_section(failure: &PollFailure, strings: Strings) -> FlyoutSection {
+    let reason = match failure.error {
+        poller::PollError::AuthRequired => strings.reason_auth_required,
+        poller::PollError::TokenExpired => strings.reason_token_expired,
+        poller::PollError::NoCredentials => strings.reason_no_credentials,
+        poller::PollError::RequestFailed => strings.reason_request_failed,
+    };
+    let mut lines = vec![format!("{}: {reason}", strings.failure_reason)];
+    if let Some(response) = &failure.response {
+        lines.push(format!("{}: {response}", strings.last_response));
+    }
+    if !failure.credentials.is_empty() {
+        lines.push(format!("{}:", strings.credentials_checked));
+        lines.extend(
+            failure
+                .credentials
+                .iter()
+                .map(|location| format!("    {location}")),
+        );
+    }
+    FlyoutSection {
+        title: strings.poll_failed.to_string(),
+        lines,
+        strip: None,
+        heatmap: None,
+        button: Some(FlyoutButton {
+            label: strings.retry.to_string(),
+            on_click: refresh_now,
+        }),
+    }
+}
+
+fn flyout_style(state: &AppState) -> Style {
+    Style {
+        compact_tokens: !state.ui.exact_token_counts,
+        ..Style::DETAIL
+    }
+}
+
+fn provider_name(provider: Provider, strings: Strings) -> &'static str {
+    match provider {
+        Provider::ClaudeCode => strings.claude_code_model,
+        Provider::Codex => strings.codex_model,
+        Provider::Antigravity => strings.antigravity_model,
+    }
+}
+
+fn usage_jump_text(jump: &UsageJump, strings: Strings) -> String {
+    let window = match jump.window {
+        UsageWindow::Session => strings.session_window,
+        UsageWindow::Weekly => strings.weekly_window,
+    };
+    strings
+        .usage_jump_body
+        .replace("{model}", provider_name(jump.provider, strings))
+        .replace("{window}", window)
+        .replace("{from}", &format::percent(jump.from, Style::WIDGET))
+        .replace("{to}", &format::percent(jump.to, Style::WIDGET))
+        .replace("{minutes}", &jump.secs.div_ceil(60).max(1).to_string())
+}
+
+fn reset_text(reset: &UsageReset, strings: Strings) -> String {
+    let window = match reset.window {
+        UsageWindow::Session => strings.session_window,
+        UsageWindow::Weekly => strings.weekly_window,
+    };
+    format!(
+        "{} {window} window reset at {}",
+        provider_name(reset.provider, strings),
+        history::format_local_short(reset.at, native_interop::utc_offset_secs())
+    )
+}
+
+fn alerts_snoozed(state: &AppState) -> bool {
+    state
+        .poll
+        .alerts_snoozed_until
+        .is_some_and(|until| now_unix_secs() < until)
+}
+
+/// Silence alerts for `secs`, or until the earliest reset of a window an
+/// alert watches with None.
+fn snooze_alerts(secs: Option<u64>) {
+    {
+        let mut state = lock_state();
+        let Some(s) = state.as_mut() else {
+            return;
+        };
+        let now = now_unix_secs();
+        let until = secs.map(|secs| now + secs).unwrap_or_else(|| {
+            s.poll
+                .alerts
+                .iter()
+                .filter_map(|rule| {
+                    let data = s.poll.data.as_ref()?;
+                    let usage = match rule.provider {
+                        Provider::ClaudeCode => data.claude_code.as_ref(),
+                        Provider::Codex => data.codex.as_ref(),
+                        Provider::Antigravity => data.antigravity.as_ref(),
+                    }?;
+                    let section = rule.window.section(usage)?;
+                    let resets_at = section.resets_at?.duration_since(UNIX_EPOCH).ok()?;
+                    Some(resets_at.as_secs()).filter(|resets_at| *resets_at > now)
+                })
+                .min()
+                // Without a known reset, a 5h window is the longest a
+                // reset can be away for the default rules.
+                .unwrap_or(now + UsageWindow::Session.length().as_secs())
+        });
+        s.poll.alerts_snoozed_until = Some(until);
+        diagnose::log(format!("alerts snoozed for {}s", until - now));
+    }
+    save_state_settings();
+}
+
+fn resume_alerts() {
+    {
+        let mut state = lock_state();
+        if let Some(s) = state.as_mut() {
+            s.poll.alerts_snoozed_until = None;
+        }
+    }
+    save_state_settings();
+}
+
+fn alert_text(alert: &Alert, strings: Strings) -> String {
+    let window = match alert.rule.window {
+        AlertWindow::Session => strings.session_window,
+        AlertWindow::Weekly => strings.weekly_window,
+        AlertWindow::Opus => strings.opus_window,
+    };
+    strings
+        .alert_body
+        .replace("{model}", provider_name(alert.rule.provider, strings))
+        .replace("{window}", window)
+        .replace("{percent}", &format::percent(alert.percent, Style::WIDGET))
+}
+
+/// Run each of the actions of an alert rule that has just fired.
+fn fire_alert(hwnd: HWND, alert: &Alert) {
+    let strings = {
+        let state = lock_state();
+        match state.as_ref() {
+            Some(s) => s.ui.language.strings(),
+            None => return,
+        }
+    };
+    let text = alert_text(alert, LanguageId::English.strings());
+    diagnose::log(format!("alert: {text}"));
+    for action in &alert.rule.actions {
+        match action {
+            AlertAction::Toast => {
+                if !fullscreen_quiet() {
+                    let kind = match alert.rule.provider {
+                        Provider::ClaudeCode => tray_icon::TrayIconKind::Claude,
+                        Provider::Codex => tray_icon::TrayIconKind::Codex,
+                        Provider::Antigravity => tray_icon::TrayIconKind::Antigravity,
+                    };
+                    notify(hwnd, kind, strings.alert_title, &alert_text(alert, strings));
+                }
+            }
+            AlertAction::Sound => native_interop::play_alert_sound(),
+            AlertAction::Webhook => alerts::send_webhook(alert),
+            AlertAction::Flash => {
+                {
+                    let mut state = lock_state();
+                    if let Some(s) = state.as_mut() {
+                        s.ui.flash_steps = ALERT_FLASH_STEPS;
+                    }
+                }
+                unsafe {
+                    SetTimer(hwnd, TIMER_ALERT_FLASH, ALERT_FLASH_STEP_MS, None);
+                }
+            }
+        }
+    }
+}
+
+fn notify_usage_jump(hwnd: HWND, jump: &UsageJump) {
+    if fullscreen_quiet() {
+        return;
+    }
+    let strings = {
+        let state = lock_state();
+        match state.as_ref() {
+            Some(s) => s.ui.language.strings(),
+            None => return,
+        }
+    };
+    let kind = match jump.provider {
+        Provider::ClaudeCode => tray_icon::TrayIconKind::Claude,
+        Provider::Codex => tray_icon::TrayIconKind::Codex,
+        Provider::Antigravity => tray_icon::TrayIconKind::Antigravity,
+    };
+    notify(
+        hwnd,
+        kind,
+        strings.usage_jump_title,
+        &usage_jump_text(jump, strings),
+    );
+}
+
+/// One balloon for the windows that reset since the last poll. Windows that
+/// were at their limit are left to the limit reset notice when that is on.
+fn notify_usage_reset(hwnd: HWND, resets: &[UsageReset]) {
+    if fullscreen_quiet() {
+        return;
+    }
+    let (strings, notify_limit_resets) = {
+        let state = lock_state();
+        match state.as_ref() {
+            Some(s) => (s.ui.language.strings(), s.ui.notify_limit_resets),
+            None => return,
+        }
+    };
+    let Some(reset) = resets
+        .iter()
+        .find(|reset| !(notify_limit_resets && reset.from >= 100.0))
+    else {
+        return;
+    };
+    let window = match reset.window {
+        UsageWindow::Session => strings.session_window,
+        UsageWindow::Weekly => strings.weekly_window,
+    };
+    let kind = match reset.provider {
+        Provider::ClaudeCode => tray_icon::TrayIconKind::Claude,
+        Provider::Codex => tray_icon::TrayIconKind::Codex,
+        Provider::Antigravity => tray_icon::TrayIconKind::Antigravity,
+    };
+    let body = strings
+        .usage_reset_body
+        .replace("{model}", provider_name(reset.provider, strings))
+        .replace("{window}", window)
+        .replace("{from}", &format::percent(reset.from, Style::WIDGET));
+    notify(hwnd, kind, strings.usage_reset_title, &body);
+}
+
+/// Flyout lines for one model's plan usage.
+fn usage_lines(
+    state: &AppState,
+    provider: Provider,
+    usage: &UsageData,
+    strings: Strings,
+) -> Vec<String> {
+    let style = flyout_style(state);
+    let mut lines = Vec::new();
+    if let Some(plan) = usage.plan {
+        lines.push(match plan.session_messages() {
+            Some(count) => format!(
+                "{}: {} \u{00b7} {}",
+                strings.plan,
+                plan.label(),
+                strings
+                    .plan_session_messages
+                    .replace("{count}", &count.to_string())
+                    .replace("{window}", strings.session_window)
+            ),
+            None => format!("{}: {}", strings.plan, plan.label()),
+        });
+    }
+    lines.push(format!(
+        "{}: {}",
+        strings.session_window,
+        format::usage_line(&usage.session, style, strings)
+    ));
+    if let Some(count) = state
+        .poll
+        .messages_left
+        .filter(|_| provider == Provider::ClaudeCode)
+    {
+        lines.push(strings.messages_left.replace("{count}", &count.to_string()));
+    }
+    if let Some(rate) = history::session_burn_rate(provider) {
+        lines.push(format!(
+            "{}: {}",
+            strings.burn_rate,
+            format::burn_rate(rate, style)
+        ));
+    }
+    lines.push(format!(
+        "{}: {}",
+        strings.weekly_window,
+        format::usage_line(&usage.weekly, style, strings)
+    ));
+    if let Some(projection) = history::weekly_projection(provider) {
+        let text = match projection {
+            WeeklyProjection::LimitAt(at) => {
+                let utc_offset = native_interop::utc_offset_secs();
+                let day = strings
+                    .weekday_names
+                    .split(',')
+                    .nth(history::local_weekday(at, utc_offset))
+                    .unwrap_or_default();
+                strings.projected_limit.replace(
+                    "{when}",
+                    &format!("{day} {}", history::format_local_time(at, utc_offset)),
+                )
+            }
+            WeeklyProjection::AtReset(percent) => strings
+                .projected_at_reset
+                .replace("{percent}", &format::percent(percent, style)),
+        };
+        lines.push(format!(
+            "{}: {}",
+            strings.pace_projection,
+            text.replace("{window}", strings.weekly_window)
+        ));
+    }
+    let week_ago = now_unix_secs().saturating_sub(7 * 86_400);
+    if let Some(tokens) = history::activity_tokens(provider, week_ago) {
+        lines.push(format!(
+            "{}: {}",
+            strings.tokens_7d,
+            format::token_count(tokens, style)
+        ));
+    }
+    if let Some(tokens) = state
+        .poll
+        .tokens_today
+        .filter(|_| provider == Provider::ClaudeCode)
+    {
+        lines.push(format!(
+            "{}: {} {} \u{00b7} {} {} \u{00b7} {} {}",
+            strings.tokens_today,
+            format::token_count(tokens.input, style),
+            strings.tokens_in,
+            format::token_count(tokens.output, style),
+            strings.tokens_out,
+            format::token_count(tokens.cached, style),
+            strings.tokens_cached
+        ));
+    }
+    if let Some(cost) = state.poll.cost.filter(|_| provider == Provider::ClaudeCode) {
+        lines.push(format!(
+            "{}: {} {} \u{00b7} {} {}",
+            strings.estimated_cost,
+            strings.session_window,
+            format::usd(cost.session, style),
+            strings.weekly_window,
+            format::usd(cost.weekly, style)
+        ));
+    }
+    let resets = history::recent_resets(provider, RECENT_RESETS);
+    if !resets.is_empty() {
+        let utc_offset = native_interop::utc_offset_secs();
+        let resets: Vec<String> = resets
+            .iter()
+            .map(|reset| {
+                let window = match reset.window {
+                    UsageWindow::Session => strings.session_window,
+                    UsageWindow::Weekly => strings.weekly_window,
+                };
+                format!(
+                    "{window} {}",
+                    history::format_local_short(reset.at, utc_offset)
+                )
+            })
+            .collect();
+        lines.push(format!(
+            "{}: {}",
+            strings.recent_resets,
+            resets.join(" \u{00b7} ")
+        ));
+    }
+    lines
+}
+
+/// One line per per-minute bucket reported for an API key.
+fn api_limit_lines(limits: &ApiRateLimits, style: Style, strings: Strings) -> Vec<String> {
+    [
+        ("RPM", limits.requests.as_ref()),
+        ("TPM", limits.tokens.as_ref()),
+        ("ITPM", limits.input_tokens.as_ref()),
+        ("OTPM", limits.output_tokens.as_ref()),
+    ]
+    .into_iter()
+    .filter_map(|(label, bucket)| {
+        let bucket = bucket?;
+        Some(format!(
+            "{label}: {} ({}/{})",
+            format::usage_line(&bucket.as_section(), style, strings),
+            format::token_count(bucket.remaining, style),
+            format::token_count(bucket.limit, style)
+        ))
+    })
+    .collect()
+}
+
+/// Show placeholders and poll right away, as the Refresh menu item does.
+fn refresh_now() {
+    {
+        let mut state = lock_state();
+        if let Some(s) = state.as_mut() {
+            s.ui.session_text = "...".to_string();
+            s.ui.weekly_text = "...".to_string();
+            s.ui.codex_session_text = "...".to_string();
+            s.ui.codex_weekly_text = "...".to_string();
+            s.poll.force_notify_auth_error = true;
+        }
+    }
+    render_layered();
+    poll_worker::request();
+    events::record(EventKind::ManualRefresh, "manual refresh");
+    suggest_poll_interval();
+}
+
+/// Poll every `interval` ms from now on, or adaptively when None, and save
+/// the choice.
+fn set_poll_interval(hwnd: HWND, interval: Option<u32>) {
+    let (new_interval, paused) = {
+        let mut state = lock_state();
+        match state.as_mut() {
+            Some(s) => {
+                s.poll.smart_polling = interval.is_none();
+                if let Some(interval) = interval {
+                    s.poll.poll_interval_ms = interval;
+                }
+                (effective_poll_interval(s), s.poll.polling_paused)
+            }
+            None => (POLL_15_MIN, false),
+        }
+    };
+    save_state_settings();
+    // Reset the poll timer with the new interval
+    if !paused {
+        unsafe {
+            SetTimer(hwnd, TIMER_POLL, new_interval, None);
+        }
+    }
+}
+
+/// Ask for a poll interval until the answer is one we accept, such as "90s".
+/// None when cancelled.
+fn ask_custom_poll_interval() -> Option<u32> {
+    let (strings, current_ms) = {
+        let state = lock_state();
+        let s = state.as_ref()?;
+        (s.ui.language.strings(), s.poll.poll_interval_ms)
+    };
+    let mut text = match current_ms / 1_000 {
+        secs if secs % 3_600 == 0 => format!("{}h", secs / 3_600),
+        secs if secs % 60 == 0 => format!("{}m", secs / 60),
+        secs => format!("{secs}s"),
+    };
+    let mut label = strings.custom_interval_prompt.to_string();
+    loop {
+        text = prompt::ask(&prompt::Prompt {
+            title: strings.update_frequency,
+            label: &label,
+            initial: &text,
+            ok: strings.ok,
+            cancel: strings.cancel,
+        })?;
+        let interval = format::parse_duration(&text)
+            .map(|interval| interval.as_millis().min(MAX_POLL_INTERVAL_MS as u128) as u32)
+            .filter(|interval| *interval >= MIN_POLL_INTERVAL_MS);
+        if let Some(interval) = interval {
+            diagnose::log(format!("custom poll interval: {interval}ms"));
+            return Some(interval);
+        }
+        label = strings.custom_interval_invalid.to_string();
+    }
+}
+
+/// The slowest preset interval that still polls about as often as the user
+/// refreshes by hand, if it is faster than `current_ms`.
+fn suggested_poll_interval(refreshes_per_hour: usize, current_ms: u32) -> Option<u32> {
+    let wanted_ms = (3_600_000 / refreshes_per_hour.max(1)) as u32;
+    [
+        POLL_1_HOUR,
+        POLL_30_MIN,
+        POLL_15_MIN,
+        POLL_5_MIN,
+        POLL_1_MIN,
+    ]
+    .into_iter()
+    .find(|interval| *interval <= wanted_ms)
+    .filter(|interval| *interval < current_ms)
+}
+
+/// The first tray icon shown, which carries notifications that are not about
+/// one particular model.
+fn primary_tray_kind(state: &AppState) -> tray_icon::TrayIconKind {
+    if state.ui.show_claude_code {
+        tray_icon::TrayIconKind::Claude
+    } else if state.ui.show_codex {
+        tray_icon::TrayIconKind::Codex
+    } else {
+        tray_icon::TrayIconKind::Antigravity
+    }
+}
+
+/// After frequent manual refreshes, offer (once) to poll faster instead.
+fn suggest_poll_interval() {
+    let (hwnd, strings, current_ms, icon_kind) = {
+        let state = lock_state();
+        match state.as_ref() {
+            Some(s) if !s.ui.interval_suggestion_offered && !s.poll.smart_polling => (
+                s.handles.hwnd.to_hwnd(),
+                s.ui.language.strings(),
+                s.poll.poll_interval_ms,
+                primary_tray_kind(s),
+            ),
+            _ => return,
+        }
+    };
+
+    let since = now_unix_secs().saturating_sub(MANUAL_REFRESH_WINDOW_SECS);
+    let count = events::count_since(EventKind::ManualRefresh, since);
+    if count < MANUAL_REFRESH_SUGGEST_COUNT {
+        return;
+    }
+    let Some(interval) = suggested_poll_interval(count, current_ms) else {
+        return;
+    };
+    let interval_label = match interval {
+        POLL_1_MIN => strings.one_minute,
+        POLL_5_MIN => strings.five_minutes,
+        POLL_15_MIN => strings.fifteen_minutes,
+        POLL_30_MIN => strings.thirty_minutes,
+        _ => strings.one_hour,
+    };
+
+    {
+        let mut state = lock_state();
+        if let Some(s) = state.as_mut() {
+            s.ui.interval_suggestion_offered = true;
+            s.poll.suggested_poll_interval = Some(interval);
+        }
+    }
+    save_state_settings();
+    diagnose::log(format!(
+        "suggesting {interval}ms polling after {count} manual refreshes in the last hour"
+    ));
+    tray_icon::notify_balloon_info(
+        hwnd,
+        icon_kind,
+        strings.interval_suggestion_title,
+        &strings
+            .interval_suggestion_body
+            .replace("{count}", &count.to_string())
+            .replace("{interval}", interval_label),
+    );
+}
+
+/// On the first launch, claim the one-time sign-in help. False when it was
+/// already shown or Claude Code is not shown.
+fn take_welcome() -> bool {
+    {
+        let mut state = lock_state();
+        match state.as_mut() {
+            Some(s) if !s.ui.welcome_offered && s.ui.show_claude_code => {
+                s.ui.welcome_offered = true;
+                s.ui.welcome_pending = true;
+            }
+            _ => return false,
+        }
+    }
+    save_state_settings();
+    true
+}
+
+/// Explain that Claude Code has to be logged in and where its sign-in is
+/// expected, in place of the usual sign-in balloon.
+fn show_welcome(hwnd: HWND, credentials: Option<&str>) {
+    let strings = {
+        let state = lock_state();
+        match state.as_ref() {
+            Some(s) => s.ui.language.strings(),
+            None => return,
+        }
+    };
+    diagnose::log("first launch without credentials: showing sign-in help");
+    tray_icon::notify_balloon_info(
+        hwnd,
+        tray_icon::TrayIconKind::Claude,
+        strings.welcome_title,
+        &strings.welcome_body.replace(
+            "{path}",
+            credentials.unwrap_or("~\\.claude\\.credentials.json"),
+        ),
+    );
+}
+
+/// Open the README after the sign-in help balloon was clicked. Returns false
+/// when that balloon was not up.
+fn open_welcome_readme() -> bool {
+    let pending = {
+        let mut state = lock_state();
+        state
+            .as_mut()
+            .is_some_and(|s| std::mem::take(&mut s.ui.welcome_pending))
+    };
+    if pending {
+        if let Err(error) = native_interop::open_url(README_URL) {
+            diagnose::log(format!("unable to open README: {error}"));
+        }
+    }
+    pending
+}
+
+/// Apply the suggested interval after its balloon was clicked. Returns false
+/// when no suggestion was pending.
+fn accept_interval_suggestion(hwnd: HWND) -> bool {
+    let (interval, paused) = {
+        let mut state = lock_state();
+        let Some(s) = state.as_mut() else {
+            return false;
+        };
+        let Some(interval) = s.poll.suggested_poll_interval.take() else {
+            return false;
+        };
+        s.poll.smart_polling = false;
+        s.poll.poll_interval_ms = interval;
+        (interval, s.poll.polling_paused)
+    };
+    save_state_settings();
+    diagnose::log(format!("poll interval suggestion accepted: {interval}ms"));
+    if !paused {
+        unsafe {
+            SetTimer(hwnd, TIMER_POLL, interval, None);
+        }
+    }
+    true
+}
+
+/// Mouse shortcuts for refresh match the Refresh menu item, which is
+/// disabled while polling is paused.
+fn refresh_unless_paused(polling_paused: bool) {
+    if !polling_paused {
+        refresh_now();
+    }
+}
+
+fn on_widget_click(hwnd: HWND) {
+    let (action, polling_paused, failed) = {
+        let state = lock_state();
+        match state.as_ref() {
+            Some(s) => (
+                s.ui.left_click,
+                s.poll.polling_paused,
+                poll_failure(s).is_some(),
+            ),
+            None => return,
+        }
+    };
+    if failed {
+        toggle_flyout(hwnd);
+        return;
+    }
+    match action {
+        ClickAction::ShowDetails => toggle_flyout(hwnd),
+        ClickAction::Refresh => refresh_unless_paused(polling_paused),
+        ClickAction::Nothing => {}
+        ClickAction::OpenUsagePage => open_usage_page(),
+    }
+}
+
+/// Open claude.ai's own usage page, to compare against when the numbers
+/// look surprising.
+fn open_usage_page() {
+    if let Err(error) = native_interop::open_url(USAGE_PAGE_URL) {
+        diagnose::log(format!("unable to open usage page: {error}"));
+    }
+}
+
+/// Another copy of the app was launched: show the details where they can be
+/// seen and refresh them.
+fn on_second_instance(hwnd: HWND) {
+    let (widget_visible, polling_paused) = {
+        let state = lock_state();
+        match state.as_ref() {
+            Some(s) => (s.ui.widget_visible, s.poll.polling_paused),
+            None => return,
+        }
+    };
+    diagnose::log("second instance started: showing details");
+    if !widget_visible {
+        show_usage_overlay();
+    } else if !flyout::is_open() {
+        toggle_flyout(hwnd);
+    }
+    refresh_unless_paused(polling_paused);
+}
+
+fn toggle_flyout(hwnd: HWND) {
+    let content = {
+        let state = lock_state();
+        state.as_ref().map(|s| (flyout_sections(s), s.ui.is_dark))
+    };
+    let (Some((sections, is_dark)), Some(anchor)) =
+        (content, native_interop::get_window_rect_safe(hwnd))
+    else {
+        return;
+    };
+    flyout::toggle(anchor, sections, is_dark);
+}
+
+fn register_overlay_hotkey(hwnd: HWND, text: &str) {
+    if text.trim().is_empty() {
+        return;
+    }
+    let Some(hotkey) = Hotkey::parse(text) else {
+        diagnose::log(format!("ignoring invalid overlay hotkey {text:?}"));
+        return;
+    };
+    // Usually fails because another application already owns the combination.
+    if let Err(error) = hotkey::register(hwnd, OVERLAY_HOTKEY_ID, hotkey) {
+        diagnose::log_error(
+            &format!("unable to register overlay hotkey {text:?}"),
+            error,
+        );
+    }
+}
+
+fn show_usage_overlay() {
+    let content = {
+        let state = lock_state();
+        state.as_ref().map(|s| (flyout_sections(s), s.ui.is_dark))
+    };
+    if let Some((sections, is_dark)) = content {
+        flyout::show_overlay(sections, is_dark, OVERLAY_DURATION);
+    }
+}
+
+fn refresh_flyout() {
+    if !flyout::is_open() {
+        return;
+    }
+    let content = {
+        let state = lock_state();
+        state.as_ref().map(|s| (flyout_sections(s), s.ui.is_dark))
+    };
+    if let Some((sections, is_dark)) = content {
+        flyout::update(sections, is_dark);
+    }
+}
+
+fn check_theme_change() {
+    let new_dark = theme::is_dark_mode();
+    let new_accent = theme::taskbar_accent_color();
+    let changed = {
+        let mut state = lock_state();
+        if let Some(s) = state.as_mut() {
+            if s.ui.is_dark != new_dark || s.ui.taskbar_accent != new_accent {
+                s.ui.is_dark = new_dark;
+                s.ui.taskbar_accent = new_accent;
+                true
+            } else {
+                false
+            }
+        } else {
+            false
+        }
+    };
+    if changed {
+        render_layered();
+    }
+}
+
+fn check_language_change() {
+    if update_language_change() {
+        render_layered();
+    }
+}
+
+fn update_display() {
+    let mut state = lock_state();
+    let s = match state.as_mut() {
+        Some(s) => s,
+        None => return,
+    };
+
+    // Don't overwrite error text with stale cached data
+    if !s.poll.last_poll_ok {
+        return;
+    }
+
+    refresh_usage_texts(s);
+}
+
+fn suppress_tray_reposition_for(duration: Duration) {
+    let mut until = SUPPRESS_TRAY_REPOSITION_UNTIL
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    *until = Some(Instant::now() + duration);
+}
+
+fn tray_reposition_is_suppressed() -> bool {
+    let now = Instant::now();
+    let mut until = SUPPRESS_TRAY_REPOSITION_UNTIL
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+
+    match *until {
+        Some(deadline) if now < deadline => true,
+        Some(_) => {
+            *until = None;
+            false
+        }
+        None => false,
+    }
+}
+
+/// Reposition once location events stop arriving for `REPOSITION_DELAY_MS`.
+/// Each event restarts the timer.
+fn schedule_reposition() {
+    let hwnd = {
+        let state = lock_state();
+        match state.as_ref() {
+            Some(s) => s.handles.hwnd.to_hwnd(),
+            None => return,
+        }
+    };
+    unsafe {
+        SetTimer(hwnd, TIMER_REPOSITION, REPOSITION_DELAY_MS, None);
+    }
+}
+
+/// Move the widget to its place on the taskbar. Returns true if it moved or
+/// changed size.
+fn position_at_taskbar() -> bool {
+    refresh_dpi();
+    // Drop the app-state lock before any Win32 call that may synchronously
+    // re-enter our window procedure.
+    let (hwnd, embedded, tray_offset, taskbar_hwnd) = {
+        let state = lock_state();
+        let s = match state.as_ref() {
+            Some(s) => s,
+            None => return false,
+        };
+
+        // Don't fight the user's drag, or reposition in a switched-away session
+        if s.handles.dragging || s.poll.session_disconnected {
+            return false;
+        }
+
+        let taskbar_hwnd = match s.handles.taskbar_hwnd {
+            Some(h) => h,
+            None => {
+                diagnose::log("position_at_taskbar skipped: no taskbar handle");
+                return false;
+            }
+        };
+
+        (
+            s.handles.hwnd.to_hwnd(),
+            s.handles.embedded,
+            s.ui.tray_offset,
+            taskbar_hwnd,
+        )
+    };
+
+    let taskbar_rect = match native_interop::get_taskbar_rect(taskbar_hwnd) {
+        Some(r) => r,
+        None => {
+            diagnose::log("position_at_taskbar skipped: unable to query taskbar rect");
+            return false;
+        }
+    };
+
+    let taskbar_height = taskbar_rect.bottom - taskbar_rect.top;
+    let refit = fit_to_taskbar(taskbar_height);
+    if refit {
+        diagnose::log(format!(
+            "widget scaled to {}% for a {taskbar_height}px taskbar",
+            TASKBAR_FIT.load(Ordering::Relaxed) / 10
+        ));
+    }
+    let tray_left = tray_left_for_taskbar(taskbar_hwnd, taskbar_rect);
+    let anchor_top = taskbar_rect.top;
+    let anchor_height = taskbar_height;
+
+    let widget_width = total_widget_width();
+    let max_offset = (tray_left - taskbar_rect.left - widget_width).max(0);
+    let tray_offset = tray_offset.clamp(0, max_offset);
+    let offset_changed = {
+        let mut state = lock_state();
+        if let Some(s) = state.as_mut() {
+            if s.ui.tray_offset != tray_offset {
+                s.ui.tray_offset = tray_offset;
+                true
+            } else {
+                false
+            }
+        } else {
+            false
+        }
+    };
+    if offset_changed {
+        save_state_settings();
+    }
+
+    let widget_height = widget_height();
+    let y = compute_anchor_y(anchor_top, anchor_height, widget_height);
+    let x = tray_left - widget_width - tray_offset;
+    let target = RECT {
+        left: x,
+        top: y,
+        right: x + widget_width,
+        bottom: y + widget_height,
+    };
+    if native_interop::get_window_rect_safe(hwnd) == Some(target) {
+        return false;
+    }
+    if embedded {
+        // Child window: coordinates relative to parent (taskbar)
+        let x = tray_left - taskbar_rect.left - widget_width - tray_offset;
+        native_interop::move_window(hwnd, x, y - taskbar_rect.top, widget_width, widget_height);
+        diagnose::log(format!(
+            "positioned embedded widget at x={x} y={} w={widget_width} h={widget_height}",
+            y - taskbar_rect.top
+        ));
+    } else {
+        // Topmost popup: screen coordinates
+        native_interop::move_window(hwnd, x, y, widget_width, widget_height);
+        diagnose::log(format!(
+            "positioned fallback widget at x={x} y={y} w={widget_width} h={widget_height}"
+        ));
+    }
+    if refit {
+        render_layered();
+    }
+    true
+}
+
+/// Bottom-aligned at the full widget height; a compact widget sits centred
+/// where the full one would be.
+fn compute_anchor_y(anchor_top: i32, anchor_height: i32, widget_height: i32) -> i32 {
+    let anchor_bottom = anchor_top + anchor_height;
+    let full_height = sc(WIDGET_HEIGHT);
+    (anchor_bottom - full_height).max(anchor_top) + (full_height - widget_height).max(0) / 2
+}
+
+/// WinEvent callback for location changes on the taskbar's thread: the tray
+/// growing or shrinking as icons come and go, or the taskbar itself moving to
+/// another monitor or edge.
+unsafe extern "system" fn on_taskbar_location_changed(
+    _hook: HWINEVENTHOOK,
+    _event: u32,
+    hwnd: HWND,
+    id_object: i32,
+    _id_child: i32,
+    _thread: u32,
+    _time: u32,
+) {
+    static LAST_TASKBAR_RECT: Mutex<Option<RECT>> = Mutex::new(None);
+
+    let (is_tray, is_taskbar) = {
+        let state = lock_state();
+        state.as_ref().map_or((false, false), |s| {
+            (
+                s.handles.tray_notify_hwnd == Some(hwnd),
+                s.handles.taskbar_hwnd == Some(hwnd),
+            )
+        })
+    };
+
+    // Children of the taskbar report changes too; only the window counts.
+    if is_taskbar && id_object == OBJID_WINDOW.0 {
+        // An auto-hide taskbar sliding away is not a move; the widget goes
+        // with it.
+        if native_interop::is_taskbar_autohidden(hwnd) {
+            return;
+        }
+        let Some(rect) = native_interop::get_window_rect_safe(hwnd) else {
+            return;
+        };
+        let moved = {
+            let mut last = LAST_TASKBAR_RECT.lock().unwrap_or_else(|e| e.into_inner());
+            last.replace(rect) != Some(rect)
+        };
+        if moved {
+            diagnose::log(format!(
+                "taskbar moved to left={} top={} right={} bottom={}",
+                rect.left, rect.top, rect.right, rect.bottom
+            ));
+            schedule_reposition();
+        }
+        return;
+    }
+
+    if is_tray && !tray_reposition_is_suppressed() {
+        schedule_reposition();
+    }
+}
+
+/// Main window procedure
+unsafe extern "system" fn wnd_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_PAINT => {
+            // For non-embedded fallback, paint normally
+            let embedded = {
+                let state = lock_state();
+                state.as_ref().map(|s| s.handles.embedded).unwrap_or(false)
+            };
+            if embedded {
+                // Layered windows don't use WM_PAINT; just validate the region
+                let mut ps = PAINTSTRUCT::default();
+                let _ = BeginPaint(hwnd, &mut ps);
+                let _ = EndPaint(hwnd, &ps);
+            } else {
+                let mut ps = PAINTSTRUCT::default();
+                let hdc = BeginPaint(hwnd, &mut ps);
+                paint(hdc, hwnd);
+                let _ = EndPaint(hwnd, &ps);
+            }
+            LRESULT(0)
+        }
+        WM_ERASEBKGND => LRESULT(1),
+        WM_DISPLAYCHANGE | WM_DPICHANGED_MSG | WM_SETTINGCHANGE => {
+            if msg == WM_DPICHANGED_MSG {
+                let new_dpi = (wparam.0 & 0xFFFF) as u32;
+                CURRENT_DPI.store(new_dpi, Ordering::Relaxed);
+            }
+            if msg == WM_SETTINGCHANGE {
+                check_theme_change();
+                check_language_change();
+            }
+            refresh_dpi();
+            position_at_taskbar();
+            render_layered();
+            LRESULT(0)
+        }
+        WM_TIMER => {
+            let timer_id = wparam.0;
+            match timer_id {
+                TIMER_POLL => {
+                    let auth_watch = {
+                        let state = lock_state();
+                        state.as_ref().map(|s| {
+                            (
+                                s.poll.auth_error_paused_polling,
+                                s.poll.auth_watch_mode,
+                                s.poll.auth_watch_snapshot.clone(),
+                            )
+                        })
+                    };
+                    match auth_watch {
+                        Some((true, watch_mode, previous_snapshot)) => {
+                            let current_snapshot = poller::credential_watch_snapshot(watch_mode);
+                            if current_snapshot != previous_snapshot {
+                                let mut state = lock_state();
+                                if let Some(s) = state.as_mut() {
+                                    if s.poll.auth_error_paused_polling
+                                        && s.poll.auth_watch_mode == watch_mode
+                                    {
+                                        s.poll.auth_watch_snapshot = current_snapshot;
+                                    }
+                                }
+                                drop(state);
+                                poll_worker::request();
+                            }
+                        }
+                        Some((false, _, _)) => {
+                            poll_worker::request();
+                        }
+                        None => {}
+                    }
+                }
+                TIMER_COUNTDOWN => {
+                    update_display();
+                    render_layered();
+                    refresh_flyout();
+                    schedule_countdown_timer();
+                }
+                TIMER_RESET_POLL => {
+                    let should_poll = {
+                        let state = lock_state();
+                        state
+                            .as_ref()
+                            .map(|s| !s.poll.auth_error_paused_polling)
+                            .unwrap_or(false)
+                    };
+                    if should_poll {
+                        poll_worker::request();
+                    }
+                }
+                TIMER_UPDATE_CHECK => {
+                    begin_update_check(hwnd, false);
+                }
+                TIMER_DEFERRED_RENDER => {
+                    render_if_dirty();
+                }
+                TIMER_METERED_CHECK => {
+                    poll_worker::request();
+                }
+                TIMER_WEEKLY_REMINDER => {
+                    schedule_weekly_reminder();
+                    deliver_held_notifications(hwnd);
+                }
+                TIMER_HELD_NOTIFICATIONS => {
+                    deliver_held_notifications(hwnd);
+                }
+                TIMER_LIMIT_RESET => {
+                    schedule_limit_resets();
+                }
+                TIMER_REPOSITION => {
+                    let _ = KillTimer(hwnd, TIMER_REPOSITION);
+                    if position_at_taskbar() {
+                        render_layered();
+                    }
+                }
+                TIMER_BAR_ANIMATION => {
+                    let done = {
+                        let mut state = lock_state();
+                        state.as_mut().is_none_or(|s| {
+                            s.ui.bar_animation = s.ui.bar_animation.filter(|animation| {
+                                animation.started.elapsed()
+                                    < Duration::from_millis(BAR_ANIMATION_MS as u64)
+                            });
+                            s.ui.bar_animation.is_none()
+                        })
+                    };
+                    if done {
+                        let _ = KillTimer(hwnd, TIMER_BAR_ANIMATION);
+                    }
+                    render_layered();
+                }
+                TIMER_ALERT_FLASH => {
+                    let done = {
+                        let mut state = lock_state();
+                        state.as_mut().is_none_or(|s| {
+                            s.ui.flash_steps = s.ui.flash_steps.saturating_sub(1);
+                            s.ui.flash_steps == 0
+                        })
+                    };
+                    if done {
+                        let _ = KillTimer(hwnd, TIMER_ALERT_FLASH);
+                    }
+                    render_layered();
+                }
+                _ => {}
+            }
+            LRESULT(0)
+        }
+        WM_APP_USAGE_UPDATED => {
+            check_theme_change();
+            check_language_change();
+            let animating = {
+                let state = lock_state();
+                state.as_ref().is_some_and(|s| s.ui.bar_animation.is_some())
+            };
+            if animating {
+                SetTimer(hwnd, TIMER_BAR_ANIMATION, BAR_ANIMATION_FRAME_MS, None);
+            }
+            render_layered();
+            sync_taskbar_progress(hwnd);
+            sync_badge();
+            refresh_flyout();
+            schedule_countdown_timer();
+            schedule_metered_check();
+            schedule_weekly_reminder();
+            schedule_limit_resets();
+            deliver_held_notifications(hwnd);
+            suppress_tray_reposition_for(Duration::from_millis(
+                TRAY_ICON_UPDATE_REPOSITION_SUPPRESS_MS,
+            ));
+            sync_tray_icons(hwnd);
+            LRESULT(0)
+        }
+        WM_APP_ACTIVATE => {
+            on_second_instance(hwnd);
+            LRESULT(0)
+        }
+        WM_APP_BADGE_CLICKED => {
+            show_usage_overlay();
+            LRESULT(0)
+        }
+        WM_APP_BADGE_CLOSED => {
+            let badge_mode = {
+                let state = lock_state();
+                state.as_ref().is_some_and(|s| s.ui.badge_mode)
+            };
+            if badge_mode {
+                toggle_badge_mode(hwnd);
+            }
+            LRESULT(0)
+        }
+        WM_APP_TIME_CHANGED => {
+            diagnose::log("system time changed: recalculating countdowns");
+            refresh_countdowns();
+            LRESULT(0)
+        }
+        WM_APP_POWER_BROADCAST => {
+            match wparam.0 as u32 {
+                PBT_APMPOWERSTATUSCHANGE => update_power_saving(hwnd),
+                PBT_APMRESUMEAUTOMATIC => resume_after_sleep(hwnd),
+                _ => {}
+            }
+            LRESULT(0)
+        }
+        WM_WTSSESSION_CHANGE => {
+            match wparam.0 as u32 {
+                WTS_SESSION_LOCK => set_session_locked(true),
+                WTS_SESSION_UNLOCK => {
+                    set_session_locked(false);
+                    render_if_dirty();
+                    poll_after_unlock();
+                }
+                WTS_CONSOLE_DISCONNECT | WTS_REMOTE_DISCONNECT => suspend_for_disconnect(hwnd),
+                WTS_CONSOLE_CONNECT | WTS_REMOTE_CONNECT => resume_after_reconnect(hwnd),
+                _ => {}
+            }
+            LRESULT(0)
+        }
+        WM_APP_UPDATE_CHECK_COMPLETE => {
+            schedule_auto_update_check(hwnd);
+            LRESULT(0)
+        }
+        WM_APP_OFFER_BACKFILL => {
+            offer_transcript_backfill(hwnd);
+            LRESULT(0)
+        }
+        WM_SETCURSOR => {
+            let is_dragging = {
+                let state = lock_state();
+                state.as_ref().map(|s| s.handles.dragging).unwrap_or(false)
+            };
+            if is_dragging {
+                let cursor = LoadCursorW(HINSTANCE::default(), IDC_SIZEWE).unwrap_or_default();
+                SetCursor(cursor);
+                return LRESULT(1);
+            }
+            if cursor_is_on_drag_handle(hwnd) {
+                let cursor = LoadCursorW(HINSTANCE::default(), IDC_SIZEWE).unwrap_or_default();
+                SetCursor(cursor);
+                return LRESULT(1);
+            }
+            DefWindowProcW(hwnd, msg, wparam, lparam)
+        }
+        WM_LBUTTONDOWN => {
+            let client_x = (lparam.0 & 0xFFFF) as i16 as i32;
+            let client_y = ((lparam.0 >> 16) & 0xFFFF) as i16 as i32;
+            if !is_drag_handle_point(client_x, client_y) {
+                return LRESULT(0);
+            }
+
+            let mut pt = POINT::default();
+            let _ = GetCursorPos(&mut pt);
+            let mut state = lock_state();
+            if let Some(s) = state.as_mut() {
+                s.handles.dragging = true;
+                s.handles.drag_start_mouse_x = pt.x;
+                s.handles.drag_start_client_x = client_x;
+                s.handles.drag_start_offset = s.ui.tray_offset;
+            }
+            SetCapture(hwnd);
+            LRESULT(0)
+        }
+        WM_MOUSEMOVE => {
+            let is_dragging = {
+                let state = lock_state();
+                state.as_ref().map(|s| s.handles.dragging).unwrap_or(false)
+            };
+            if is_dragging {
+                let mut pt = POINT::default();
+                let _ = GetCursorPos(&mut pt);
+                let move_target = {
+                    let mut state = lock_state();
+                    let s = match state.as_mut() {
+                        Some(s) => s,
+                        None => return LRESULT(0),
+                    };
+
+                    // Moving mouse left = positive delta = larger offset (further left)
+                    let delta = s.handles.drag_start_mouse_x - pt.x;
+                    let mut new_offset = s.handles.drag_start_offset + delta;
+
+                    // Clamp: offset >= 0 (can't go right of default)
+                    if new_offset < 0 {
+                        new_offset = 0;
+                    }
+
+                    let taskbar_hwnd = s.handles.taskbar_hwnd;
+                    let embedded = s.handles.embedded;
+                    let hwnd_val = s.handles.hwnd.to_hwnd();
+
+                    // Clamp: don't go past left edge of taskbar
+                    if let Some(taskbar_hwnd) = taskbar_hwnd {
+                        if let Some(taskbar_rect) = native_interop::get_taskbar_rect(taskbar_hwnd) {
+                            let tray_left = tray_left_for_taskbar(taskbar_hwnd, taskbar_rect);
+                            let widget_width = total_widget_width_for_state(s);
+                            let max_offset = (tray_left - taskbar_rect.left - widget_width).max(0);
+                            if new_offset > max_offset {
+                                new_offset = max_offset;
+                            }
+
+                            s.ui.tray_offset = new_offset;
+
+                            let taskbar_height = taskbar_rect.bottom - taskbar_rect.top;
+                            let anchor_top = taskbar_rect.top;
+                            let anchor_height = taskbar_height;
+                            let widget_height = widget_height_for_state(s);
+                            let y = compute_anchor_y(anchor_top, anchor_height, widget_height);
+                            let x = if embedded {
+                                tray_left - taskbar_rect.left - widget_width - new_offset
+                            } else {
+                                tray_left - widget_width - new_offset
+                            };
+                            Some((
+                                hwnd_val,
+                                embedded,
+                                x,
+                                y,
+                                taskbar_rect.top,
+                                widget_width,
+                                widget_height,
+                            ))
+                        } else {
+                            s.ui.tray_offset = new_offset;
+                            None
+                        }
+                    } else {
+                        s.ui.tray_offset = new_offset;
+                        None
+                    }
+                };
+
+                if let Some((hwnd_val, embedded, x, y, taskbar_top, widget_width, widget_height)) =
+                    move_target
+                {
+                    if embedded {
+                        native_interop::move_window(
+                            hwnd_val,
+                            x,
+                            y - taskbar_top,
+                            widget_width,
+                            widget_height,
+                        );
+                    } else {
+                        native_interop::move_window(hwnd_val, x, y, widget_width, widget_height);
+                    }
+                }
+            }
+            LRESULT(0)
+        }
+        WM_LBUTTONUP => {
+            let mut pt = POINT::default();
+            let _ = GetCursorPos(&mut pt);
+            let drag_result = {
+                let mut state = lock_state();
+                if let Some(s) = state.as_mut() {
+                    if s.handles.dragging {
+                        s.handles.dragging = false;
+                        Some((s.ui.taskbar_index, s.handles.drag_start_client_x))
+                    } else {
+                        None
+                    }
+                } else {
+                    None
+                }
+            };
+            if let Some((current_taskbar_index, drag_start_client_x)) = drag_result {
+                let _ = ReleaseCapture();
+                if let Some((target_index, target_taskbar)) = taskbar_at_point(pt) {
+                    if target_index != current_taskbar_index {
+                        let new_offset = offset_for_drop_point(
+                            target_taskbar.hwnd,
+                            target_taskbar.rect,
+                            pt,
+                            drag_start_client_x,
+                        );
+                        {
+                            let mut state = lock_state();
+                            if let Some(s) = state.as_mut() {
+                                s.ui.tray_offset = new_offset;
+                            }
+                        }
+                        if attach_to_taskbar(hwnd, target_index) {
+                            position_at_taskbar();
+                            render_layered();
+                        }
+                    }
+                }
+                save_state_settings();
+            } else {
+                on_widget_click(hwnd);
+            }
+            LRESULT(0)
+        }
+        WM_RBUTTONUP => {
+            show_context_menu(hwnd);
+            LRESULT(0)
+        }
+        // Clicks and drags on the widget never take focus from the active
+        // app; only the context menu briefly does, and hands it back.
+        WM_MOUSEACTIVATE => LRESULT(MA_NOACTIVATE as isize),
+        WM_MOUSEWHEEL => {
+            let delta = ((wparam.0 >> 16) & 0xFFFF) as i16 as i32;
+            let changed = {
+                let mut state = lock_state();
+                match state.as_mut() {
+                    Some(s) => {
+                        // Touchpads send many small deltas; change mode once
+                        // per notch.
+                        s.ui.wheel_delta += delta;
+                        let notches = s.ui.wheel_delta / WHEEL_DELTA as i32;
+                        s.ui.wheel_delta %= WHEEL_DELTA as i32;
+                        if notches != 0 {
+                            // Scrolling down moves forward through the modes.
+                            s.ui.display_mode = s.ui.display_mode.cycled(-notches);
+                        }
+                        notches != 0
+                    }
+                    None => false,
+                }
+            };
+            if changed {
+                save_state_settings();
+                position_at_taskbar();
+                render_layered();
+            }
+            LRESULT(0)
+        }
+        WM_HOTKEY if wparam.0 as i32 == OVERLAY_HOTKEY_ID => {
+            show_usage_overlay();
+            LRESULT(0)
+        }
+        WM_MBUTTONUP => {
+            let polling_paused = {
+                let state = lock_state();
+                state
+                    .as_ref()
+                    .map(|s| s.poll.polling_paused)
+                    .unwrap_or(false)
+            };
+            refresh_unless_paused(polling_paused);
+            LRESULT(0)
+        }
+        WM_COMMAND => {
+            let id = wparam.0 as u16;
+            match id {
+                1 => refresh_now(),
+                IDM_VERSION_ACTION => {
+                    let (install_channel, release) = {
+                        let state = lock_state();
+                        match state.as_ref() {
+                            Some(s) => (
+                                s.ui.install_channel,
+                                match &s.poll.update_status {
+                                    UpdateStatus::Available(release) => Some(release.clone()),
+                                    _ => None,
+                                },
+                            ),
+                            None => (InstallChannel::Portable, None),
+                        }
+                    };
+
+                    match install_channel {
+                        InstallChannel::Winget => {
+                            if release.is_some() {
+                                begin_winget_update(hwnd);
+                            } else {
+                                begin_update_check(hwnd, true);
+                            }
+                        }
+                        InstallChannel::Portable => {
+                            if let Some(release) = release {
+                                begin_update_apply(hwnd, release);
+                            } else {
+                                begin_update_check(hwnd, true);
+                            }
+                        }
+                    }
+                }
+                2 => {
+                    // WM_DESTROY unhooks, stops the poll worker, removes the
+                    // tray icons and ends the message loop.
+                    let _ = DestroyWindow(hwnd);
+                }
+                IDM_RESET_POSITION => {
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            s.ui.tray_offset = 0;
+                        }
+                    }
+                    save_state_settings();
+                    position_at_taskbar();
+                }
+                IDM_REEMBED => {
+                    reembed_in_taskbar(hwnd);
+                }
+                IDM_RESTART => {
+                    restart_widget(hwnd);
+                }
+                IDM_FLOAT_WIDGET => {
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            s.ui.float_widget = !s.ui.float_widget;
+                        }
+                    }
+                    save_state_settings();
+                    // A child of the taskbar cannot cleanly become a popup
+                    // again, so switch modes in a fresh process.
+                    restart_widget(hwnd);
+                }
+                IDM_START_WITH_WINDOWS => {
+                    let enable = !is_startup_enabled();
+                    set_startup_enabled(enable);
+                    // One way of starting at sign-in at a time.
+                    if enable {
+                        if let Err(error) = startup_task::unregister() {
+                            report_startup_task_error(hwnd, &error);
+                        }
+                    }
+                }
+                IDM_START_WITH_WINDOWS_TASK => {
+                    let result = if startup_task::is_registered() {
+                        startup_task::unregister()
+                    } else {
+                        let delay_secs = {
+                            let state = lock_state();
+                            state
+                                .as_ref()
+                                .map(|s| s.ui.startup_delay_secs)
+                                .unwrap_or(DEFAULT_STARTUP_DELAY_SECS)
+                        };
+                        startup_task::register(delay_secs).map(|()| set_startup_enabled(false))
+                    };
+                    if let Err(error) = result {
+                        report_startup_task_error(hwnd, &error);
+                    }
+                }
+                IDM_PAUSE_ON_METERED => {
+                    let resume_deferred = {
+                        let mut state = lock_state();
+                        match state.as_mut() {
+                            Some(s) => {
+                                s.poll.pause_on_metered = !s.poll.pause_on_metered;
+                                !s.poll.pause_on_metered && s.poll.metered_deferred
+                            }
+                            None => false,
+                        }
+                    };
+                    save_state_settings();
+                    // Switching the option off resumes a deferred poll straight
+                    // away; switching it on takes effect at the next poll.
+                    if resume_deferred {
+                        poll_worker::request();
+                    }
+                }
+                IDM_POLL_LESS_ON_BATTERY => {
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            s.poll.poll_less_on_battery = !s.poll.poll_less_on_battery;
+                        }
+                    }
+                    save_state_settings();
+                    update_power_saving(hwnd);
+                }
+                IDM_API_KEY_LIMITS => {
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            s.ui.api_key_limits = !s.ui.api_key_limits;
+                            poller::set_api_key_mode(s.ui.api_key_limits);
+                            s.ui.session_text = "...".to_string();
+                            s.ui.weekly_text = "...".to_string();
+                        }
+                    }
+                    save_state_settings();
+                    render_layered();
+                    poll_worker::request();
+                }
+                IDM_SHOW_COST => {
+                    let enabled = {
+                        let mut state = lock_state();
+                        match state.as_mut() {
+                            Some(s) => {
+                                s.poll.show_cost = !s.poll.show_cost;
+                                s.poll.cost = None;
+                                s.poll.show_cost
+                            }
+                            None => false,
+                        }
+                    };
+                    save_state_settings();
+                    sync_tray_icons(hwnd);
+                    refresh_flyout();
+                    // The estimate is computed alongside a poll.
+                    if enabled {
+                        poll_worker::request();
+                    }
+                }
+                IDM_SHOW_TOKEN_COUNTS => {
+                    let enabled = {
+                        let mut state = lock_state();
+                        match state.as_mut() {
+                            Some(s) => {
+                                s.poll.show_token_counts = !s.poll.show_token_counts;
+                                s.poll.tokens_today = None;
+                                s.poll.show_token_counts
+                            }
+                            None => false,
+                        }
+                    };
+                    save_state_settings();
+                    refresh_flyout();
+                    // Totals are read alongside a poll.
+                    if enabled {
+                        poll_worker::request();
+                    }
+                }
+                IDM_SHOW_MESSAGES_LEFT => {
+                    let enabled = {
+                        let mut state = lock_state();
+                        match state.as_mut() {
+                            Some(s) => {
+                                s.poll.show_messages_left = !s.poll.show_messages_left;
+                                s.poll.messages_left = None;
+                                s.poll.show_messages_left
+                            }
+                            None => false,
+                        }
+                    };
+                    save_state_settings();
+                    refresh_flyout();
+                    // The estimate is made alongside a poll.
+                    if enabled {
+                        poll_worker::request();
+                    }
+                }
+                IDM_EXPORT_HISTORY => export_history(hwnd),
+                IDM_OPEN_USAGE_PAGE => open_usage_page(),
+                IDM_COPY_SNAPSHOT => copy_snapshot(hwnd),
+                IDM_SAVE_SNAPSHOT => save_snapshot(hwnd),
+                IDM_NOTIFY_USAGE_JUMPS => {
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            s.poll.notify_usage_jumps = !s.poll.notify_usage_jumps;
+                        }
+                    }
+                    save_state_settings();
+                }
+                IDM_NOTIFY_LIMIT_RESETS => {
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            s.ui.notify_limit_resets = !s.ui.notify_limit_resets;
+                        }
+                    }
+                    save_state_settings();
+                    schedule_limit_resets();
+                }
+                IDM_NOTIFY_USAGE_RESETS => {
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            s.poll.notify_usage_resets = !s.poll.notify_usage_resets;
+                        }
+                    }
+                    save_state_settings();
+                }
+                IDM_OPACITY_100 | IDM_OPACITY_85 | IDM_OPACITY_70 | IDM_OPACITY_50 => {
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            s.ui.opacity = match id {
+                                IDM_OPACITY_85 => 85,
+                                IDM_OPACITY_70 => 70,
+                                IDM_OPACITY_50 => 50,
+                                _ => 100,
+                            };
+                        }
+                    }
+                    save_state_settings();
+                    apply_opacity();
+                }
+                IDM_REMINDER_OFF | IDM_REMINDER_6H | IDM_REMINDER_12H | IDM_REMINDER_24H => {
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            s.ui.weekly_reminder_hours = match id {
+                                IDM_REMINDER_6H => 6,
+                                IDM_REMINDER_12H => 12,
+                                IDM_REMINDER_24H => 24,
+                                _ => 0,
+                            };
+                        }
+                    }
+                    save_state_settings();
+                    schedule_weekly_reminder();
+                }
+                IDM_QUIET_WHEN_FULLSCREEN => {
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            s.ui.quiet_when_fullscreen = !s.ui.quiet_when_fullscreen;
+                        }
+                    }
+                    save_state_settings();
+                    render_layered();
+                }
+                IDM_CARD_BACKGROUND => {
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            s.ui.card_background = !s.ui.card_background;
+                        }
+                    }
+                    save_state_settings();
+                    render_layered();
+                }
+                IDM_ELAPSED_SESSION | IDM_ELAPSED_WEEKLY => {
+                    let window = if id == IDM_ELAPSED_SESSION {
+                        UsageWindow::Session
+                    } else {
+                        UsageWindow::Weekly
+                    };
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            if let Some(index) = s.ui.elapsed_bars.iter().position(|w| *w == window)
+                            {
+                                s.ui.elapsed_bars.remove(index);
+                            } else {
+                                s.ui.elapsed_bars.push(window);
+                            }
+                        }
+                    }
+                    save_state_settings();
+                    render_layered();
+                }
+                IDM_SHOW_SESSION_ROW | IDM_SHOW_WEEKLY_ROW => {
+                    let window = if id == IDM_SHOW_SESSION_ROW {
+                        UsageWindow::Session
+                    } else {
+                        UsageWindow::Weekly
+                    };
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            let visible = s.ui.display_mode.shows(window);
+                            s.ui.display_mode = s.ui.display_mode.with_row(window, !visible);
+                        }
+                    }
+                    save_state_settings();
+                    position_at_taskbar();
+                    render_layered();
+                }
+                IDM_MIRROR_LAYOUT => {
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            s.ui.mirror_layout = !s.ui.mirror_layout;
+                        }
+                    }
+                    save_state_settings();
+                    render_layered();
+                }
+                IDM_ONE_DECIMAL => {
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            s.ui.percent_decimals = if s.ui.percent_decimals == 0 { 1 } else { 0 };
+                            refresh_usage_texts(s);
+                        }
+                    }
+                    save_state_settings();
+                    position_at_taskbar();
+                    render_layered();
+                }
+                IDM_SHOW_DIVIDER => {
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            s.ui.show_divider = !s.ui.show_divider;
+                        }
+                    }
+                    save_state_settings();
+                    position_at_taskbar();
+                    render_layered();
+                }
+                IDM_EMPHASIZE_WARNINGS => {
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            s.ui.emphasize_warnings = !s.ui.emphasize_warnings;
+                        }
+                    }
+                    save_state_settings();
+                    // Bold text can need a wider widget.
+                    position_at_taskbar();
+                    render_layered();
+                }
+                IDM_BLEND_WITH_TASKBAR => {
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            s.ui.blend_with_taskbar = !s.ui.blend_with_taskbar;
+                        }
+                    }
+                    save_state_settings();
+                    render_layered();
+                }
+                IDM_CLICK_SHOW_DETAILS
+                | IDM_CLICK_REFRESH
+                | IDM_CLICK_OPEN_USAGE_PAGE
+                | IDM_CLICK_NOTHING => {
+                    let action = match id {
+                        IDM_CLICK_REFRESH => ClickAction::Refresh,
+                        IDM_CLICK_OPEN_USAGE_PAGE => ClickAction::OpenUsagePage,
+                        IDM_CLICK_NOTHING => ClickAction::Nothing,
+                        _ => ClickAction::ShowDetails,
+                    };
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            s.ui.left_click = action;
+                        }
+                    }
+                    save_state_settings();
+                }
+                IDM_PALETTE_STANDARD | IDM_PALETTE_DEUTERANOPIA | IDM_PALETTE_PROTANOPIA => {
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            s.ui.palette = match id {
+                                IDM_PALETTE_DEUTERANOPIA => Palette::Deuteranopia,
+                                IDM_PALETTE_PROTANOPIA => Palette::Protanopia,
+                                _ => Palette::Standard,
+                            };
+                        }
+                    }
+                    save_state_settings();
+                    render_layered();
+                    sync_tray_icons(hwnd);
+                    refresh_flyout();
+                }
+                IDM_BADGE_MODE => {
+                    toggle_badge_mode(hwnd);
+                }
+                IDM_URGENT_ROW_FIRST => {
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            s.ui.urgent_row_first = !s.ui.urgent_row_first;
+                        }
+                    }
+                    save_state_settings();
+                    render_layered();
+                }
+                IDM_WEEKLY_FIRST => {
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            let top = if s.ui.row_order.first() == Some(&UsageWindow::Weekly) {
+                                UsageWindow::Session
+                            } else {
+                                UsageWindow::Weekly
+                            };
+                            s.ui.row_order.retain(|window| *window != top);
+                            s.ui.row_order.insert(0, top);
+                        }
+                    }
+                    save_state_settings();
+                    render_layered();
+                }
+                IDM_SHOW_BURN_RATE => {
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            s.ui.show_burn_rate = !s.ui.show_burn_rate;
+                            refresh_usage_texts(s);
+                        }
+                    }
+                    save_state_settings();
+                    render_layered();
+                }
+                IDM_FREQ_30SEC | IDM_FREQ_1MIN | IDM_FREQ_5MIN | IDM_FREQ_15MIN
+                | IDM_FREQ_30MIN | IDM_FREQ_1HOUR | IDM_FREQ_SMART => {
+                    let interval = match id {
+                        IDM_FREQ_30SEC => Some(POLL_30_SEC),
+                        IDM_FREQ_1MIN => Some(POLL_1_MIN),
+                        IDM_FREQ_5MIN => Some(POLL_5_MIN),
+                        IDM_FREQ_15MIN => Some(POLL_15_MIN),
+                        IDM_FREQ_30MIN => Some(POLL_30_MIN),
+                        IDM_FREQ_1HOUR => Some(POLL_1_HOUR),
+                        _ => None,
+                    };
+                    set_poll_interval(hwnd, interval);
+                }
+                IDM_FREQ_CUSTOM => {
+                    if let Some(interval) = ask_custom_poll_interval() {
+                        set_poll_interval(hwnd, Some(interval));
+                    }
+                }
+                IDM_MODEL_CLAUDE_CODE | IDM_MODEL_CODEX | IDM_MODEL_ANTIGRAVITY => {
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            match id {
+                                IDM_MODEL_CLAUDE_CODE => {
+                                    if s.ui.show_codex
+                                        || s.ui.show_antigravity
+                                        || !s.ui.show_claude_code
+                                    {
+                                        s.ui.show_claude_code = !s.ui.show_claude_code;
+                                    }
+                                }
+                                IDM_MODEL_CODEX => {
+                                    if s.ui.show_claude_code
+                                        || s.ui.show_antigravity
+                                        || !s.ui.show_codex
+                                    {
+                                        s.ui.show_codex = !s.ui.show_codex;
+                                    }
+                                }
+                                IDM_MODEL_ANTIGRAVITY => {
+                                    if s.ui.show_claude_code
+                                        || s.ui.show_codex
+                                        || !s.ui.show_antigravity
+                                    {
+                                        s.ui.show_antigravity = !s.ui.show_antigravity;
+                                    }
+                                }
+                                _ => {}
+                            }
+                            s.ui.session_text = "...".to_string();
+                            s.ui.weekly_text = "...".to_string();
+                            s.ui.codex_session_text = "...".to_string();
+                            s.ui.codex_weekly_text = "...".to_string();
+                            s.ui.antigravity_session_text = "...".to_string();
+                            s.ui.antigravity_weekly_text = "...".to_string();
+                        }
+                    }
+                    save_state_settings();
+                    position_at_taskbar();
+                    render_layered();
+                    sync_tray_icons(hwnd);
+                    poll_worker::request();
+                }
+                IDM_LANG_SYSTEM
+                | IDM_LANG_ENGLISH
+                | IDM_LANG_DUTCH
+                | IDM_LANG_SPANISH
+                | IDM_LANG_FRENCH
+                | IDM_LANG_GERMAN
+                | IDM_LANG_JAPANESE
+                | IDM_LANG_KOREAN
+                | IDM_LANG_TRADITIONAL_CHINESE
+                | IDM_LANG_RUSSIAN
+                | IDM_LANG_PORTUGUESE_BRAZIL => {
+                    let language_override = match id {
+                        IDM_LANG_SYSTEM => None,
+                        IDM_LANG_ENGLISH => Some(LanguageId::English),
+                        IDM_LANG_DUTCH => Some(LanguageId::Dutch),
+                        IDM_LANG_SPANISH => Some(LanguageId::Spanish),
+                        IDM_LANG_FRENCH => Some(LanguageId::French),
+                        IDM_LANG_GERMAN => Some(LanguageId::German),
+                        IDM_LANG_JAPANESE => Some(LanguageId::Japanese),
+                        IDM_LANG_KOREAN => Some(LanguageId::Korean),
+                        IDM_LANG_TRADITIONAL_CHINESE => Some(LanguageId::TraditionalChinese),
+                        IDM_LANG_RUSSIAN => Some(LanguageId::Russian),
+                        IDM_LANG_PORTUGUESE_BRAZIL => Some(LanguageId::PortugueseBrazil),
+                        _ => None,
+                    };
+                    {
+                        let mut state = lock_state();
+                        if let Some(s) = state.as_mut() {
+                            apply_language_to_state(s, language_override);
+                        }
+                    }
+                    save_state_settings();
+                    render_layered();
+                }
+                IDM_SNOOZE_ALERTS_HOUR => snooze_alerts(Some(3_600)),
+                IDM_SNOOZE_ALERTS_RESET => snooze_alerts(None),
+                IDM_RESUME_ALERTS => resume_alerts(),
+                IDM_PAUSE_POLLING => {
+                    let paused = {
+                        let state = lock_state();
+                        state
+                            .as_ref()
+                            .map(|s| s.poll.polling_paused)
+                            .unwrap_or(false)
+                    };
+                    set_polling_paused(hwnd, !paused);
+                }
+                IDM_SIMULATE_RESET => {
+                    simulate_reset();
+                }
+                IDM_SIMULATE_NEAR_LIMIT => set_simulation(Some(Simulation::NearSessionLimit)),
+                IDM_SIMULATE_LIMIT_HIT => set_simulation(Some(Simulation::LimitHit)),
+                IDM_SIMULATE_POLL_FAILURE => set_simulation(Some(Simulation::PollFailure)),
+                IDM_STOP_SIMULATION => set_simulation(None),
+                id if id == tray_icon::IDM_TOGGLE_WIDGET => {
+                    toggle_widget_visibility(hwnd);
+                }
+                _ => {}
+            }
+            LRESULT(0)
+        }
+        _ if msg == WM_APP_TRAY => {
+            match tray_icon::handle_message(lparam) {
+                tray_icon::TrayAction::ToggleWidget => {
+                    toggle_widget_visibility(hwnd);
+                }
+                tray_icon::TrayAction::ShowContextMenu => {
+                    show_context_menu(hwnd);
+                }
+                tray_icon::TrayAction::BalloonClicked => {
+                    if !accept_interval_suggestion(hwnd)
+                        && !open_welcome_readme()
+                        && !flyout::is_open()
+                    {
+                        toggle_flyout(hwnd);
+                    }
+                }
+                tray_icon::TrayAction::BalloonDismissed => {
+                    let mut state = lock_state();
+                    if let Some(s) = state.as_mut() {
+                        s.poll.suggested_poll_interval = None;
+                        s.ui.welcome_pending = false;
+                    }
+                }
+                tray_icon::TrayAction::None => {}
+            }
+            LRESULT(0)
+        }
+        WM_DESTROY => {
+            let hook = {
+                let state = lock_state();
+                state.as_ref().and_then(|s| s.handles.win_event_hook)
+            };
+            if let Some(h) = hook {
+                native_interop::unhook_win_event(h);
+            }
+            let _ = WTSUnRegisterSessionNotification(hwnd);
+            hotkey::unregister(hwnd, OVERLAY_HOTKEY_ID);
+            poll_worker::shutdown();
+            badge::hide();
+            tray_icon::remove_all(hwnd);
+            PostQuitMessage(0);
+            LRESULT(0)
+        }
+        _ if msg == native_interop::taskbar_button_created_message() => {
+            sync_taskbar_progress(hwnd);
+            LRESULT(0)
+        }
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
+
+/// The fallback popup has its own taskbar button; mirror the Claude 5h usage
+/// on it, turning yellow at the warning level and red at the limit.
+fn sync_taskbar_progress(hwnd: HWND) {
+    let progress = {
+        let state = lock_state();
+        match state.as_ref() {
+            Some(s) if !s.handles.embedded => s
+                .poll
+                .data
+                .as_ref()
+                .and_then(|data| data.claude_code.as_ref())
+                .filter(|_| s.ui.show_claude_code)
+                .map(|claude_code| {
+                    let percent = claude_code.session.percentage;
+                    let state = if percent >= 100.0 {
+                        TaskbarProgressState::Error
+                    } else if percent >= WARNING_PERCENT {
+                        TaskbarProgressState::Warning
+                    } else {
+                        TaskbarProgressState::Normal
+                    };
+                    (percent, state)
+                }),
+            _ => return,
+        }
+    };
+    native_interop::set_taskbar_progress(hwnd, progress);
+}
+
+fn show_context_menu(hwnd: HWND) {
+    unsafe {
+        let (
+            current_interval,
+            smart_polling,
+            strings,
+            language,
+            language_override,
+            install_channel,
+            update_status,
+            widget_visible,
+            show_claude_code,
+            show_codex,
+            show_antigravity,
+            polling_paused,
+            show_burn_rate,
+            pause_on_metered,
+            api_key_limits,
+            card_background,
+            blend_with_taskbar,
+            show_divider,
+            one_decimal,
+            weekly_first,
+            palette,
+            left_click,
+            show_cost,
+            show_token_counts,
+            notify_usage_jumps,
+            weekly_reminder_hours,
+            quiet_when_fullscreen,
+            poll_less_on_battery,
+            elapsed_bars,
+            row_labels,
+            mirror_layout,
+            display_mode,
+            urgent_row_first,
+            badge_mode,
+            has_alerts,
+            alerts_snoozed,
+            notify_limit_resets,
+            show_messages_left,
+            notify_usage_resets,
+            emphasize_warnings,
+            opacity,
+            float_widget,
+        ) = {
+            let state = lock_state();
+            match state.as_ref() {
+                Some(s) => (
+                    s.poll.poll_interval_ms,
+                    s.poll.smart_polling,
+                    s.ui.language.strings(),
+                    s.ui.language,
+                    s.ui.language_override,
+                    s.ui.install_channel,
+                    s.poll.update_status.clone(),
+                    s.ui.widget_visible,
+                    s.ui.show_claude_code,
+                    s.ui.show_codex,
+                    s.ui.show_antigravity,
+                    s.poll.polling_paused,
+                    s.ui.show_burn_rate,
+                    s.poll.pause_on_metered,
+                    s.ui.api_key_limits,
+                    s.ui.card_background,
+                    s.ui.blend_with_taskbar,
+                    s.ui.show_divider,
+                    s.ui.percent_decimals > 0,
+                    s.ui.row_order.first() == Some(&UsageWindow::Weekly),
+                    s.ui.palette,
+                    s.ui.left_click,
+                    s.poll.show_cost,
+                    s.poll.show_token_counts,
+                    s.poll.notify_usage_jumps,
+                    s.ui.weekly_reminder_hours,
+                    s.ui.quiet_when_fullscreen,
+                    s.poll.poll_less_on_battery,
+                    s.ui.elapsed_bars.clone(),
+                    UsageWindow::ALL.map(|window| row_label(s, window)),
+                    s.ui.mirror_layout,
+                    s.ui.display_mode,
+                    s.ui.urgent_row_first,
+                    s.ui.badge_mode,
+                    !s.poll.alerts.is_empty(),
+                    alerts_snoozed(s),
+                    s.ui.notify_limit_resets,
+                    s.poll.show_messages_left,
+                    s.poll.notify_usage_resets,
+                    s.ui.emphasize_warnings,
+                    s.ui.opacity,
+                    s.ui.float_widget,
+                ),
+                None => (
+                    POLL_15_MIN,
+                    false,
+                    LanguageId::English.strings(),
+                    LanguageId::English,
+                    None,
+                    InstallChannel::Portable,
+                    UpdateStatus::Idle,
+                    true,
+                    true,
+                    false,
+                    false,
+                    false,
+                    false,
+                    false,
+                    false,
+                    false,
+                    false,
+                    true,
+                    false,
+                    false,
+                    Palette::default(),
+                    ClickAction::default(),
+                    false,
+                    false,
+                    false,
+                    0,
+                    false,
+                    true,
+                    Vec::new(),
+                    [String::from("5h"), String::from("7d")],
+                    false,
+                    DisplayMode::default(),
+                    false,
+                    false,
+                    false,
+                    false,
+                    false,
+                    false,
+                    false,
+                    false,
+                    100,
+                    false,
+                ),
+            }
+        };
+
+        let menu = CreatePopupMenu().unwrap();
+
+        let refresh_str = native_interop::wide_str(strings.refresh);
+        let refresh_flags = if polling_paused {
+            MF_GRAYED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        let _ = AppendMenuW(
+            menu,
+            refresh_flags,
+            1,
+            PCWSTR::from_raw(refresh_str.as_ptr()),
+        );
+
+        let pause_str = native_interop::wide_str(strings.pause_polling);
+        let pause_flags = if polling_paused {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        let _ = AppendMenuW(
+            menu,
+            pause_flags,
+            IDM_PAUSE_POLLING as usize,
+            PCWSTR::from_raw(pause_str.as_ptr()),
+        );
+
+        let usage_page_str = native_interop::wide_str(strings.open_usage_dashboard);
+        let _ = AppendMenuW(
+            menu,
+            MENU_ITEM_FLAGS(0),
+            IDM_OPEN_USAGE_PAGE as usize,
+            PCWSTR::from_raw(usage_page_str.as_ptr()),
+        );
+
+        if has_alerts {
+            let snooze_menu = CreatePopupMenu().unwrap();
+            let snooze_items = [
+                (IDM_SNOOZE_ALERTS_HOUR, strings.snooze_for_hour, false),
+                (IDM_SNOOZE_ALERTS_RESET, strings.snooze_until_reset, false),
+                (IDM_RESUME_ALERTS, strings.resume_alerts, !alerts_snoozed),
+            ];
+            for (id, label, grayed) in snooze_items {
+                let label_str = native_interop::wide_str(label);
+                let flags = if grayed {
+                    MF_GRAYED
+                } else {
+                    MENU_ITEM_FLAGS(0)
+                };
+                let _ = AppendMenuW(
+                    snooze_menu,
+                    flags,
+                    id as usize,
+                    PCWSTR::from_raw(label_str.as_ptr()),
+                );
+            }
+            let snooze_label = native_interop::wide_str(strings.snooze_alerts);
+            let snooze_flags = if alerts_snoozed {
+                MF_POPUP | MF_CHECKED
+            } else {
+                MF_POPUP
+            };
+            let _ = AppendMenuW(
+                menu,
+                snooze_flags,
+                snooze_menu.0 as usize,
+                PCWSTR::from_raw(snooze_label.as_ptr()),
+            );
+        }
+
+        // Update Frequency submenu
+        let freq_menu = CreatePopupMenu().unwrap();
+        let freq_items: [(u16, u32, &str); 6] = [
+            (IDM_FREQ_30SEC, POLL_30_SEC, strings.thirty_seconds),
+            (IDM_FREQ_1MIN, POLL_1_MIN, strings.one_minute),
+            (IDM_FREQ_5MIN, POLL_5_MIN, strings.five_minutes),
+            (IDM_FREQ_15MIN, POLL_15_MIN, strings.fifteen_minutes),
+            (IDM_FREQ_30MIN, POLL_30_MIN, strings.thirty_minutes),
+            (IDM_FREQ_1HOUR, POLL_1_HOUR, strings.one_hour),
+        ];
+        let custom_interval = !freq_items
+            .iter()
+            .any(|(_, interval, _)| *interval == current_interval);
+        for (id, interval, label) in freq_items {
+            let label_str = native_interop::wide_str(label);
+            let flags = if !smart_polling && interval == current_interval {
+                MF_CHECKED
+            } else {
+                MENU_ITEM_FLAGS(0)
+            };
+            let _ = AppendMenuW(
+                freq_menu,
+                flags,
+                id as usize,
+                PCWSTR::from_raw(label_str.as_ptr()),
+            );
+        }
+
+        // The custom item shows the interval while it is the one in use.
+        let custom_label = if custom_interval {
+            format!(
+                "{} ({})",
+                strings.custom_interval,
+                format::duration(
+                    Duration::from_millis(current_interval as u64),
+                    Style::DETAIL,
+                    strings
+                )
+            )
+        } else {
+            strings.custom_interval.to_string()
+        };
+        let custom_str = native_interop::wide_str(&custom_label);
+        let custom_flags = if !smart_polling && custom_interval {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        let _ = AppendMenuW(
+            freq_menu,
+            custom_flags,
+            IDM_FREQ_CUSTOM as usize,
+            PCWSTR::from_raw(custom_str.as_ptr()),
+        );
+
+        let _ = AppendMenuW(freq_menu, MF_SEPARATOR, 0, PCWSTR::null());
+        let smart_label = native_interop::wide_str(strings.smart_polling);
+        let smart_flags = if smart_polling {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        let _ = AppendMenuW(
+            freq_menu,
+            smart_flags,
+            IDM_FREQ_SMART as usize,
+            PCWSTR::from_raw(smart_label.as_ptr()),
+        );
+
+        let freq_label = native_interop::wide_str(strings.update_frequency);
+        let _ = AppendMenuW(
+            menu,
+            MF_POPUP,
+            freq_menu.0 as usize,
+            PCWSTR::from_raw(freq_label.as_ptr()),
+        );
+
+        // Models submenu
+        let models_menu = CreatePopupMenu().unwrap();
+        let claude_model = native_interop::wide_str(strings.claude_code_model);
+        let claude_flags = if show_claude_code {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        let _ = AppendMenuW(
+            models_menu,
+            claude_flags,
+            IDM_MODEL_CLAUDE_CODE as usize,
+            PCWSTR::from_raw(claude_model.as_ptr()),
+        );
+
+        let codex_model = native_interop::wide_str(strings.codex_model);
+        let codex_flags = if show_codex {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        let _ = AppendMenuW(
+            models_menu,
+            codex_flags,
+            IDM_MODEL_CODEX as usize,
+            PCWSTR::from_raw(codex_model.as_ptr()),
+        );
+
+        let antigravity_model = native_interop::wide_str(strings.antigravity_model);
+        let antigravity_flags = if show_antigravity {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        let _ = AppendMenuW(
+            models_menu,
+            antigravity_flags,
+            IDM_MODEL_ANTIGRAVITY as usize,
+            PCWSTR::from_raw(antigravity_model.as_ptr()),
+        );
+
+        let models_label = native_interop::wide_str(strings.models);
+        let _ = AppendMenuW(
+            menu,
+            MF_POPUP,
+            models_menu.0 as usize,
+            PCWSTR::from_raw(models_label.as_ptr()),
+        );
+
+        // Settings submenu
+        let settings_menu = CreatePopupMenu().unwrap();
+
+        let startup_str = native_interop::wide_str(strings.start_with_windows);
+        let startup_flags = if is_startup_enabled() {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        let _ = AppendMenuW(
+            settings_menu,
+            startup_flags,
+            IDM_START_WITH_WINDOWS as usize,
+            PCWSTR::from_raw(startup_str.as_ptr()),
+        );
+
+        let startup_task_str = native_interop::wide_str(strings.start_with_windows_task);
+        let startup_task_flags = if startup_task::is_registered() {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        let _ = AppendMenuW(
+            settings_menu,
+            startup_task_flags,
+            IDM_START_WITH_WINDOWS_TASK as usize,
+            PCWSTR::from_raw(startup_task_str.as_ptr()),
+        );
+
+        let burn_rate_str = native_interop::wide_str(strings.show_burn_rate);
+        let burn_rate_flags = if show_burn_rate {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        let _ = AppendMenuW(
+            settings_menu,
+            burn_rate_flags,
+            IDM_SHOW_BURN_RATE as usize,
+            PCWSTR::from_raw(burn_rate_str.as_ptr()),
+        );
+
+        let card_str = native_interop::wide_str(strings.card_background);
+        let card_flags = if card_background {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        let _ = AppendMenuW(
+            settings_menu,
+            card_flags,
+            IDM_CARD_BACKGROUND as usize,
+            PCWSTR::from_raw(card_str.as_ptr()),
+        );
+
+        let decimal_str = native_interop::wide_str(strings.one_decimal);
+        let decimal_flags = if one_decimal {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        let _ = AppendMenuW(
+            settings_menu,
+            decimal_flags,
+            IDM_ONE_DECIMAL as usize,
+            PCWSTR::from_raw(decimal_str.as_ptr()),
+        );
+
+        let mirror_str = native_interop::wide_str(strings.mirror_layout);
+        let mirror_flags = if mirror_layout {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        let _ = AppendMenuW(
+            settings_menu,
+            mirror_flags,
+            IDM_MIRROR_LAYOUT as usize,
+            PCWSTR::from_raw(mirror_str.as_ptr()),
+        );
+
+        let divider_str = native_interop::wide_str(strings.show_divider);
+        let divider_flags = if show_divider {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        let _ = AppendMenuW(
+            settings_menu,
+            divider_flags,
+            IDM_SHOW_DIVIDER as usize,
+            PCWSTR::from_raw(divider_str.as_ptr()),
+        );
+
+        let emphasize_str = native_interop::wide_str(strings.emphasize_high_usage);
+        let emphasize_flags = if emphasize_warnings {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        let _ = AppendMenuW(
+            settings_menu,
+            emphasize_flags,
+            IDM_EMPHASIZE_WARNINGS as usize,
+            PCWSTR::from_raw(emphasize_str.as_ptr()),
+        );
+
+        // The card is opaque, so blending only applies without it.
+        let blend_str = native_interop::wide_str(strings.blend_with_taskbar);
+        let mut blend_flags = if blend_with_taskbar {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        if card_background {
+            blend_flags |= MF_GRAYED;
+        }
+        let _ = AppendMenuW(
+            settings_menu,
+            blend_flags,
+            IDM_BLEND_WITH_TASKBAR as usize,
+            PCWSTR::from_raw(blend_str.as_ptr()),
+        );
+
+        let opacity_menu = CreatePopupMenu().unwrap();
+        let opacity_items: [(u16, u8); 4] = [
+            (IDM_OPACITY_100, 100),
+            (IDM_OPACITY_85, 85),
+            (IDM_OPACITY_70, 70),
+            (IDM_OPACITY_50, 50),
+        ];
+        for (id, percent) in opacity_items {
+            let label_str = native_interop::wide_str(&format!("{percent}%"));
+            let flags = if percent == opacity {
+                MF_CHECKED
+            } else {
+                MENU_ITEM_FLAGS(0)
+            };
+            let _ = AppendMenuW(
+                opacity_menu,
+                flags,
+                id as usize,
+                PCWSTR::from_raw(label_str.as_ptr()),
+            );
+        }
+        let opacity_label = native_interop::wide_str(strings.opacity);
+        let _ = AppendMenuW(
+            settings_menu,
+            MF_POPUP,
+            opacity_menu.0 as usize,
+            PCWSTR::from_raw(opacity_label.as_ptr()),
+        );
+
+        // The manual order only applies while rows are not sorted by urgency.
+        let weekly_first_str = native_interop::wide_str(strings.weekly_first);
+        let mut weekly_first_flags = if weekly_first {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        if urgent_row_first {
+            weekly_first_flags |= MF_GRAYED;
+        }
+        let _ = AppendMenuW(
+            settings_menu,
+            weekly_first_flags,
+            IDM_WEEKLY_FIRST as usize,
+            PCWSTR::from_raw(weekly_first_str.as_ptr()),
+        );
+
+        let urgent_str = native_interop::wide_str(strings.urgent_row_first);
+        let urgent_flags = if urgent_row_first {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        let _ = AppendMenuW(
+            settings_menu,
+            urgent_flags,
+            IDM_URGENT_ROW_FIRST as usize,
+            PCWSTR::from_raw(urgent_str.as_ptr()),
+        );
+
+        let cost_str = native_interop::wide_str(strings.show_cost);
+        let cost_flags = if show_cost {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        let _ = AppendMenuW(
+            settings_menu,
+            cost_flags,
+            IDM_SHOW_COST as usize,
+            PCWSTR::from_raw(cost_str.as_ptr()),
+        );
+
+        let tokens_str = native_interop::wide_str(strings.show_token_counts);
+        let tokens_flags = if show_token_counts {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        let _ = AppendMenuW(
+            settings_menu,
+            tokens_flags,
+            IDM_SHOW_TOKEN_COUNTS as usize,
+            PCWSTR::from_raw(tokens_str.as_ptr()),
+        );
+
+        let messages_str = native_interop::wide_str(strings.show_messages_left);
+        let messages_flags = if show_messages_left {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        let _ = AppendMenuW(
+            settings_menu,
+            messages_flags,
+            IDM_SHOW_MESSAGES_LEFT as usize,
+            PCWSTR::from_raw(messages_str.as_ptr()),
+        );
+
+        let jumps_str = native_interop::wide_str(strings.notify_usage_jumps);
+        let jumps_flags = if notify_usage_jumps {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        let _ = AppendMenuW(
+            settings_menu,
+            jumps_flags,
+            IDM_NOTIFY_USAGE_JUMPS as usize,
+            PCWSTR::from_raw(jumps_str.as_ptr()),
+        );
+
+        let limit_resets_str = native_interop::wide_str(strings.notify_limit_resets);
+        let limit_resets_flags = if notify_limit_resets {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        let _ = AppendMenuW(
+            settings_menu,
+            limit_resets_flags,
+            IDM_NOTIFY_LIMIT_RESETS as usize,
+            PCWSTR::from_raw(limit_resets_str.as_ptr()),
+        );
+
+        let usage_resets_str = native_interop::wide_str(strings.notify_usage_resets);
+        let usage_resets_flags = if notify_usage_resets {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
         let _ = AppendMenuW(
-            menu,
-            MENU_ITEM_FLAGS(0),
-            1,
-            PCWSTR::from_raw(refresh_str.as_ptr()),
+            settings_menu,
+            usage_resets_flags,
+            IDM_NOTIFY_USAGE_RESETS as usize,
+            PCWSTR::from_raw(usage_resets_str.as_ptr()),
         );
 
-        // Update Frequency submenu
-        let freq_menu = CreatePopupMenu().unwrap();
-        let freq_items: [(u16, u32, &str); 4] = [
-            (IDM_FREQ_1MIN, POLL_1_MIN, strings.one_minute),
-            (IDM_FREQ_5MIN, POLL_5_MIN, strings.five_minutes),
-            (IDM_FREQ_15MIN, POLL_15_MIN, strings.fifteen_minutes),
-            (IDM_FREQ_1HOUR, POLL_1_HOUR, strings.one_hour),
+        let reminder_menu = CreatePopupMenu().unwrap();
+        let reminder_items: [(u16, u32); 4] = [
+            (IDM_REMINDER_OFF, 0),
+            (IDM_REMINDER_6H, 6),
+            (IDM_REMINDER_12H, 12),
+            (IDM_REMINDER_24H, 24),
         ];
-        for (id, interval, label) in freq_items {
-            let label_str = native_interop::wide_str(label);
-            let flags = if interval == current_interval {
+        for (id, hours) in reminder_items {
+            let label = if hours == 0 {
+                strings.reminder_off.to_string()
+            } else {
+                strings
+                    .reminder_hours_before
+                    .replace("{hours}", &hours.to_string())
+            };
+            let label_str = native_interop::wide_str(&label);
+            let flags = if hours == weekly_reminder_hours {
                 MF_CHECKED
             } else {
                 MENU_ITEM_FLAGS(0)
             };
             let _ = AppendMenuW(
-                freq_menu,
+                reminder_menu,
                 flags,
                 id as usize,
                 PCWSTR::from_raw(label_str.as_ptr()),
             );
         }
+        let reminder_label = native_interop::wide_str(strings.weekly_reminder);
+        let _ = AppendMenuW(
+            settings_menu,
+            MF_POPUP,
+            reminder_menu.0 as usize,
+            PCWSTR::from_raw(reminder_label.as_ptr()),
+        );
 
-        let freq_label = native_interop::wide_str(strings.update_frequency);
+        let row_items = [IDM_SHOW_SESSION_ROW, IDM_SHOW_WEEKLY_ROW];
+        for ((id, window), label) in row_items.into_iter().zip(UsageWindow::ALL).zip(&row_labels) {
+            let label_str = native_interop::wide_str(&strings.show_row.replace("{row}", label));
+            let flags = match (display_mode.shows(window), display_mode.row_count()) {
+                // The last row shown cannot be hidden.
+                (true, 1) => MF_CHECKED | MF_GRAYED,
+                (true, _) => MF_CHECKED,
+                (false, _) => MENU_ITEM_FLAGS(0),
+            };
+            let _ = AppendMenuW(
+                settings_menu,
+                flags,
+                id as usize,
+                PCWSTR::from_raw(label_str.as_ptr()),
+            );
+        }
+
+        let elapsed_menu = CreatePopupMenu().unwrap();
+        let elapsed_items = [IDM_ELAPSED_SESSION, IDM_ELAPSED_WEEKLY];
+        for ((id, window), label) in elapsed_items
+            .into_iter()
+            .zip(UsageWindow::ALL)
+            .zip(&row_labels)
+        {
+            let label_str = native_interop::wide_str(label);
+            let flags = if elapsed_bars.contains(&window) {
+                MF_CHECKED
+            } else {
+                MENU_ITEM_FLAGS(0)
+            };
+            let _ = AppendMenuW(
+                elapsed_menu,
+                flags,
+                id as usize,
+                PCWSTR::from_raw(label_str.as_ptr()),
+            );
+        }
+        let elapsed_label = native_interop::wide_str(strings.bars_show_time_elapsed);
         let _ = AppendMenuW(
-            menu,
+            settings_menu,
             MF_POPUP,
-            freq_menu.0 as usize,
-            PCWSTR::from_raw(freq_label.as_ptr()),
+            elapsed_menu.0 as usize,
+            PCWSTR::from_raw(elapsed_label.as_ptr()),
         );
 
-        // Models submenu
-        let models_menu = CreatePopupMenu().unwrap();
-        let claude_model = native_interop::wide_str(strings.claude_code_model);
-        let claude_flags = if show_claude_code {
+        let quiet_str = native_interop::wide_str(strings.quiet_when_fullscreen);
+        let quiet_flags = if quiet_when_fullscreen {
             MF_CHECKED
         } else {
             MENU_ITEM_FLAGS(0)
         };
         let _ = AppendMenuW(
-            models_menu,
-            claude_flags,
-            IDM_MODEL_CLAUDE_CODE as usize,
-            PCWSTR::from_raw(claude_model.as_ptr()),
+            settings_menu,
+            quiet_flags,
+            IDM_QUIET_WHEN_FULLSCREEN as usize,
+            PCWSTR::from_raw(quiet_str.as_ptr()),
         );
 
-        let codex_model = native_interop::wide_str(strings.codex_model);
-        let codex_flags = if show_codex {
+        let metered_str = native_interop::wide_str(strings.pause_on_metered);
+        let metered_flags = if pause_on_metered {
             MF_CHECKED
         } else {
             MENU_ITEM_FLAGS(0)
         };
         let _ = AppendMenuW(
-            models_menu,
-            codex_flags,
-            IDM_MODEL_CODEX as usize,
-            PCWSTR::from_raw(codex_model.as_ptr()),
+            settings_menu,
+            metered_flags,
+            IDM_PAUSE_ON_METERED as usize,
+            PCWSTR::from_raw(metered_str.as_ptr()),
         );
 
-        let antigravity_model = native_interop::wide_str(strings.antigravity_model);
-        let antigravity_flags = if show_antigravity {
+        let battery_str = native_interop::wide_str(strings.poll_less_on_battery);
+        let battery_flags = if poll_less_on_battery {
             MF_CHECKED
         } else {
             MENU_ITEM_FLAGS(0)
         };
         let _ = AppendMenuW(
-            models_menu,
-            antigravity_flags,
-            IDM_MODEL_ANTIGRAVITY as usize,
-            PCWSTR::from_raw(antigravity_model.as_ptr()),
+            settings_menu,
+            battery_flags,
+            IDM_POLL_LESS_ON_BATTERY as usize,
+            PCWSTR::from_raw(battery_str.as_ptr()),
         );
 
-        let models_label = native_interop::wide_str(strings.models);
+        let api_key_str = native_interop::wide_str(strings.api_key_limits);
+        let api_key_flags = if api_key_limits {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
         let _ = AppendMenuW(
-            menu,
+            settings_menu,
+            api_key_flags,
+            IDM_API_KEY_LIMITS as usize,
+            PCWSTR::from_raw(api_key_str.as_ptr()),
+        );
+
+        let palette_menu = CreatePopupMenu().unwrap();
+        let palette_items: [(u16, Palette, &str); 3] = [
+            (
+                IDM_PALETTE_STANDARD,
+                Palette::Standard,
+                strings.palette_standard,
+            ),
+            (
+                IDM_PALETTE_DEUTERANOPIA,
+                Palette::Deuteranopia,
+                strings.palette_deuteranopia,
+            ),
+            (
+                IDM_PALETTE_PROTANOPIA,
+                Palette::Protanopia,
+                strings.palette_protanopia,
+            ),
+        ];
+        for (id, item_palette, label) in palette_items {
+            let label_str = native_interop::wide_str(label);
+            let flags = if item_palette == palette {
+                MF_CHECKED
+            } else {
+                MENU_ITEM_FLAGS(0)
+            };
+            let _ = AppendMenuW(
+                palette_menu,
+                flags,
+                id as usize,
+                PCWSTR::from_raw(label_str.as_ptr()),
+            );
+        }
+        let palette_label = native_interop::wide_str(strings.color_palette);
+        let _ = AppendMenuW(
+            settings_menu,
             MF_POPUP,
-            models_menu.0 as usize,
-            PCWSTR::from_raw(models_label.as_ptr()),
+            palette_menu.0 as usize,
+            PCWSTR::from_raw(palette_label.as_ptr()),
         );
 
-        // Settings submenu
-        let settings_menu = CreatePopupMenu().unwrap();
+        let click_menu = CreatePopupMenu().unwrap();
+        let click_items: [(u16, ClickAction, &str); 4] = [
+            (
+                IDM_CLICK_SHOW_DETAILS,
+                ClickAction::ShowDetails,
+                strings.click_show_details,
+            ),
+            (IDM_CLICK_REFRESH, ClickAction::Refresh, strings.refresh),
+            (
+                IDM_CLICK_OPEN_USAGE_PAGE,
+                ClickAction::OpenUsagePage,
+                strings.click_open_usage_page,
+            ),
+            (
+                IDM_CLICK_NOTHING,
+                ClickAction::Nothing,
+                strings.click_do_nothing,
+            ),
+        ];
+        for (id, action, label) in click_items {
+            let label_str = native_interop::wide_str(label);
+            let flags = if action == left_click {
+                MF_CHECKED
+            } else {
+                MENU_ITEM_FLAGS(0)
+            };
+            let _ = AppendMenuW(
+                click_menu,
+                flags,
+                id as usize,
+                PCWSTR::from_raw(label_str.as_ptr()),
+            );
+        }
+        let click_label = native_interop::wide_str(strings.left_click);
+        let _ = AppendMenuW(
+            settings_menu,
+            MF_POPUP,
+            click_menu.0 as usize,
+            PCWSTR::from_raw(click_label.as_ptr()),
+        );
 
-        let startup_str = native_interop::wide_str(strings.start_with_windows);
-        let startup_flags = if is_startup_enabled() {
+        let reset_pos_str = native_interop::wide_str(strings.reset_position);
+        let _ = AppendMenuW(
+            settings_menu,
+            MENU_ITEM_FLAGS(0),
+            IDM_RESET_POSITION as usize,
+            PCWSTR::from_raw(reset_pos_str.as_ptr()),
+        );
+
+        let reembed_str = native_interop::wide_str(strings.reembed_in_taskbar);
+        let _ = AppendMenuW(
+            settings_menu,
+            MENU_ITEM_FLAGS(0),
+            IDM_REEMBED as usize,
+            PCWSTR::from_raw(reembed_str.as_ptr()),
+        );
+
+        let float_str = native_interop::wide_str(strings.float_over_taskbar);
+        let float_flags = if embedding_forced_off() {
+            MF_CHECKED | MF_GRAYED
+        } else if float_widget {
             MF_CHECKED
         } else {
             MENU_ITEM_FLAGS(0)
         };
         let _ = AppendMenuW(
             settings_menu,
-            startup_flags,
-            IDM_START_WITH_WINDOWS as usize,
-            PCWSTR::from_raw(startup_str.as_ptr()),
+            float_flags,
+            IDM_FLOAT_WIDGET as usize,
+            PCWSTR::from_raw(float_str.as_ptr()),
         );
 
-        let reset_pos_str = native_interop::wide_str(strings.reset_position);
+        let restart_str = native_interop::wide_str(strings.restart_widget);
         let _ = AppendMenuW(
             settings_menu,
             MENU_ITEM_FLAGS(0),
-            IDM_RESET_POSITION as usize,
-            PCWSTR::from_raw(reset_pos_str.as_ptr()),
+            IDM_RESTART as usize,
+            PCWSTR::from_raw(restart_str.as_ptr()),
         );
 
         let language_menu = CreatePopupMenu().unwrap();
@@ -2949,8 +8863,116 @@ fn show_context_menu(hwnd: HWND) {
             PCWSTR::from_raw(widget_label.as_ptr()),
         );
 
+        let badge_label = native_interop::wide_str(strings.badge_mode);
+        let badge_flags = if badge_mode {
+            MF_CHECKED
+        } else {
+            MENU_ITEM_FLAGS(0)
+        };
+        let _ = AppendMenuW(
+            menu,
+            badge_flags,
+            IDM_BADGE_MODE as usize,
+            PCWSTR::from_raw(badge_label.as_ptr()),
+        );
+
+        let copy_snapshot_str = native_interop::wide_str(strings.copy_snapshot);
+        let _ = AppendMenuW(
+            menu,
+            MENU_ITEM_FLAGS(0),
+            IDM_COPY_SNAPSHOT as usize,
+            PCWSTR::from_raw(copy_snapshot_str.as_ptr()),
+        );
+
+        let save_snapshot_str = native_interop::wide_str(strings.save_snapshot);
+        let _ = AppendMenuW(
+            menu,
+            MENU_ITEM_FLAGS(0),
+            IDM_SAVE_SNAPSHOT as usize,
+            PCWSTR::from_raw(save_snapshot_str.as_ptr()),
+        );
+
+        let export_str = native_interop::wide_str(strings.export_history);
+        let _ = AppendMenuW(
+            menu,
+            MENU_ITEM_FLAGS(0),
+            IDM_EXPORT_HISTORY as usize,
+            PCWSTR::from_raw(export_str.as_ptr()),
+        );
+
         let _ = AppendMenuW(menu, MF_SEPARATOR, 0, PCWSTR::null());
 
+        let (debug_menu, simulation) = {
+            let state = lock_state();
+            state
+                .as_ref()
+                .map(|s| (s.poll.debug_menu, s.poll.simulation))
+                .unwrap_or((false, None))
+        };
+        // Developer menu; not translated.
+        if debug_menu || GetKeyState(VK_SHIFT.0 as i32) < 0 {
+            let debug_submenu = CreatePopupMenu().unwrap();
+            for (id, label, item_simulation) in [
+                (
+                    IDM_SIMULATE_NEAR_LIMIT,
+                    "Simulate 95% Session",
+                    Some(Simulation::NearSessionLimit),
+                ),
+                (
+                    IDM_SIMULATE_LIMIT_HIT,
+                    "Simulate Limit Hit",
+                    Some(Simulation::LimitHit),
+                ),
+                (
+                    IDM_SIMULATE_POLL_FAILURE,
+                    "Simulate Poll Failure",
+                    Some(Simulation::PollFailure),
+                ),
+            ] {
+                let label_str = native_interop::wide_str(label);
+                let flags = if simulation == item_simulation {
+                    MF_CHECKED
+                } else {
+                    MENU_ITEM_FLAGS(0)
+                };
+                let _ = AppendMenuW(
+                    debug_submenu,
+                    flags,
+                    id as usize,
+                    PCWSTR::from_raw(label_str.as_ptr()),
+                );
+            }
+            let stop_str = native_interop::wide_str("Stop Simulating");
+            let stop_flags = if simulation.is_some() {
+                MENU_ITEM_FLAGS(0)
+            } else {
+                MF_GRAYED
+            };
+            let _ = AppendMenuW(
+                debug_submenu,
+                stop_flags,
+                IDM_STOP_SIMULATION as usize,
+                PCWSTR::from_raw(stop_str.as_ptr()),
+            );
+            let _ = AppendMenuW(debug_submenu, MF_SEPARATOR, 0, PCWSTR::null());
+            let simulate_reset_str = native_interop::wide_str("Simulate Reset");
+            let _ = AppendMenuW(
+                debug_submenu,
+                MENU_ITEM_FLAGS(0),
+                IDM_SIMULATE_RESET as usize,
+                PCWSTR::from_raw(simulate_reset_str.as_ptr()),
+            );
+
+            let debug_str = native_interop::wide_str("Debug");
+            let _ = AppendMenuW(
+                menu,
+                MF_POPUP,
+                debug_submenu.0 as usize,
+                PCWSTR::from_raw(debug_str.as_ptr()),
+            );
+            let _ = AppendMenuW(menu, MF_SEPARATOR, 0, PCWSTR::null());
+        }
+
         let exit_str = native_interop::wide_str(strings.exit);
         let _ = AppendMenuW(
             menu,
@@ -2961,76 +8983,24 @@ fn show_context_menu(hwnd: HWND) {
 
         let mut pt = POINT::default();
         let _ = GetCursorPos(&mut pt);
-        let _ = SetForegroundWindow(hwnd);
-        let _ = TrackPopupMenu(menu, TPM_RIGHTBUTTON, pt.x, pt.y, 0, hwnd, None);
+        let command = native_interop::track_popup_menu(hwnd, menu, pt);
         let _ = DestroyMenu(menu);
+        if command != 0 {
+            SendMessageW(hwnd, WM_COMMAND, WPARAM(command as usize), LPARAM(0));
+        }
     }
 }
 
 /// Paint for non-embedded fallback (normal WM_PAINT path)
 fn paint(hdc: HDC, hwnd: HWND) {
-    let (
-        is_dark,
-        strings,
-        session_pct,
-        session_text,
-        weekly_pct,
-        weekly_text,
-        codex_session_pct,
-        codex_session_text,
-        codex_weekly_pct,
-        codex_weekly_text,
-        antigravity_session_pct,
-        antigravity_session_text,
-        antigravity_weekly_pct,
-        antigravity_weekly_text,
-        show_claude_code,
-        show_codex,
-        show_antigravity,
-    ) = {
+    let mut snapshot = {
         let state = lock_state();
         match state.as_ref() {
-            Some(s) => (
-                s.is_dark,
-                s.language.strings(),
-                s.session_percent,
-                s.session_text.clone(),
-                s.weekly_percent,
-                s.weekly_text.clone(),
-                s.codex_session_percent,
-                s.codex_session_text.clone(),
-                s.codex_weekly_percent,
-                s.codex_weekly_text.clone(),
-                s.antigravity_session_percent,
-                s.antigravity_session_text.clone(),
-                s.antigravity_weekly_percent,
-                s.antigravity_weekly_text.clone(),
-                s.show_claude_code,
-                s.show_codex,
-                s.show_antigravity,
-            ),
+            Some(s) => RenderSnapshot::capture(s),
             None => return,
         }
     };
-
-    let accent = claude_accent_color();
-    let codex_accent = codex_accent_color(is_dark);
-    let antigravity_accent = antigravity_accent_color();
-    let track = if is_dark {
-        Color::from_hex("#444444")
-    } else {
-        Color::from_hex("#AAAAAA")
-    };
-    let text_color = if is_dark {
-        Color::from_hex("#888888")
-    } else {
-        Color::from_hex("#404040")
-    };
-    let bg_color = if is_dark {
-        Color::from_hex("#1C1C1C")
-    } else {
-        Color::from_hex("#F3F3F3")
-    };
+    snapshot.layout.card_background = false;
 
     unsafe {
         let mut client_rect = RECT::default();
@@ -3041,39 +9011,17 @@ fn paint(hdc: HDC, hwnd: HWND) {
         if width <= 0 || height <= 0 {
             return;
         }
+        snapshot.width = width;
+        snapshot.height = height;
 
         let mem_dc = CreateCompatibleDC(hdc);
         let mem_bmp = CreateCompatibleBitmap(hdc, width, height);
         let old_bmp = SelectObject(mem_dc, mem_bmp);
 
-        paint_content(
-            mem_dc,
-            width,
-            height,
-            is_dark,
-            &bg_color,
-            &text_color,
-            &accent,
-            &track,
-            strings,
-            session_pct,
-            &session_text,
-            weekly_pct,
-            &weekly_text,
-            codex_session_pct,
-            &codex_session_text,
-            codex_weekly_pct,
-            &codex_weekly_text,
-            antigravity_session_pct,
-            &antigravity_session_text,
-            antigravity_weekly_pct,
-            &antigravity_weekly_text,
-            show_claude_code,
-            show_codex,
-            show_antigravity,
-            &codex_accent,
-            &antigravity_accent,
-        );
+        let mut cache = lock_render_cache();
+        let resources = cache.resources(snapshot.colors.is_dark, &snapshot.colors.backdrop, false);
+        snapshot.paint(mem_dc, resources, resources.background);
+        drop(cache);
 
         let _ = BitBlt(hdc, 0, 0, width, height, mem_dc, 0, 0, SRCCOPY);
 
@@ -3087,15 +9035,9 @@ fn draw_row(
     hdc: HDC,
     x: i32,
     y: i32,
-    is_dark: bool,
+    resources: &PaintResources,
     text_color: &Color,
-    label: &str,
-    claude_percent: f64,
-    claude_text: &str,
-    codex_percent: f64,
-    codex_text: &str,
-    antigravity_percent: f64,
-    antigravity_text: &str,
+    row: &WidgetRow,
     show_claude_code: bool,
     show_codex: bool,
     show_antigravity: bool,
@@ -3103,13 +9045,22 @@ fn draw_row(
     codex_accent: &Color,
     antigravity_accent: &Color,
     track: &Color,
+    palette: Palette,
+    text_only: bool,
+    layout: &WidgetLayout,
 ) {
     let seg_h = sc(SEGMENT_H);
-    let active_models = active_model_count(show_claude_code, show_codex, show_antigravity);
-    let segment_count = row_bar_segment_count(active_models);
+    let is_dark = resources.is_dark;
+    let active_models = active_model_count(
+        show_claude_code,
+        show_codex,
+        show_antigravity,
+        row.secondary.is_some(),
+    );
+    let segment_count = row_bar_segment_count(active_models, text_only);
     let use_model_text_colors = active_models > 1;
     let claude_value_color = if use_model_text_colors {
-        claude_usage_text_color(is_dark)
+        palette.claude_text(is_dark)
     } else {
         *text_color
     };
@@ -3119,18 +9070,39 @@ fn draw_row(
         *text_color
     };
     let antigravity_value_color = if use_model_text_colors {
-        antigravity_usage_text_color(is_dark)
+        palette.antigravity_text(is_dark)
     } else {
         *text_color
     };
+    // Emphasized text is bold in the warning colour, whatever the model, and
+    // a reached limit takes the warning colour too.
+    let warning_color = palette.warning_text(is_dark);
+    let value_style = |value: &RowValue, color: &Color| {
+        let (font, color) = if value.emphasized {
+            (resources.bold_font, warning_color)
+        } else if value.limit_reached {
+            (resources.font, warning_color)
+        } else {
+            (resources.font, *color)
+        };
+        (font, color, value.binding)
+    };
+    let limit_accent = palette.limit_accent();
+    let bar_accent = |value: &RowValue, accent: Color| {
+        if value.limit_reached {
+            limit_accent
+        } else {
+            accent
+        }
+    };
 
     unsafe {
         let _ = SetTextColor(hdc, COLORREF(text_color.to_colorref()));
-        let mut label_wide: Vec<u16> = label.encode_utf16().collect();
+        let mut label_wide: Vec<u16> = row.label.encode_utf16().collect();
         let mut label_rect = RECT {
             left: x,
             top: y,
-            right: x + sc(LABEL_WIDTH),
+            right: x + sc(layout.label_width),
             bottom: y + seg_h,
         };
         let _ = DrawTextW(
@@ -3140,20 +9112,24 @@ fn draw_row(
             DT_LEFT | DT_VCENTER | DT_SINGLELINE,
         );
 
-        let mut model_x = x + sc(LABEL_WIDTH) + sc(LABEL_RIGHT_MARGIN);
+        let mut model_x = x + sc(layout.label_width) + sc(layout.margins.label_right);
+        let model_gap = sc(layout.margins.model_gap);
         if show_claude_code {
             draw_usage_bar(
                 hdc,
                 model_x,
                 y,
                 segment_count,
-                claude_percent,
-                claude_text,
-                claude_accent,
+                row.claude.fill,
+                &row.claude.text,
+                &bar_accent(&row.claude, *claude_accent),
                 track,
-                &claude_value_color,
+                value_style(&row.claude, &claude_value_color),
+                palette.patterned_warnings(),
+                &resources.brushes,
+                layout,
             );
-            model_x += model_usage_width(segment_count) + sc(MODEL_RIGHT_MARGIN);
+            model_x += model_usage_width(segment_count, layout) + model_gap;
         }
         if show_codex {
             draw_usage_bar(
@@ -3161,13 +9137,16 @@ fn draw_row(
                 model_x,
                 y,
                 segment_count,
-                codex_percent,
-                codex_text,
-                codex_accent,
+                row.codex.fill,
+                &row.codex.text,
+                &bar_accent(&row.codex, *codex_accent),
                 track,
-                &codex_value_color,
+                value_style(&row.codex, &codex_value_color),
+                palette.patterned_warnings(),
+                &resources.brushes,
+                layout,
             );
-            model_x += model_usage_width(segment_count) + sc(MODEL_RIGHT_MARGIN);
+            model_x += model_usage_width(segment_count, layout) + model_gap;
         }
         if show_antigravity {
             draw_usage_bar(
@@ -3175,20 +9154,52 @@ fn draw_row(
                 model_x,
                 y,
                 segment_count,
-                antigravity_percent,
-                antigravity_text,
-                antigravity_accent,
+                row.antigravity.fill,
+                &row.antigravity.text,
+                &bar_accent(&row.antigravity, *antigravity_accent),
+                track,
+                value_style(&row.antigravity, &antigravity_value_color),
+                palette.patterned_warnings(),
+                &resources.brushes,
+                layout,
+            );
+            model_x += model_usage_width(segment_count, layout) + model_gap;
+        }
+        if let Some(secondary) = &row.secondary {
+            // A lighter Claude bar tells the second account apart.
+            draw_usage_bar(
+                hdc,
+                model_x,
+                y,
+                segment_count,
+                secondary.fill,
+                &secondary.text,
+                &bar_accent(secondary, claude_accent.blend(*track, 0.45)),
                 track,
-                &antigravity_value_color,
+                value_style(secondary, &claude_value_color),
+                palette.patterned_warnings(),
+                &resources.brushes,
+                layout,
             );
         }
     }
 }
 
-fn model_usage_width(segment_count: i32) -> i32 {
-    (sc(SEGMENT_W) + sc(SEGMENT_GAP)) * segment_count - sc(SEGMENT_GAP)
-        + sc(BAR_RIGHT_MARGIN)
-        + sc(TEXT_WIDTH)
+fn model_usage_width(segment_count: i32, layout: &WidgetLayout) -> i32 {
+    bar_width(segment_count, layout.bar_style) + sc(layout.text_width)
+}
+
+/// Width of a bar and the margin before its text; 0 without segments.
+fn bar_width(segment_count: i32, style: BarStyle) -> i32 {
+    if segment_count == 0 {
+        return 0;
+    }
+    let bar = if style.is_square() {
+        sc(SEGMENT_H)
+    } else {
+        (sc(SEGMENT_W) + sc(SEGMENT_GAP)) * segment_count - sc(SEGMENT_GAP)
+    };
+    bar + sc(BAR_RIGHT_MARGIN)
 }
 
 fn draw_usage_bar(
@@ -3200,93 +9211,85 @@ fn draw_usage_bar(
     text: &str,
     accent: &Color,
     track: &Color,
-    text_color: &Color,
+    (font, text_color, underline): (HFONT, Color, bool),
+    hatch_warnings: bool,
+    brushes: &Brushes,
+    layout: &WidgetLayout,
 ) {
-    let seg_w = sc(SEGMENT_W);
     let seg_h = sc(SEGMENT_H);
-    let seg_gap = sc(SEGMENT_GAP);
-    let corner_r = sc(CORNER_RADIUS);
 
     unsafe {
-        let percent_clamped = percent.clamp(0.0, 100.0);
-        let segment_percent = 100.0 / segment_count as f64;
-
-        for i in 0..segment_count {
-            let seg_x = bar_x + i * (seg_w + seg_gap);
-            let seg_start = (i as f64) * segment_percent;
-            let seg_end = seg_start + segment_percent;
-
-            let seg_rect = RECT {
-                left: seg_x,
-                top: y,
-                right: seg_x + seg_w,
-                bottom: y + seg_h,
-            };
-
-            if percent_clamped >= seg_end {
-                draw_rounded_rect(hdc, &seg_rect, accent, corner_r);
-            } else if percent_clamped <= seg_start {
-                draw_rounded_rect(hdc, &seg_rect, track, corner_r);
-            } else {
-                draw_rounded_rect(hdc, &seg_rect, track, corner_r);
-                let fraction = (percent_clamped - seg_start) / segment_percent;
-                let fill_width = (seg_w as f64 * fraction) as i32;
-                if fill_width > 0 {
-                    let fill_rect = RECT {
-                        left: seg_x,
-                        top: y,
-                        right: seg_x + fill_width,
-                        bottom: y + seg_h,
-                    };
-                    let rgn = CreateRoundRectRgn(
-                        seg_rect.left,
-                        seg_rect.top,
-                        seg_rect.right + 1,
-                        seg_rect.bottom + 1,
-                        corner_r * 2,
-                        corner_r * 2,
-                    );
-                    let _ = SelectClipRgn(hdc, rgn);
-                    let brush = CreateSolidBrush(COLORREF(accent.to_colorref()));
-                    FillRect(hdc, &fill_rect, brush);
-                    let _ = DeleteObject(brush);
-                    let _ = SelectClipRgn(hdc, HRGN::default());
-                    let _ = DeleteObject(rgn);
-                }
-            }
-        }
+        let bar_area = RECT {
+            left: bar_x,
+            top: y,
+            right: bar_x + bar_width(segment_count, layout.bar_style) - sc(BAR_RIGHT_MARGIN),
+            bottom: y + seg_h,
+        };
+        bars::draw(
+            hdc,
+            layout.bar_style,
+            &bar_area,
+            segment_count,
+            sc(SEGMENT_GAP),
+            sc(CORNER_RADIUS),
+            &Bar {
+                percent,
+                accent,
+                track,
+                hatch_warnings,
+                right_to_left: layout.bars_right_to_left,
+                brushes,
+            },
+        );
 
-        let text_x = bar_x + segment_count * (seg_w + seg_gap) - seg_gap + sc(BAR_RIGHT_MARGIN);
+        let text_x = bar_x + bar_width(segment_count, layout.bar_style);
         let mut text_wide: Vec<u16> = text.encode_utf16().collect();
         let mut text_rect = RECT {
             left: text_x,
             top: y,
-            right: text_x + sc(TEXT_WIDTH),
+            right: text_x + sc(layout.text_width),
             bottom: y + seg_h,
         };
         let _ = SetTextColor(hdc, COLORREF(text_color.to_colorref()));
+        let old_font = SelectObject(hdc, font);
         let _ = DrawTextW(
             hdc,
             &mut text_wide,
             &mut text_rect,
             DT_LEFT | DT_VCENTER | DT_SINGLELINE,
         );
+        if underline {
+            let mut extent = text_rect;
+            let _ = DrawTextW(
+                hdc,
+                &mut text_wide,
+                &mut extent,
+                DT_LEFT | DT_SINGLELINE | DT_CALCRECT,
+            );
+            // Just below the vertically centred text.
+            let thickness = sc(1).max(1);
+            let top = y + (seg_h + extent.bottom - extent.top) / 2;
+            let line = RECT {
+                left: text_rect.left,
+                top,
+                right: extent.right.min(text_rect.right),
+                bottom: top + thickness,
+            };
+            FillRect(hdc, &line, brushes.solid(&text_color));
+        }
+        SelectObject(hdc, old_font);
     }
 }
 
-fn draw_rounded_rect(hdc: HDC, rect: &RECT, color: &Color, radius: i32) {
-    unsafe {
-        let brush = CreateSolidBrush(COLORREF(color.to_colorref()));
-        let rgn = CreateRoundRectRgn(
-            rect.left,
-            rect.top,
-            rect.right + 1,
-            rect.bottom + 1,
-            radius * 2,
-            radius * 2,
-        );
-        let _ = FillRgn(hdc, rgn, brush);
-        let _ = DeleteObject(rgn);
-        let _ = DeleteObject(brush);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn widget_window_does_not_take_focus() {
+        assert!(WIDGET_EX_STYLE.contains(WS_EX_NOACTIVATE));
+        // Handled before the window is looked at, so no real window is needed.
+        let result = unsafe { wnd_proc(HWND::default(), WM_MOUSEACTIVATE, WPARAM(0), LPARAM(0)) };
+        assert_eq!(result, LRESULT(MA_NOACTIVATE as isize));
     }
 }